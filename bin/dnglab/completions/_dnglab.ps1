@@ -35,8 +35,14 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('cameras', 'cameras', [CompletionResultType]::ParameterValue, 'List supported cameras')
             [CompletionResult]::new('lenses', 'lenses', [CompletionResultType]::ParameterValue, 'List supported lenses')
             [CompletionResult]::new('makedng', 'makedng', [CompletionResultType]::ParameterValue, 'Lowlevel command to make a DNG file')
+            [CompletionResult]::new('pixelshift', 'pixelshift', [CompletionResultType]::ParameterValue, 'Merge pixel-shift frames into a single DNG')
+            [CompletionResult]::new('hdrmerge', 'hdrmerge', [CompletionResultType]::ParameterValue, 'Merge an exposure bracket into a floating point DNG')
+            [CompletionResult]::new('calibrate', 'calibrate', [CompletionResultType]::ParameterValue, 'Calibrate raw files with bias, dark and flat frames')
+            [CompletionResult]::new('profile', 'profile', [CompletionResultType]::ParameterValue, 'Create a camera profile from a color target shot')
+            [CompletionResult]::new('diff', 'diff', [CompletionResultType]::ParameterValue, 'Compare raw data and metadata of two raw or DNG files')
             [CompletionResult]::new('gui', 'gui', [CompletionResultType]::ParameterValue, 'Start GUI (not implemented)')
             [CompletionResult]::new('extract', 'extract', [CompletionResultType]::ParameterValue, 'Extract embedded original Raw from DNG')
+            [CompletionResult]::new('edit', 'edit', [CompletionResultType]::ParameterValue, 'Edit metadata of DNG file(s) in place')
             [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
             break
         }
@@ -54,6 +60,7 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('--srgb', '--srgb', [CompletionResultType]::ParameterName, 'Write sRGB 16-bit TIFF to STDOUT')
             [CompletionResult]::new('--meta', '--meta', [CompletionResultType]::ParameterName, 'Write metadata to STDOUT')
             [CompletionResult]::new('--structure', '--structure', [CompletionResultType]::ParameterName, 'Write file structure to STDOUT')
+            [CompletionResult]::new('--stats', '--stats', [CompletionResultType]::ParameterName, 'Write raw pixel statistics (histograms, black level, noise, clipping, exposure) to STDOUT')
             [CompletionResult]::new('--summary', '--summary', [CompletionResultType]::ParameterName, 'Write summary information for file to STDOUT')
             [CompletionResult]::new('--json', '--json', [CompletionResultType]::ParameterName, 'Format metadata as JSON')
             [CompletionResult]::new('--yaml', '--yaml', [CompletionResultType]::ParameterName, 'Format metadata as YAML')
@@ -67,6 +74,7 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('--keep-mtime', '--keep-mtime', [CompletionResultType]::ParameterName, 'Keep mtime, read from EXIF with fallback to original file mtime')
             [CompletionResult]::new('--image-index', '--image-index', [CompletionResultType]::ParameterName, 'Select a specific image index (or ''all'') if file is a image container')
             [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('--profile', '--profile', [CompletionResultType]::ParameterName, 'Use DNG camera profile (DCP) for color rendering')
             [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
             [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
             [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
@@ -82,13 +90,22 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('-c', '-c', [CompletionResultType]::ParameterName, 'Compression for raw image')
             [CompletionResult]::new('--compression', '--compression', [CompletionResultType]::ParameterName, 'Compression for raw image')
             [CompletionResult]::new('--ljpeg92-predictor', '--ljpeg92-predictor', [CompletionResultType]::ParameterName, 'LJPEG-92 predictor')
+            [CompletionResult]::new('--deflate-predictor', '--deflate-predictor', [CompletionResultType]::ParameterName, 'Deflate predictor')
+            [CompletionResult]::new('--tile-size', '--tile-size', [CompletionResultType]::ParameterName, 'Tile width and height for compressed raw images, a multiple of 16')
+            [CompletionResult]::new('--dng-version', '--dng-version', [CompletionResultType]::ParameterName, 'DNG specification version')
             [CompletionResult]::new('--dng-preview', '--dng-preview', [CompletionResultType]::ParameterName, 'DNG include preview image')
             [CompletionResult]::new('--dng-thumbnail', '--dng-thumbnail', [CompletionResultType]::ParameterName, 'DNG include thumbnail image')
+            [CompletionResult]::new('--preview-size', '--preview-size', [CompletionResultType]::ParameterName, 'Maximum preview width or height in pixels, or ''full''')
+            [CompletionResult]::new('--preview-filter', '--preview-filter', [CompletionResultType]::ParameterName, 'Filter for preview and thumbnail downscaling')
+            [CompletionResult]::new('--preview-quality', '--preview-quality', [CompletionResultType]::ParameterName, 'JPEG quality of preview image')
+            [CompletionResult]::new('--preview-source', '--preview-source', [CompletionResultType]::ParameterName, 'Use camera preview or render preview from raw')
+            [CompletionResult]::new('--thumbnail-size', '--thumbnail-size', [CompletionResultType]::ParameterName, 'Maximum thumbnail width or height in pixels')
             [CompletionResult]::new('--embed-raw', '--embed-raw', [CompletionResultType]::ParameterName, 'Embed the raw file into DNG')
             [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag')
             [CompletionResult]::new('--keep-mtime', '--keep-mtime', [CompletionResultType]::ParameterName, 'Keep mtime, read from EXIF with fallback to original file mtime')
             [CompletionResult]::new('--image-index', '--image-index', [CompletionResultType]::ParameterName, 'Select a specific image index (or ''all'') if file is a image container')
             [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('--profile', '--profile', [CompletionResultType]::ParameterName, 'Embed DNG camera profile (DCP) instead of built-in color matrix')
             [CompletionResult]::new('-j', '-j', [CompletionResultType]::ParameterName, 'Number of files to convert in parallel (0 = auto)')
             [CompletionResult]::new('--jobs', '--jobs', [CompletionResultType]::ParameterName, 'Number of files to convert in parallel (0 = auto)')
             [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
@@ -106,13 +123,22 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('-c', '-c', [CompletionResultType]::ParameterName, 'Compression for raw image')
             [CompletionResult]::new('--compression', '--compression', [CompletionResultType]::ParameterName, 'Compression for raw image')
             [CompletionResult]::new('--ljpeg92-predictor', '--ljpeg92-predictor', [CompletionResultType]::ParameterName, 'LJPEG-92 predictor')
+            [CompletionResult]::new('--deflate-predictor', '--deflate-predictor', [CompletionResultType]::ParameterName, 'Deflate predictor')
+            [CompletionResult]::new('--tile-size', '--tile-size', [CompletionResultType]::ParameterName, 'Tile width and height for compressed raw images, a multiple of 16')
+            [CompletionResult]::new('--dng-version', '--dng-version', [CompletionResultType]::ParameterName, 'DNG specification version')
             [CompletionResult]::new('--dng-preview', '--dng-preview', [CompletionResultType]::ParameterName, 'DNG include preview image')
             [CompletionResult]::new('--dng-thumbnail', '--dng-thumbnail', [CompletionResultType]::ParameterName, 'DNG include thumbnail image')
+            [CompletionResult]::new('--preview-size', '--preview-size', [CompletionResultType]::ParameterName, 'Maximum preview width or height in pixels, or ''full''')
+            [CompletionResult]::new('--preview-filter', '--preview-filter', [CompletionResultType]::ParameterName, 'Filter for preview and thumbnail downscaling')
+            [CompletionResult]::new('--preview-quality', '--preview-quality', [CompletionResultType]::ParameterName, 'JPEG quality of preview image')
+            [CompletionResult]::new('--preview-source', '--preview-source', [CompletionResultType]::ParameterName, 'Use camera preview or render preview from raw')
+            [CompletionResult]::new('--thumbnail-size', '--thumbnail-size', [CompletionResultType]::ParameterName, 'Maximum thumbnail width or height in pixels')
             [CompletionResult]::new('--embed-raw', '--embed-raw', [CompletionResultType]::ParameterName, 'Embed the raw file into DNG')
             [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag')
             [CompletionResult]::new('--keep-mtime', '--keep-mtime', [CompletionResultType]::ParameterName, 'Keep mtime, read from EXIF with fallback to original file mtime')
             [CompletionResult]::new('--image-index', '--image-index', [CompletionResultType]::ParameterName, 'Select a specific image index (or ''all'') if file is a image container')
             [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('--profile', '--profile', [CompletionResultType]::ParameterName, 'Embed DNG camera profile (DCP) instead of built-in color matrix')
             [CompletionResult]::new('--port', '--port', [CompletionResultType]::ParameterName, 'FTP listen port')
             [CompletionResult]::new('--listen', '--listen', [CompletionResultType]::ParameterName, 'FTP listen address')
             [CompletionResult]::new('--keep-original', '--keep-original', [CompletionResultType]::ParameterName, 'Keep original raw')
@@ -173,6 +199,107 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help (see more with ''--help'')')
             break
         }
+        'dnglab;pixelshift' {
+            [CompletionResult]::new('-o', '-o', [CompletionResultType]::ParameterName, 'Output DNG file path')
+            [CompletionResult]::new('--output', '--output', [CompletionResultType]::ParameterName, 'Output DNG file path')
+            [CompletionResult]::new('-i', '-i', [CompletionResultType]::ParameterName, 'Input files, all raw images of each file are used as frames')
+            [CompletionResult]::new('--input', '--input', [CompletionResultType]::ParameterName, 'Input files, all raw images of each file are used as frames')
+            [CompletionResult]::new('--pattern', '--pattern', [CompletionResultType]::ParameterName, 'Sensor shift for each frame as ''dx,dy;dx,dy;...''')
+            [CompletionResult]::new('--motion-correction', '--motion-correction', [CompletionResultType]::ParameterName, 'Use reference frame for pixels with motion')
+            [CompletionResult]::new('--motion-threshold', '--motion-threshold', [CompletionResultType]::ParameterName, 'Relative difference between frames detected as motion')
+            [CompletionResult]::new('-c', '-c', [CompletionResultType]::ParameterName, 'Compression for raw image')
+            [CompletionResult]::new('--compression', '--compression', [CompletionResultType]::ParameterName, 'Compression for raw image')
+            [CompletionResult]::new('--ljpeg92-predictor', '--ljpeg92-predictor', [CompletionResultType]::ParameterName, 'LJPEG-92 predictor')
+            [CompletionResult]::new('--deflate-predictor', '--deflate-predictor', [CompletionResultType]::ParameterName, 'Deflate predictor')
+            [CompletionResult]::new('--dng-preview', '--dng-preview', [CompletionResultType]::ParameterName, 'DNG include preview image')
+            [CompletionResult]::new('--dng-thumbnail', '--dng-thumbnail', [CompletionResultType]::ParameterName, 'DNG include thumbnail image')
+            [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag')
+            [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('--override', '--override', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help (see more with ''--help'')')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help (see more with ''--help'')')
+            break
+        }
+        'dnglab;hdrmerge' {
+            [CompletionResult]::new('-o', '-o', [CompletionResultType]::ParameterName, 'Output DNG file path')
+            [CompletionResult]::new('--output', '--output', [CompletionResultType]::ParameterName, 'Output DNG file path')
+            [CompletionResult]::new('-i', '-i', [CompletionResultType]::ParameterName, 'Input raw files of the bracket')
+            [CompletionResult]::new('--input', '--input', [CompletionResultType]::ParameterName, 'Input raw files of the bracket')
+            [CompletionResult]::new('--bits', '--bits', [CompletionResultType]::ParameterName, 'Bits per sample for floating point data')
+            [CompletionResult]::new('--dng-preview', '--dng-preview', [CompletionResultType]::ParameterName, 'DNG include preview image')
+            [CompletionResult]::new('--dng-thumbnail', '--dng-thumbnail', [CompletionResultType]::ParameterName, 'DNG include thumbnail image')
+            [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag')
+            [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('--override', '--override', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
+            break
+        }
+        'dnglab;calibrate' {
+            [CompletionResult]::new('-o', '-o', [CompletionResultType]::ParameterName, 'Output directory for calibrated DNG files')
+            [CompletionResult]::new('--output', '--output', [CompletionResultType]::ParameterName, 'Output directory for calibrated DNG files')
+            [CompletionResult]::new('-i', '-i', [CompletionResultType]::ParameterName, 'Input raw files (lights)')
+            [CompletionResult]::new('--input', '--input', [CompletionResultType]::ParameterName, 'Input raw files (lights)')
+            [CompletionResult]::new('--bias', '--bias', [CompletionResultType]::ParameterName, 'Bias frames')
+            [CompletionResult]::new('--dark', '--dark', [CompletionResultType]::ParameterName, 'Dark frames, matching exposure time of lights')
+            [CompletionResult]::new('--flat', '--flat', [CompletionResultType]::ParameterName, 'Flat frames')
+            [CompletionResult]::new('--method', '--method', [CompletionResultType]::ParameterName, 'Method to stack master frames')
+            [CompletionResult]::new('--sigma', '--sigma', [CompletionResultType]::ParameterName, 'Rejection threshold for sigma clipping and hot pixels')
+            [CompletionResult]::new('-c', '-c', [CompletionResultType]::ParameterName, 'Compression for raw image')
+            [CompletionResult]::new('--compression', '--compression', [CompletionResultType]::ParameterName, 'Compression for raw image')
+            [CompletionResult]::new('--ljpeg92-predictor', '--ljpeg92-predictor', [CompletionResultType]::ParameterName, 'LJPEG-92 predictor')
+            [CompletionResult]::new('--deflate-predictor', '--deflate-predictor', [CompletionResultType]::ParameterName, 'Deflate predictor')
+            [CompletionResult]::new('--dng-preview', '--dng-preview', [CompletionResultType]::ParameterName, 'DNG include preview image')
+            [CompletionResult]::new('--dng-thumbnail', '--dng-thumbnail', [CompletionResultType]::ParameterName, 'DNG include thumbnail image')
+            [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag')
+            [CompletionResult]::new('--crop', '--crop', [CompletionResultType]::ParameterName, 'DNG default crop')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--hot-pixels', '--hot-pixels', [CompletionResultType]::ParameterName, 'Detect hot pixels in master dark and replace them')
+            [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('--override', '--override', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
+            break
+        }
+        'dnglab;profile' {
+            [CompletionResult]::new('--chart', '--chart', [CompletionResultType]::ParameterName, 'Color target in the image')
+            [CompletionResult]::new('--reference', '--reference', [CompletionResultType]::ParameterName, 'Reference values of the target (CGATS), required for ColorChecker SG')
+            [CompletionResult]::new('--corners', '--corners', [CompletionResultType]::ParameterName, 'Centers of the corner patches: x1,y1,...,x4,y4 (top-left, top-right, bottom-right, bottom-left)')
+            [CompletionResult]::new('--illuminant', '--illuminant', [CompletionResultType]::ParameterName, 'Illuminant of the shot, like D65 or A (default: from EXIF, fallback to D65)')
+            [CompletionResult]::new('--name', '--name', [CompletionResultType]::ParameterName, 'Profile name')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--hue-sat-map', '--hue-sat-map', [CompletionResultType]::ParameterName, 'Add a hue/saturation map to correct the matrix')
+            [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('--override', '--override', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
+            break
+        }
+        'dnglab;diff' {
+            [CompletionResult]::new('--heatmap', '--heatmap', [CompletionResultType]::ParameterName, 'Write heat map of pixel differences to image file')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--json', '--json', [CompletionResultType]::ParameterName, 'Format result as JSON')
+            [CompletionResult]::new('--yaml', '--yaml', [CompletionResultType]::ParameterName, 'Format result as YAML')
+            [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('--override', '--override', [CompletionResultType]::ParameterName, 'Override existing files')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
+            break
+        }
         'dnglab;gui' {
             [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
             [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
@@ -185,6 +312,7 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
             [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
             [CompletionResult]::new('--skipchecks', '--skipchecks', [CompletionResultType]::ParameterName, 'Skip integrity checks')
+            [CompletionResult]::new('--auxiliary', '--auxiliary', [CompletionResultType]::ParameterName, 'Extract depth map and semantic masks as grayscale PNG instead of the original raw')
             [CompletionResult]::new('-r', '-r', [CompletionResultType]::ParameterName, 'Process input directory recursive')
             [CompletionResult]::new('--recursive', '--recursive', [CompletionResultType]::ParameterName, 'Process input directory recursive')
             [CompletionResult]::new('-f', '-f', [CompletionResultType]::ParameterName, 'Override existing files')
@@ -194,6 +322,25 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
             break
         }
+        'dnglab;edit' {
+            [CompletionResult]::new('--artist', '--artist', [CompletionResultType]::ParameterName, 'Set the artist tag, empty value removes it')
+            [CompletionResult]::new('--copyright', '--copyright', [CompletionResultType]::ParameterName, 'Set the copyright tag, empty value removes it')
+            [CompletionResult]::new('--description', '--description', [CompletionResultType]::ParameterName, 'Set the image description tag, empty value removes it')
+            [CompletionResult]::new('--orientation', '--orientation', [CompletionResultType]::ParameterName, 'Set the orientation (EXIF value 1-8)')
+            [CompletionResult]::new('--gps', '--gps', [CompletionResultType]::ParameterName, 'Set GPS position in decimal degrees: latitude,longitude[,altitude]')
+            [CompletionResult]::new('--as-shot-neutral', '--as-shot-neutral', [CompletionResultType]::ParameterName, 'Set white balance as neutral values, one per color plane: r,g,b')
+            [CompletionResult]::new('--xmp', '--xmp', [CompletionResultType]::ParameterName, 'Replace XMP packet by file content')
+            [CompletionResult]::new('--profile', '--profile', [CompletionResultType]::ParameterName, 'Embed DNG camera profile (DCP), replacing the existing color matrices')
+            [CompletionResult]::new('-d', '-d', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--loglevel', '--loglevel', [CompletionResultType]::ParameterName, 'Log level')
+            [CompletionResult]::new('--remove-gps', '--remove-gps', [CompletionResultType]::ParameterName, 'Remove GPS information')
+            [CompletionResult]::new('-r', '-r', [CompletionResultType]::ParameterName, 'Process input directory recursive')
+            [CompletionResult]::new('--recursive', '--recursive', [CompletionResultType]::ParameterName, 'Process input directory recursive')
+            [CompletionResult]::new('-v', '-v', [CompletionResultType]::ParameterName, 'Print status for every file')
+            [CompletionResult]::new('-h', '-h', [CompletionResultType]::ParameterName, 'Print help')
+            [CompletionResult]::new('--help', '--help', [CompletionResultType]::ParameterName, 'Print help')
+            break
+        }
         'dnglab;help' {
             [CompletionResult]::new('analyze', 'analyze', [CompletionResultType]::ParameterValue, 'Analyze raw image')
             [CompletionResult]::new('process-raw', 'process-raw', [CompletionResultType]::ParameterValue, 'process-raw')
@@ -202,8 +349,14 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
             [CompletionResult]::new('cameras', 'cameras', [CompletionResultType]::ParameterValue, 'List supported cameras')
             [CompletionResult]::new('lenses', 'lenses', [CompletionResultType]::ParameterValue, 'List supported lenses')
             [CompletionResult]::new('makedng', 'makedng', [CompletionResultType]::ParameterValue, 'Lowlevel command to make a DNG file')
+            [CompletionResult]::new('pixelshift', 'pixelshift', [CompletionResultType]::ParameterValue, 'Merge pixel-shift frames into a single DNG')
+            [CompletionResult]::new('hdrmerge', 'hdrmerge', [CompletionResultType]::ParameterValue, 'Merge an exposure bracket into a floating point DNG')
+            [CompletionResult]::new('calibrate', 'calibrate', [CompletionResultType]::ParameterValue, 'Calibrate raw files with bias, dark and flat frames')
+            [CompletionResult]::new('profile', 'profile', [CompletionResultType]::ParameterValue, 'Create a camera profile from a color target shot')
+            [CompletionResult]::new('diff', 'diff', [CompletionResultType]::ParameterValue, 'Compare raw data and metadata of two raw or DNG files')
             [CompletionResult]::new('gui', 'gui', [CompletionResultType]::ParameterValue, 'Start GUI (not implemented)')
             [CompletionResult]::new('extract', 'extract', [CompletionResultType]::ParameterValue, 'Extract embedded original Raw from DNG')
+            [CompletionResult]::new('edit', 'edit', [CompletionResultType]::ParameterValue, 'Edit metadata of DNG file(s) in place')
             [CompletionResult]::new('help', 'help', [CompletionResultType]::ParameterValue, 'Print this message or the help of the given subcommand(s)')
             break
         }
@@ -228,12 +381,30 @@ Register-ArgumentCompleter -Native -CommandName 'dnglab' -ScriptBlock {
         'dnglab;help;makedng' {
             break
         }
+        'dnglab;help;pixelshift' {
+            break
+        }
+        'dnglab;help;hdrmerge' {
+            break
+        }
+        'dnglab;help;calibrate' {
+            break
+        }
+        'dnglab;help;profile' {
+            break
+        }
+        'dnglab;help;diff' {
+            break
+        }
         'dnglab;help;gui' {
             break
         }
         'dnglab;help;extract' {
             break
         }
+        'dnglab;help;edit' {
+            break
+        }
         'dnglab;help;help' {
             break
         }
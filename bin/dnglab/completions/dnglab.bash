@@ -19,12 +19,21 @@ _dnglab() {
             dnglab,analyze)
                 cmd="dnglab__analyze"
                 ;;
+            dnglab,calibrate)
+                cmd="dnglab__calibrate"
+                ;;
             dnglab,cameras)
                 cmd="dnglab__cameras"
                 ;;
             dnglab,convert)
                 cmd="dnglab__convert"
                 ;;
+            dnglab,diff)
+                cmd="dnglab__diff"
+                ;;
+            dnglab,edit)
+                cmd="dnglab__edit"
+                ;;
             dnglab,extract)
                 cmd="dnglab__extract"
                 ;;
@@ -34,6 +43,9 @@ _dnglab() {
             dnglab,gui)
                 cmd="dnglab__gui"
                 ;;
+            dnglab,hdrmerge)
+                cmd="dnglab__hdrmerge"
+                ;;
             dnglab,help)
                 cmd="dnglab__help"
                 ;;
@@ -43,18 +55,33 @@ _dnglab() {
             dnglab,makedng)
                 cmd="dnglab__makedng"
                 ;;
+            dnglab,pixelshift)
+                cmd="dnglab__pixelshift"
+                ;;
             dnglab,process-raw)
                 cmd="dnglab__process__raw"
                 ;;
+            dnglab,profile)
+                cmd="dnglab__profile"
+                ;;
             dnglab__help,analyze)
                 cmd="dnglab__help__analyze"
                 ;;
+            dnglab__help,calibrate)
+                cmd="dnglab__help__calibrate"
+                ;;
             dnglab__help,cameras)
                 cmd="dnglab__help__cameras"
                 ;;
             dnglab__help,convert)
                 cmd="dnglab__help__convert"
                 ;;
+            dnglab__help,diff)
+                cmd="dnglab__help__diff"
+                ;;
+            dnglab__help,edit)
+                cmd="dnglab__help__edit"
+                ;;
             dnglab__help,extract)
                 cmd="dnglab__help__extract"
                 ;;
@@ -64,6 +91,9 @@ _dnglab() {
             dnglab__help,gui)
                 cmd="dnglab__help__gui"
                 ;;
+            dnglab__help,hdrmerge)
+                cmd="dnglab__help__hdrmerge"
+                ;;
             dnglab__help,help)
                 cmd="dnglab__help__help"
                 ;;
@@ -73,9 +103,15 @@ _dnglab() {
             dnglab__help,makedng)
                 cmd="dnglab__help__makedng"
                 ;;
+            dnglab__help,pixelshift)
+                cmd="dnglab__help__pixelshift"
+                ;;
             dnglab__help,process-raw)
                 cmd="dnglab__help__process__raw"
                 ;;
+            dnglab__help,profile)
+                cmd="dnglab__help__profile"
+                ;;
             *)
                 ;;
         esac
@@ -83,7 +119,7 @@ _dnglab() {
 
     case "${cmd}" in
         dnglab)
-            opts="-d -v -h -V --loglevel --help --version analyze process-raw convert ftpserver cameras lenses makedng gui extract help"
+            opts="-d -v -h -V --loglevel --help --version analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 1 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
@@ -105,12 +141,102 @@ _dnglab() {
             return 0
             ;;
         dnglab__analyze)
-            opts="-d -v -h --raw-pixel --full-pixel --preview-pixel --thumbnail-pixel --raw-checksum --full-checksum --preview-checksum --thumbnail-checksum --srgb --meta --structure --summary --json --yaml --loglevel --help <FILE>"
+            opts="-d -v -h --raw-pixel --full-pixel --preview-pixel --thumbnail-pixel --raw-checksum --full-checksum --preview-checksum --thumbnail-checksum --srgb --meta --structure --stats --summary --json --yaml --loglevel --help <FILE>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        dnglab__calibrate)
+            opts="-o -i -c -f -d -v -h --output --input --bias --dark --flat --method --sigma --hot-pixels --compression --ljpeg92-predictor --deflate-predictor --dng-preview --dng-thumbnail --artist --crop --override --loglevel --help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
             fi
             case "${prev}" in
+                --output)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -o)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -i)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --bias)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --dark)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --flat)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --method)
+                    COMPREPLY=($(compgen -W "median sigma-clip" -- "${cur}"))
+                    return 0
+                    ;;
+                --sigma)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --compression)
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
+                    return 0
+                    ;;
+                -c)
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
+                    return 0
+                    ;;
+                --ljpeg92-predictor)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --deflate-predictor)
+                    COMPREPLY=($(compgen -W "none horizontal horizontal2 horizontal4" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-preview)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-thumbnail)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --artist)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --crop)
+                    COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
+                    return 0
+                    ;;
                 --loglevel)
                     COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
                     return 0
@@ -149,24 +275,36 @@ _dnglab() {
             return 0
             ;;
         dnglab__convert)
-            opts="-c -f -r -j -d -v -h --compression --ljpeg92-predictor --dng-preview --dng-thumbnail --embed-raw --artist --keep-mtime --image-index --crop --override --recursive --jobs --loglevel --help <INPUT> <OUTPUT>"
+            opts="-c -f -r -j -d -v -h --compression --ljpeg92-predictor --deflate-predictor --tile-size --dng-version --dng-preview --dng-thumbnail --preview-size --preview-filter --preview-quality --preview-source --thumbnail-size --embed-raw --artist --keep-mtime --image-index --crop --profile --override --recursive --jobs --loglevel --help <INPUT> <OUTPUT>"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
             fi
             case "${prev}" in
                 --compression)
-                    COMPREPLY=($(compgen -W "lossless uncompressed" -- "${cur}"))
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
                     return 0
                     ;;
                 -c)
-                    COMPREPLY=($(compgen -W "lossless uncompressed" -- "${cur}"))
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
                     return 0
                     ;;
                 --ljpeg92-predictor)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --deflate-predictor)
+                    COMPREPLY=($(compgen -W "none horizontal horizontal2 horizontal4" -- "${cur}"))
+                    return 0
+                    ;;
+                --tile-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --dng-version)
+                    COMPREPLY=($(compgen -W "1.0 1.1 1.2 1.3 1.4 1.5 1.6" -- "${cur}"))
+                    return 0
+                    ;;
                 --dng-preview)
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
@@ -175,6 +313,26 @@ _dnglab() {
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
                     ;;
+                --preview-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --preview-filter)
+                    COMPREPLY=($(compgen -W "nearest triangle catmullrom lanczos3" -- "${cur}"))
+                    return 0
+                    ;;
+                --preview-quality)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --preview-source)
+                    COMPREPLY=($(compgen -W "camera raw" -- "${cur}"))
+                    return 0
+                    ;;
+                --thumbnail-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --embed-raw)
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
@@ -195,6 +353,10 @@ _dnglab() {
                     COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
                     return 0
                     ;;
+                --profile)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --jobs)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
@@ -218,8 +380,88 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__diff)
+            opts="-f -d -v -h --heatmap --json --yaml --override --loglevel --help <A> <B>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --heatmap)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        dnglab__edit)
+            opts="-r -d -v -h --artist --copyright --description --orientation --gps --remove-gps --as-shot-neutral --xmp --profile --recursive --loglevel --help <INPUT>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --artist)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --copyright)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --description)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --orientation)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --gps)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --as-shot-neutral)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --xmp)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --profile)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__extract)
-            opts="-r -f -d -v -h --skipchecks --recursive --override --loglevel --help <INPUT> <OUTPUT>"
+            opts="-r -f -d -v -h --skipchecks --auxiliary --recursive --override --loglevel --help <INPUT> <OUTPUT>"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
@@ -241,24 +483,36 @@ _dnglab() {
             return 0
             ;;
         dnglab__ftpserver)
-            opts="-c -f -d -v -h --compression --ljpeg92-predictor --dng-preview --dng-thumbnail --embed-raw --artist --keep-mtime --image-index --crop --override --port --listen --keep-original --loglevel --help <OUTPUT>"
+            opts="-c -f -d -v -h --compression --ljpeg92-predictor --deflate-predictor --tile-size --dng-version --dng-preview --dng-thumbnail --preview-size --preview-filter --preview-quality --preview-source --thumbnail-size --embed-raw --artist --keep-mtime --image-index --crop --profile --override --port --listen --keep-original --loglevel --help <OUTPUT>"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
             fi
             case "${prev}" in
                 --compression)
-                    COMPREPLY=($(compgen -W "lossless uncompressed" -- "${cur}"))
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
                     return 0
                     ;;
                 -c)
-                    COMPREPLY=($(compgen -W "lossless uncompressed" -- "${cur}"))
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
                     return 0
                     ;;
                 --ljpeg92-predictor)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
                     ;;
+                --deflate-predictor)
+                    COMPREPLY=($(compgen -W "none horizontal horizontal2 horizontal4" -- "${cur}"))
+                    return 0
+                    ;;
+                --tile-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --dng-version)
+                    COMPREPLY=($(compgen -W "1.0 1.1 1.2 1.3 1.4 1.5 1.6" -- "${cur}"))
+                    return 0
+                    ;;
                 --dng-preview)
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
@@ -267,6 +521,26 @@ _dnglab() {
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
                     ;;
+                --preview-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --preview-filter)
+                    COMPREPLY=($(compgen -W "nearest triangle catmullrom lanczos3" -- "${cur}"))
+                    return 0
+                    ;;
+                --preview-quality)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --preview-source)
+                    COMPREPLY=($(compgen -W "camera raw" -- "${cur}"))
+                    return 0
+                    ;;
+                --thumbnail-size)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --embed-raw)
                     COMPREPLY=($(compgen -W "true false" -- "${cur}"))
                     return 0
@@ -287,6 +561,10 @@ _dnglab() {
                     COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
                     return 0
                     ;;
+                --profile)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --port)
                     COMPREPLY=($(compgen -f "${cur}"))
                     return 0
@@ -336,8 +614,66 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__hdrmerge)
+            opts="-o -i -f -d -v -h --output --input --bits --dng-preview --dng-thumbnail --artist --crop --override --loglevel --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --output)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -o)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -i)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --bits)
+                    COMPREPLY=($(compgen -W "16 24 32" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-preview)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-thumbnail)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --artist)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --crop)
+                    COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
+                    return 0
+                    ;;
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__help)
-            opts="analyze process-raw convert ftpserver cameras lenses makedng gui extract help"
+            opts="analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
@@ -364,6 +700,20 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__help__calibrate)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__help__cameras)
             opts=""
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
@@ -392,6 +742,34 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__help__diff)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        dnglab__help__edit)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__help__extract)
             opts=""
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
@@ -434,6 +812,20 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__help__hdrmerge)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__help__help)
             opts=""
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
@@ -476,6 +868,20 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__help__pixelshift)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__help__process__raw)
             opts=""
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
@@ -490,6 +896,20 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__help__profile)
+            opts=""
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 3 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__lenses)
             opts="-d -v -h --md --loglevel --help"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
@@ -614,8 +1034,90 @@ _dnglab() {
             COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
             return 0
             ;;
+        dnglab__pixelshift)
+            opts="-o -i -c -f -d -v -h --output --input --pattern --motion-correction --motion-threshold --compression --ljpeg92-predictor --deflate-predictor --dng-preview --dng-thumbnail --artist --crop --override --loglevel --help"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --output)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -o)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --input)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                -i)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --pattern)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --motion-correction)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --motion-threshold)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --compression)
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
+                    return 0
+                    ;;
+                -c)
+                    COMPREPLY=($(compgen -W "lossless uncompressed deflate" -- "${cur}"))
+                    return 0
+                    ;;
+                --ljpeg92-predictor)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --deflate-predictor)
+                    COMPREPLY=($(compgen -W "none horizontal horizontal2 horizontal4" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-preview)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --dng-thumbnail)
+                    COMPREPLY=($(compgen -W "true false" -- "${cur}"))
+                    return 0
+                    ;;
+                --artist)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --crop)
+                    COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
+                    return 0
+                    ;;
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
         dnglab__process__raw)
-            opts="-f -r -d -v -h --artist --keep-mtime --image-index --crop --override --recursive --loglevel --help <INPUT> <OUTPUT>"
+            opts="-f -r -d -v -h --artist --keep-mtime --image-index --crop --profile --override --recursive --loglevel --help <INPUT> <OUTPUT>"
             if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
                 COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
                 return 0
@@ -637,6 +1139,52 @@ _dnglab() {
                     COMPREPLY=($(compgen -W "best activearea none" -- "${cur}"))
                     return 0
                     ;;
+                --profile)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --loglevel)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                -d)
+                    COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
+                    return 0
+                    ;;
+                *)
+                    COMPREPLY=()
+                    ;;
+            esac
+            COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+            return 0
+            ;;
+        dnglab__profile)
+            opts="-f -d -v -h --chart --reference --corners --illuminant --hue-sat-map --name --override --loglevel --help <INPUT> <OUTPUT>"
+            if [[ ${cur} == -* || ${COMP_CWORD} -eq 2 ]] ; then
+                COMPREPLY=( $(compgen -W "${opts}" -- "${cur}") )
+                return 0
+            fi
+            case "${prev}" in
+                --chart)
+                    COMPREPLY=($(compgen -W "colorchecker24 colorchecker-sg" -- "${cur}"))
+                    return 0
+                    ;;
+                --reference)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --corners)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --illuminant)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
+                --name)
+                    COMPREPLY=($(compgen -f "${cur}"))
+                    return 0
+                    ;;
                 --loglevel)
                     COMPREPLY=($(compgen -W "error warn info debug trace" -- "${cur}"))
                     return 0
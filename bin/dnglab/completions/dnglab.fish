@@ -39,8 +39,14 @@ complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "ftpserver" -d 'Conver
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "cameras" -d 'List supported cameras'
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "lenses" -d 'List supported lenses'
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "makedng" -d 'Lowlevel command to make a DNG file'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "pixelshift" -d 'Merge pixel-shift frames into a single DNG'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "hdrmerge" -d 'Merge an exposure bracket into a floating point DNG'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "calibrate" -d 'Calibrate raw files with bias, dark and flat frames'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "profile" -d 'Create a camera profile from a color target shot'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "diff" -d 'Compare raw data and metadata of two raw or DNG files'
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "gui" -d 'Start GUI (not implemented)'
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "extract" -d 'Extract embedded original Raw from DNG'
+complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "edit" -d 'Edit metadata of DNG file(s) in place'
 complete -c dnglab -n "__fish_dnglab_needs_command" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
 warn\t''
@@ -58,6 +64,7 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l thumbnail-chec
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l srgb -d 'Write sRGB 16-bit TIFF to STDOUT'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l meta -d 'Write metadata to STDOUT'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l structure -d 'Write file structure to STDOUT'
+complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l stats -d 'Write raw pixel statistics (histograms, black level, noise, clipping, exposure) to STDOUT'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l summary -d 'Write summary information for file to STDOUT'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l json -d 'Format metadata as JSON'
 complete -c dnglab -n "__fish_dnglab_using_subcommand analyze" -l yaml -d 'Format metadata as YAML'
@@ -70,6 +77,7 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -l image-inde
 complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -l crop -d 'DNG default crop' -r -f -a "best\t''
 activearea\t''
 none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -l profile -d 'Use DNG camera profile (DCP) for color rendering' -r -F
 complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
 warn\t''
 info\t''
@@ -80,12 +88,34 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -s r -l recur
 complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -s v -d 'Print status for every file'
 complete -c dnglab -n "__fish_dnglab_using_subcommand process-raw" -s h -l help -d 'Print help'
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s c -l compression -d 'Compression for raw image' -r -f -a "lossless\t''
-uncompressed\t''"
+uncompressed\t''
+deflate\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l ljpeg92-predictor -d 'LJPEG-92 predictor' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l deflate-predictor -d 'Deflate predictor' -r -f -a "none\t''
+horizontal\t''
+horizontal2\t''
+horizontal4\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l tile-size -d 'Tile width and height for compressed raw images, a multiple of 16' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l dng-version -d 'DNG specification version' -r -f -a "1.0\t''
+1.1\t''
+1.2\t''
+1.3\t''
+1.4\t''
+1.5\t''
+1.6\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l dng-preview -d 'DNG include preview image' -r -f -a "true\t''
 false\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l dng-thumbnail -d 'DNG include thumbnail image' -r -f -a "true\t''
 false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l preview-size -d 'Maximum preview width or height in pixels, or \'full\'' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l preview-filter -d 'Filter for preview and thumbnail downscaling' -r -f -a "nearest\t''
+triangle\t''
+catmullrom\t''
+lanczos3\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l preview-quality -d 'JPEG quality of preview image' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l preview-source -d 'Use camera preview or render preview from raw' -r -f -a "camera\t''
+raw\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l thumbnail-size -d 'Maximum thumbnail width or height in pixels' -r
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l embed-raw -d 'Embed the raw file into DNG' -r -f -a "true\t''
 false\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l artist -d 'Set the artist tag' -r
@@ -95,6 +125,7 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l image-index -d
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l crop -d 'DNG default crop' -r -f -a "best\t''
 activearea\t''
 none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -l profile -d 'Embed DNG camera profile (DCP) instead of built-in color matrix' -r -F
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s j -l jobs -d 'Number of files to convert in parallel (0 = auto)' -r
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
 warn\t''
@@ -106,12 +137,34 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s r -l recursive
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s v -d 'Print status for every file'
 complete -c dnglab -n "__fish_dnglab_using_subcommand convert" -s h -l help -d 'Print help'
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -s c -l compression -d 'Compression for raw image' -r -f -a "lossless\t''
-uncompressed\t''"
+uncompressed\t''
+deflate\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l ljpeg92-predictor -d 'LJPEG-92 predictor' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l deflate-predictor -d 'Deflate predictor' -r -f -a "none\t''
+horizontal\t''
+horizontal2\t''
+horizontal4\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l tile-size -d 'Tile width and height for compressed raw images, a multiple of 16' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l dng-version -d 'DNG specification version' -r -f -a "1.0\t''
+1.1\t''
+1.2\t''
+1.3\t''
+1.4\t''
+1.5\t''
+1.6\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l dng-preview -d 'DNG include preview image' -r -f -a "true\t''
 false\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l dng-thumbnail -d 'DNG include thumbnail image' -r -f -a "true\t''
 false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l preview-size -d 'Maximum preview width or height in pixels, or \'full\'' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l preview-filter -d 'Filter for preview and thumbnail downscaling' -r -f -a "nearest\t''
+triangle\t''
+catmullrom\t''
+lanczos3\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l preview-quality -d 'JPEG quality of preview image' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l preview-source -d 'Use camera preview or render preview from raw' -r -f -a "camera\t''
+raw\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l thumbnail-size -d 'Maximum thumbnail width or height in pixels' -r
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l embed-raw -d 'Embed the raw file into DNG' -r -f -a "true\t''
 false\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l artist -d 'Set the artist tag' -r
@@ -121,6 +174,7 @@ complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l image-index
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l crop -d 'DNG default crop' -r -f -a "best\t''
 activearea\t''
 none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l profile -d 'Embed DNG camera profile (DCP) instead of built-in color matrix' -r -F
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l port -d 'FTP listen port' -r
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l listen -d 'FTP listen address' -r
 complete -c dnglab -n "__fish_dnglab_using_subcommand ftpserver" -l keep-original -d 'Keep original raw' -r -f -a "true\t''
@@ -237,6 +291,116 @@ trace\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand makedng" -s f -l override -d 'Override existing files'
 complete -c dnglab -n "__fish_dnglab_using_subcommand makedng" -s v -d 'Print status for every file'
 complete -c dnglab -n "__fish_dnglab_using_subcommand makedng" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s o -l output -d 'Output DNG file path' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s i -l input -d 'Input files, all raw images of each file are used as frames' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l pattern -d 'Sensor shift for each frame as \'dx,dy;dx,dy;...\'' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l motion-correction -d 'Use reference frame for pixels with motion' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l motion-threshold -d 'Relative difference between frames detected as motion' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s c -l compression -d 'Compression for raw image' -r -f -a "lossless\t''
+uncompressed\t''
+deflate\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l ljpeg92-predictor -d 'LJPEG-92 predictor' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l deflate-predictor -d 'Deflate predictor' -r -f -a "none\t''
+horizontal\t''
+horizontal2\t''
+horizontal4\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l dng-preview -d 'DNG include preview image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l dng-thumbnail -d 'DNG include thumbnail image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l artist -d 'Set the artist tag' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -l crop -d 'DNG default crop' -r -f -a "best\t''
+activearea\t''
+none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s f -l override -d 'Override existing files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand pixelshift" -s h -l help -d 'Print help (see more with \'--help\')'
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s o -l output -d 'Output DNG file path' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s i -l input -d 'Input raw files of the bracket' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -l bits -d 'Bits per sample for floating point data' -r -f -a "16\t''
+24\t''
+32\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -l dng-preview -d 'DNG include preview image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -l dng-thumbnail -d 'DNG include thumbnail image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -l artist -d 'Set the artist tag' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -l crop -d 'DNG default crop' -r -f -a "best\t''
+activearea\t''
+none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s f -l override -d 'Override existing files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand hdrmerge" -s h -l help -d 'Print help'
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s o -l output -d 'Output directory for calibrated DNG files' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s i -l input -d 'Input raw files (lights)' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l bias -d 'Bias frames' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l dark -d 'Dark frames, matching exposure time of lights' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l flat -d 'Flat frames' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l method -d 'Method to stack master frames' -r -f -a "median\t''
+sigma-clip\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l sigma -d 'Rejection threshold for sigma clipping and hot pixels' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s c -l compression -d 'Compression for raw image' -r -f -a "lossless\t''
+uncompressed\t''
+deflate\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l ljpeg92-predictor -d 'LJPEG-92 predictor' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l deflate-predictor -d 'Deflate predictor' -r -f -a "none\t''
+horizontal\t''
+horizontal2\t''
+horizontal4\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l dng-preview -d 'DNG include preview image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l dng-thumbnail -d 'DNG include thumbnail image' -r -f -a "true\t''
+false\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l artist -d 'Set the artist tag' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l crop -d 'DNG default crop' -r -f -a "best\t''
+activearea\t''
+none\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -l hot-pixels -d 'Detect hot pixels in master dark and replace them'
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s f -l override -d 'Override existing files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand calibrate" -s h -l help -d 'Print help'
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l chart -d 'Color target in the image' -r -f -a "colorchecker24\t''
+colorchecker-sg\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l reference -d 'Reference values of the target (CGATS), required for ColorChecker SG' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l corners -d 'Centers of the corner patches: x1,y1,...,x4,y4 (top-left, top-right, bottom-right, bottom-left)' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l illuminant -d 'Illuminant of the shot, like D65 or A (default: from EXIF, fallback to D65)' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l name -d 'Profile name' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -l hue-sat-map -d 'Add a hue/saturation map to correct the matrix'
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -s f -l override -d 'Override existing files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand profile" -s h -l help -d 'Print help'
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -l heatmap -d 'Write heat map of pixel differences to image file' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -l json -d 'Format result as JSON'
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -l yaml -d 'Format result as YAML'
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -s f -l override -d 'Override existing files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand diff" -s h -l help -d 'Print help'
 complete -c dnglab -n "__fish_dnglab_using_subcommand gui" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
 warn\t''
 info\t''
@@ -250,17 +414,41 @@ info\t''
 debug\t''
 trace\t''"
 complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -l skipchecks -d 'Skip integrity checks'
+complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -l auxiliary -d 'Extract depth map and semantic masks as grayscale PNG instead of the original raw'
 complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -s r -l recursive -d 'Process input directory recursive'
 complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -s f -l override -d 'Override existing files'
 complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -s v -d 'Print status for every file'
 complete -c dnglab -n "__fish_dnglab_using_subcommand extract" -s h -l help -d 'Print help'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "analyze" -d 'Analyze raw image'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "process-raw"
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "convert" -d 'Convert raw image(s) into dng format'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "ftpserver" -d 'Convert raw image(s) into dng format'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "cameras" -d 'List supported cameras'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "lenses" -d 'List supported lenses'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "makedng" -d 'Lowlevel command to make a DNG file'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "gui" -d 'Start GUI (not implemented)'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "extract" -d 'Extract embedded original Raw from DNG'
-complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng gui extract help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l artist -d 'Set the artist tag, empty value removes it' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l copyright -d 'Set the copyright tag, empty value removes it' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l description -d 'Set the image description tag, empty value removes it' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l orientation -d 'Set the orientation (EXIF value 1-8)' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l gps -d 'Set GPS position in decimal degrees: latitude,longitude[,altitude]' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l as-shot-neutral -d 'Set white balance as neutral values, one per color plane: r,g,b' -r
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l xmp -d 'Replace XMP packet by file content' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l profile -d 'Embed DNG camera profile (DCP), replacing the existing color matrices' -r -F
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -s d -l loglevel -d 'Log level' -r -f -a "error\t''
+warn\t''
+info\t''
+debug\t''
+trace\t''"
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -l remove-gps -d 'Remove GPS information'
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -s r -l recursive -d 'Process input directory recursive'
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -s v -d 'Print status for every file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand edit" -s h -l help -d 'Print help'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "analyze" -d 'Analyze raw image'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "process-raw"
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "convert" -d 'Convert raw image(s) into dng format'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "ftpserver" -d 'Convert raw image(s) into dng format'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "cameras" -d 'List supported cameras'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "lenses" -d 'List supported lenses'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "makedng" -d 'Lowlevel command to make a DNG file'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "pixelshift" -d 'Merge pixel-shift frames into a single DNG'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "hdrmerge" -d 'Merge an exposure bracket into a floating point DNG'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "calibrate" -d 'Calibrate raw files with bias, dark and flat frames'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "profile" -d 'Create a camera profile from a color target shot'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "diff" -d 'Compare raw data and metadata of two raw or DNG files'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "gui" -d 'Start GUI (not implemented)'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "extract" -d 'Extract embedded original Raw from DNG'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "edit" -d 'Edit metadata of DNG file(s) in place'
+complete -c dnglab -n "__fish_dnglab_using_subcommand help; and not __fish_seen_subcommand_from analyze process-raw convert ftpserver cameras lenses makedng pixelshift hdrmerge calibrate profile diff gui extract edit help" -f -a "help" -d 'Print this message or the help of the given subcommand(s)'
@@ -4,11 +4,12 @@
 use clap::ArgMatches;
 use log::debug;
 use rawler::analyze::{
-  analyze_file_structure, analyze_metadata, extract_full_pixels, extract_preview_pixels, extract_raw_pixels, extract_thumbnail_pixels, full_image_digest,
-  preview_digest, raw_as_pgm, raw_pixels_digest, rgb8_as_ppm8, thumbnail_digest,
+  analyze_file_structure, analyze_metadata, analyze_statistics, analyze_summary, extract_full_pixels, extract_preview_pixels, extract_raw_pixels,
+  extract_thumbnail_pixels, full_image_digest, preview_digest, raw_as_pgm, raw_pixels_digest, rgb8_as_ppm8, thumbnail_digest,
 };
 use rawler::analyze::{raw_as_ppm16, raw_to_srgb};
 use rawler::decoders::RawDecodeParams;
+use rawler::probe::ProbeParams;
 use serde::Serialize;
 use std::{
   io::{BufWriter, Write},
@@ -40,6 +41,12 @@ pub async fn analyze(options: &ArgMatches) -> crate::Result<()> {
   } else if options.get_flag("structure") {
     let analyze = analyze_file_structure(PathBuf::from(in_file))?;
     print_output(&analyze, options)?;
+  } else if options.get_flag("stats") {
+    let analyze = analyze_statistics(PathBuf::from(in_file), &RawDecodeParams::default())?;
+    print_output(&analyze, options)?;
+  } else if options.get_flag("summary") {
+    let analyze = analyze_summary(PathBuf::from(in_file), &ProbeParams::default())?;
+    print_output(&analyze, options)?;
   } else if options.get_flag("raw_checksum") {
     let digest = raw_pixels_digest(PathBuf::from(in_file), &RawDecodeParams::default())?;
     println!("{}", hex::encode(digest));
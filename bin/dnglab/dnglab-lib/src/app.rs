@@ -9,7 +9,13 @@ use clap::{
   command, value_parser,
 };
 use log::debug;
-use rawler::dng::{CropMode, DngCompression};
+use rawler::{
+  compose::pixelshift::ShiftPattern,
+  dng::{
+    CropMode, DeflatePredictor, DngCompression,
+    preview::{PreviewFilter, PreviewSource},
+  },
+};
 
 use crate::makedng::{
   CalibrationIlluminantArgParser, ColorMatrixArgParser, DngColorimetricReference, DngVersion, InputSourceUsageMap, LinearizationTableArgParser,
@@ -58,6 +64,24 @@ pub fn create_app() -> Command {
         .value_parser(clap::value_parser!(u8).range(1..=7))
         .default_value("1"),
     )
+    .arg(
+      arg!(deflate_predictor: --"deflate-predictor" <predictor> "Deflate predictor")
+        .required(false)
+        .value_parser(value_parser!(DeflatePredictor))
+        .default_value("horizontal"),
+    )
+    .arg(
+      arg!(tile_size: --"tile-size" <pixels> "Tile width and height for compressed raw images, a multiple of 16")
+        .required(false)
+        .value_parser(clap::value_parser!(u16).range(16..))
+        .default_value("256"),
+    )
+    .arg(
+      arg!(dng_version: --"dng-version" <version> "DNG specification version")
+        .required(false)
+        .value_parser(value_parser!(DngVersion))
+        .default_value("1.6"),
+    )
     .arg(
       arg!(preview: --"dng-preview" <preview> "DNG include preview image")
         .value_parser(ValueParser::bool())
@@ -72,6 +96,36 @@ pub fn create_app() -> Command {
         .default_value("true")
         .default_missing_value("true"),
     )
+    .arg(
+      arg!(preview_size: --"preview-size" <size> "Maximum preview width or height in pixels, or 'full'")
+        .required(false)
+        .value_parser(NonEmptyStringValueParser::new())
+        .default_value("1024"),
+    )
+    .arg(
+      arg!(preview_filter: --"preview-filter" <filter> "Filter for preview and thumbnail downscaling")
+        .required(false)
+        .value_parser(value_parser!(PreviewFilter))
+        .default_value("catmullrom"),
+    )
+    .arg(
+      arg!(preview_quality: --"preview-quality" <quality> "JPEG quality of preview image")
+        .required(false)
+        .value_parser(clap::value_parser!(u8).range(1..=100))
+        .default_value("75"),
+    )
+    .arg(
+      arg!(preview_source: --"preview-source" <source> "Use camera preview or render preview from raw")
+        .required(false)
+        .value_parser(value_parser!(PreviewSource))
+        .default_value("camera"),
+    )
+    .arg(
+      arg!(thumbnail_size: --"thumbnail-size" <size> "Maximum thumbnail width or height in pixels")
+        .required(false)
+        .value_parser(clap::value_parser!(u32).range(16..=1024))
+        .default_value("256"),
+    )
     .arg(
       arg!(embedded: --"embed-raw" <embedded> "Embed the raw file into DNG")
         .value_parser(ValueParser::bool())
@@ -102,6 +156,11 @@ pub fn create_app() -> Command {
         .value_parser(value_parser!(CropMode))
         .default_value("best"),
     )
+    .arg(
+      arg!(--"profile" <file> "Embed DNG camera profile (DCP) instead of built-in color matrix")
+        .required(false)
+        .value_parser(clap::value_parser!(PathBuf)),
+    )
     .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue));
 
   command!()
@@ -131,6 +190,7 @@ pub fn create_app() -> Command {
         .arg(arg!(srgb: --srgb "Write sRGB 16-bit TIFF to STDOUT").action(ArgAction::SetTrue))
         .arg(arg!(meta: --meta "Write metadata to STDOUT").action(ArgAction::SetTrue))
         .arg(arg!(structure: --structure "Write file structure to STDOUT").action(ArgAction::SetTrue))
+        .arg(arg!(stats: --stats "Write raw pixel statistics (histograms, black level, noise, clipping, exposure) to STDOUT").action(ArgAction::SetTrue))
         .arg(arg!(summary: --summary "Write summary information for file to STDOUT").action(ArgAction::SetTrue))
         .arg(arg!(json: --json "Format metadata as JSON").action(ArgAction::SetTrue))
         .arg(arg!(yaml: --yaml "Format metadata as YAML").action(ArgAction::SetTrue))
@@ -161,6 +221,11 @@ pub fn create_app() -> Command {
             .value_parser(value_parser!(CropMode))
             .default_value("best"),
         )
+        .arg(
+          arg!(--"profile" <file> "Use DNG camera profile (DCP) for color rendering")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
         .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue))
         .arg(arg!(-r --recursive "Process input directory recursive").action(ArgAction::SetTrue))
         .arg(arg!(<INPUT> "Input file or directory").value_parser(clap::value_parser!(PathBuf)))
@@ -330,15 +395,328 @@ Input files which are not mapped are ignored.",
         )
         .arg(arg!(-f --override "Override existing files")),
     )
+    .subcommand(
+      Command::new("pixelshift")
+        .about("Merge pixel-shift frames into a single DNG")
+        .arg_required_else_help(true)
+        .arg(arg!(OUTPUT: -o --"output" <OUTPUT> "Output DNG file path").value_parser(clap::value_parser!(PathBuf)))
+        .arg(
+          arg!(inputs: -i --"input" <INPUT> "Input files, all raw images of each file are used as frames")
+            .required(true)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(1..),
+        )
+        .arg(
+          arg!(pattern: --"pattern" <pattern> "Sensor shift for each frame as 'dx,dy;dx,dy;...'")
+            .required(false)
+            .long_help(
+              "Sensor shift in pixels for each frame in capture order, separated by ';'.
+Half pixel offsets like '0.5,0' give an image with twice the width and height.
+Frames are sorted by the shot number recorded by the camera.
+If not given, the pattern from the camera definition is used, or detected
+from image data for four-shot sequences.",
+            )
+            .value_parser(value_parser!(ShiftPattern)),
+        )
+        .arg(
+          arg!(motion_correction: --"motion-correction" <enabled> "Use reference frame for pixels with motion")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(motion_threshold: --"motion-threshold" <threshold> "Relative difference between frames detected as motion")
+            .required(false)
+            .value_parser(clap::value_parser!(f32))
+            .default_value("0.15"),
+        )
+        .arg(
+          arg!(compression: -c --"compression" <compression> "Compression for raw image")
+            .required(false)
+            .value_parser(value_parser!(DngCompression))
+            .default_value("lossless"),
+        )
+        .arg(
+          arg!(predictor: --"ljpeg92-predictor" <predictor> "LJPEG-92 predictor")
+            .required(false)
+            .value_parser(clap::value_parser!(u8).range(1..=7))
+            .default_value("1"),
+        )
+        .arg(
+          arg!(deflate_predictor: --"deflate-predictor" <predictor> "Deflate predictor")
+            .required(false)
+            .value_parser(value_parser!(DeflatePredictor))
+            .default_value("horizontal"),
+        )
+        .arg(
+          arg!(preview: --"dng-preview" <preview> "DNG include preview image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(thumbnail: --"dng-thumbnail" <thumbnail> "DNG include thumbnail image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(--"artist" <artist> "Set the artist tag")
+            .required(false)
+            .value_parser(NonEmptyStringValueParser::new()),
+        )
+        .arg(
+          arg!(--"crop" <crop> "DNG default crop")
+            .required(false)
+            .value_parser(value_parser!(CropMode))
+            .default_value("best"),
+        )
+        .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue)),
+    )
+    .subcommand(
+      Command::new("hdrmerge")
+        .about("Merge an exposure bracket into a floating point DNG")
+        .arg_required_else_help(true)
+        .arg(arg!(OUTPUT: -o --"output" <OUTPUT> "Output DNG file path").value_parser(clap::value_parser!(PathBuf)))
+        .arg(
+          arg!(inputs: -i --"input" <INPUT> "Input raw files of the bracket")
+            .required(true)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(2..),
+        )
+        .arg(
+          arg!(bits: --"bits" <bits> "Bits per sample for floating point data")
+            .required(false)
+            .value_parser(["16", "24", "32"])
+            .default_value("16"),
+        )
+        .arg(
+          arg!(preview: --"dng-preview" <preview> "DNG include preview image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(thumbnail: --"dng-thumbnail" <thumbnail> "DNG include thumbnail image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(--"artist" <artist> "Set the artist tag")
+            .required(false)
+            .value_parser(NonEmptyStringValueParser::new()),
+        )
+        .arg(
+          arg!(--"crop" <crop> "DNG default crop")
+            .required(false)
+            .value_parser(value_parser!(CropMode))
+            .default_value("best"),
+        )
+        .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue)),
+    )
+    .subcommand(
+      Command::new("calibrate")
+        .about("Calibrate raw files with bias, dark and flat frames")
+        .arg_required_else_help(true)
+        .arg(
+          arg!(OUTPUT: -o --"output" <OUTPUT> "Output directory for calibrated DNG files")
+            .required(true)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+          arg!(inputs: -i --"input" <INPUT> "Input raw files (lights)")
+            .required(true)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(1..),
+        )
+        .arg(
+          arg!(bias: --"bias" <BIAS> "Bias frames")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(1..),
+        )
+        .arg(
+          arg!(dark: --"dark" <DARK> "Dark frames, matching exposure time of lights")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(1..),
+        )
+        .arg(
+          arg!(flat: --"flat" <FLAT> "Flat frames")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf))
+            .num_args(1..),
+        )
+        .arg(
+          arg!(method: --"method" <method> "Method to stack master frames")
+            .required(false)
+            .value_parser(["median", "sigma-clip"])
+            .default_value("sigma-clip"),
+        )
+        .arg(
+          arg!(sigma: --"sigma" <sigma> "Rejection threshold for sigma clipping and hot pixels")
+            .required(false)
+            .value_parser(clap::value_parser!(f32))
+            .default_value("3.0"),
+        )
+        .arg(arg!(hot_pixels: --"hot-pixels" "Detect hot pixels in master dark and replace them").action(ArgAction::SetTrue))
+        .arg(
+          arg!(compression: -c --"compression" <compression> "Compression for raw image")
+            .required(false)
+            .value_parser(value_parser!(DngCompression))
+            .default_value("lossless"),
+        )
+        .arg(
+          arg!(predictor: --"ljpeg92-predictor" <predictor> "LJPEG-92 predictor")
+            .required(false)
+            .value_parser(clap::value_parser!(u8).range(1..=7))
+            .default_value("1"),
+        )
+        .arg(
+          arg!(deflate_predictor: --"deflate-predictor" <predictor> "Deflate predictor")
+            .required(false)
+            .value_parser(value_parser!(DeflatePredictor))
+            .default_value("horizontal"),
+        )
+        .arg(
+          arg!(preview: --"dng-preview" <preview> "DNG include preview image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(thumbnail: --"dng-thumbnail" <thumbnail> "DNG include thumbnail image")
+            .value_parser(ValueParser::bool())
+            .required(false)
+            .default_value("true")
+            .default_missing_value("true"),
+        )
+        .arg(
+          arg!(--"artist" <artist> "Set the artist tag")
+            .required(false)
+            .value_parser(NonEmptyStringValueParser::new()),
+        )
+        .arg(
+          arg!(--"crop" <crop> "DNG default crop")
+            .required(false)
+            .value_parser(value_parser!(CropMode))
+            .default_value("best"),
+        )
+        .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue)),
+    )
+    .subcommand(
+      Command::new("profile")
+        .about("Create a camera profile from a color target shot")
+        .arg_required_else_help(true)
+        .arg(
+          arg!(chart: --"chart" <chart> "Color target in the image")
+            .required(false)
+            .value_parser(["colorchecker24", "colorchecker-sg"])
+            .default_value("colorchecker24"),
+        )
+        .arg(
+          arg!(reference: --"reference" <file> "Reference values of the target (CGATS), required for ColorChecker SG")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+          arg!(corners: --"corners" <corners> "Centers of the corner patches: x1,y1,...,x4,y4 (top-left, top-right, bottom-right, bottom-left)")
+            .required(false)
+            .value_parser(clap::value_parser!(f32))
+            .value_delimiter(',')
+            .num_args(8),
+        )
+        .arg(
+          arg!(illuminant: --"illuminant" <illuminant> "Illuminant of the shot, like D65 or A (default: from EXIF, fallback to D65)")
+            .required(false)
+            .value_parser(NonEmptyStringValueParser::new()),
+        )
+        .arg(arg!(hue_sat_map: --"hue-sat-map" "Add a hue/saturation map to correct the matrix").action(ArgAction::SetTrue))
+        .arg(
+          arg!(name: --"name" <name> "Profile name")
+            .required(false)
+            .value_parser(NonEmptyStringValueParser::new()),
+        )
+        .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue))
+        .arg(arg!(<INPUT> "Raw file of the color target").value_parser(clap::value_parser!(PathBuf)))
+        .arg(arg!(<OUTPUT> "Output file, camera profile (.dcp) or color matrix for camera definitions (.toml)").value_parser(clap::value_parser!(PathBuf))),
+    )
+    .subcommand(
+      Command::new("diff")
+        .about("Compare raw data and metadata of two raw or DNG files")
+        .arg(
+          arg!(heatmap: --"heatmap" <file> "Write heat map of pixel differences to image file")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(json: --json "Format result as JSON").action(ArgAction::SetTrue))
+        .arg(arg!(yaml: --yaml "Format result as YAML").action(ArgAction::SetTrue))
+        .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue))
+        .arg(arg!(<A> "First raw file").value_parser(clap::value_parser!(PathBuf)))
+        .arg(arg!(<B> "Second raw file").value_parser(clap::value_parser!(PathBuf))),
+    )
     .subcommand(Command::new("gui").about("Start GUI (not implemented)").arg_required_else_help(false))
     .subcommand(
       Command::new("extract")
         .about("Extract embedded original Raw from DNG")
         .arg_required_else_help(true)
         .arg(arg!(skipchecks: --skipchecks "Skip integrity checks").action(ArgAction::SetTrue))
+        .arg(arg!(auxiliary: --auxiliary "Extract depth map and semantic masks as grayscale PNG instead of the original raw").action(ArgAction::SetTrue))
         .arg(arg!(-r --recursive "Process input directory recursive").action(ArgAction::SetTrue))
         .arg(arg!(-f --override "Override existing files").action(ArgAction::SetTrue))
         .arg(arg!(<INPUT> "Input file or directory").value_parser(clap::value_parser!(PathBuf)))
         .arg(arg!(<OUTPUT> "Output file or existing directory").value_parser(clap::value_parser!(PathBuf))),
     )
+    .subcommand(
+      Command::new("edit")
+        .about("Edit metadata of DNG file(s) in place")
+        .arg_required_else_help(true)
+        .arg(arg!(--"artist" <artist> "Set the artist tag, empty value removes it").required(false))
+        .arg(arg!(--"copyright" <copyright> "Set the copyright tag, empty value removes it").required(false))
+        .arg(arg!(description: --"description" <description> "Set the image description tag, empty value removes it").required(false))
+        .arg(
+          arg!(--"orientation" <orientation> "Set the orientation (EXIF value 1-8)")
+            .required(false)
+            .value_parser(clap::value_parser!(u16).range(1..=8)),
+        )
+        .arg(
+          arg!(--"gps" <position> "Set GPS position in decimal degrees: latitude,longitude[,altitude]")
+            .required(false)
+            .allow_hyphen_values(true)
+            .value_parser(clap::value_parser!(f64))
+            .value_delimiter(',')
+            .num_args(1),
+        )
+        .arg(
+          arg!(remove_gps: --"remove-gps" "Remove GPS information")
+            .action(ArgAction::SetTrue)
+            .conflicts_with("gps"),
+        )
+        .arg(
+          arg!(as_shot_neutral: --"as-shot-neutral" <neutral> "Set white balance as neutral values, one per color plane: r,g,b")
+            .required(false)
+            .value_parser(clap::value_parser!(f32))
+            .value_delimiter(',')
+            .num_args(1),
+        )
+        .arg(
+          arg!(--"xmp" <file> "Replace XMP packet by file content")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(
+          arg!(--"profile" <file> "Embed DNG camera profile (DCP), replacing the existing color matrices")
+            .required(false)
+            .value_parser(clap::value_parser!(PathBuf)),
+        )
+        .arg(arg!(-r --recursive "Process input directory recursive").action(ArgAction::SetTrue))
+        .arg(arg!(<INPUT> "Input file or directory").value_parser(clap::value_parser!(PathBuf))),
+    )
 }
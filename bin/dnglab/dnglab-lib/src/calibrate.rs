@@ -0,0 +1,130 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::RawImage;
+use rawler::calibration::{Calibration, HotPixelMap, MasterFrame, StackMethod};
+use rawler::compose::{load_frame, load_frames};
+use rawler::dng::convert::{ConvertParams, convert_raw_image};
+use std::fs::{File, remove_file};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::{AppError, PKG_VERSION};
+
+/// Entry point for Clap sub command `calibrate`
+pub async fn calibrate(options: &ArgMatches) -> crate::Result<()> {
+  let now = Instant::now();
+
+  let output_dir: &PathBuf = options
+    .get_one("OUTPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("Output directory is required".into()))?;
+  if !output_dir.is_dir() {
+    return Err(AppError::NotFound(output_dir.to_owned()));
+  }
+
+  let inputs: Vec<&PathBuf> = options
+    .get_many("inputs")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("inputs are required".into()))?
+    .collect();
+
+  // Fail early, before master frames are stacked
+  let outputs = inputs
+    .iter()
+    .map(|input| {
+      let stem = input
+        .file_stem()
+        .ok_or_else(|| AppError::InvalidCmdSwitch(format!("Invalid input file name: {}", input.display())))?;
+      let dest_path = output_dir.join(stem).with_extension("dng");
+      if dest_path.exists() && !options.get_flag("override") {
+        return Err(AppError::AlreadyExists(dest_path));
+      }
+      Ok(dest_path)
+    })
+    .collect::<crate::Result<Vec<PathBuf>>>()?;
+
+  let calibration = build_calibration(options)?;
+
+  let convert_params = ConvertParams {
+    embedded: false,
+    predictor: *options
+      .get_one("predictor")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("predictor has no default".into()))?,
+    deflate_predictor: *options
+      .get_one("deflate_predictor")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("deflate_predictor has no default".into()))?,
+    crop: *options
+      .get_one("crop")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("crop has no default".into()))?,
+    preview: options.get_flag("preview"),
+    thumbnail: options.get_flag("thumbnail"),
+    compression: *options
+      .get_one("compression")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("compression has no default".into()))?,
+    artist: options.get_one("artist").cloned(),
+    software: format!("{} {}", "DNGLab", PKG_VERSION),
+    ..Default::default()
+  };
+
+  for (input, dest_path) in inputs.iter().zip(outputs.iter()) {
+    // Only the primary image of a container is calibrated
+    let mut frame = load_frame(input, 0)?;
+    calibration.apply(&mut frame.rawimage)?;
+    let mut stream = BufWriter::new(File::create(dest_path)?);
+    if let Err(err) = convert_raw_image(&frame.rawimage, &frame.metadata, &mut stream, &convert_params) {
+      drop(stream);
+      if let Err(err) = remove_file(dest_path) {
+        log::error!("Failed to delete DNG file after calibration error: {:?}", err);
+      }
+      return Err(err.into());
+    }
+    if options.get_flag("verbose") {
+      println!("Calibrated {} -> {}", input.display(), dest_path.display());
+    }
+  }
+
+  if options.get_flag("verbose") {
+    println!("Calibrated {} files in {:.2}s", inputs.len(), now.elapsed().as_secs_f32());
+  }
+  Ok(())
+}
+
+/// Stack master frames from the files given on command line
+fn build_calibration(options: &ArgMatches) -> crate::Result<Calibration> {
+  let sigma: f32 = *options
+    .get_one("sigma")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("sigma has no default".into()))?;
+  let method = match options.get_one::<String>("method").map(String::as_str) {
+    Some("median") => StackMethod::Median,
+    _ => StackMethod::SigmaClip { sigma, iterations: 5 },
+  };
+
+  let load = |id: &str| -> crate::Result<Option<Vec<RawImage>>> {
+    match options.get_many::<PathBuf>(id) {
+      Some(paths) => {
+        let paths: Vec<&PathBuf> = paths.collect();
+        Ok(Some(load_frames(&paths)?.into_iter().map(|frame| frame.rawimage).collect()))
+      }
+      None => Ok(None),
+    }
+  };
+
+  let bias = load("bias")?.map(|frames| MasterFrame::stack(&frames, method)).transpose()?;
+  let dark = load("dark")?.map(|frames| MasterFrame::stack(&frames, method)).transpose()?;
+  let flat = load("flat")?
+    .map(|frames| MasterFrame::stack_flat(&frames, bias.as_ref(), method))
+    .transpose()?;
+  let hot_pixels = if options.get_flag("hot_pixels") {
+    let dark = dark
+      .as_ref()
+      .ok_or_else(|| AppError::InvalidCmdSwitch("Hot pixel detection requires dark frames".into()))?;
+    let map = HotPixelMap::from_dark(dark, sigma);
+    log::info!("Found {} hot pixels", map.len());
+    Some(map)
+  } else {
+    None
+  };
+
+  Ok(Calibration { bias, dark, flat, hot_pixels })
+}
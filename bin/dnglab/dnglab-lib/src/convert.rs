@@ -3,6 +3,7 @@
 
 use clap::ArgMatches;
 use futures::future::join_all;
+use rawler::decoders::RawImageKind;
 use rawler::decoders::supported_extensions;
 use std::collections::HashSet;
 use std::fs::create_dir_all;
@@ -13,8 +14,11 @@ use std::time::Instant;
 use crate::filemap::{FileMap, MapMode};
 use crate::jobs::Job;
 use crate::jobs::raw2dng::{JobResult, Raw2DngJob};
+use crate::makedng::DngVersion;
 use crate::{AppError, PKG_VERSION, Result};
 use rawler::dng::convert::ConvertParams;
+use rawler::dng::dcp::DcpProfile;
+use rawler::dng::preview::PreviewParams;
 
 /// Entry point for Clap sub command `convert`
 pub async fn convert(options: &ArgMatches) -> crate::Result<()> {
@@ -38,11 +42,12 @@ pub async fn convert(options: &ArgMatches) -> crate::Result<()> {
   // distinct source files that would otherwise produce the same DNG name
   // (e.g. FOO.CR3 and FOO.NEF -> FOO.dng / FOO_1.dng).
   let mut claimed: HashSet<PathBuf> = HashSet::new();
+  let profile = load_profile(options)?;
 
   match proc {
     // We have only one input file, so output must be a file, too.
     MapMode::File(sd) => {
-      jobs.append(&mut generate_job(&sd, options, &mut claimed)?);
+      jobs.append(&mut generate_job(&sd, options, profile.as_ref(), &mut claimed)?);
     }
     // Input is directory, to process all files
     MapMode::Dir(sd) => {
@@ -54,7 +59,7 @@ pub async fn convert(options: &ArgMatches) -> crate::Result<()> {
         }
       })?;
       for entry in list {
-        jobs.append(&mut generate_job(&entry, options, &mut claimed)?);
+        jobs.append(&mut generate_job(&entry, options, profile.as_ref(), &mut claimed)?);
       }
     }
   }
@@ -100,8 +105,60 @@ pub async fn convert(options: &ArgMatches) -> crate::Result<()> {
   }
 }
 
+/// Load DNG camera profile given by `--profile`
+pub(crate) fn load_profile(options: &ArgMatches) -> Result<Option<DcpProfile>> {
+  match options.get_one::<PathBuf>("profile") {
+    Some(path) => {
+      let profile = DcpProfile::read_file(path)?;
+      log::info!(
+        "Loaded camera profile '{}' from {}",
+        profile.name.as_deref().unwrap_or_default(),
+        path.display()
+      );
+      Ok(Some(profile))
+    }
+    None => Ok(None),
+  }
+}
+
+/// Build preview settings from `--preview-*` and `--thumbnail-size`
+pub(crate) fn preview_params(options: &ArgMatches) -> Result<PreviewParams> {
+  let size = options
+    .get_one::<String>("preview_size")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("preview-size has no default".into()))?;
+  let max_size = if size.eq_ignore_ascii_case("full") {
+    None
+  } else {
+    match size.parse::<u32>() {
+      Ok(size) if size >= 16 => Some(size),
+      _ => {
+        return Err(AppError::InvalidCmdSwitch(format!(
+          "Invalid preview size '{}', expected pixels (>= 16) or 'full'",
+          size
+        )));
+      }
+    }
+  };
+  Ok(PreviewParams {
+    max_size,
+    thumbnail_size: *options
+      .get_one("thumbnail_size")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("thumbnail-size has no default".into()))?,
+    filter: *options
+      .get_one("preview_filter")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("preview-filter has no default".into()))?,
+    quality: *options
+      .get_one::<u8>("preview_quality")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("preview-quality has no default".into()))? as f32
+      / 100.0,
+    source: *options
+      .get_one("preview_source")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("preview-source has no default".into()))?,
+  })
+}
+
 /// Convert given raw file to dng file
-fn generate_job(entry: &FileMap, options: &ArgMatches, claimed: &mut HashSet<PathBuf>) -> Result<Vec<Raw2DngJob>> {
+fn generate_job(entry: &FileMap, options: &ArgMatches, profile: Option<&DcpProfile>, claimed: &mut HashSet<PathBuf>) -> Result<Vec<Raw2DngJob>> {
   let (do_batch, index) = match options.get_one::<String>("index") {
     Some(index) => {
       if index.to_lowercase().eq("all") {
@@ -113,7 +170,12 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, claimed: &mut HashSet<Pat
     None => (false, 0),
   };
 
-  let batch_count = if do_batch { rawler::raw_image_count_file(&entry.src)? } else { 1 };
+  let kinds = if do_batch {
+    rawler::raw_image_kinds_file(&entry.src)?
+  } else {
+    vec![RawImageKind::Main]
+  };
+  let batch_count = kinds.len();
   let multi_frame = do_batch && batch_count > 1;
   let replace = options.get_flag("override");
 
@@ -149,12 +211,19 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, claimed: &mut HashSet<Pat
   //   single frame, k>0:    FOO_<k>.dng
   //   multi  frame, k=0:    FOO_<i>.dng                      (i = 0..batch_count)
   //   multi  frame, k>0:    FOO_<k>_<i>.dng
+  //
+  // Frames which are not a main image get their kind appended, like
+  // FOO_0001_enhanced.dng or FOO_0002_shift2.dng.
   let resolve_frame = |k: usize, frame: usize| -> PathBuf {
     let stem = if multi_frame {
+      let label = match kinds[frame] {
+        RawImageKind::Main => String::new(),
+        kind => format!("_{}", kind),
+      };
       if k == 0 {
-        format!("{}_{:04}", base_stem, frame)
+        format!("{}_{:04}{}", base_stem, frame, label)
       } else {
-        format!("{}_{}_{:04}", base_stem, k, frame)
+        format!("{}_{}_{:04}{}", base_stem, k, frame, label)
       }
     } else if k == 0 {
       base_stem.clone()
@@ -205,6 +274,9 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, claimed: &mut HashSet<Pat
       predictor: *options
         .get_one("predictor")
         .ok_or_else(|| AppError::InvalidCmdSwitch("predictor has no default".into()))?,
+      deflate_predictor: *options
+        .get_one("deflate_predictor")
+        .ok_or_else(|| AppError::InvalidCmdSwitch("deflate_predictor has no default".into()))?,
       embedded: options.get_flag("embedded"),
       photometric_conversion: Default::default(),
       crop: *options
@@ -220,6 +292,15 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, claimed: &mut HashSet<Pat
       index: if do_batch { i } else { index },
       apply_scaling: false,
       keep_mtime: options.get_flag("keep_mtime"),
+      profile: profile.cloned(),
+      preview_params: preview_params(options)?,
+      tile_size: *options
+        .get_one::<u16>("tile_size")
+        .ok_or_else(|| AppError::InvalidCmdSwitch("tile_size has no default".into()))? as usize,
+      dng_version: options
+        .get_one::<DngVersion>("dng_version")
+        .ok_or_else(|| AppError::InvalidCmdSwitch("dng_version has no default".into()))?
+        .as_dng_value(),
     };
     jobs.push(Raw2DngJob {
       input: input.clone(),
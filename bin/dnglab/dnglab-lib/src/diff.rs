@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::analyze::diff::{diff_heatmap, diff_raw_images};
+use rawler::analyze::extract_raw_pixels;
+use rawler::decoders::RawDecodeParams;
+use std::path::PathBuf;
+
+use crate::AppError;
+
+/// Entry point for Clap sub command `diff`
+pub async fn diff(options: &ArgMatches) -> crate::Result<()> {
+  let file_a: &PathBuf = options.get_one("A").ok_or_else(|| AppError::InvalidCmdSwitch("A not available".into()))?;
+  let file_b: &PathBuf = options.get_one("B").ok_or_else(|| AppError::InvalidCmdSwitch("B not available".into()))?;
+  let heatmap: Option<&PathBuf> = options.get_one("heatmap");
+  if let Some(path) = heatmap
+    && path.exists()
+    && !options.get_flag("override")
+  {
+    return Err(AppError::AlreadyExists(path.to_owned()));
+  }
+
+  let image_a = extract_raw_pixels(file_a, &RawDecodeParams::default())?;
+  let image_b = extract_raw_pixels(file_b, &RawDecodeParams::default())?;
+  let result = diff_raw_images(&image_a, &image_b)?;
+
+  if options.get_flag("yaml") {
+    println!("{}", serde_yaml::to_string(&result)?);
+  } else if options.get_flag("json") {
+    println!("{}", serde_json::to_string_pretty(&result)?);
+  } else {
+    for diff in &result.metadata {
+      println!("{}: {} <> {}", diff.field, diff.a, diff.b);
+    }
+    println!(
+      "Compared area: {}x{} at {},{} <> {},{}",
+      result.area_a.d.w, result.area_a.d.h, result.area_a.p.x, result.area_a.p.y, result.area_b.p.x, result.area_b.p.y
+    );
+    for channel in &result.channels {
+      println!(
+        "{:3}: {} of {} pixels differ, max abs diff {}, mean abs diff {:.3}, PSNR {}",
+        channel.name,
+        channel.differing_pixels,
+        channel.compared_pixels,
+        channel.max_abs_diff,
+        channel.mean_abs_diff,
+        channel.psnr_db.map(|psnr| format!("{:.2} dB", psnr)).unwrap_or_else(|| "inf".into())
+      );
+    }
+  }
+
+  if let Some(path) = heatmap {
+    let image = diff_heatmap(&image_a, &image_b)?;
+    image
+      .save(path)
+      .map_err(|err| AppError::General(format!("Failed to write heat map {}: {}", path.display(), err)))?;
+  }
+
+  if result.is_identical() {
+    Ok(())
+  } else {
+    Err(AppError::General("Images differ".into()))
+  }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::Orientation;
+use rawler::dng::edit::{DngMetadataEdit, GpsPosition, edit_dng_file};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::convert::load_profile;
+use crate::filemap::read_filtered_dir;
+use crate::{AppError, Result};
+
+/// Entry point for Clap sub command `edit`
+pub async fn edit(options: &ArgMatches) -> crate::Result<()> {
+  let now = Instant::now();
+  let input: &PathBuf = options
+    .get_one("INPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("INPUT not available".into()))?;
+
+  let edit = build_edit(options)?;
+  if edit.is_empty() {
+    return Err(AppError::InvalidCmdSwitch("No metadata changes given".into()));
+  }
+
+  if !input.exists() {
+    return Err(AppError::NotFound(input.to_owned()));
+  }
+  let files = if input.is_dir() {
+    eprintln!("Scanning directory, please wait...");
+    read_filtered_dir(input, options.get_flag("recursive"), |file| {
+      file.extension().map(|ext| ext.to_string_lossy().eq_ignore_ascii_case("dng")).unwrap_or(false)
+    })?
+  } else {
+    vec![input.to_owned()]
+  };
+
+  let verbose = options.get_flag("verbose");
+  let results: Vec<(&PathBuf, Result<()>)> = files
+    .par_iter()
+    .map(|file| {
+      let result = edit_dng_file(file, &edit).map_err(AppError::from);
+      if verbose {
+        match &result {
+          Ok(_) => println!("Edited {}", file.display()),
+          Err(err) => println!("Failed {}: {}", file.display(), err),
+        }
+      }
+      (file, result)
+    })
+    .collect();
+
+  let total = results.len();
+  let failed: Vec<&(&PathBuf, Result<()>)> = results.iter().filter(|(_, result)| result.is_err()).collect();
+  if failed.is_empty() {
+    eprintln!("Edited {}/{} files", total, total);
+  } else {
+    eprintln!("Edited {}/{} files, {} failed:", total - failed.len(), total, failed.len());
+    for (file, _) in &failed {
+      eprintln!("   {}", file.display());
+    }
+  }
+  eprintln!("Total time: {:.2}s", now.elapsed().as_secs_f32());
+
+  // In case of errors, return the first error in the queue
+  match results.into_iter().find_map(|(_, result)| result.err()) {
+    Some(err) => Err(err),
+    None => Ok(()),
+  }
+}
+
+/// Collect metadata changes from command line
+fn build_edit(options: &ArgMatches) -> Result<DngMetadataEdit> {
+  let gps = match options.get_many::<f64>("gps") {
+    Some(values) => {
+      let values: Vec<f64> = values.copied().collect();
+      if !(2..=3).contains(&values.len()) {
+        return Err(AppError::InvalidCmdSwitch("GPS position requires latitude,longitude[,altitude]".into()));
+      }
+      Some(GpsPosition {
+        latitude: values[0],
+        longitude: values[1],
+        altitude: values.get(2).copied(),
+      })
+    }
+    None => None,
+  };
+  Ok(DngMetadataEdit {
+    artist: options.get_one::<String>("artist").cloned(),
+    copyright: options.get_one::<String>("copyright").cloned(),
+    image_description: options.get_one::<String>("description").cloned(),
+    orientation: options.get_one::<u16>("orientation").map(|value| Orientation::from_u16(*value)),
+    gps,
+    remove_gps: options.get_flag("remove_gps"),
+    as_shot_neutral: options.get_many::<f32>("as_shot_neutral").map(|values| values.copied().collect()),
+    xmp: options.get_one::<PathBuf>("xmp").map(|path| read_xmp(path)).transpose()?,
+    profile: load_profile(options)?,
+  })
+}
+
+/// Read XMP packet from file
+fn read_xmp(path: &Path) -> Result<Vec<u8>> {
+  std::fs::read(path).map_err(|err| AppError::General(format!("Failed to read XMP file {}: {}", path.display(), err)))
+}
@@ -108,6 +108,24 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, use_orig_filename: bool)
     return Err(AppError::General("Input file is not a DNG".into()));
   }
 
+  let auxiliary = options.get_flag("auxiliary");
+  if auxiliary {
+    // Output is used as base name: <base>_<mask>.png
+    let stem = entry.src.file_stem().map(PathBuf::from).unwrap_or_default();
+    let output = if entry.dest.is_dir() {
+      entry.dest.join(stem)
+    } else {
+      entry.dest.with_extension("")
+    };
+    return Ok(ExtractRawJob {
+      input: PathBuf::from(&entry.src),
+      output,
+      replace: options.get_flag("override"),
+      skip_checks: options.get_flag("skipchecks"),
+      auxiliary,
+    });
+  }
+
   let orig_filename = get_original_name(&file).ok_or(AppError::General("No embedded raw file found".into()))?;
 
   let output = if entry.dest.is_dir() {
@@ -125,6 +143,7 @@ fn generate_job(entry: &FileMap, options: &ArgMatches, use_orig_filename: bool)
     output,
     replace: options.get_flag("override"),
     skip_checks: options.get_flag("skipchecks"),
+    auxiliary,
   })
 }
 
@@ -108,7 +108,7 @@ impl MapMode {
 }
 
 /// Read directory (optionally recursive) and filter entries
-fn read_filtered_dir<F>(input: &Path, recursive: bool, filter: F) -> Result<Vec<PathBuf>>
+pub(crate) fn read_filtered_dir<F>(input: &Path, recursive: bool, filter: F) -> Result<Vec<PathBuf>>
 where
   F: Fn(&Path) -> bool + Copy,
 {
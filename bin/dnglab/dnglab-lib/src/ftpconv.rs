@@ -15,6 +15,7 @@ use std::sync::Arc;
 use tokio::runtime::Handle;
 
 use crate::jobs::raw2dng::copy_mtime_from_rawsource;
+use crate::makedng::DngVersion;
 use crate::{PKG_NAME, PKG_VERSION};
 use rawler::dng::convert::{ConvertParams, convert_raw_source};
 
@@ -68,6 +69,9 @@ pub async fn ftpserver(options: &ArgMatches) -> crate::Result<()> {
     predictor: *options
       .get_one("predictor")
       .ok_or_else(|| crate::AppError::InvalidCmdSwitch("predictor has no default".into()))?,
+    deflate_predictor: *options
+      .get_one("deflate_predictor")
+      .ok_or_else(|| crate::AppError::InvalidCmdSwitch("deflate_predictor has no default".into()))?,
     embedded: options.get_flag("embedded"),
     photometric_conversion: Default::default(),
     crop: *options
@@ -83,6 +87,15 @@ pub async fn ftpserver(options: &ArgMatches) -> crate::Result<()> {
     index: 0,
     apply_scaling: false,
     keep_mtime: options.get_flag("keep_mtime"),
+    profile: crate::convert::load_profile(options)?,
+    preview_params: crate::convert::preview_params(options)?,
+    tile_size: *options
+      .get_one::<u16>("tile_size")
+      .ok_or_else(|| crate::AppError::InvalidCmdSwitch("tile_size has no default".into()))? as usize,
+    dng_version: options
+      .get_one::<DngVersion>("dng_version")
+      .ok_or_else(|| crate::AppError::InvalidCmdSwitch("dng_version has no default".into()))?
+      .as_dng_value(),
   };
   let keep_orig = options.get_flag("keep_orig");
 
@@ -0,0 +1,81 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::compose::hdr::{HdrMergeParams, merge_hdr};
+use rawler::compose::load_frames;
+use rawler::dng::DngCompression;
+use rawler::dng::convert::{ConvertParams, convert_raw_image};
+use std::fs::{File, remove_file};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::{AppError, PKG_VERSION};
+
+/// Entry point for Clap sub command `hdrmerge`
+pub async fn hdrmerge(options: &ArgMatches) -> crate::Result<()> {
+  let dest_path: &PathBuf = options
+    .get_one("OUTPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("Output path is required".into()))?;
+
+  if dest_path.exists() && !options.get_flag("override") {
+    return Err(AppError::AlreadyExists(dest_path.to_owned()));
+  }
+
+  match hdrmerge_internal(options, dest_path).await {
+    Ok(_) => Ok(()),
+    Err(err) => {
+      if let Err(err) = remove_file(dest_path) {
+        log::error!("Failed to delete DNG file after merge error: {:?}", err);
+      }
+      Err(err)
+    }
+  }
+}
+
+async fn hdrmerge_internal(options: &ArgMatches, dest_path: &Path) -> crate::Result<()> {
+  let now = Instant::now();
+
+  let inputs: Vec<&PathBuf> = options
+    .get_many("inputs")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("inputs are required".into()))?
+    .collect();
+
+  let frames = load_frames(&inputs)?;
+
+  let params = HdrMergeParams {
+    bits: options
+      .get_one::<String>("bits")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("bits has no default".into()))?
+      .parse()
+      .map_err(|err| AppError::InvalidCmdSwitch(format!("Invalid bits: {}", err)))?,
+  };
+  let merged = merge_hdr(&frames, &params)?;
+
+  let convert_params = ConvertParams {
+    embedded: false,
+    compression: DngCompression::Deflate,
+    crop: *options
+      .get_one("crop")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("crop has no default".into()))?,
+    preview: options.get_flag("preview"),
+    thumbnail: options.get_flag("thumbnail"),
+    artist: options.get_one("artist").cloned(),
+    software: format!("{} {}", "DNGLab", PKG_VERSION),
+    ..Default::default()
+  };
+
+  let mut stream = BufWriter::new(File::create(dest_path)?);
+  convert_raw_image(&merged.rawimage, &merged.metadata, &mut stream, &convert_params)?;
+
+  if options.get_flag("verbose") {
+    println!(
+      "Merged {} frames into {} in {:.2}s",
+      frames.len(),
+      dest_path.display(),
+      now.elapsed().as_secs_f32()
+    );
+  }
+  Ok(())
+}
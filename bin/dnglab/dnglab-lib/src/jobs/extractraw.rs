@@ -8,6 +8,7 @@ use log::debug;
 use rawler::{
   dng::original::{OriginalCompressed, OriginalDigest},
   formats::tiff::{GenericTiffReader, Value, reader::TiffReader},
+  rawsource::RawSource,
   tags::DngTag,
 };
 use std::{
@@ -24,6 +25,9 @@ pub struct ExtractRawJob {
   pub output: PathBuf,
   pub replace: bool,
   pub skip_checks: bool,
+  /// Extract depth maps and masks instead of the original raw.
+  /// In this mode, `output` is the base name for the written images.
+  pub auxiliary: bool,
 }
 
 /// State of conversion
@@ -53,6 +57,9 @@ impl Display for JobResult {
 
 impl ExtractRawJob {
   fn internal_exec(&self) -> Result<JobResult> {
+    if self.auxiliary {
+      return self.extract_auxiliary();
+    }
     if self.output.exists() && !self.replace {
       return Err(AppError::AlreadyExists(self.output.clone()));
     }
@@ -94,6 +101,33 @@ impl ExtractRawJob {
       Err(AppError::General("No embedded raw file found".into()))
     }
   }
+
+  /// Write depth maps and semantic masks as grayscale PNG files
+  fn extract_auxiliary(&self) -> Result<JobResult> {
+    let rawfile = RawSource::new(&self.input)?;
+    let decoder = rawler::get_decoder(&rawfile)?;
+    let images = decoder.auxiliary_images(&rawfile)?;
+    if images.is_empty() {
+      return Err(AppError::General("No auxiliary images found".into()));
+    }
+    let stem = self.output.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    for aux in &images {
+      let path = self.output.with_file_name(format!("{}_{}.png", stem, aux.name()));
+      if path.exists() && !self.replace {
+        return Err(AppError::AlreadyExists(path));
+      }
+      let img = aux
+        .to_dynamic_image()
+        .ok_or_else(|| AppError::General(format!("Invalid dimension for auxiliary image {}", aux.name())))?;
+      debug!("Writing auxiliary image {:?} to {}", aux.kind, path.display());
+      img.save(&path)?;
+    }
+    Ok(JobResult {
+      job: self.clone(),
+      duration: 0.0,
+      error: None,
+    })
+  }
 }
 
 #[async_trait]
@@ -109,8 +109,11 @@ impl Raw2Image {
               err.to_string()
             );
           }
-          RawlerError::DecoderFailed(msg) => {
-            log::error!("Failed to decode file: {}", msg);
+          RawlerError::UnsupportedFeature { .. } => {
+            log::error!("Unsupported file: \"{}\", {}", orig_filename, err);
+          }
+          _ => {
+            log::error!("Failed to decode file: \"{}\", {} [{}]", orig_filename, err, err.code());
           }
         }
         drop(image_file);
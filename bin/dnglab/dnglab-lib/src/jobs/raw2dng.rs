@@ -124,8 +124,11 @@ impl Raw2DngJob {
               err.to_string()
             );
           }
-          RawlerError::DecoderFailed(msg) => {
-            log::error!("Failed to decode file: {}", msg);
+          RawlerError::UnsupportedFeature { .. } => {
+            log::error!("Unsupported file: \"{}\", {}", orig_filename, err);
+          }
+          _ => {
+            log::error!("Failed to decode file: \"{}\", {} [{}]", orig_filename, err, err.code());
           }
         }
         drop(dng);
@@ -10,16 +10,24 @@ use thiserror::Error;
 
 pub mod analyze;
 pub mod app;
+pub mod calibrate;
 pub mod cameras;
 pub mod convert;
+pub mod diff;
+pub mod edit;
 pub mod extract;
 pub mod filemap;
 pub mod ftpconv;
 pub mod gui;
+pub mod hdrmerge;
 pub mod jobs;
 pub mod lenses;
 pub mod makedng;
+pub mod pixelshift;
 pub mod process_raw;
+pub mod profile;
+
+pub use rawler::ErrorCode;
 
 const PKG_VERSION: &str = env!("CARGO_PKG_VERSION");
 const PKG_NAME: &str = env!("CARGO_PKG_NAME");
@@ -36,10 +44,8 @@ pub enum AppError {
   NotFound(PathBuf),
   #[error("Already exists: {}", _0.display())]
   AlreadyExists(PathBuf),
-  #[error("Decoder failed: {}", _0)]
-  DecoderFailed(String),
-  #[error("Unsupported file: {}", _0)]
-  UnsupportedFile(String),
+  #[error("{} [{}]", _0, _0.code())]
+  Rawler(#[from] RawlerError),
   #[error(transparent)]
   Other(#[from] anyhow::Error),
 }
@@ -62,15 +68,6 @@ impl From<AddrParseError> for AppError {
   }
 }
 
-impl From<RawlerError> for AppError {
-  fn from(value: RawlerError) -> Self {
-    match value {
-      RawlerError::DecoderFailed(_) => Self::DecoderFailed(value.to_string()),
-      RawlerError::Unsupported { .. } => Self::UnsupportedFile(value.to_string()),
-    }
-  }
-}
-
 impl From<ImageError> for AppError {
   fn from(value: ImageError) -> Self {
     anyhow::Error::new(value).into()
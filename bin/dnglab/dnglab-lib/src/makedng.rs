@@ -678,7 +678,7 @@ pub enum DngVersion {
 }
 
 impl DngVersion {
-  fn as_dng_value(&self) -> [u8; 4] {
+  pub(crate) fn as_dng_value(&self) -> [u8; 4] {
     match self {
       DngVersion::V1_0 => dng::DNG_VERSION_V1_0,
       DngVersion::V1_1 => dng::DNG_VERSION_V1_1,
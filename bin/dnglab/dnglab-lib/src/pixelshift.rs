@@ -0,0 +1,91 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::compose::load_frames;
+use rawler::compose::pixelshift::{PixelShiftParams, ShiftPattern, compose_pixelshift};
+use rawler::dng::convert::{ConvertParams, convert_raw_image};
+use std::fs::{File, remove_file};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::{AppError, PKG_VERSION};
+
+/// Entry point for Clap sub command `pixelshift`
+pub async fn pixelshift(options: &ArgMatches) -> crate::Result<()> {
+  let dest_path: &PathBuf = options
+    .get_one("OUTPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("Output path is required".into()))?;
+
+  if dest_path.exists() && !options.get_flag("override") {
+    return Err(AppError::AlreadyExists(dest_path.to_owned()));
+  }
+
+  match pixelshift_internal(options, dest_path).await {
+    Ok(_) => Ok(()),
+    Err(err) => {
+      if let Err(err) = remove_file(dest_path) {
+        log::error!("Failed to delete DNG file after composition error: {:?}", err);
+      }
+      Err(err)
+    }
+  }
+}
+
+async fn pixelshift_internal(options: &ArgMatches, dest_path: &Path) -> crate::Result<()> {
+  let now = Instant::now();
+
+  let inputs: Vec<&PathBuf> = options
+    .get_many("inputs")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("inputs are required".into()))?
+    .collect();
+
+  let frames = load_frames(&inputs)?;
+  if frames.is_empty() {
+    return Err(AppError::General("No frames found in input files".into()));
+  }
+
+  let params = PixelShiftParams {
+    pattern: options.get_one::<ShiftPattern>("pattern").cloned(),
+    motion_correction: options.get_flag("motion_correction"),
+    motion_threshold: *options
+      .get_one("motion_threshold")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("motion-threshold has no default".into()))?,
+  };
+  let composed = compose_pixelshift(&frames, &params)?;
+
+  let convert_params = ConvertParams {
+    embedded: false,
+    predictor: *options
+      .get_one("predictor")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("predictor has no default".into()))?,
+    deflate_predictor: *options
+      .get_one("deflate_predictor")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("deflate_predictor has no default".into()))?,
+    crop: *options
+      .get_one("crop")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("crop has no default".into()))?,
+    preview: options.get_flag("preview"),
+    thumbnail: options.get_flag("thumbnail"),
+    compression: *options
+      .get_one("compression")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("compression has no default".into()))?,
+    artist: options.get_one("artist").cloned(),
+    software: format!("{} {}", "DNGLab", PKG_VERSION),
+    ..Default::default()
+  };
+
+  let mut stream = BufWriter::new(File::create(dest_path)?);
+  convert_raw_image(&composed.rawimage, &composed.metadata, &mut stream, &convert_params)?;
+
+  if options.get_flag("verbose") {
+    println!(
+      "Composed {} frames into {} in {:.2}s",
+      frames.len(),
+      dest_path.display(),
+      now.elapsed().as_secs_f32()
+    );
+  }
+  Ok(())
+}
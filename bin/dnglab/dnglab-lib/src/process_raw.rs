@@ -4,6 +4,7 @@
 use clap::ArgMatches;
 use futures::future::join_all;
 use rawler::decoders::supported_extensions;
+use rawler::dng::dcp::DcpProfile;
 use rawler::imgop::develop::RawProcessingParams;
 use std::fs::create_dir_all;
 use std::path::PathBuf;
@@ -33,11 +34,12 @@ pub async fn process_raw(options: &ArgMatches) -> crate::Result<()> {
 
   // List of jobs
   let mut jobs: Vec<Raw2Image> = Vec::new();
+  let profile = crate::convert::load_profile(options)?;
 
   match proc {
     // We have only one input file, so output must be a file, too.
     MapMode::File(sd) => {
-      jobs.append(&mut generate_job(&sd, options)?);
+      jobs.append(&mut generate_job(&sd, options, profile.as_ref())?);
     }
     // Input is directory, to process all files
     MapMode::Dir(sd) => {
@@ -49,7 +51,7 @@ pub async fn process_raw(options: &ArgMatches) -> crate::Result<()> {
         }
       })?;
       for entry in list {
-        jobs.append(&mut generate_job(&entry, options)?);
+        jobs.append(&mut generate_job(&entry, options, profile.as_ref())?);
       }
     }
   }
@@ -95,7 +97,7 @@ pub async fn process_raw(options: &ArgMatches) -> crate::Result<()> {
 }
 
 /// Convert given raw file to dng file
-fn generate_job(entry: &FileMap, options: &ArgMatches) -> Result<Vec<Raw2Image>> {
+fn generate_job(entry: &FileMap, options: &ArgMatches, profile: Option<&DcpProfile>) -> Result<Vec<Raw2Image>> {
   let (do_batch, index) = match options.get_one::<String>("index") {
     Some(index) => {
       if index.to_lowercase().eq("all") {
@@ -122,6 +124,7 @@ fn generate_job(entry: &FileMap, options: &ArgMatches) -> Result<Vec<Raw2Image>>
       software: format!("{} {}", "DNGLab", PKG_VERSION),
       index: if do_batch { i } else { index },
       keep_mtime: options.get_flag("keep_mtime"),
+      profile: profile.cloned(),
     };
 
     let input = PathBuf::from(&entry.src);
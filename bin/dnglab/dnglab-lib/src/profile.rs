@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use clap::ArgMatches;
+use rawler::compose::load_frames;
+use rawler::imgop::xyz::Illuminant;
+use rawler::profiling::{ChartCorners, PROFILE_ILLUMINANTS, TargetChart, TargetReference, profile_from_raw};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use crate::AppError;
+
+/// Entry point for Clap sub command `profile`
+pub async fn profile(options: &ArgMatches) -> crate::Result<()> {
+  let now = Instant::now();
+
+  let input: &PathBuf = options
+    .get_one("INPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("INPUT not available".into()))?;
+  let output: &PathBuf = options
+    .get_one("OUTPUT")
+    .ok_or_else(|| AppError::InvalidCmdSwitch("OUTPUT not available".into()))?;
+  if output.exists() && !options.get_flag("override") {
+    return Err(AppError::AlreadyExists(output.to_owned()));
+  }
+  let extension = output.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+  if extension != "dcp" && extension != "toml" {
+    return Err(AppError::InvalidCmdSwitch("OUTPUT must be a .dcp or .toml file".into()));
+  }
+
+  let chart = TargetChart::new_from_str(
+    options
+      .get_one::<String>("chart")
+      .ok_or_else(|| AppError::InvalidCmdSwitch("chart has no default".into()))?,
+  )?;
+  let reference = match options.get_one::<PathBuf>("reference") {
+    Some(path) => TargetReference::from_cgats(chart, &std::fs::read_to_string(path)?)?,
+    None if chart == TargetChart::ColorChecker24 => TargetReference::colorchecker24(),
+    None => return Err(AppError::InvalidCmdSwitch("Reference values (--reference) are required for this chart".into())),
+  };
+  let corners = match options.get_many::<f32>("corners") {
+    Some(values) => Some(ChartCorners::new_from_slice(&values.copied().collect::<Vec<f32>>())?),
+    None => None,
+  };
+
+  // Only the primary image of a container is used
+  let frame = load_frames(&[input])?
+    .into_iter()
+    .next()
+    .ok_or_else(|| AppError::General(format!("No raw image found in {}", input.display())))?;
+
+  let illuminant = match options.get_one::<String>("illuminant") {
+    Some(name) => Illuminant::new_from_str(name).map_err(AppError::InvalidCmdSwitch)?,
+    None => match frame.metadata.exif.light_source.and_then(|value| Illuminant::try_from(value).ok()) {
+      Some(illuminant) if PROFILE_ILLUMINANTS.contains(&illuminant) => illuminant,
+      _ => {
+        log::warn!("No usable light source found in EXIF, assume D65");
+        Illuminant::D65
+      }
+    },
+  };
+
+  let fit = profile_from_raw(&frame.rawimage, &reference, corners, illuminant, options.get_flag("hue_sat_map"))?;
+
+  if options.get_flag("verbose") {
+    for (i, delta_e) in fit.delta_e.iter().enumerate() {
+      println!("Patch {:3}: delta E {:.2}", i + 1, delta_e);
+    }
+  }
+  println!("Illuminant: {:?}", fit.illuminant);
+  println!(
+    "Camera neutral: [{:.4}, {:.4}, {:.4}]",
+    fit.camera_neutral[0], fit.camera_neutral[1], fit.camera_neutral[2]
+  );
+  println!("Delta E (CIE76): mean {:.2}, max {:.2}", fit.mean_delta_e(), fit.max_delta_e());
+
+  let rawimage = &frame.rawimage;
+  if extension == "dcp" {
+    let name = match options.get_one::<String>("name") {
+      Some(name) => name.clone(),
+      None => format!("{} {} ({:?})", rawimage.clean_make, rawimage.clean_model, illuminant),
+    };
+    let profile = fit.to_dcp_profile(&format!("{} {}", rawimage.clean_make, rawimage.clean_model), &name);
+    profile.write_file(output)?;
+  } else {
+    std::fs::write(output, fit.to_camera_toml())?;
+  }
+
+  if options.get_flag("verbose") {
+    println!("Created profile {} in {:.2}s", output.display(), now.elapsed().as_secs_f32());
+  }
+  Ok(())
+}
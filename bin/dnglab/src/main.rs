@@ -77,9 +77,15 @@ async fn main_async() -> dnglab_lib::Result<()> {
   match matches.subcommand() {
     Some(("analyze", sc)) => analyze::analyze(sc).await,
     Some(("convert", sc)) => convert::convert(sc).await,
+    Some(("diff", sc)) => diff::diff(sc).await,
     Some(("makedng", sc)) => makedng::makedng(sc).await,
+    Some(("pixelshift", sc)) => pixelshift::pixelshift(sc).await,
+    Some(("hdrmerge", sc)) => hdrmerge::hdrmerge(sc).await,
+    Some(("calibrate", sc)) => calibrate::calibrate(sc).await,
     Some(("process-raw", sc)) => process_raw::process_raw(sc).await,
+    Some(("profile", sc)) => profile::profile(sc).await,
     Some(("extract", sc)) => extract::extract(sc).await,
+    Some(("edit", sc)) => edit::edit(sc).await,
     Some(("ftpserver", sc)) => ftpconv::ftpserver(sc).await,
     Some(("lenses", sc)) => lenses::lenses(sc).await,
     Some(("cameras", sc)) => cameras::cameras(sc).await,
@@ -95,12 +101,17 @@ impl Termination for AppResult {
     match self.0 {
       Ok(_) => ExitCode::SUCCESS,
       Err(AppError::InvalidCmdSwitch(_)) => ExitCode::from(1),
-      Err(AppError::DecoderFailed(_)) => ExitCode::from(2),
+      Err(AppError::Rawler(err)) => match err.code() {
+        ErrorCode::DecoderFailed => ExitCode::from(2),
+        ErrorCode::Io => ExitCode::from(4),
+        ErrorCode::UnsupportedCamera | ErrorCode::UnsupportedFeature => ExitCode::from(7),
+        ErrorCode::Truncated => ExitCode::from(8),
+        ErrorCode::Malformed => ExitCode::from(9),
+      },
       Err(AppError::General(_)) => ExitCode::from(3),
       Err(AppError::Io(_)) => ExitCode::from(4),
       Err(AppError::NotFound(_)) => ExitCode::from(5),
       Err(AppError::AlreadyExists(_)) => ExitCode::from(6),
-      Err(AppError::UnsupportedFile(_)) => ExitCode::from(7),
       Err(AppError::Other(_)) => ExitCode::from(99),
     }
   }
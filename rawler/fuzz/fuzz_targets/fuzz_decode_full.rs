@@ -3,6 +3,11 @@
 // Tests the complete decode path including format detection, TIFF/BMFF parsing,
 // metadata extraction, and actual image decompression. This is the most
 // comprehensive target — any crash here is a real bug.
+//
+// The decoder is driven directly instead of through `rawler::decode()`,
+// because the latter catches panics and converts them into errors. Here,
+// every panic must abort the run, as decoding is required to be panic-free
+// and report failures as `RawlerError`.
 
 #![no_main]
 use libfuzzer_sys::fuzz_target;
@@ -11,5 +16,9 @@ use rawler::rawsource::RawSource;
 
 fuzz_target!(|data: &[u8]| {
     let source = RawSource::new_from_slice(data);
-    let _ = rawler::decode(&source, &RawDecodeParams::default());
+    let params = RawDecodeParams::default();
+    if let Ok(decoder) = rawler::get_decoder(&source) {
+        let _ = decoder.raw_image(&source, &params, false);
+        let _ = decoder.raw_metadata(&source, &params);
+    }
 });
@@ -3,12 +3,20 @@
 // Tests TIFF/BMFF/CIFF format parsing, IFD chain traversal, makernote parsing,
 // and metadata extraction — but uses dummy decoders so actual decompression
 // is skipped. Good for catching bugs in format parsers.
+//
+// Like `fuzz_decode_full`, panics are not caught and abort the run.
 
 #![no_main]
 use libfuzzer_sys::fuzz_target;
+use rawler::decoders::RawDecodeParams;
 use rawler::rawsource::RawSource;
 
 fuzz_target!(|data: &[u8]| {
     let source = RawSource::new_from_slice(data);
-    let _ = rawler::decode_dummy(&source);
+    let params = RawDecodeParams::default();
+    if let Ok(decoder) = rawler::get_decoder(&source) {
+        let _ = decoder.raw_image(&source, &params, true);
+        let _ = decoder.raw_metadata(&source, &params);
+        let _ = decoder.xpacket(&source, &params);
+    }
 });
@@ -1,3 +1,6 @@
+pub mod diff;
+pub mod stats;
+
 use std::{fs::metadata, io::Write, path::Path};
 
 use byteorder::{BigEndian, WriteBytesExt};
@@ -14,10 +17,13 @@ use crate::{
   formats::tiff::Rational,
   formats::tiff::SRational,
   imgop::{Rect, develop::RawDevelop},
+  probe::{ProbeParams, ProbeResult, probe_file},
   rawimage::{BlackLevel, WhiteLevel},
   rawsource::RawSource,
 };
 
+use self::stats::{RawStatistics, raw_statistics};
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Md5Digest {
   digest: md5::Digest,
@@ -78,6 +84,8 @@ pub enum AnalyzerData {
   FileStructure(FormatDump),
   Metadata(AnalyzerMetadata),
   RawParams(RawParams),
+  Statistics(RawStatistics),
+  Summary(ProbeResult),
 }
 
 #[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
@@ -147,7 +155,7 @@ pub enum FormatDump {
 
 fn file_metadata<P: AsRef<Path>>(path: P, rawfile: &RawSource) -> Result<FileMetadata> {
   let fs_meta = metadata(&path).map_err(|e| RawlerError::with_io_error("read metadata", &path, e))?;
-  let digest = rawfile.digest();
+  let digest = rawfile.digest().map_err(|e| RawlerError::with_io_error("calculate digest", &path, e))?;
   Ok(FileMetadata {
     file_name: path.as_ref().file_name().ok_or("path has no file name")?.to_string_lossy().to_string(),
     file_size: fs_meta.len(),
@@ -174,6 +182,35 @@ pub fn analyze_metadata<P: AsRef<Path>>(path: P) -> Result<AnalyzerResult> {
   Ok(result)
 }
 
+/// Analyze raw pixel data (histograms, black level, noise, clipping and exposure)
+pub fn analyze_statistics<P: AsRef<Path>>(path: P, params: &RawDecodeParams) -> Result<AnalyzerResult> {
+  let rawfile = RawSource::new(path.as_ref())?;
+  let decoder = crate::get_decoder(&rawfile)?;
+  let rawimage = decoder.raw_image(&rawfile, params, false)?;
+
+  let result = AnalyzerResult {
+    file: file_metadata(path, &rawfile)?,
+    data: Some(AnalyzerData::Statistics(raw_statistics(&rawimage)?)),
+  };
+  Ok(result)
+}
+
+/// Summary information by probing, without reading pixel data
+///
+/// No digest is calculated, because this would require reading the whole file.
+pub fn analyze_summary<P: AsRef<Path>>(path: P, params: &ProbeParams) -> Result<AnalyzerResult> {
+  let fs_meta = metadata(&path).map_err(|e| RawlerError::with_io_error("read metadata", &path, e))?;
+  let summary = probe_file(&path, params)?;
+  Ok(AnalyzerResult {
+    file: FileMetadata {
+      file_name: path.as_ref().file_name().ok_or("path has no file name")?.to_string_lossy().to_string(),
+      file_size: fs_meta.len(),
+      digest: None,
+    },
+    data: Some(AnalyzerData::Summary(summary)),
+  })
+}
+
 pub fn analyze_file_structure<P: AsRef<Path>>(path: P) -> Result<AnalyzerResult> {
   let rawfile = RawSource::new(path.as_ref())?;
   let decoder = crate::get_decoder(&rawfile)?;
@@ -0,0 +1,340 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use std::collections::BTreeSet;
+
+use image::{DynamicImage, Rgb, RgbImage};
+use serde::{Deserialize, Serialize};
+
+use super::stats::{ChannelLayout, black_level_at, clip_rect};
+use crate::{
+  RawImage, RawlerError, Result,
+  imgop::{Dim2, Point, Rect},
+  rawimage::RawPhotometricInterpretation,
+};
+
+/// Tolerance for comparing floating point metadata like
+/// white balance coefficients and color matrices.
+const METADATA_TOLERANCE: f32 = 1.0e-4;
+
+/// A metadata value which differs between both images
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataDifference {
+  pub field: String,
+  pub a: String,
+  pub b: String,
+}
+
+/// Pixel differences for a single CFA position or color channel
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelDifference {
+  pub name: String,
+  pub compared_pixels: u64,
+  pub differing_pixels: u64,
+  pub max_abs_diff: f32,
+  pub mean_abs_diff: f32,
+  /// Peak signal-to-noise ratio relative to the white level,
+  /// `None` if both channels are identical.
+  pub psnr_db: Option<f32>,
+}
+
+/// Result of comparing two raw images
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawDiff {
+  /// Compared area of image A
+  pub area_a: Rect,
+  /// Compared area of image B
+  pub area_b: Rect,
+  pub metadata: Vec<MetadataDifference>,
+  pub channels: Vec<ChannelDifference>,
+}
+
+impl RawDiff {
+  /// Check if metadata and pixels are identical
+  pub fn is_identical(&self) -> bool {
+    self.metadata.is_empty() && !self.channels.is_empty() && self.channels.iter().all(|channel| channel.differing_pixels == 0)
+  }
+}
+
+/// Area to compare, both images are aligned on their active area.
+/// If active areas differ in size, the common top-left part is used.
+fn compare_areas(a: &RawImage, b: &RawImage) -> (Rect, Rect) {
+  let area = |rawimage: &RawImage| clip_rect(rawimage.active_area.unwrap_or_else(|| Rect::new(Point::zero(), rawimage.dim())), rawimage);
+  let (area_a, area_b) = (area(a), area(b));
+  let dim = Dim2::new(area_a.d.w.min(area_b.d.w), area_a.d.h.min(area_b.d.h));
+  (Rect::new(area_a.p, dim), Rect::new(area_b.p, dim))
+}
+
+fn cfa_name(rawimage: &RawImage, area: &Rect) -> String {
+  match &rawimage.photometric {
+    RawPhotometricInterpretation::Cfa(config) => config.cfa.shift(area.p.x, area.p.y).name,
+    RawPhotometricInterpretation::LinearRaw => "LinearRaw".into(),
+    RawPhotometricInterpretation::BlackIsZero => "BlackIsZero".into(),
+  }
+}
+
+/// Black levels for the first 2x2 pixels of the area, so black levels
+/// with different repeat dimensions or crop offsets are comparable.
+fn area_black_levels(rawimage: &RawImage, area: &Rect) -> Vec<f32> {
+  (0..2)
+    .flat_map(|row| (0..2).flat_map(move |col| (0..rawimage.cpp).map(move |sample| (row, col, sample))))
+    .map(|(row, col, sample)| black_level_at(&rawimage.blacklevel, area.p.y + row, area.p.x + col, sample))
+    .collect()
+}
+
+fn white_levels(rawimage: &RawImage) -> Vec<u32> {
+  (0..rawimage.cpp)
+    .map(|sample| rawimage.whitelevel.0.get(sample).or(rawimage.whitelevel.0.first()).copied().unwrap_or_default())
+    .collect()
+}
+
+/// White balance coefficients normalized to green
+fn normalized_wb(rawimage: &RawImage) -> Vec<f32> {
+  let green = rawimage.wb_coeffs[1];
+  rawimage
+    .wb_coeffs
+    .iter()
+    .filter(|c| !c.is_nan())
+    .map(|c| if green.is_normal() { c / green } else { *c })
+    .collect()
+}
+
+fn approx_eq(a: &[f32], b: &[f32]) -> bool {
+  a.len() == b.len()
+    && a
+      .iter()
+      .zip(b)
+      .all(|(a, b)| (a - b).abs() <= METADATA_TOLERANCE * a.abs().max(b.abs()).max(1.0))
+}
+
+fn compare_metadata(a: &RawImage, b: &RawImage, area_a: &Rect, area_b: &Rect) -> Vec<MetadataDifference> {
+  let mut diffs = Vec::new();
+  let mut check = |field: &str, value_a: String, value_b: String, equal: bool| {
+    if !equal {
+      diffs.push(MetadataDifference {
+        field: field.into(),
+        a: value_a,
+        b: value_b,
+      });
+    }
+  };
+
+  let (dim_a, dim_b) = (format!("{}x{}", a.width, a.height), format!("{}x{}", b.width, b.height));
+  check("dimension", dim_a.clone(), dim_b.clone(), dim_a == dim_b);
+  let area = |rawimage: &RawImage| format!("{:?}", rawimage.active_area.map(|area| area.as_ltrb()));
+  check("activeArea", area(a), area(b), a.active_area.map(|r| r.d) == b.active_area.map(|r| r.d));
+  check("cpp", a.cpp.to_string(), b.cpp.to_string(), a.cpp == b.cpp);
+  check("bitsPerSample", a.bps.to_string(), b.bps.to_string(), a.bps == b.bps);
+  let (cfa_a, cfa_b) = (cfa_name(a, area_a), cfa_name(b, area_b));
+  check("cfa", cfa_a.clone(), cfa_b.clone(), cfa_a == cfa_b);
+  let (black_a, black_b) = (area_black_levels(a, area_a), area_black_levels(b, area_b));
+  check("blackLevel", format!("{:?}", black_a), format!("{:?}", black_b), approx_eq(&black_a, &black_b));
+  let (white_a, white_b) = (white_levels(a), white_levels(b));
+  check("whiteLevel", format!("{:?}", white_a), format!("{:?}", white_b), white_a == white_b);
+  let (wb_a, wb_b) = (normalized_wb(a), normalized_wb(b));
+  check("wbCoeffs", format!("{:?}", wb_a), format!("{:?}", wb_b), approx_eq(&wb_a, &wb_b));
+
+  let illuminants: BTreeSet<_> = a.color_matrix.keys().chain(b.color_matrix.keys()).map(|illu| format!("{:?}", illu)).collect();
+  for illuminant in illuminants {
+    let find = |rawimage: &RawImage| {
+      rawimage
+        .color_matrix
+        .iter()
+        .find(|(illu, _)| format!("{:?}", illu) == illuminant)
+        .map(|(_, matrix)| matrix.clone())
+    };
+    let (matrix_a, matrix_b) = (find(a), find(b));
+    let equal = match (&matrix_a, &matrix_b) {
+      (Some(matrix_a), Some(matrix_b)) => approx_eq(matrix_a, matrix_b),
+      _ => false,
+    };
+    check(
+      &format!("colorMatrix{}", illuminant),
+      format!("{:?}", matrix_a),
+      format!("{:?}", matrix_b),
+      equal,
+    );
+  }
+  diffs
+}
+
+/// Compare metadata and pixels of two raw images.
+///
+/// Pixel values are compared without any scaling, so both images
+/// must use the same black and white levels to be identical.
+pub fn diff_raw_images(a: &RawImage, b: &RawImage) -> Result<RawDiff> {
+  let (area_a, area_b) = compare_areas(a, b);
+  let metadata = compare_metadata(a, b, &area_a, &area_b);
+
+  let mut channels = Vec::new();
+  if a.cpp == b.cpp {
+    let (layout, names) = ChannelLayout::new(a);
+    let (data_a, data_b) = (a.data.as_f32(), b.data.as_f32());
+    let white = white_levels(a);
+    let cpp = a.cpp;
+
+    #[derive(Default, Clone)]
+    struct Acc {
+      count: u64,
+      differing: u64,
+      max: f32,
+      sum_abs: f64,
+      sum_sq: f64,
+      peak: f32,
+    }
+    let mut acc = vec![Acc::default(); names.len()];
+    for row in 0..area_a.d.h {
+      for col in 0..area_a.d.w {
+        let (row_a, col_a, row_b, col_b) = (area_a.p.y + row, area_a.p.x + col, area_b.p.y + row, area_b.p.x + col);
+        for sample in 0..cpp {
+          let value_a = data_a[(row_a * a.width + col_a) * cpp + sample];
+          let value_b = data_b[(row_b * b.width + col_b) * cpp + sample];
+          let diff = (value_a - value_b).abs();
+          let channel = &mut acc[layout.channel(row_a, col_a, sample)];
+          channel.count += 1;
+          channel.peak = white[sample] as f32;
+          if diff > 0.0 {
+            channel.differing += 1;
+            channel.max = channel.max.max(diff);
+            channel.sum_abs += diff as f64;
+            channel.sum_sq += diff as f64 * diff as f64;
+          }
+        }
+      }
+    }
+    channels = acc
+      .into_iter()
+      .zip(names)
+      .filter(|(acc, _)| acc.count > 0)
+      .map(|(acc, name)| {
+        let mse = acc.sum_sq / acc.count as f64;
+        ChannelDifference {
+          name,
+          compared_pixels: acc.count,
+          differing_pixels: acc.differing,
+          max_abs_diff: acc.max,
+          mean_abs_diff: (acc.sum_abs / acc.count as f64) as f32,
+          psnr_db: (mse > 0.0).then(|| (10.0 * ((acc.peak as f64).powi(2) / mse).log10()) as f32),
+        }
+      })
+      .collect();
+  }
+
+  Ok(RawDiff {
+    area_a,
+    area_b,
+    metadata,
+    channels,
+  })
+}
+
+/// Create a heat map of the absolute pixel differences.
+///
+/// Identical pixels are black, differences are mapped from red (small)
+/// over yellow to white (largest difference in image).
+pub fn diff_heatmap(a: &RawImage, b: &RawImage) -> Result<DynamicImage> {
+  if a.cpp != b.cpp {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Can't compare images with different components per pixel: {} and {}",
+      a.cpp, b.cpp
+    )));
+  }
+  let (area_a, area_b) = compare_areas(a, b);
+  let (data_a, data_b) = (a.data.as_f32(), b.data.as_f32());
+  let cpp = a.cpp;
+  let diffs: Vec<f32> = (0..area_a.d.h)
+    .flat_map(|row| (0..area_a.d.w).map(move |col| (row, col)))
+    .map(|(row, col)| {
+      let offset_a = ((area_a.p.y + row) * a.width + area_a.p.x + col) * cpp;
+      let offset_b = ((area_b.p.y + row) * b.width + area_b.p.x + col) * cpp;
+      (0..cpp).map(|s| (data_a[offset_a + s] - data_b[offset_b + s]).abs()).fold(0.0, f32::max)
+    })
+    .collect();
+  let max = diffs.iter().copied().fold(0.0, f32::max);
+
+  let mut image = RgbImage::new(area_a.d.w as u32, area_a.d.h as u32);
+  for (pixel, diff) in image.pixels_mut().zip(diffs) {
+    if diff > 0.0 {
+      // Map into 0.25..1.0 so even tiny differences are visible
+      let v = 0.25 + 0.75 * diff / max;
+      *pixel = Rgb([v * 3.0, v * 3.0 - 1.0, v * 3.0 - 2.0].map(|c| (c.clamp(0.0, 1.0) * 255.0) as u8));
+    }
+  }
+  Ok(DynamicImage::ImageRgb8(image))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    CFA,
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, CFAConfig, WhiteLevel},
+  };
+  use image::GenericImageView;
+
+  fn bayer(width: usize, height: usize, f: impl Fn(usize, usize) -> u16) -> Result<RawImage> {
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    let mut pixels = PixU16::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        *pixels.at_mut(y, x) = f(y, x);
+      }
+    }
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)?);
+    RawImage::new(
+      cam,
+      pixels,
+      1,
+      [2.0, 1.0, 1.5, f32::NAN],
+      photometric,
+      Some(BlackLevel::new(&[64_u16], 1, 1, 1)),
+      Some(WhiteLevel::new([4095])),
+      false,
+    )
+  }
+
+  #[test]
+  fn diff_identical_and_modified() -> Result<()> {
+    let a = bayer(16, 8, |y, x| (y * 100 + x * 10) as u16)?;
+    assert!(diff_raw_images(&a, &a.clone())?.is_identical());
+
+    let mut b = bayer(16, 8, |y, x| if (y, x) == (2, 4) { 1000 } else { (y * 100 + x * 10) as u16 })?;
+    b.blacklevel = BlackLevel::new(&[64_u16, 64, 64, 60], 2, 2, 1);
+    let diff = diff_raw_images(&a, &b)?;
+    assert!(!diff.is_identical());
+    assert_eq!(diff.metadata.len(), 1);
+    assert_eq!(diff.metadata[0].field, "blackLevel");
+    let red = &diff.channels[0];
+    assert_eq!((red.name.as_str(), red.compared_pixels, red.differing_pixels), ("R", 32, 1));
+    assert_eq!(red.max_abs_diff, 760.0);
+    assert!(
+      diff.channels[1..]
+        .iter()
+        .all(|channel| channel.differing_pixels == 0 && channel.psnr_db.is_none())
+    );
+
+    let heatmap = diff_heatmap(&a, &b)?;
+    assert_eq!(heatmap.dimensions(), (16, 8));
+    assert_eq!(heatmap.to_rgb8().get_pixel(4, 2).0, [255, 255, 255]);
+    assert_eq!(heatmap.to_rgb8().get_pixel(5, 2).0, [0, 0, 0]);
+    Ok(())
+  }
+
+  #[test]
+  fn diff_aligns_active_area() -> Result<()> {
+    // Same image data, but B is cropped to the active area of A
+    let mut a = bayer(20, 10, |y, x| (y * 31 + x * 7) as u16)?;
+    a.active_area = Some(Rect::new(Point::new(4, 2), Dim2::new(16, 8)));
+    let b = bayer(16, 8, |y, x| ((y + 2) * 31 + (x + 4) * 7) as u16)?;
+    let diff = diff_raw_images(&a, &b)?;
+    assert_eq!(diff.metadata.iter().map(|d| d.field.as_str()).collect::<Vec<_>>(), ["dimension", "activeArea"]);
+    assert!(diff.channels.iter().all(|channel| channel.differing_pixels == 0));
+    Ok(())
+  }
+}
@@ -0,0 +1,430 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  RawImage, RawImageData, RawlerError, Result,
+  imgop::{Dim2, Point, Rect},
+  rawimage::{BlackLevel, RawPhotometricInterpretation},
+};
+
+/// Number of bins in reported histograms
+pub const HISTOGRAM_BINS: usize = 256;
+
+/// Fraction of pixels below the highlight level, the remaining
+/// pixels are treated as specular highlights or hot pixels.
+const HIGHLIGHT_PERCENTILE: f64 = 0.999;
+
+/// Histogram of raw values, bin `i` covers the range
+/// `i * bin_width .. (i + 1) * bin_width`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Histogram {
+  pub bin_width: u32,
+  pub counts: Vec<u64>,
+}
+
+/// Statistics for a single CFA position or color channel
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelStatistics {
+  pub name: String,
+  pub pixel_count: u64,
+  pub min: u16,
+  pub max: u16,
+  pub mean: f32,
+  /// Black level from file metadata
+  pub black_level: f32,
+  /// Black level measured in masked sensor areas
+  pub measured_black_level: Option<f32>,
+  /// Difference between measured and tagged black level
+  pub black_level_deviation: Option<f32>,
+  /// Standard deviation of masked sensor areas
+  pub noise_stddev: Option<f32>,
+  pub white_level: u32,
+  pub clipped_pixels: u64,
+  pub clipped_percent: f32,
+  /// Raw value of the 99.9th percentile
+  pub highlight_level: u16,
+  /// Headroom between highlight level and white level in EV
+  pub exposure_margin_ev: Option<f32>,
+  pub histogram: Histogram,
+}
+
+/// Exposure summary over all channels
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExposureStatistics {
+  /// Smallest headroom of all channels in EV, the exposure could
+  /// be increased by this value (expose to the right).
+  pub margin_ev: Option<f32>,
+  /// Channel with the smallest headroom
+  pub limiting_channel: Option<String>,
+  pub clipped_pixels: u64,
+  pub clipped_percent: f32,
+}
+
+/// Statistics of raw pixel data
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawStatistics {
+  /// Bit depth from file metadata
+  pub bit_depth: usize,
+  /// Bits actually used by the pixel values
+  pub effective_bit_depth: u32,
+  /// Engineering dynamic range (white level over noise) in EV
+  pub dynamic_range_ev: Option<f32>,
+  /// Image area used for statistics
+  pub area: Rect,
+  pub channels: Vec<ChannelStatistics>,
+  pub exposure: ExposureStatistics,
+}
+
+/// Accumulator for a single channel
+struct Accumulator {
+  histogram: Vec<u64>,
+  count: u64,
+  sum: f64,
+  black_sum: f64,
+  masked_count: u64,
+  masked_sum: f64,
+  masked_sum_sq: f64,
+  white_level: u32,
+}
+
+impl Default for Accumulator {
+  fn default() -> Self {
+    Self {
+      histogram: vec![0; u16::MAX as usize + 1],
+      count: 0,
+      sum: 0.0,
+      black_sum: 0.0,
+      masked_count: 0,
+      masked_sum: 0.0,
+      masked_sum_sq: 0.0,
+      white_level: 0,
+    }
+  }
+}
+
+impl Accumulator {
+  /// Find the smallest value for which `fraction` of all pixels are
+  /// less or equal.
+  fn percentile(&self, fraction: f64) -> u16 {
+    let limit = (self.count as f64 * fraction).ceil() as u64;
+    let mut cumulative = 0;
+    for (value, count) in self.histogram.iter().enumerate() {
+      cumulative += count;
+      if cumulative >= limit && cumulative > 0 {
+        return value as u16;
+      }
+    }
+    u16::MAX
+  }
+
+  fn finalize(&self, name: String) -> ChannelStatistics {
+    let min = self.histogram.iter().position(|c| *c > 0).unwrap_or_default() as u16;
+    let max = self.histogram.iter().rposition(|c| *c > 0).unwrap_or_default() as u16;
+    let black_level = if self.count > 0 { (self.black_sum / self.count as f64) as f32 } else { 0.0 };
+    let (measured_black_level, noise_stddev) = if self.masked_count > 1 {
+      let mean = self.masked_sum / self.masked_count as f64;
+      let variance = (self.masked_sum_sq / self.masked_count as f64 - mean * mean).max(0.0);
+      (Some(mean as f32), Some(variance.sqrt() as f32))
+    } else {
+      (None, None)
+    };
+    let clipped_pixels: u64 = self.histogram.iter().skip(self.white_level as usize).sum();
+    let highlight_level = self.percentile(HIGHLIGHT_PERCENTILE);
+    let range = self.white_level as f32 - black_level;
+    let exposure_margin_ev = if range > 0.0 && highlight_level as f32 > black_level {
+      Some((range / (highlight_level as f32 - black_level)).log2())
+    } else {
+      None
+    };
+
+    let bin_width = (self.white_level.max(max as u32) + 1).div_ceil(HISTOGRAM_BINS as u32).max(1);
+    let mut counts = vec![0; HISTOGRAM_BINS];
+    for (value, count) in self.histogram.iter().enumerate().filter(|(_, count)| **count > 0) {
+      counts[(value / bin_width as usize).min(HISTOGRAM_BINS - 1)] += count;
+    }
+
+    ChannelStatistics {
+      name,
+      pixel_count: self.count,
+      min,
+      max,
+      mean: if self.count > 0 { (self.sum / self.count as f64) as f32 } else { 0.0 },
+      black_level,
+      measured_black_level,
+      black_level_deviation: measured_black_level.map(|measured| measured - black_level),
+      noise_stddev,
+      white_level: self.white_level,
+      clipped_pixels,
+      clipped_percent: percent(clipped_pixels, self.count),
+      highlight_level,
+      exposure_margin_ev,
+      histogram: Histogram { bin_width, counts },
+    }
+  }
+}
+
+fn percent(part: u64, total: u64) -> f32 {
+  if total > 0 { (part as f64 * 100.0 / total as f64) as f32 } else { 0.0 }
+}
+
+/// Channel layout of raw data, maps pixel positions to channels
+pub(crate) enum ChannelLayout {
+  /// Each position of a small CFA pattern is a channel (e.g. R, G1, G2, B)
+  CfaPosition { width: usize, height: usize },
+  /// Each CFA color is a channel, used for large patterns like X-Trans
+  CfaColor(Box<crate::CFA>),
+  /// Each sample of a pixel is a channel
+  Sample,
+}
+
+impl ChannelLayout {
+  pub(crate) fn new(rawimage: &RawImage) -> (Self, Vec<String>) {
+    match &rawimage.photometric {
+      RawPhotometricInterpretation::Cfa(config) if rawimage.cpp == 1 && config.cfa.width > 0 => {
+        let cfa = &config.cfa;
+        let colors: Vec<char> = cfa.name.chars().collect();
+        if cfa.width * cfa.height <= 4 {
+          let names = colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| {
+              let duplicates = colors.iter().filter(|c| *c == color).count();
+              if duplicates > 1 {
+                format!("{}{}", color, colors[..=i].iter().filter(|c| *c == color).count())
+              } else {
+                color.to_string()
+              }
+            })
+            .collect();
+          (
+            Self::CfaPosition {
+              width: cfa.width,
+              height: cfa.height,
+            },
+            names,
+          )
+        } else {
+          let color_count = (0..cfa.height)
+            .flat_map(|row| (0..cfa.width).map(move |col| cfa.color_at(row, col)))
+            .max()
+            .unwrap_or_default()
+            + 1;
+          let names = (0..color_count)
+            .map(|color| {
+              (0..cfa.height * cfa.width)
+                .find(|i| cfa.color_at(i / cfa.width, i % cfa.width) == color)
+                .map(|i| colors[i].to_string())
+                .unwrap_or_else(|| format!("C{}", color))
+            })
+            .collect();
+          (Self::CfaColor(Box::new(cfa.clone())), names)
+        }
+      }
+      _ => {
+        let names = match rawimage.cpp {
+          1 => vec!["Y".to_string()],
+          3 => vec!["R".to_string(), "G".to_string(), "B".to_string()],
+          cpp => (0..cpp).map(|s| format!("C{}", s)).collect(),
+        };
+        (Self::Sample, names)
+      }
+    }
+  }
+
+  pub(crate) fn channel(&self, row: usize, col: usize, sample: usize) -> usize {
+    match self {
+      Self::CfaPosition { width, height } => (row % height) * width + (col % width),
+      Self::CfaColor(cfa) => cfa.color_at(row, col),
+      Self::Sample => sample,
+    }
+  }
+}
+
+/// Limit rectangle to the image dimension
+pub(super) fn clip_rect(rect: Rect, rawimage: &RawImage) -> Rect {
+  let x = rect.p.x.min(rawimage.width);
+  let y = rect.p.y.min(rawimage.height);
+  Rect::new(Point::new(x, y), Dim2::new(rect.d.w.min(rawimage.width - x), rect.d.h.min(rawimage.height - y)))
+}
+
+/// Tagged black level for a pixel position and sample
+pub(crate) fn black_level_at(blacklevel: &BlackLevel, row: usize, col: usize, sample: usize) -> f32 {
+  let index = ((row % blacklevel.height) * blacklevel.width + (col % blacklevel.width)) * blacklevel.cpp + sample.min(blacklevel.cpp - 1);
+  blacklevel.levels.get(index).map(|level| level.as_f32()).unwrap_or_default()
+}
+
+/// Calculate statistics for raw pixel data.
+///
+/// Statistics are calculated for the active area, masked areas
+/// (`blackareas`) are used to measure black level and noise.
+pub fn raw_statistics(rawimage: &RawImage) -> Result<RawStatistics> {
+  let RawImageData::Integer(data) = &rawimage.data else {
+    return Err(RawlerError::DecoderFailed("Statistics are only supported for integer raw data".into()));
+  };
+  let (layout, names) = ChannelLayout::new(rawimage);
+  let cpp = rawimage.cpp;
+  let area = clip_rect(rawimage.active_area.unwrap_or_else(|| Rect::new(Point::zero(), rawimage.dim())), rawimage);
+
+  let mut acc: Vec<Accumulator> = names.iter().map(|_| Accumulator::default()).collect();
+  for (s, channel) in acc.iter_mut().enumerate() {
+    let index = if cpp > 1 { s } else { 0 };
+    channel.white_level = rawimage
+      .whitelevel
+      .0
+      .get(index)
+      .or(rawimage.whitelevel.0.first())
+      .copied()
+      .unwrap_or(u16::MAX as u32);
+  }
+
+  let mut used_bits = 0_u16;
+  for row in area.p.y..area.p.y + area.d.h {
+    let line = &data[row * rawimage.width * cpp..(row + 1) * rawimage.width * cpp];
+    for col in area.p.x..area.p.x + area.d.w {
+      for sample in 0..cpp {
+        let value = line[col * cpp + sample];
+        let channel = &mut acc[layout.channel(row, col, sample)];
+        channel.histogram[value as usize] += 1;
+        channel.count += 1;
+        channel.sum += value as f64;
+        channel.black_sum += black_level_at(&rawimage.blacklevel, row, col, sample) as f64;
+        used_bits |= value;
+      }
+    }
+  }
+
+  for blackarea in &rawimage.blackareas {
+    let blackarea = clip_rect(*blackarea, rawimage);
+    for row in blackarea.p.y..blackarea.p.y + blackarea.d.h {
+      for col in blackarea.p.x..blackarea.p.x + blackarea.d.w {
+        for sample in 0..cpp {
+          let value = data[(row * rawimage.width + col) * cpp + sample] as f64;
+          let channel = &mut acc[layout.channel(row, col, sample)];
+          channel.masked_count += 1;
+          channel.masked_sum += value;
+          channel.masked_sum_sq += value * value;
+        }
+      }
+    }
+  }
+
+  let channels: Vec<ChannelStatistics> = acc
+    .iter()
+    .zip(names)
+    .filter(|(acc, _)| acc.count > 0)
+    .map(|(acc, name)| acc.finalize(name))
+    .collect();
+
+  let effective_bit_depth = if used_bits > 0 {
+    (16 - used_bits.leading_zeros()) - used_bits.trailing_zeros()
+  } else {
+    0
+  };
+
+  let noise: Vec<f32> = channels.iter().filter_map(|channel| channel.noise_stddev).collect();
+  let dynamic_range_ev = if !noise.is_empty() && channels.len() == noise.len() {
+    let noise = noise.iter().sum::<f32>() / noise.len() as f32;
+    let range = channels.iter().map(|channel| channel.white_level as f32 - channel.black_level).sum::<f32>() / channels.len() as f32;
+    (noise > 0.0 && range > 0.0).then(|| (range / noise).log2())
+  } else {
+    None
+  };
+
+  let limiting = channels
+    .iter()
+    .filter_map(|channel| channel.exposure_margin_ev.map(|margin| (margin, channel)))
+    .min_by(|a, b| a.0.total_cmp(&b.0));
+  let clipped_pixels = channels.iter().map(|channel| channel.clipped_pixels).sum();
+  let pixel_count = channels.iter().map(|channel| channel.pixel_count).sum();
+  let exposure = ExposureStatistics {
+    margin_ev: limiting.map(|(margin, _)| margin),
+    limiting_channel: limiting.map(|(_, channel)| channel.name.clone()),
+    clipped_pixels,
+    clipped_percent: percent(clipped_pixels, pixel_count),
+  };
+
+  Ok(RawStatistics {
+    bit_depth: rawimage.bps,
+    effective_bit_depth,
+    dynamic_range_ev,
+    area,
+    channels,
+    exposure,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    CFA,
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{CFAConfig, WhiteLevel},
+  };
+
+  #[test]
+  fn bayer_statistics() -> Result<()> {
+    let (width, height) = (40, 20);
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    // Values are multiple of 4 (14 bit data in 16 bit container), first
+    // 8 columns are masked with alternating 252/260 (mean 256, stddev 4).
+    let mut pixels = PixU16::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        *pixels.at_mut(y, x) = match (x, cam.cfa.color_at(y, x)) {
+          (0..8, _) => 252 + 8 * ((y / 2) % 2) as u16,
+          (_, 0) if x >= 36 => 16380,
+          (_, 0) => 8000,
+          (_, 1) => 4000,
+          _ => 1000,
+        };
+      }
+    }
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)?);
+    let mut rawimage = RawImage::new(
+      cam,
+      pixels,
+      1,
+      [1.0, 1.0, 1.0, f32::NAN],
+      photometric,
+      Some(BlackLevel::new(&[250_u16], 1, 1, 1)),
+      Some(WhiteLevel::new([16380])),
+      false,
+    )?;
+    rawimage.bps = 16;
+    rawimage.active_area = Some(Rect::new(Point::new(8, 0), Dim2::new(32, 20)));
+    rawimage.blackareas = vec![Rect::new(Point::new(0, 0), Dim2::new(8, 20))];
+
+    let stats = raw_statistics(&rawimage)?;
+    let names: Vec<&str> = stats.channels.iter().map(|channel| channel.name.as_str()).collect();
+    assert_eq!(names, ["R", "G1", "G2", "B"]);
+    assert_eq!(stats.effective_bit_depth, 12);
+
+    let red = &stats.channels[0];
+    assert_eq!(red.pixel_count, 160);
+    assert_eq!(red.clipped_pixels, 20);
+    assert_eq!(red.highlight_level, 16380);
+    assert_eq!(red.exposure_margin_ev, Some(0.0));
+    assert_eq!(red.histogram.counts.iter().sum::<u64>(), 160);
+    assert_eq!(red.measured_black_level, Some(256.0));
+    assert_eq!(red.black_level_deviation, Some(6.0));
+    assert!((red.noise_stddev.expect("masked area") - 4.0).abs() < 0.01);
+
+    let blue = &stats.channels[3];
+    assert_eq!(blue.clipped_pixels, 0);
+    assert!((blue.exposure_margin_ev.expect("margin") - (16130.0_f32 / 750.0).log2()).abs() < 0.001);
+
+    assert_eq!(stats.exposure.limiting_channel.as_deref(), Some("R"));
+    assert_eq!(stats.exposure.clipped_pixels, 20);
+    assert!((stats.dynamic_range_ev.expect("noise is known") - (16130.0_f32 / 4.0).log2()).abs() < 0.01);
+    Ok(())
+  }
+}
@@ -353,6 +353,62 @@ pub(crate) fn extend_binary_floating_point<NARROW: FloatingPointParameters, WIDE
   return (sign << WIDE::SIGN_BIT_POS) | (wide_exponent << WIDE::EXPONENT_POS) | wide_fraction;
 }
 
+/// Narrow a floating point value to a smaller format, rounding to nearest even.
+///
+/// Values above the narrow range become infinity, values below the
+/// smallest subnormal become zero.
+pub(crate) fn narrow_binary_floating_point<WIDE: FloatingPointParameters, NARROW: FloatingPointParameters>(value: u32) -> u32 {
+  let sign = (value >> WIDE::SIGN_BIT_POS) & 1;
+  let wide_exponent = (value >> WIDE::EXPONENT_POS) & ((1 << WIDE::EXPONENT_WIDTH) - 1);
+  let wide_fraction = value & ((1 << WIDE::FRACTION_WIDTH) - 1);
+  let narrow_exponent_max = (1 << NARROW::EXPONENT_WIDTH) - 1;
+  let shift = (WIDE::FRACTION_WIDTH - NARROW::FRACTION_WIDTH) as u32;
+
+  let bits = if wide_exponent == ((1 << WIDE::EXPONENT_WIDTH) - 1) {
+    // Infinity or NaN, make sure NaN stays NaN
+    let fraction = if wide_fraction != 0 { (wide_fraction >> shift) | 1 } else { 0 };
+    (narrow_exponent_max << NARROW::EXPONENT_POS) | fraction
+  } else if wide_exponent == 0 {
+    // Zero and wide subnormals are too small for the narrow format
+    0
+  } else {
+    let exponent = wide_exponent as i32 - WIDE::BIAS + NARROW::BIAS;
+    let mantissa = wide_fraction | (1 << WIDE::FRACTION_WIDTH);
+    if exponent >= narrow_exponent_max as i32 {
+      narrow_exponent_max << NARROW::EXPONENT_POS
+    } else if exponent <= 0 {
+      // Subnormal numbers, a carry into the exponent field gives the smallest normal number
+      let subnormal_shift = shift + (1 - exponent) as u32;
+      if subnormal_shift > WIDE::FRACTION_WIDTH as u32 + 1 {
+        0
+      } else {
+        round_shift_right(mantissa, subnormal_shift)
+      }
+    } else {
+      // Rounding may carry into the exponent field, which is still correct
+      // and overflows to infinity for the largest values.
+      let fraction = round_shift_right(mantissa, shift) - (1 << NARROW::FRACTION_WIDTH);
+      ((exponent as u32) << NARROW::EXPONENT_POS) + fraction
+    }
+  };
+  (sign << NARROW::SIGN_BIT_POS) | bits
+}
+
+/// Shift right and round to nearest, ties to even.
+fn round_shift_right(value: u32, shift: u32) -> u32 {
+  if shift == 0 {
+    return value;
+  }
+  let half = 1 << (shift - 1);
+  let rem = value & ((1 << shift) - 1);
+  let quotient = value >> shift;
+  if rem > half || (rem == half && quotient & 1 == 1) {
+    quotient + 1
+  } else {
+    quotient
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -377,4 +433,28 @@ mod tests {
     assert_eq!(Binary32::SIGN_BIT_POS, 31);
     assert_eq!(Binary32::STORAGE_BYTES, 4);
   }
+
+  #[test]
+  fn narrow_floating_point_roundtrip() {
+    fn f16_roundtrip(v: f32) -> f32 {
+      let narrow = narrow_binary_floating_point::<Binary32, Binary16>(v.to_bits());
+      f32::from_bits(extend_binary_floating_point::<Binary16, Binary32>(narrow))
+    }
+    fn f24_roundtrip(v: f32) -> f32 {
+      let narrow = narrow_binary_floating_point::<Binary32, Binary24>(v.to_bits());
+      f32::from_bits(extend_binary_floating_point::<Binary24, Binary32>(narrow))
+    }
+    for v in [0.0, 1.0, -2.5, 0.5, 0.1, 65504.0, 6.1035156e-5] {
+      assert!((f16_roundtrip(v) - v).abs() <= v.abs() / 1024.0, "f16: {}", v);
+      assert!((f24_roundtrip(v) - v).abs() <= v.abs() / 65536.0, "f24: {}", v);
+    }
+    // Subnormal half
+    assert!((f16_roundtrip(1.0e-5) - 1.0e-5).abs() < 6.0e-8);
+    // Rounding up to the next power of two
+    assert_eq!(f16_roundtrip(2047.9), 2048.0);
+    assert_eq!(f16_roundtrip(1.0e6), f32::INFINITY);
+    assert_eq!(f16_roundtrip(-1.0e6), f32::NEG_INFINITY);
+    assert!(f16_roundtrip(f32::NAN).is_nan());
+    assert_eq!(f16_roundtrip(1.0e-10), 0.0);
+  }
 }
@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Calibration with bias, dark and flat frames
+//!
+//! Master frames are stacked from multiple raw frames and applied to
+//! light frames of the same camera and mode:
+//!
+//! * The master dark (or the master bias, if no darks are given) is subtracted
+//!   and the black level of the light frame is restored as pedestal.
+//! * The light is divided by the normalized master flat, each CFA position
+//!   (or color channel for non-CFA images) is normalized on its own.
+//! * Hot pixels found in the master dark are replaced by the median of their
+//!   neighbours of the same color.
+//!
+//! Darks are expected to match the exposure time and temperature of the lights,
+//! no scaling is applied.
+
+use rayon::prelude::*;
+
+use crate::{
+  RawImage, RawImageData, RawlerError, Result,
+  analyze::stats::{ChannelLayout, black_level_at},
+  compose::check_compatible,
+  pixarray::PixF32,
+};
+
+/// Method to combine the frames for a master frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StackMethod {
+  /// Median of all values
+  Median,
+  /// Mean after iterative rejection of values more than `sigma` standard deviations off the median
+  SigmaClip { sigma: f32, iterations: usize },
+}
+
+impl Default for StackMethod {
+  fn default() -> Self {
+    Self::SigmaClip { sigma: 3.0, iterations: 5 }
+  }
+}
+
+impl StackMethod {
+  fn combine(&self, values: &mut [f32]) -> f32 {
+    match *self {
+      Self::Median => median(values),
+      Self::SigmaClip { sigma, iterations } => sigma_clipped_mean(values, sigma, iterations),
+    }
+  }
+}
+
+/// Stacked calibration frame
+///
+/// Pixel data is stored with `width * cpp` samples per row.
+#[derive(Clone)]
+pub struct MasterFrame {
+  pub pixels: PixF32,
+  pub cpp: usize,
+}
+
+impl MasterFrame {
+  /// Stack raw frames into a master frame. Values are not scaled,
+  /// so the black level is still included.
+  pub fn stack(frames: &[RawImage], method: StackMethod) -> Result<Self> {
+    check_compatible(frames)?;
+    let reference = &frames[0];
+    let planes: Vec<_> = frames.iter().map(|frame| frame.data.as_f32()).collect();
+    let mut data = vec![0.0_f32; planes[0].len()];
+    let sample_width = reference.width * reference.cpp;
+    data.par_chunks_exact_mut(sample_width).enumerate().for_each(|(row, line)| {
+      let mut values = vec![0.0; planes.len()];
+      for (col, out) in line.iter_mut().enumerate() {
+        for (value, plane) in values.iter_mut().zip(planes.iter()) {
+          *value = plane[row * sample_width + col];
+        }
+        *out = method.combine(&mut values);
+      }
+    });
+    Ok(Self {
+      pixels: PixF32::new_with(data, sample_width, reference.height),
+      cpp: reference.cpp,
+    })
+  }
+
+  /// Stack flat frames and normalize them to a mean of 1.0 for each
+  /// CFA position or color channel.
+  ///
+  /// The master bias is subtracted from the flats, if given.
+  /// Otherwise, the black level of the flat frames is used.
+  pub fn stack_flat(frames: &[RawImage], bias: Option<&MasterFrame>, method: StackMethod) -> Result<Self> {
+    let mut flat = Self::stack(frames, method)?;
+    let reference = &frames[0];
+    if let Some(bias) = bias {
+      flat.check_dimension(reference)?;
+      bias.check_dimension(reference)?;
+    }
+    let cpp = reference.cpp;
+    let (layout, names) = ChannelLayout::new(reference);
+    let mut sums = vec![0.0_f64; names.len()];
+    let mut counts = vec![0_usize; names.len()];
+    for row in 0..flat.pixels.height {
+      for col in 0..flat.pixels.width {
+        let idx = row * flat.pixels.width + col;
+        let offset = match bias {
+          Some(bias) => bias.pixels.data[idx],
+          None => black_level_at(&reference.blacklevel, row, col / cpp, col % cpp),
+        };
+        let value = (flat.pixels.data[idx] - offset).max(0.0);
+        flat.pixels.data[idx] = value;
+        let channel = layout.channel(row, col / cpp, col % cpp);
+        sums[channel] += value as f64;
+        counts[channel] += 1;
+      }
+    }
+    let means: Vec<f32> = sums
+      .iter()
+      .zip(counts.iter())
+      .map(|(sum, count)| (sum / (*count).max(1) as f64) as f32)
+      .collect();
+    log::debug!("Flat channel means: {:?}", means);
+    if means.iter().any(|mean| *mean <= 0.0) {
+      return Err(RawlerError::DecoderFailed("Master flat has no signal in at least one channel".into()));
+    }
+    for row in 0..flat.pixels.height {
+      for col in 0..flat.pixels.width {
+        flat.pixels.data[row * flat.pixels.width + col] /= means[layout.channel(row, col / cpp, col % cpp)];
+      }
+    }
+    Ok(flat)
+  }
+
+  fn check_dimension(&self, rawimage: &RawImage) -> Result<()> {
+    if self.pixels.width != rawimage.width * rawimage.cpp || self.pixels.height != rawimage.height || self.cpp != rawimage.cpp {
+      return Err(RawlerError::DecoderFailed(format!(
+        "Calibration frame has dimension {}x{} (cpp: {}), but image has {}x{} (cpp: {})",
+        self.pixels.width / self.cpp,
+        self.pixels.height,
+        self.cpp,
+        rawimage.width,
+        rawimage.height,
+        rawimage.cpp
+      )));
+    }
+    Ok(())
+  }
+}
+
+/// Map of hot pixels, stored as sample indices
+#[derive(Clone, Debug, Default)]
+pub struct HotPixelMap {
+  pub pixels: Vec<usize>,
+}
+
+impl HotPixelMap {
+  /// Detect hot pixels in a master dark. Pixels above the median
+  /// by more than `sigma` robust standard deviations (from the median
+  /// absolute deviation) are hot.
+  pub fn from_dark(dark: &MasterFrame, sigma: f32) -> Self {
+    let mut values = dark.pixels.data.clone();
+    let med = median(&mut values);
+    values.iter_mut().zip(dark.pixels.data.iter()).for_each(|(dev, v)| *dev = (v - med).abs());
+    let mad = median(&mut values) * 1.4826;
+    // A noise free dark would mark every pixel above the median
+    let threshold = med + sigma * mad.max(1.0);
+    let pixels: Vec<usize> = dark.pixels.data.iter().enumerate().filter(|(_, v)| **v > threshold).map(|(i, _)| i).collect();
+    log::debug!("Hot pixel detection: median: {}, MAD: {}, found: {}", med, mad, pixels.len());
+    Self { pixels }
+  }
+
+  pub fn len(&self) -> usize {
+    self.pixels.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.pixels.is_empty()
+  }
+}
+
+/// Set of master frames to calibrate light frames
+#[derive(Clone, Default)]
+pub struct Calibration {
+  pub bias: Option<MasterFrame>,
+  pub dark: Option<MasterFrame>,
+  pub flat: Option<MasterFrame>,
+  pub hot_pixels: Option<HotPixelMap>,
+}
+
+impl Calibration {
+  /// Apply calibration to a raw image.
+  ///
+  /// Black and white level of the image are not modified.
+  pub fn apply(&self, rawimage: &mut RawImage) -> Result<()> {
+    let offset = self.dark.as_ref().or(self.bias.as_ref());
+    for master in [offset, self.flat.as_ref()].into_iter().flatten() {
+      master.check_dimension(rawimage)?;
+    }
+    let (layout, _) = ChannelLayout::new(rawimage);
+    let cpp = rawimage.cpp;
+    let blacklevel = rawimage.blacklevel.clone();
+    let sample_width = rawimage.width * rawimage.cpp;
+    let data = match &mut rawimage.data {
+      RawImageData::Integer(data) => data,
+      RawImageData::Float(_) => return Err(RawlerError::unsupported_feature("calibration", "floating point raw data")),
+    };
+
+    data.par_chunks_exact_mut(sample_width).enumerate().for_each(|(row, line)| {
+      for (col, pix) in line.iter_mut().enumerate() {
+        let idx = row * sample_width + col;
+        let mut value = *pix as f32;
+        if let Some(offset) = offset {
+          value = value - offset.pixels.data[idx] + black_level_at(&blacklevel, row, col / cpp, col % cpp);
+        }
+        if let Some(flat) = &self.flat {
+          let pedestal = black_level_at(&blacklevel, row, col / cpp, col % cpp);
+          let gain = flat.pixels.data[idx];
+          if gain > f32::EPSILON {
+            value = (value - pedestal) / gain + pedestal;
+          }
+        }
+        *pix = value.round().clamp(0.0, u16::MAX as f32) as u16;
+      }
+    });
+
+    if let Some(hot_pixels) = &self.hot_pixels {
+      let width = sample_width;
+      let height = rawimage.height;
+      let len = data.len();
+      let mut is_hot = vec![false; len];
+      hot_pixels.pixels.iter().filter(|i| **i < len).for_each(|i| is_hot[*i] = true);
+      let source = data.clone();
+      for &idx in hot_pixels.pixels.iter().filter(|i| **i < len) {
+        let (row, col) = (idx / width, idx % width);
+        let channel = layout.channel(row, col / cpp, col % cpp);
+        let mut neighbours: Vec<f32> = Vec::with_capacity(24);
+        for nrow in row.saturating_sub(2)..(row + 3).min(height) {
+          for ncol in col.saturating_sub(2 * cpp)..(col + 2 * cpp + 1).min(width) {
+            let nidx = nrow * width + ncol;
+            if nidx != idx && !is_hot[nidx] && layout.channel(nrow, ncol / cpp, ncol % cpp) == channel {
+              neighbours.push(source[nidx] as f32);
+            }
+          }
+        }
+        if !neighbours.is_empty() {
+          data[idx] = median(&mut neighbours).round() as u16;
+        }
+      }
+    }
+    Ok(())
+  }
+}
+
+fn median(values: &mut [f32]) -> f32 {
+  if values.is_empty() {
+    return 0.0;
+  }
+  values.sort_unstable_by(f32::total_cmp);
+  let mid = values.len() / 2;
+  if values.len().is_multiple_of(2) {
+    (values[mid - 1] + values[mid]) / 2.0
+  } else {
+    values[mid]
+  }
+}
+
+fn sigma_clipped_mean(values: &mut [f32], sigma: f32, iterations: usize) -> f32 {
+  // The deviation is estimated from the median absolute deviation, as the
+  // standard deviation of a few frames is dominated by the outliers.
+  let mut kept = values.len();
+  for _ in 0..iterations {
+    let center = median(&mut values[..kept]);
+    let mut deviations: Vec<f32> = values[..kept].iter().map(|v| (v - center).abs()).collect();
+    let limit = median(&mut deviations) * 1.4826 * sigma;
+    values[..kept].sort_unstable_by(|a, b| (a - center).abs().total_cmp(&(b - center).abs()));
+    let n = values[..kept].iter().take_while(|v| (**v - center).abs() <= limit).count();
+    if n == kept || n == 0 {
+      break;
+    }
+    kept = n;
+  }
+  values[..kept].iter().sum::<f32>() / kept.max(1) as f32
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    CFA,
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, CFAConfig, RawPhotometricInterpretation, WhiteLevel},
+  };
+
+  const WIDTH: usize = 8;
+  const HEIGHT: usize = 6;
+
+  fn frame(f: impl Fn(usize, usize) -> u16) -> RawImage {
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    let mut pixels = PixU16::new(WIDTH, HEIGHT);
+    for row in 0..HEIGHT {
+      for col in 0..WIDTH {
+        *pixels.at_mut(row, col) = f(row, col);
+      }
+    }
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam).expect("Bayer CFA"));
+    RawImage::new(
+      cam,
+      pixels,
+      1,
+      [1.0, 1.0, 1.0, f32::NAN],
+      photometric,
+      Some(BlackLevel::new(&[100_u16], 1, 1, 1)),
+      Some(WhiteLevel::new([4095])),
+      false,
+    )
+    .expect("valid raw image")
+  }
+
+  #[test]
+  fn stack_methods() {
+    assert_eq!(median(&mut [3.0, 1.0, 2.0]), 2.0);
+    assert_eq!(median(&mut [4.0, 1.0, 2.0, 3.0]), 2.5);
+    let mut values = [10.0, 10.0, 11.0, 9.0, 10.0, 10.0, 11.0, 9.0, 1000.0];
+    assert!((sigma_clipped_mean(&mut values, 3.0, 5) - 10.0).abs() < 1.0e-4);
+  }
+
+  #[test]
+  fn stack_rejects_outliers() -> Result<()> {
+    let mut frames: Vec<RawImage> = (0..5).map(|_| frame(|_, _| 200)).collect();
+    frames.push(frame(|row, col| if row == 1 && col == 1 { 4000 } else { 200 }));
+    for method in [StackMethod::Median, StackMethod::default()] {
+      let master = MasterFrame::stack(&frames, method)?;
+      assert!(master.pixels.data.iter().all(|v| *v == 200.0), "{:?}", method);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn calibrate_dark_flat_hot_pixels() -> Result<()> {
+    // Dark current of 20 and one hot pixel
+    let dark = MasterFrame::stack(&[frame(|row, col| if row == 2 && col == 2 { 3000 } else { 120 })], StackMethod::Median)?;
+    // Vignetting: left half receives only half of the light, blue is darker in general
+    let vignetting = |row: usize, col: usize| if col < WIDTH / 2 { 0.5 } else { 1.0 } * if row % 2 == 1 && col % 2 == 1 { 0.5 } else { 1.0 };
+    let flats: Vec<RawImage> = (0..3).map(|_| frame(|row, col| 100 + (2000.0 * vignetting(row, col)) as u16)).collect();
+    let flat = MasterFrame::stack_flat(&flats, None, StackMethod::default())?;
+
+    let calibration = Calibration {
+      hot_pixels: Some(HotPixelMap::from_dark(&dark, 5.0)),
+      dark: Some(dark),
+      flat: Some(flat),
+      bias: None,
+    };
+    assert_eq!(calibration.hot_pixels.as_ref().map(HotPixelMap::len), Some(1));
+
+    let mut light = frame(|row, col| (120.0 + 1000.0 * vignetting(row, col)) as u16 + if row == 2 && col == 2 { 2880 } else { 0 });
+    calibration.apply(&mut light)?;
+    let pixels = light.pixels_u16();
+    // Blacklevel is kept, flat gain is normalized to a mean of 1.0 for each channel,
+    // so the vignetting is removed but the blue channel is still darker.
+    for row in 0..HEIGHT {
+      for col in 0..WIDTH {
+        let expected = if row % 2 == 1 && col % 2 == 1 { 100 + 375 } else { 100 + 750 };
+        let value = pixels[row * WIDTH + col] as i32;
+        assert!((value - expected).abs() <= 1, "pixel {},{}: {} != {}", row, col, value, expected);
+      }
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,264 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! HDR merge of exposure brackets
+//!
+//! All frames are normalized by their exposure (time, aperture and ISO)
+//! and merged into a single floating point image, still in the raw
+//! mosaic of the sensor. Frames must be aligned (taken from a tripod).
+
+use rayon::prelude::*;
+
+use crate::{
+  RawImage, RawImageData, RawlerError, Result,
+  decoders::{BlackLevel, RawPhotometricInterpretation, WhiteLevel},
+  exif::Exif,
+  formats::tiff::SRational,
+  tags::DngTag,
+};
+
+use super::{Frame, check_compatible};
+
+/// Normalized values above this level are treated as clipped
+const CLIPPING_LEVEL: f32 = 0.98;
+
+/// Parameters for HDR merge
+#[derive(Debug, Clone)]
+pub struct HdrMergeParams {
+  /// Bits per sample for the floating point output (16, 24 or 32)
+  pub bits: usize,
+}
+
+impl Default for HdrMergeParams {
+  fn default() -> Self {
+    Self { bits: 16 }
+  }
+}
+
+/// Relative exposure of each frame: exposure time * ISO / fnumber²
+///
+/// Aperture and ISO are only used if all frames provide them, otherwise
+/// frames would be scaled by made-up values. If none of the frames
+/// provides one of them, it is ignored for all frames.
+pub fn relative_exposures(frames: &[Frame]) -> Result<Vec<f32>> {
+  let times = frames
+    .iter()
+    .enumerate()
+    .map(|(i, frame)| {
+      frame
+        .metadata
+        .exif
+        .exposure_time
+        .filter(|t| t.d != 0 && t.n != 0)
+        .map(|t| t.as_f32())
+        .ok_or_else(|| RawlerError::DecoderFailed(format!("Frame {} has no exposure time", i)))
+    })
+    .collect::<Result<Vec<f32>>>()?;
+  let fnumbers = consistent_values(frames, "aperture", |exif| exif.fnumber.filter(|f| f.d != 0 && f.n != 0).map(|f| f.as_f32()))?;
+  let isos = consistent_values(frames, "ISO", |exif| {
+    exif
+      .iso_speed_ratings
+      .map(u32::from)
+      .or(exif.iso_speed)
+      .or(exif.recommended_exposure_index)
+      .filter(|iso| *iso > 0)
+      .map(|iso| iso as f32)
+  })?;
+  Ok(
+    times
+      .iter()
+      .enumerate()
+      .map(|(i, time)| {
+        let fnumber = fnumbers.as_ref().map_or(1.0, |f| f[i]);
+        let iso = isos.as_ref().map_or(1.0, |iso| iso[i]);
+        time * iso / (fnumber * fnumber)
+      })
+      .collect(),
+  )
+}
+
+/// Get a value from all frames, `None` if no frame provides it
+fn consistent_values(frames: &[Frame], name: &str, value: impl Fn(&Exif) -> Option<f32>) -> Result<Option<Vec<f32>>> {
+  let values: Vec<Option<f32>> = frames.iter().map(|frame| value(&frame.metadata.exif)).collect();
+  if values.iter().all(Option::is_none) {
+    return Ok(None);
+  }
+  values
+    .iter()
+    .enumerate()
+    .map(|(i, v)| v.ok_or_else(|| RawlerError::DecoderFailed(format!("Frame {} has no {}, but other frames have", i, name))))
+    .collect::<Result<Vec<f32>>>()
+    .map(Some)
+}
+
+/// Merge an exposure bracket into a single floating point raw image.
+///
+/// Values are scaled to the shortest exposure, so 1.0 is the clipping
+/// point of that frame. The frame with the median exposure is used as
+/// reference for metadata and its exposure is restored by `BaselineExposure`.
+pub fn merge_hdr(frames: &[Frame], params: &HdrMergeParams) -> Result<Frame> {
+  if frames.len() < 2 {
+    return Err(RawlerError::DecoderFailed(format!(
+      "HDR merge requires at least 2 frames, got {}",
+      frames.len()
+    )));
+  }
+  if ![16, 24, 32].contains(&params.bits) {
+    return Err(RawlerError::unsupported_feature(
+      "HDR merge",
+      format!("{} bit floating point output", params.bits),
+    ));
+  }
+  let rawimages: Vec<&RawImage> = frames.iter().map(|frame| &frame.rawimage).collect();
+  check_compatible(&rawimages)?;
+  if let Some(other) = rawimages
+    .iter()
+    .find(|image| image.clean_make != rawimages[0].clean_make || image.clean_model != rawimages[0].clean_model)
+  {
+    return Err(RawlerError::DecoderFailed(format!(
+      "HDR merge requires frames from the same camera, got {} {} and {} {}",
+      rawimages[0].clean_make, rawimages[0].clean_model, other.clean_make, other.clean_model
+    )));
+  }
+  if !matches!(rawimages[0].photometric, RawPhotometricInterpretation::Cfa(_)) {
+    return Err(RawlerError::unsupported_feature(
+      "HDR merge",
+      format!("photometric interpretation {:?}", rawimages[0].photometric),
+    ));
+  }
+
+  let exposures = relative_exposures(frames)?;
+  let min_exposure = exposures.iter().cloned().fold(f32::MAX, f32::min);
+  let mut order: Vec<usize> = (0..frames.len()).collect();
+  order.sort_by(|a, b| exposures[*a].total_cmp(&exposures[*b]));
+  let (shortest, longest, median) = (order[0], order[order.len() - 1], order[order.len() / 2]);
+  log::debug!("HDR exposures: {:?}, reference frame: {}", exposures, median);
+
+  let planes = rawimages
+    .iter()
+    .map(|image| {
+      let mut image = (*image).clone();
+      image.apply_scaling()?;
+      Ok(image.data.as_f32().into_owned())
+    })
+    .collect::<Result<Vec<Vec<f32>>>>()?;
+  let scales: Vec<f32> = exposures.iter().map(|e| min_exposure / e).collect();
+
+  let mut merged = vec![0.0_f32; planes[0].len()];
+  merged.par_iter_mut().enumerate().for_each(|(i, out)| {
+    let mut sum = 0.0;
+    let mut weights = 0.0;
+    for (plane, scale) in planes.iter().zip(scales.iter()) {
+      let v = plane[i];
+      let weight = if v >= CLIPPING_LEVEL { 0.0 } else { v.min(1.0 - v).max(0.0) };
+      sum += weight * v * scale;
+      weights += weight;
+    }
+    *out = if weights > 0.0 {
+      sum / weights
+    } else if planes[shortest][i] >= 0.5 {
+      // Clipped in all frames
+      planes[shortest][i] * scales[shortest]
+    } else {
+      // Black in all frames
+      planes[longest][i].max(0.0) * scales[longest]
+    };
+  });
+
+  let mut image = rawimages[median].clone();
+  image.data = RawImageData::Float(merged);
+  image.bps = params.bits;
+  image.whitelevel = WhiteLevel::new([1]);
+  image.blacklevel = BlackLevel::zero(1, 1, 1);
+  image.blackareas.clear();
+  let baseline_exposure = (exposures[median] / min_exposure).log2();
+  image.add_dng_tag(DngTag::BaselineExposure, SRational::new((baseline_exposure * 100.0).round() as i32, 100));
+  Ok(Frame {
+    rawimage: image,
+    metadata: frames[median].metadata.clone(),
+    shot: None,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    CFA,
+    decoders::{Camera, RawMetadata},
+    formats::tiff::Rational,
+    pixarray::PixU16,
+    rawimage::CFAConfig,
+  };
+
+  fn bracket_frame(scene: &[f32], exposure: Rational) -> Frame {
+    let (width, height) = (8, 8);
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    let mut pixels = PixU16::new(width, height);
+    for (pix, radiance) in pixels.pixels_mut().iter_mut().zip(scene.iter()) {
+      *pix = (256.0 + radiance * exposure.as_f32() * 3840.0).min(4095.0) as u16;
+    }
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam).expect("Bayer CFA"));
+    let rawimage = RawImage::new(
+      cam,
+      pixels,
+      1,
+      [1.0, 1.0, 1.0, f32::NAN],
+      photometric,
+      Some(BlackLevel::new(&[256_u16], 1, 1, 1)),
+      Some(WhiteLevel::new([4095])),
+      false,
+    )
+    .expect("valid raw image");
+    let mut metadata = RawMetadata::default();
+    metadata.exif.exposure_time = Some(exposure);
+    metadata.exif.fnumber = Some(Rational::new(8, 1));
+    metadata.exif.iso_speed_ratings = Some(100);
+    Frame {
+      rawimage,
+      metadata,
+      shot: None,
+    }
+  }
+
+  #[test]
+  fn merge_bracket() -> Result<()> {
+    let scene: Vec<f32> = (0..64).map(|i| i as f32 / 8.0).collect();
+    let frames = [
+      bracket_frame(&scene, Rational::new(1, 16)),
+      bracket_frame(&scene, Rational::new(1, 4)),
+      bracket_frame(&scene, Rational::new(1, 1)),
+    ];
+    let merged = merge_hdr(&frames, &HdrMergeParams::default())?.rawimage;
+    assert_eq!(merged.bps, 16);
+    assert_eq!(merged.dng_tags.get(&(DngTag::BaselineExposure as u16)), Some(&SRational::new(200, 100).into()));
+    let data = merged.data.as_f32();
+    for (i, radiance) in scene.iter().enumerate() {
+      let expected = (radiance / 16.0).min(1.0);
+      assert!((data[i] - expected).abs() < 0.002, "pixel {}: {} != {}", i, data[i], expected);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn merge_requires_exposure() {
+    let scene = vec![0.5; 64];
+    let mut frames = [bracket_frame(&scene, Rational::new(1, 16)), bracket_frame(&scene, Rational::new(1, 4))];
+    frames[1].metadata.exif.exposure_time = None;
+    assert!(merge_hdr(&frames, &HdrMergeParams::default()).is_err());
+  }
+
+  #[test]
+  fn exposure_metadata_must_be_consistent() -> Result<()> {
+    let scene = vec![0.5; 64];
+    let mut frames = [bracket_frame(&scene, Rational::new(1, 16)), bracket_frame(&scene, Rational::new(1, 4))];
+    frames[1].metadata.exif.fnumber = None;
+    assert!(relative_exposures(&frames).is_err());
+    frames[0].metadata.exif.fnumber = None;
+    frames[0].metadata.exif.iso_speed_ratings = None;
+    frames[1].metadata.exif.iso_speed_ratings = None;
+    assert_eq!(relative_exposures(&frames)?, vec![1.0 / 16.0, 1.0 / 4.0]);
+    Ok(())
+  }
+}
@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Composition of multiple raw frames into a single raw image
+
+use std::{borrow::Borrow, path::Path};
+
+use crate::{
+  RawImage, RawlerError, Result,
+  decoders::{Decoder, PixelShiftShot, RawDecodeParams, RawMetadata},
+  rawsource::RawSource,
+};
+
+pub mod hdr;
+pub mod pixelshift;
+
+/// A single raw frame used as input for composition
+#[derive(Debug, Clone)]
+pub struct Frame {
+  pub rawimage: RawImage,
+  pub metadata: RawMetadata,
+  /// Position in a pixel-shift capture, if recorded by the camera
+  pub shot: Option<PixelShiftShot>,
+}
+
+/// Load all raw frames from the given files.
+///
+/// Container files with more than one raw image (like pixel-shift files
+/// which store all sub-frames in one file) contribute all of their images,
+/// in the order reported by the decoder.
+pub fn load_frames<P: AsRef<Path>>(paths: &[P]) -> Result<Vec<Frame>> {
+  let mut frames = Vec::new();
+  for path in paths {
+    let rawfile = RawSource::new(path.as_ref()).map_err(|err| RawlerError::with_io_error("load_frames()", path, err))?;
+    let decoder = crate::get_decoder(&rawfile)?;
+    for image_index in 0..decoder.raw_image_count()? {
+      frames.push(decode_frame(&rawfile, decoder.as_ref(), image_index, path.as_ref())?);
+    }
+  }
+  Ok(frames)
+}
+
+/// Load a single raw frame, `image_index` 0 is the primary image.
+pub fn load_frame<P: AsRef<Path>>(path: P, image_index: usize) -> Result<Frame> {
+  let rawfile = RawSource::new(path.as_ref()).map_err(|err| RawlerError::with_io_error("load_frame()", &path, err))?;
+  let decoder = crate::get_decoder(&rawfile)?;
+  decode_frame(&rawfile, decoder.as_ref(), image_index, path.as_ref())
+}
+
+fn decode_frame(rawfile: &RawSource, decoder: &dyn Decoder, image_index: usize, path: &Path) -> Result<Frame> {
+  let params = RawDecodeParams { image_index };
+  let rawimage = decoder.raw_image(rawfile, &params, false)?;
+  let metadata = decoder.raw_metadata(rawfile, &params)?;
+  let shot = decoder.pixel_shift_shot(rawfile, &params)?;
+  log::debug!("Loaded frame {} from '{}', pixel-shift: {:?}", image_index, path.display(), shot);
+  Ok(Frame { rawimage, metadata, shot })
+}
+
+/// Check that all frames can be merged pixel by pixel.
+pub(crate) fn check_compatible<T: Borrow<RawImage>>(frames: &[T]) -> Result<()> {
+  let reference = frames
+    .first()
+    .map(Borrow::borrow)
+    .ok_or_else(|| RawlerError::DecoderFailed("No frames for composition given".into()))?;
+  for (i, frame) in frames.iter().map(Borrow::borrow).enumerate().skip(1) {
+    if frame.width != reference.width || frame.height != reference.height || frame.cpp != reference.cpp {
+      return Err(RawlerError::DecoderFailed(format!(
+        "Frame {} has dimension {}x{} (cpp: {}), but reference frame has {}x{} (cpp: {})",
+        i, frame.width, frame.height, frame.cpp, reference.width, reference.height, reference.cpp
+      )));
+    }
+    if frame.photometric != reference.photometric {
+      return Err(RawlerError::DecoderFailed(format!(
+        "Frame {} has photometric interpretation {:?}, but reference frame has {:?}",
+        i, frame.photometric, reference.photometric
+      )));
+    }
+  }
+  Ok(())
+}
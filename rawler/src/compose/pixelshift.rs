@@ -0,0 +1,638 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Pixel-shift (multi-shot) composition
+//!
+//! In pixel-shift mode, the camera moves the sensor by whole pixels between
+//! the shots, so each scene position is sampled through different color filters.
+//! Merging the frames gives a full-color image without demosaicing.
+//! Sequences with additional half pixel offsets (Sony 16-shot) are merged
+//! into an image with twice the width and height.
+//!
+//! The capture order is taken from the vendor metadata (Sony PixelShiftInfo,
+//! Pentax sub-frames) and the sensor offsets for this order from the camera
+//! definition (`pixel_shift_pattern_<shots>` or `pixel_shift_pattern` parameter).
+//! Only if both are missing, the offsets of four-shot sequences are detected
+//! from image data.
+//! Olympus/OM System and Panasonic high resolution shots are merged in camera
+//! and decoded as a single raw image.
+
+use std::str::FromStr;
+
+use rayon::prelude::*;
+
+use crate::{
+  RawImage, RawImageData, RawlerError, Result,
+  cfa::{CFA, CFAColor},
+  decoders::{BlackLevel, Camera, PixelShiftShot, RawPhotometricInterpretation, WhiteLevel},
+  imgop::{
+    Dim2, Point, Rect,
+    sensor::{Demosaic, SensorType, bayer::ppg::PPGDemosaic},
+  },
+  pixarray::PixF32,
+};
+
+use super::{Frame, check_compatible};
+
+/// Lower bound for the signal used as reference for motion detection,
+/// prevents noise in dark areas from being detected as motion.
+const MOTION_NOISE_FLOOR: f32 = 0.01;
+
+/// Sensor offset of a single frame in subpixels of the [`ShiftPattern`]
+///
+/// A frame with offset (dx, dy) records at sensor position (x, y)
+/// the light of scene position (x * subpixels + dx, y * subpixels + dy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameShift {
+  pub dx: isize,
+  pub dy: isize,
+}
+
+impl FrameShift {
+  pub const fn new(dx: isize, dy: isize) -> Self {
+    Self { dx, dy }
+  }
+}
+
+/// Sensor offsets for all frames of a pixel-shift sequence
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShiftPattern {
+  pub shifts: Vec<FrameShift>,
+  /// Number of steps per pixel, 1 for whole pixel and 2 for half pixel offsets
+  pub subpixels: usize,
+}
+
+impl ShiftPattern {
+  /// Pattern with offsets in whole pixels
+  pub fn new(shifts: impl Into<Vec<FrameShift>>) -> Self {
+    Self::with_subpixels(shifts, 1)
+  }
+
+  /// Pattern with offsets in 1/`subpixels` pixels
+  pub fn with_subpixels(shifts: impl Into<Vec<FrameShift>>, subpixels: usize) -> Self {
+    Self {
+      shifts: shifts.into(),
+      subpixels,
+    }
+  }
+
+  /// Four shots, the sensor moves clockwise around a single pixel.
+  pub fn four_shot() -> Self {
+    Self::new([FrameShift::new(0, 0), FrameShift::new(1, 0), FrameShift::new(1, 1), FrameShift::new(0, 1)])
+  }
+
+  pub fn len(&self) -> usize {
+    self.shifts.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.shifts.is_empty()
+  }
+
+  /// Detect the shift order of a four-shot sequence.
+  ///
+  /// This is used if the sensor offsets are not known from metadata. The
+  /// first frame is used as reference, for all other frames the assignment
+  /// is selected which gives the best matching green samples for each scene position.
+  fn detect_four_shot(planes: &[Vec<f32>], cfa: &CFA, green: usize, width: usize, height: usize) -> Self {
+    const PERMUTATIONS: [[usize; 3]; 6] = [[1, 2, 3], [1, 3, 2], [2, 1, 3], [2, 3, 1], [3, 1, 2], [3, 2, 1]];
+    let square = Self::four_shot();
+    let step_y = (height / 256).max(1);
+    let step_x = (width / 256).max(1);
+
+    let mut best = (f32::MAX, square.clone());
+    for perm in PERMUTATIONS {
+      let mut shifts = vec![square.shifts[0]];
+      shifts.extend(perm.iter().map(|i| square.shifts[*i]));
+      let candidate = Self::new(shifts);
+
+      let mut score = 0.0;
+      let mut count = 0;
+      for y in (2..height.saturating_sub(2)).step_by(step_y) {
+        for x in (2..width.saturating_sub(2)).step_by(step_x) {
+          let mut min = f32::MAX;
+          let mut max = f32::MIN;
+          for (plane, shift) in planes.iter().zip(candidate.shifts.iter()) {
+            let (sx, sy) = ((x as isize - shift.dx) as usize, (y as isize - shift.dy) as usize);
+            if cfa.color_at(sy, sx) == green {
+              let v = plane[sy * width + sx];
+              min = min.min(v);
+              max = max.max(v);
+            }
+          }
+          if max >= min {
+            score += max - min;
+            count += 1;
+          }
+        }
+      }
+      let score = if count > 0 { score / count as f32 } else { f32::MAX };
+      log::debug!("Pixel-shift candidate {:?}, score: {}", candidate.shifts, score);
+      if score < best.0 {
+        best = (score, candidate);
+      }
+    }
+    best.1
+  }
+}
+
+/// Parse a pattern like `0,0;1,0;1,1;0,1` (dx,dy in pixels for each frame).
+///
+/// Offsets may be given in half pixels, like `0.5,0`.
+impl FromStr for ShiftPattern {
+  type Err = String;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    let halves = s
+      .split(';')
+      .map(|item| {
+        let (dx, dy) = item
+          .split_once(',')
+          .ok_or_else(|| format!("Invalid frame shift '{}', expected 'dx,dy'", item))?;
+        let half_steps = |v: &str| {
+          let v = v.trim().parse::<f32>().map_err(|err| format!("Invalid frame shift '{}': {}", item, err))?;
+          let halves = v * 2.0;
+          if halves.fract() != 0.0 {
+            return Err(format!("Invalid frame shift '{}': only whole and half pixel offsets are supported", item));
+          }
+          Ok(halves as isize)
+        };
+        Ok(FrameShift::new(half_steps(dx)?, half_steps(dy)?))
+      })
+      .collect::<std::result::Result<Vec<_>, String>>()?;
+    if halves.iter().all(|shift| shift.dx % 2 == 0 && shift.dy % 2 == 0) {
+      Ok(Self::new(
+        halves.iter().map(|shift| FrameShift::new(shift.dx / 2, shift.dy / 2)).collect::<Vec<_>>(),
+      ))
+    } else {
+      Ok(Self::with_subpixels(halves, 2))
+    }
+  }
+}
+
+/// Frame indices in capture order, from the pixel-shift metadata
+///
+/// Frames without metadata are kept in the given order. Metadata must be
+/// present for all frames and describe one complete capture.
+fn capture_order(frames: &[Frame]) -> Result<Vec<usize>> {
+  let shots: Vec<&PixelShiftShot> = frames.iter().filter_map(|frame| frame.shot.as_ref()).collect();
+  if shots.is_empty() {
+    return Ok((0..frames.len()).collect());
+  }
+  if shots.len() != frames.len() {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Pixel-shift metadata found for {} of {} frames",
+      shots.len(),
+      frames.len()
+    )));
+  }
+  let mut order: Vec<usize> = (0..frames.len()).collect();
+  order.sort_by_key(|i| shots[*i].shot);
+  let complete = order.iter().enumerate().all(|(n, i)| shots[*i].shot == n);
+  if !complete || shots.iter().any(|shot| shot.shots != frames.len() || shot.group != shots[0].group) {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Frames are not a complete pixel-shift capture: {:?}",
+      shots
+    )));
+  }
+  Ok(order)
+}
+
+/// Parameters for pixel-shift composition
+#[derive(Debug, Clone)]
+pub struct PixelShiftParams {
+  /// Frame offsets in capture order, taken from the camera definition
+  /// or detected from image data if not given
+  pub pattern: Option<ShiftPattern>,
+  /// Replace pixels with motion between the frames by the demosaiced reference frame
+  pub motion_correction: bool,
+  /// Maximum relative difference of green samples before a pixel is treated as motion
+  pub motion_threshold: f32,
+}
+
+impl Default for PixelShiftParams {
+  fn default() -> Self {
+    Self {
+      pattern: None,
+      motion_correction: true,
+      motion_threshold: 0.15,
+    }
+  }
+}
+
+/// Merge pixel-shift frames into a single `LinearRaw` image.
+///
+/// Frames are sorted into capture order if the camera recorded it. The first
+/// shot is the reference, its metadata is used for the result.
+/// Image data is scaled to 0.0 .. 1.0. For patterns with half pixel offsets,
+/// the result has twice the width and height of the frames.
+pub fn compose_pixelshift(frames: &[Frame], params: &PixelShiftParams) -> Result<Frame> {
+  if frames.len() < 2 {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Pixel-shift requires at least 2 frames, got {}",
+      frames.len()
+    )));
+  }
+  let order = capture_order(frames)?;
+  let frames: Vec<&Frame> = order.iter().map(|i| &frames[*i]).collect();
+  let rawimages: Vec<&RawImage> = frames.iter().map(|frame| &frame.rawimage).collect();
+  check_compatible(&rawimages)?;
+
+  let reference = rawimages[0];
+  let config = match &reference.photometric {
+    RawPhotometricInterpretation::Cfa(config) if config.sensor == SensorType::Bayer && config.cfa.is_rgb() => config,
+    RawPhotometricInterpretation::Cfa(config) => {
+      return Err(RawlerError::unsupported_feature("pixel-shift", format!("CFA pattern {}", config.cfa)));
+    }
+    other => {
+      return Err(RawlerError::unsupported_feature(
+        "pixel-shift",
+        format!("photometric interpretation {:?}", other),
+      ));
+    }
+  };
+  let (width, height) = (reference.width, reference.height);
+  let lookup = config.colors.plane_lookup_table();
+  let green = CFAColor::GREEN as usize;
+  let green_plane = lookup[green];
+
+  let planes = rawimages
+    .iter()
+    .map(|frame| {
+      let mut frame = (*frame).clone();
+      frame.apply_scaling()?;
+      Ok(frame.data.as_f32().into_owned())
+    })
+    .collect::<Result<Vec<Vec<f32>>>>()?;
+
+  let camera_pattern = camera_pattern(&reference.camera, frames.len())?;
+  let pattern = match params.pattern.as_ref().or(camera_pattern.as_ref()) {
+    Some(pattern) => pattern.clone(),
+    None if frames.len() == 4 => ShiftPattern::detect_four_shot(&planes, &config.cfa, green, width, height),
+    None => {
+      return Err(RawlerError::unsupported_feature(
+        "pixel-shift",
+        format!("{} frames without given shift pattern", frames.len()),
+      ));
+    }
+  };
+  if pattern.len() != frames.len() {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Shift pattern has {} entries, but {} frames given",
+      pattern.len(),
+      frames.len()
+    )));
+  }
+  if !matches!(pattern.subpixels, 1 | 2) {
+    return Err(RawlerError::unsupported_feature(
+      "pixel-shift",
+      format!("shift pattern with {} subpixels", pattern.subpixels),
+    ));
+  }
+  log::debug!("Pixel-shift pattern: {:?}, subpixels: {}", pattern.shifts, pattern.subpixels);
+
+  let scale = pattern.subpixels;
+  let (out_width, out_height) = (width * scale, height * scale);
+  let mut rgb = vec![0.0_f32; out_width * out_height * 3];
+  let mut fallback = vec![false; out_width * out_height];
+
+  rgb
+    .par_chunks_exact_mut(out_width * 3)
+    .zip(fallback.par_chunks_exact_mut(out_width))
+    .enumerate()
+    .for_each(|(y, (line, mask))| {
+      for x in 0..out_width {
+        let mut sums = [0.0_f32; 3];
+        let mut counts = [0_usize; 3];
+        let (mut gmin, mut gmax) = (f32::MAX, f32::MIN);
+        for (plane, shift) in planes.iter().zip(pattern.shifts.iter()) {
+          let (px, py) = (x as isize - shift.dx, y as isize - shift.dy);
+          if px < 0 || py < 0 || px % scale as isize != 0 || py % scale as isize != 0 {
+            continue;
+          }
+          let (sx, sy) = (px as usize / scale, py as usize / scale);
+          if sx >= width || sy >= height {
+            continue;
+          }
+          let color = config.cfa.color_at(sy, sx);
+          let v = plane[sy * width + sx];
+          let c = lookup[color];
+          sums[c] += v;
+          counts[c] += 1;
+          if color == green {
+            gmin = gmin.min(v);
+            gmax = gmax.max(v);
+          }
+        }
+        if counts.contains(&0) {
+          mask[x] = true;
+          continue;
+        }
+        for c in 0..3 {
+          line[x * 3 + c] = sums[c] / counts[c] as f32;
+        }
+        if params.motion_correction && counts[green_plane] >= 2 {
+          let gmean = sums[green_plane] / counts[green_plane] as f32;
+          mask[x] = gmax - gmin > params.motion_threshold * gmean.max(MOTION_NOISE_FLOOR);
+        }
+      }
+    });
+
+  let fallback = dilate_mask(&fallback, out_width, out_height);
+  let fallback_count = fallback.iter().filter(|x| **x).count();
+  log::debug!(
+    "Pixel-shift: {} of {} pixels replaced by reference frame",
+    fallback_count,
+    out_width * out_height
+  );
+
+  if fallback_count > 0 {
+    let pixels = PixF32::new_with(planes[0].clone(), width, height);
+    let demosaic = PPGDemosaic::new().demosaic(&pixels, &config.cfa, &config.colors, pixels.rect());
+    for (i, _) in fallback.iter().enumerate().filter(|(_, x)| **x) {
+      let (x, y) = (i % out_width / scale, i / out_width / scale);
+      rgb[i * 3..i * 3 + 3].copy_from_slice(&demosaic.data[y * width + x]);
+    }
+  }
+
+  let scale_rect = |rect: Rect| Rect::new(Point::new(rect.p.x * scale, rect.p.y * scale), Dim2::new(rect.d.w * scale, rect.d.h * scale));
+  let mut composed = reference.clone();
+  composed.width = out_width;
+  composed.height = out_height;
+  composed.active_area = composed.active_area.map(scale_rect);
+  composed.crop_area = composed.crop_area.map(scale_rect);
+  composed.cpp = 3;
+  composed.photometric = RawPhotometricInterpretation::LinearRaw;
+  composed.whitelevel = WhiteLevel::new([1, 1, 1]); // Already scaled up to 0.0 .. 1.0
+  composed.blacklevel = BlackLevel::zero(1, 1, 3);
+  composed.blackareas.clear();
+  composed.data = RawImageData::Float(rgb);
+  Ok(Frame {
+    rawimage: composed,
+    metadata: frames[0].metadata.clone(),
+    shot: None,
+  })
+}
+
+/// Shift pattern from the camera definition
+///
+/// Cameras supporting captures with different number of shots define
+/// `pixel_shift_pattern_<shots>`, `pixel_shift_pattern` is used for all others.
+fn camera_pattern(camera: &Camera, shots: usize) -> Result<Option<ShiftPattern>> {
+  let name = format!("pixel_shift_pattern_{}", shots);
+  let (name, pattern) = match camera.param_str(&name) {
+    Some(pattern) => (name.as_str(), pattern),
+    None => match camera.param_str("pixel_shift_pattern") {
+      Some(pattern) => ("pixel_shift_pattern", pattern),
+      None => return Ok(None),
+    },
+  };
+  pattern
+    .parse()
+    .map(Some)
+    .map_err(|err| RawlerError::DecoderFailed(format!("Invalid {} in camera definition: {}", name, err)))
+}
+
+/// Grow masked regions by one pixel to cover the border of moving objects.
+fn dilate_mask(mask: &[bool], width: usize, height: usize) -> Vec<bool> {
+  let mut out = mask.to_vec();
+  for y in 0..height {
+    for x in 0..width {
+      if mask[y * width + x] {
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+          for nx in x.saturating_sub(1)..(x + 2).min(width) {
+            out[ny * width + nx] = true;
+          }
+        }
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    decoders::{Camera, RawMetadata},
+    pixarray::PixU16,
+    rawimage::CFAConfig,
+  };
+
+  /// Sample a scene of (width * subpixels, height * subpixels) with the given sensor offset.
+  fn bayer_frame(scene: &[[u16; 3]], width: usize, height: usize, shift: FrameShift, subpixels: usize) -> Frame {
+    let cfa = CFA::new("RGGB");
+    let mut cam = Camera::new();
+    cam.cfa = cfa.clone();
+    let (scene_width, scene_height) = (width * subpixels, height * subpixels);
+    let mut pixels = PixU16::new(width, height);
+    for y in 0..height {
+      for x in 0..width {
+        let (px, py) = (
+          ((x * subpixels) as isize + shift.dx).clamp(0, scene_width as isize - 1),
+          ((y * subpixels) as isize + shift.dy).clamp(0, scene_height as isize - 1),
+        );
+        *pixels.at_mut(y, x) = scene[py as usize * scene_width + px as usize][cfa.color_at(y, x)];
+      }
+    }
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam).expect("Bayer CFA"));
+    let rawimage = RawImage::new(
+      cam,
+      pixels,
+      1,
+      [1.0, 1.0, 1.0, f32::NAN],
+      photometric,
+      Some(BlackLevel::zero(1, 1, 1)),
+      Some(WhiteLevel::new([u16::MAX as u32])),
+      false,
+    )
+    .expect("valid raw image");
+    Frame {
+      rawimage,
+      metadata: RawMetadata::default(),
+      shot: None,
+    }
+  }
+
+  fn test_scene(width: usize, height: usize) -> Vec<[u16; 3]> {
+    (0..width * height)
+      .map(|i| [(i * 300) as u16, (i * 200 + 100) as u16, (i * 100 + 50) as u16])
+      .collect()
+  }
+
+  fn assert_scene(composed: &RawImage, scene: &[[u16; 3]]) {
+    let (width, height) = (composed.width, composed.height);
+    assert_eq!(composed.cpp, 3);
+    assert_eq!(composed.photometric, RawPhotometricInterpretation::LinearRaw);
+    let data = composed.data.as_f32();
+    // Border pixels are not covered by all frames and replaced by the reference frame.
+    for y in 4..height - 4 {
+      for x in 4..width - 4 {
+        let i = y * width + x;
+        for c in 0..3 {
+          let expected = scene[i][c] as f32 / u16::MAX as f32;
+          assert!((data[i * 3 + c] - expected).abs() < 1.0e-4, "pixel {},{} channel {}", x, y, c);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn parse_shift_pattern() {
+    let pattern: ShiftPattern = "0,0; 1,0;1,1;0,1".parse().expect("valid pattern");
+    assert_eq!(pattern, ShiftPattern::four_shot());
+    assert!("0,0;1".parse::<ShiftPattern>().is_err());
+    assert!("0,0;0.25,0".parse::<ShiftPattern>().is_err());
+
+    let pattern: ShiftPattern = "0,0;0.5,0;1.5,1".parse().expect("valid pattern");
+    assert_eq!(
+      pattern,
+      ShiftPattern::with_subpixels([FrameShift::new(0, 0), FrameShift::new(1, 0), FrameShift::new(3, 2)], 2)
+    );
+  }
+
+  fn sony_camera(model: &str) -> Camera {
+    let loader = crate::RawLoader::new();
+    loader
+      .get_cameras()
+      .values()
+      .find(|cam| cam.make == "SONY" && cam.model == model)
+      .cloned()
+      .expect("camera definition")
+  }
+
+  /// Frames for all shots of the camera pattern, in reverse capture order
+  fn shot_frames(scene: &[[u16; 3]], width: usize, height: usize, camera: &Camera, shots: usize) -> Vec<Frame> {
+    let pattern = camera_pattern(camera, shots).expect("valid pattern").expect("pattern defined");
+    assert_eq!(pattern.len(), shots);
+    let mut frames: Vec<Frame> = pattern
+      .shifts
+      .iter()
+      .enumerate()
+      .map(|(shot, shift)| {
+        let mut frame = bayer_frame(scene, width, height, *shift, pattern.subpixels);
+        frame.rawimage.camera = camera.clone();
+        frame.shot = Some(PixelShiftShot { shot, shots, group: Some(1) });
+        frame
+      })
+      .collect();
+    frames.reverse();
+    frames
+  }
+
+  #[test]
+  fn sony_pixel_shift_cameras_define_patterns() {
+    for model in ["ILCE-7RM3", "ILCE-7RM3A", "ILCE-7RM4", "ILCE-7RM4A", "ILCE-7RM5", "ILCE-1", "ILCE-1M2"] {
+      let cam = sony_camera(model);
+      let pattern = camera_pattern(&cam, 4).expect("valid pattern").expect("4-shot pattern");
+      assert_eq!((pattern.len(), pattern.subpixels), (4, 1), "{}", model);
+      if model != "ILCE-7RM3" && model != "ILCE-7RM3A" {
+        let pattern = camera_pattern(&cam, 16).expect("valid pattern").expect("16-shot pattern");
+        assert_eq!((pattern.len(), pattern.subpixels), (16, 2), "{}", model);
+      }
+    }
+  }
+
+  #[test]
+  fn compose_sony_four_shot_from_camera_definition() -> Result<()> {
+    let (width, height) = (16, 12);
+    let scene = test_scene(width, height);
+    let frames = shot_frames(&scene, width, height, &sony_camera("ILCE-7RM4"), 4);
+    let composed = compose_pixelshift(&frames, &PixelShiftParams::default())?;
+    assert_scene(&composed.rawimage, &scene);
+    Ok(())
+  }
+
+  #[test]
+  fn compose_sony_sixteen_shot_from_camera_definition() -> Result<()> {
+    let (width, height) = (16, 12);
+    let scene = test_scene(width * 2, height * 2);
+    let frames = shot_frames(&scene, width, height, &sony_camera("ILCE-7RM4"), 16);
+    let composed = compose_pixelshift(&frames, &PixelShiftParams::default())?;
+    assert_eq!((composed.rawimage.width, composed.rawimage.height), (width * 2, height * 2));
+    assert_scene(&composed.rawimage, &scene);
+    Ok(())
+  }
+
+  #[test]
+  fn compose_four_shot_static_scene() -> Result<()> {
+    let (width, height) = (16, 12);
+    let scene = test_scene(width, height);
+    // Store the frames in a different order than the default pattern to test detection.
+    let order = [0, 2, 1, 3];
+    let square = ShiftPattern::four_shot();
+    let frames: Vec<Frame> = order.iter().map(|i| bayer_frame(&scene, width, height, square.shifts[*i], 1)).collect();
+
+    let composed = compose_pixelshift(&frames, &PixelShiftParams::default())?;
+    assert_scene(&composed.rawimage, &scene);
+    Ok(())
+  }
+
+  #[test]
+  fn compose_in_capture_order_with_camera_pattern() -> Result<()> {
+    let (width, height) = (16, 12);
+    let scene = test_scene(width, height);
+    // Detection only knows the clockwise square, so the pattern must come from the camera.
+    let camera_pattern = "0,0;-1,0;-1,-1;0,-1";
+    let shifts = camera_pattern.parse::<ShiftPattern>().expect("valid pattern").shifts;
+    let mut frames: Vec<Frame> = shifts
+      .iter()
+      .enumerate()
+      .map(|(shot, shift)| {
+        let mut frame = bayer_frame(&scene, width, height, *shift, 1);
+        frame.rawimage.camera.params.insert("pixel_shift_pattern".into(), camera_pattern.into());
+        frame.shot = Some(PixelShiftShot {
+          shot,
+          shots: 4,
+          group: Some(7),
+        });
+        frame
+      })
+      .collect();
+    frames.reverse();
+
+    let composed = compose_pixelshift(&frames, &PixelShiftParams::default())?;
+    assert_scene(&composed.rawimage, &scene);
+
+    frames[1].shot = Some(PixelShiftShot {
+      shot: 2,
+      shots: 4,
+      group: Some(8),
+    });
+    assert!(compose_pixelshift(&frames, &PixelShiftParams::default()).is_err());
+    frames[1].shot = None;
+    assert!(compose_pixelshift(&frames, &PixelShiftParams::default()).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn composed_image_to_dng() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::{
+      decoders::RawDecodeParams,
+      dng::convert::{ConvertParams, convert_raw_image},
+      rawsource::RawSource,
+    };
+    let (width, height) = (16, 12);
+    let scene: Vec<[u16; 3]> = (0..width * height).map(|i| [(i * 100) as u16, (i * 100) as u16, (i * 100) as u16]).collect();
+    let square = ShiftPattern::four_shot();
+    let frames: Vec<Frame> = square.shifts.iter().map(|shift| bayer_frame(&scene, width, height, *shift, 1)).collect();
+    let composed = compose_pixelshift(&frames, &PixelShiftParams::default())?;
+
+    let mut buf = std::io::Cursor::new(Vec::new());
+    convert_raw_image(&composed.rawimage, &composed.metadata, &mut buf, &ConvertParams::default())?;
+
+    let source = RawSource::new_from_slice(buf.get_ref());
+    let decoder = crate::get_decoder(&source)?;
+    let rawimage = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+    assert_eq!(rawimage.cpp, 3);
+    assert_eq!(rawimage.photometric, RawPhotometricInterpretation::LinearRaw);
+    Ok(())
+  }
+
+  #[test]
+  fn compose_requires_multiple_frames() {
+    let scene = vec![[0_u16; 3]; 4 * 4];
+    let frames = [bayer_frame(&scene, 4, 4, FrameShift::new(0, 0), 1)];
+    assert!(compose_pixelshift(&frames, &PixelShiftParams::default()).is_err());
+  }
+}
@@ -47,6 +47,7 @@ use crate::tags::TiffCommonTag;
 use super::Camera;
 use super::Decoder;
 use super::FormatHint;
+use super::PixelShiftShot;
 use super::RawDecodeParams;
 use super::RawMetadata;
 use super::ok_cfa_image;
@@ -194,12 +195,12 @@ impl<'a> Decoder for ArwDecoder<'a> {
     let whitelevel = white.map(|white| WhiteLevel(vec![white as u32; cpp]));
 
     let photometric = match cpp {
-      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)),
+      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?),
       3 => RawPhotometricInterpretation::LinearRaw,
       _ => return Err(RawlerError::DecoderFailed(format!("ARW: Unsupported cpp: {}", cpp))),
     };
 
-    let mut img = RawImage::new(self.camera.clone(), image, cpp, params.wb, photometric, blacklevel, whitelevel, dummy);
+    let mut img = RawImage::new(self.camera.clone(), image, cpp, params.wb, photometric, blacklevel, whitelevel, dummy)?;
 
     if cpp == 3 {
       // For debayer images, we assume WB coeffs already applied
@@ -251,6 +252,25 @@ impl<'a> Decoder for ArwDecoder<'a> {
     Ok(mdata)
   }
 
+  /// Pixel shift shots are stored as separate files, the makernote
+  /// records the group id, shot number (starting with 1) and number of shots.
+  fn pixel_shift_shot(&self, _file: &RawSource, _params: &RawDecodeParams) -> Result<Option<PixelShiftShot>> {
+    if let Some(Entry {
+      value: Value::Undefined(info), ..
+    }) = self.makernote.get_entry(ArwMakernoteTag::PixelShiftInfo)
+      && info.len() >= 6
+      && info[4] > 0
+    {
+      debug!("Pixel shift info: {:?}", info);
+      return Ok(Some(PixelShiftShot {
+        shot: info[4] as usize - 1,
+        shots: info[5] as usize,
+        group: Some(LEu32(info, 0)),
+      }));
+    }
+    Ok(None)
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::ARW
   }
@@ -383,22 +403,21 @@ impl<'a> ArwDecoder<'a> {
     let image = if dummy {
       PixU16::new_uninit(width, height)
     } else {
-      let buffer = file.as_vec()?;
       let len = width * height * 2;
 
       // Constants taken from dcraw
-      let off: usize = 862144;
-      let key_off: usize = 200896;
-      let head_off: usize = 164600;
+      let off: u64 = 862144;
+      let key_off: u64 = 200896;
+      let head_off: u64 = 164600;
 
       // Replicate the dcraw contortions to get the "decryption" key
-      let offset = (buffer[key_off] as usize) * 4;
-      let first_key = BEu32(&buffer, key_off + offset);
-      let head = ArwDecoder::sony_decrypt(&buffer, head_off, 40, first_key)?;
+      let offset = (file.subview(key_off, 1)?[0] as u64) * 4;
+      let first_key = BEu32(file.subview(key_off + offset, 4)?, 0);
+      let head = ArwDecoder::sony_decrypt(file.subview(head_off, 40)?, 0, 40, first_key)?;
       let second_key = LEu32(&head, 22);
 
       // "Decrypt" the whole image buffer
-      let image_data = ArwDecoder::sony_decrypt(&buffer, off, len, second_key)?;
+      let image_data = ArwDecoder::sony_decrypt(file.subview(off, len as u64)?, 0, len, second_key)?;
       decompress_16be(&image_data, width, height, dummy)?
     };
     let cpp = 1;
@@ -480,6 +499,18 @@ impl<'a> ArwDecoder<'a> {
     )?)
   }
 
+  /// Compressed data of a LJPEG tile
+  ///
+  /// The tile is limited by TileByteCounts (plus some padding for the bit
+  /// pump), if available. Otherwise, the data is read until EOF.
+  fn ljpeg_tile<'f>(file: &'f RawSource, offsets: &Entry, bytecounts: Option<&Entry>, tile: usize) -> std::io::Result<&'f [u8]> {
+    let offset = offsets.force_u64(tile);
+    match bytecounts {
+      Some(counts) => file.subview(offset, (counts.force_u64(tile) + 16).min((file.len() as u64).saturating_sub(offset))),
+      None => file.subview_until_eof(offset),
+    }
+  }
+
   /// Some newer cameras like Alpha-1 uses LJPEG compression, but in an awkward way.
   /// The image is split into 512x512 tiles with cpp = 1, but the LJPEG stream is
   /// compressed as 256x256 with cpp = 4. So the total of bytes matches, but the dimension
@@ -507,7 +538,7 @@ impl<'a> ArwDecoder<'a> {
         format!("ARW LJPEG: trying to decode {} tiles from {} offsets", coltiles * rowtiles, offsets.count()),
       ));
     }
-    let buffer = file.as_vec()?;
+    let bytecounts = raw.get_entry(TiffCommonTag::TileByteCounts);
 
     if cpp == 3 {
       let mut image = decompress_strips_fn(
@@ -519,8 +550,7 @@ impl<'a> ArwDecoder<'a> {
           let row = row / tlength;
           for col in 0..coltiles {
             log::debug!("Decode tile: row({}), col({})", row, col);
-            let offset = offsets.force_usize(row * coltiles + col);
-            let src = &buffer[offset..];
+            let src = Self::ljpeg_tile(file, offsets, bytecounts, row * coltiles + col).map_err(|err| format!("ARW LJPEG tile ({row},{col}): {err}"))?;
             let decompressor =
               LjpegDecompressor::new(src).map_err(|err| format!("Creating LJPEG decompressor for ARW LJPEG tile ({row},{col}) failed: {err}"))?;
             let cpp = 3;
@@ -556,8 +586,7 @@ impl<'a> ArwDecoder<'a> {
         &(|lines: &mut [u16], _strip, row| {
           let row = row / tlength;
           for col in 0..coltiles {
-            let offset = offsets.force_usize(row * coltiles + col);
-            let src = &buffer[offset..];
+            let src = Self::ljpeg_tile(file, offsets, bytecounts, row * coltiles + col).map_err(|err| format!("ARW LJPEG tile ({row},{col}): {err}"))?;
             let decompressor = LjpegDecompressor::new(src)?;
             let cpp = 4;
             let w = 256;
@@ -608,8 +637,8 @@ impl<'a> ArwDecoder<'a> {
       let tag = fetch_tiff_tag!(priv_tiff, TiffCommonTag::SonyKey).get_data();
       LEu32(tag, 0)
     };
-    let buffer = file.as_vec()?;
-    let decrypted_buf = ArwDecoder::sony_decrypt(&buffer, sony_offset as usize, sony_length, sony_key)?;
+    let buffer = file.subview(sony_offset as u64, sony_length as u64)?;
+    let decrypted_buf = ArwDecoder::sony_decrypt(buffer, 0, sony_length, sony_key)?;
 
     let decrypted_tiff = IFD::new(&mut Cursor::new(decrypted_buf), 0, 0, -(sony_offset as i32), Endian::Little, &[])?;
 
@@ -759,6 +788,7 @@ crate::tags::tiff_tag_enum!(ArwMakernoteTag);
 #[allow(non_camel_case_types)]
 pub enum ArwMakernoteTag {
   CameraInfo = 0x0010,
+  PixelShiftInfo = 0x202f,
   Tag_940C = 0x940C,
   Tag_9050 = 0x9050,
   Tag_9405 = 0x9405,
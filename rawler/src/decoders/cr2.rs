@@ -187,6 +187,20 @@ impl<'a> Decoder for Cr2Decoder<'a> {
             }
             fieldwidths.push(canoncol.value.force_usize(2));
 
+            // Slice widths for sRaw are given in sensor pixels, not in samples.
+            let sh = decompressor.super_h();
+            let total = if decompressor.super_v() == 2 {
+              fieldwidths[0] * fieldwidths.len()
+            } else {
+              fieldwidths.iter().map(|w| w / sh * cpp).sum()
+            };
+            if total > width || width * height > ljpegout.pixels().len() {
+              return Err(RawlerError::DecoderFailed(format!(
+                "CR2: slice widths {:?} don't match image width {}",
+                fieldwidths, width
+              )));
+            }
+
             if decompressor.super_v() == 2 {
               debug!("CR2 v=2 decoder used, h={}", decompressor.super_h());
               // We've decoded 2 lines at a time so we also need to copy two strips at a time
@@ -212,12 +226,11 @@ impl<'a> Decoder for Cr2Decoder<'a> {
                 fieldstart += fieldwidth;
               }
             } else {
-              let sh = decompressor.super_h();
               debug!("CR2 v=1 decoder used, super_h: {}", sh);
               let mut fieldstart = 0;
               let mut fieldpos = 0;
               for fieldwidth in fieldwidths {
-                // fix the inconsistent slice width in sRaw mode, ask Canon.
+                // Slice widths are given in sensor pixels, sRaw is subsampled by super_h.
                 let fieldwidth = fieldwidth / sh * cpp;
                 // The output for full height of a vertical stripe is
                 // composed by the lines of all input stripes N:
@@ -258,12 +271,12 @@ impl<'a> Decoder for Cr2Decoder<'a> {
     let whitelevel = self.get_whitelevel(cpp)?;
 
     let photometric = match cpp {
-      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)),
+      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?),
       3 => RawPhotometricInterpretation::LinearRaw,
-      _ => todo!(),
+      _ => return Err(RawlerError::unsupported_feature("CR2", format!("{} components per pixel", cpp))),
     };
 
-    let mut img = RawImage::new(camera.clone(), image, cpp, wb, photometric, blacklevel, whitelevel, dummy);
+    let mut img = RawImage::new(camera.clone(), image, cpp, wb, photometric, blacklevel, whitelevel, dummy)?;
 
     if let Some(file_crop) = self.get_sensor_area()? {
       assert!(
@@ -580,7 +593,7 @@ impl<'a> Cr2Decoder<'a> {
             let levels: [u16; 3] = [avg, avg, avg];
             return Ok(Some(BlackLevel::new(&levels, 1, 1, cpp)));
           }
-          _ => unreachable!(),
+          _ => return Err(RawlerError::unsupported_feature("CR2", format!("black level for {} components per pixel", cpp))),
         }
       }
     }
@@ -713,6 +726,7 @@ impl<'a> Cr2Decoder<'a> {
       self.interpolate_yuv(ljpeg, width, height, image);
     }
 
+    let version = SrawVersion::detect(cam)?;
     let coeffs = self.get_sraw_wb(rawfile, cam)?;
     let (c1, c2, c3) = if cam.find_hint("invert_sraw_wb") {
       let c1 = (1024.0 * 1024.0 / coeffs[0]) as i32;
@@ -722,18 +736,7 @@ impl<'a> Cr2Decoder<'a> {
     } else {
       (coeffs[0] as i32, coeffs[1] as i32, coeffs[2] as i32)
     };
-
-    // Starting with 40D, sRaw format was introduced. This uses
-    // version 0. With 5D Mark II, version 1 gets used.
-    // And with 5D Mark III, back to version 0 method
-    // but without an offset of 512 for y.
-    let version = if cam.find_hint("sraw_40d") {
-      0
-    } else if cam.find_hint("sraw_new") {
-      2
-    } else {
-      1
-    };
+    debug!("SRAW version: {:?}, coeffs: {:?}", version, (c1, c2, c3));
 
     let fw = self.get_firmware()?;
     debug!("Firmware: {:?}", fw);
@@ -754,49 +757,69 @@ impl<'a> Cr2Decoder<'a> {
     debug!("SRAW hue correction: {:?}", hue);
 
     // Now calculate RGB for each YUV tuple.
-    image.par_chunks_exact_mut(3).for_each(|pix| {
-      let y = pix[0] as i32;
-      let cb = pix[1] as i32 - 16383;
-      let cr = pix[2] as i32 - 16383;
-      match version {
-        0 => {
-          let y = y - 512; // correction for 40D and others
-          let r = c1 * (y + cr);
-          let g = c2 * (y + ((-778 * cb - (cr << 11)) >> 12));
-          let b = c3 * (y + cb);
-          pix[0] = clampbits(r >> 8, 16);
-          pix[1] = clampbits(g >> 8, 16);
-          pix[2] = clampbits(b >> 8, 16);
-        }
-        1 => {
-          // found in EOS 5D Mark II
-          let cb = (cb << 2) + hue;
-          let cr = (cr << 2) + hue;
-          let r = c1 * (y + ((50 * cb + 22929 * cr) >> 14));
-          let g = c2 * (y + ((-5640 * cb - 11751 * cr) >> 14));
-          let b = c3 * (y + ((29040 * cb - 101 * cr) >> 14));
-          pix[0] = clampbits(r >> 8, 16);
-          pix[1] = clampbits(g >> 8, 16);
-          pix[2] = clampbits(b >> 8, 16);
-        }
-        2 => {
-          // found in EOS 5D Mark III and others
-          let r = c1 * (y + cr);
-          let g = c2 * (y + ((-778 * cb - (cr << 11)) >> 12));
-          let b = c3 * (y + cb);
-          pix[0] = clampbits(r >> 8, 16);
-          pix[1] = clampbits(g >> 8, 16);
-          pix[2] = clampbits(b >> 8, 16);
-        }
-        _ => {
-          unreachable!()
-        }
-      }
-    });
+    image.par_chunks_exact_mut(3).for_each(|pix| version.ycbcr_to_rgb(pix, (c1, c2, c3), hue));
     Ok(())
   }
 }
 
+/// Generations of the sRAW/mRAW YCbCr encoding
+///
+/// Starting with 40D, sRaw format was introduced. This uses
+/// version 0. With 5D Mark II, version 1 gets used.
+/// And with 5D Mark III, back to version 0 method
+/// but without an offset of 512 for y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SrawVersion {
+  /// Hint `sraw_40d`
+  V0,
+  /// Hint `sraw_5dmk2`
+  V1,
+  /// Hint `sraw_new`
+  V2,
+}
+
+impl SrawVersion {
+  /// Hints for the version in camera definitions
+  const HINTS: [(&'static str, Self); 3] = [("sraw_40d", Self::V0), ("sraw_5dmk2", Self::V1), ("sraw_new", Self::V2)];
+
+  /// Get the version from the camera definition hints
+  ///
+  /// The encodings are not distinguishable by the file content, so
+  /// cameras without a hint are not supported.
+  fn detect(cam: &Camera) -> Result<Self> {
+    Self::HINTS
+      .iter()
+      .find(|(hint, _)| cam.find_hint(hint))
+      .map(|(_, version)| *version)
+      .ok_or_else(|| RawlerError::unsupported(cam, "sRAW version hint is missing in camera definition"))
+  }
+
+  /// Convert a single YCbCr pixel in-place to RGB
+  fn ycbcr_to_rgb(self, pix: &mut [u16], (c1, c2, c3): (i32, i32, i32), hue: i32) {
+    let y = pix[0] as i32;
+    let cb = pix[1] as i32 - 16383;
+    let cr = pix[2] as i32 - 16383;
+    let (r, g, b) = match self {
+      Self::V0 | Self::V2 => {
+        let y = if self == Self::V0 { y - 512 } else { y };
+        (y + cr, y + ((-778 * cb - (cr << 11)) >> 12), y + cb)
+      }
+      Self::V1 => {
+        let cb = (cb << 2) + hue;
+        let cr = (cr << 2) + hue;
+        (
+          y + ((50 * cb + 22929 * cr) >> 14),
+          y + ((-5640 * cb - 11751 * cr) >> 14),
+          y + ((29040 * cb - 101 * cr) >> 14),
+        )
+      }
+    };
+    pix[0] = clampbits((c1 * r) >> 8, 16);
+    pix[1] = clampbits((c2 * g) >> 8, 16);
+    pix[2] = clampbits((c3 * b) >> 8, 16);
+  }
+}
+
 fn normalize_wb(raw_wb: [f32; 4]) -> [f32; 4] {
   debug!("CR2 raw wb: {:?}", raw_wb);
   // We never have more then RGB colors so far (no RGBE etc.)
@@ -902,3 +925,47 @@ pub enum Cr2MakernoteTag {
 }
 
 //const CR2_MODEL_40D: u32 = 0x80000190;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn sraw_version_from_hints() {
+    let hinted = |hint: &str| Camera {
+      hints: vec![hint.to_string()],
+      ..Default::default()
+    };
+    assert_eq!(SrawVersion::detect(&hinted("sraw_40d")).ok(), Some(SrawVersion::V0));
+    assert_eq!(SrawVersion::detect(&hinted("sraw_5dmk2")).ok(), Some(SrawVersion::V1));
+    assert_eq!(SrawVersion::detect(&hinted("sraw_new")).ok(), Some(SrawVersion::V2));
+    assert!(SrawVersion::detect(&Camera::default()).is_err());
+  }
+
+  #[test]
+  fn sraw_modes_have_version_hint() {
+    let loader = RawLoader::new();
+    for ((make, model, mode), cam) in loader.get_cameras() {
+      if mode.starts_with("sRaw") {
+        let hints = SrawVersion::HINTS.iter().filter(|(hint, _)| cam.find_hint(hint)).count();
+        assert_eq!(hints, 1, "{} {} ({}) needs exactly one sRAW version hint", make, model, mode);
+      }
+    }
+  }
+
+  #[test]
+  fn sraw_neutral_ycbcr_is_gray() {
+    for version in [SrawVersion::V0, SrawVersion::V1, SrawVersion::V2] {
+      let mut pix = [4096, 16383, 16383];
+      version.ycbcr_to_rgb(&mut pix, (256, 256, 256), 0);
+      let expected = if version == SrawVersion::V0 { 4096 - 512 } else { 4096 };
+      assert_eq!(pix, [expected; 3], "{:?}", version);
+    }
+    // Chroma is applied to red and blue
+    let mut pix = [4096, 16383 + 100, 16383 + 200];
+    SrawVersion::V2.ycbcr_to_rgb(&mut pix, (256, 256, 256), 0);
+    assert_eq!(pix[0], 4096 + 200);
+    assert_eq!(pix[2], 4096 + 100);
+    assert!(pix[1] < 4096);
+  }
+}
@@ -46,18 +46,6 @@ pub(crate) fn parse_colordata(colordata: &Entry) -> Result<ColorData> {
 
         _ => return Err(format!("Unknown2 COLORDATA version: {}", data[0]).into()),
       })
-
-      /*
-      Ok(match data.len() {
-          // 20D and 350D
-          582 => ColorData::new(&data, version, 0x19, None, None, None),
-          // 1DmkII and 1DSmkII
-          653 => ColorData::new(&data, version, 0x22, None, None, None),
-          // 1DmkIIN, 5D, 30D, 400D
-          796 => ColorData::new(&data, version, 0x3f, Some(0xc4), None, None),
-          _ => panic!("COLORDATA count of {} is unknown", data.len())
-      })
-       */
     }
     Value::Short(data) => {
       match data.len() {
@@ -131,6 +131,15 @@ impl<'a> Cr3Decoder<'a> {
     craw.and_then(|craw| craw.cdi1.as_ref()).map(|cdi1| &cdi1.iad1)
   }
 
+  /// Index of the trak with the raw image
+  fn raw_trak_index(&self) -> Result<usize> {
+    Ok(
+      rawler_crx_raw_trak()
+        .or_else(|| self.get_trak_index(Cr3ImageType::CrxBix))
+        .ok_or("Unable to find trak index")?,
+    )
+  }
+
   /// Get CMP1 box for specific trak
   fn cmp1_box(&self, trak_idx: usize) -> Option<&Cmp1Box> {
     let trak = &self.bmff.filebox.moov.traks[trak_idx];
@@ -245,9 +254,7 @@ impl<'a> Decoder for Cr3Decoder<'a> {
 
   /// CR3 can store multiple samples in trak
   fn raw_image_count(&self) -> Result<usize> {
-    let raw_trak_id = rawler_crx_raw_trak()
-      .or_else(|| self.get_trak_index(Cr3ImageType::CrxBix))
-      .ok_or("Unable to find trak index")?;
+    let raw_trak_id = self.raw_trak_index()?;
     let moov_trak = self.moov_trak(raw_trak_id).ok_or(format!("Unable to get MOOV trak {}", raw_trak_id))?;
     Ok(moov_trak.mdia.minf.stbl.stsz.sample_count as usize)
   }
@@ -271,9 +278,7 @@ impl<'a> Decoder for Cr3Decoder<'a> {
       }
     }
 
-    let raw_trak_id = rawler_crx_raw_trak()
-      .or_else(|| self.get_trak_index(Cr3ImageType::CrxBix))
-      .ok_or("Unable to find trak index")?;
+    let raw_trak_id = self.raw_trak_index()?;
 
     // Load trak with raw MDAT section
     let moov_trak = self.moov_trak(raw_trak_id).ok_or(format!("Unable to get MOOV trak {}", raw_trak_id))?;
@@ -326,8 +331,8 @@ impl<'a> Decoder for Cr3Decoder<'a> {
       .blacklevels
       .map(|x| BlackLevel::new(&x, self.camera.cfa.width, self.camera.cfa.height, cpp));
     let whitelevel = WhiteLevel(vec![whitelevel as u32]);
-    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera));
-    let mut img = RawImage::new(self.camera.clone(), image, cpp, wb, photometric, blacklevel, Some(whitelevel), dummy);
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?);
+    let mut img = RawImage::new(self.camera.clone(), image, cpp, wb, photometric, blacklevel, Some(whitelevel), dummy)?;
 
     // IAD1 box contains sensor information
     // We use the sensor crop from IAD1 as recommended image crop.
@@ -449,6 +454,16 @@ impl<'a> Decoder for Cr3Decoder<'a> {
     }
   }
 
+  fn raw_dimensions(&self, _params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    let raw_trak_id = self.raw_trak_index()?;
+    let cmp1 = self.cmp1_box(raw_trak_id).ok_or(format!("CMP1 box not found for trak {}", raw_trak_id))?;
+    Ok(Some(Dim2::new(cmp1.f_width as usize, cmp1.f_height as usize)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(self.camera.mode.clone()))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::CR3
   }
@@ -241,7 +241,8 @@ impl<'a> CrwDecoder<'a> {
     }
 
     if lowbits {
-      let buffer = file.as_vec()?;
+      // The uncompressed low bits are stored in front of the compressed data
+      let buffer = file.subview(26, (out.pixels().len() / 4) as u64)?;
       // Add the uncompressed 2 low bits to the decoded 8 high bits
       for (i, o) in out.pixels_mut().chunks_exact_mut(4).enumerate() {
         let c = buffer[26 + i] as u16;
@@ -1,6 +1,7 @@
 use crate::RawImage;
 use crate::cfa::*;
 use crate::decoders::*;
+use crate::dng::auxiliary::{AuxiliaryImage, DepthInfo, ProfileGainTableMap};
 use crate::formats::tiff::Entry;
 use crate::formats::tiff::Rational;
 use crate::formats::tiff::Value;
@@ -32,8 +33,8 @@ pub struct DngFormat {
 }
 
 impl<'a> Decoder for DngDecoder<'a> {
-  fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
-    let raw = self.get_raw_ifd()?;
+  fn raw_image(&self, file: &RawSource, params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
+    let (raw, _) = self.raw_ifd(params.image_index)?;
     let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
     let cpp = fetch_tiff_tag!(raw, TiffCommonTag::SamplesPerPixel).force_usize(0);
@@ -61,14 +62,14 @@ impl<'a> Decoder for DngDecoder<'a> {
 
     let photometric = match fetch_tiff_tag!(raw, TiffCommonTag::PhotometricInt).force_u32(0) {
       1 => RawPhotometricInterpretation::BlackIsZero,
-      32803 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)),
+      32803 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)?),
       34892 => RawPhotometricInterpretation::LinearRaw,
-      _ => todo!(),
+      pi => return Err(RawlerError::unsupported_feature("DNG", format!("PhotometricInterpretation {}", pi))),
     };
 
     let raw_data = plain_image_from_ifd(raw, file)?;
     let wb_coeffs = self.get_wb(&cam)?;
-    let mut image = RawImage::new_with_data(cam, raw_data, width * cpp, height, cpp, wb_coeffs, photometric, blacklevel, whitelevel, dummy);
+    let mut image = RawImage::new_with_data(cam, raw_data, width * cpp, height, cpp, wb_coeffs, photometric, blacklevel, whitelevel, dummy)?;
     image.orientation = orientation;
     Ok(image)
   }
@@ -196,34 +197,117 @@ impl<'a> Decoder for DngDecoder<'a> {
     })
   }
 
+  fn raw_image_count(&self) -> Result<usize> {
+    Ok(self.raw_ifds().len().max(1))
+  }
+
+  fn raw_image_kind(&self, index: usize) -> Result<RawImageKind> {
+    self.raw_ifd(index).map(|(_, kind)| kind)
+  }
+
+  fn raw_dimensions(&self, params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    let (raw, _) = self.raw_ifd(params.image_index)?;
+    let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
+    let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
+    Ok(Some(Dim2::new(width, height)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(String::from("dng")))
+  }
+
+  fn auxiliary_images(&self, file: &RawSource) -> Result<Vec<AuxiliaryImage>> {
+    self.auxiliary_ifds().into_iter().map(|ifd| AuxiliaryImage::from_ifd(ifd, file)).collect()
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::DNG
   }
 }
 
 impl<'a> DngDecoder<'a> {
-  fn get_raw_ifd(&self) -> Result<&IFD> {
-    let ifds = self
+  /// All raw image IFDs, the full resolution main image is always first
+  fn raw_ifds(&self) -> Vec<(&IFD, RawImageKind)> {
+    let mut ifds = self
       .tiff
       .find_ifds_with_tag(TiffCommonTag::Compression)
       .into_iter()
-      .filter(|ifd| {
-        let compression = (**ifd)
-          .get_entry(TiffCommonTag::Compression)
-          .expect("This IFD must contains this tag")
-          .force_u32(0);
-        let subsampled = match (**ifd).get_entry(TiffCommonTag::NewSubFileType) {
-          Some(e) => e.force_u32(0) & 1 != 0,
-          None => false,
+      .filter_map(|ifd| {
+        let compression = ifd.get_entry(TiffCommonTag::Compression).expect("This IFD must contains this tag").force_u32(0);
+        if !(compression == 7 || compression == 8 || compression == 1 || compression == 5 || compression == 9 || compression == 0x884c || compression == 52546)
+        {
+          return None;
+        }
+        let is_raw = ifd
+          .get_entry(TiffCommonTag::PhotometricInt)
+          .map(|entry| matches!(entry.force_u32(0), 32803 | 34892))
+          .unwrap_or(false);
+        let kind = match ifd.get_entry(TiffCommonTag::NewSubFileType).map(|entry| entry.force_u32(0)).unwrap_or(0) {
+          0 => RawImageKind::Main,
+          // Reduced resolution images are raw only if they contain CFA or linear data,
+          // otherwise they are previews.
+          1 if is_raw => RawImageKind::Reduced,
+          // Enhanced image data (DNG 1.6)
+          0x10 => RawImageKind::Enhanced,
+          // Transparency masks, depth maps and previews
+          _ => return None,
         };
-        !subsampled && (compression == 7 || compression == 8 || compression == 1 || compression == 0x884c || compression == 52546)
+        Some((ifd, kind))
       })
-      .collect::<Vec<&IFD>>();
-    if let Some(first) = ifds.first() {
-      Ok(first)
-    } else {
-      Err(RawlerError::DecoderFailed(format!("TODO: Unsupported DNG compression")))
+      .collect::<Vec<_>>();
+    // Stable sort keeps the file order for images of the same kind
+    ifds.sort_by_key(|(_, kind)| match kind {
+      RawImageKind::Main | RawImageKind::ShiftFrame(_) => 0,
+      RawImageKind::Enhanced => 1,
+      RawImageKind::Reduced => 2,
+    });
+    ifds
+  }
+
+  /// IFDs of depth maps and semantic masks
+  fn auxiliary_ifds(&self) -> Vec<&IFD> {
+    self
+      .tiff
+      .find_ifds_with_tag(TiffCommonTag::NewSubFileType)
+      .into_iter()
+      .filter(|ifd| AuxiliaryImage::is_auxiliary_ifd(ifd))
+      .collect()
+  }
+
+  /// Depth map parameters, if the file contains a depth map
+  pub fn depth_info(&self) -> Option<DepthInfo> {
+    DepthInfo::from_ifd(self.tiff.root_ifd())
+  }
+
+  /// Local tone mapping gain table from raw or root IFD
+  pub fn profile_gain_table_map(&self) -> Result<Option<ProfileGainTableMap>> {
+    let raw = self.get_raw_ifd()?;
+    match raw
+      .get_entry(DngTag::ProfileGainTableMap)
+      .or_else(|| self.tiff.root_ifd().get_entry(DngTag::ProfileGainTableMap))
+    {
+      Some(Entry {
+        value: Value::Undefined(data) | Value::Byte(data),
+        ..
+      }) => ProfileGainTableMap::parse(data).map(Some),
+      Some(entry) => Err(format!("Unsupported ProfileGainTableMap type: {}", entry.value_type_name()).into()),
+      None => Ok(None),
+    }
+  }
+
+  fn raw_ifd(&self, index: usize) -> Result<(&IFD, RawImageKind)> {
+    let ifds = self.raw_ifds();
+    if ifds.is_empty() {
+      return Err(RawlerError::DecoderFailed("TODO: Unsupported DNG compression".to_string()));
     }
+    ifds
+      .get(index)
+      .copied()
+      .ok_or_else(|| RawlerError::DecoderFailed(format!("Raw image index {} out of range ({})", index, ifds.len())))
+  }
+
+  fn get_raw_ifd(&self) -> Result<&IFD> {
+    self.raw_ifd(0).map(|(ifd, _)| ifd)
   }
 
   fn make_camera(&self, raw: &IFD, width: usize, height: usize) -> Result<Camera> {
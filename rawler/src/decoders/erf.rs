@@ -71,8 +71,8 @@ impl<'a> Decoder for ErfDecoder<'a> {
     let cpp = 1;
 
     let blacklevel = self.get_blacklevel(cpp);
-    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera));
-    let img = RawImage::new(self.camera.clone(), image, cpp, self.get_wb()?, photometric, blacklevel, None, dummy);
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?);
+    let img = RawImage::new(self.camera.clone(), image, cpp, self.get_wb()?, photometric, blacklevel, None, dummy)?;
     Ok(img)
   }
 
@@ -33,6 +33,7 @@ use crate::exif::Exif;
 use crate::formats::tiff;
 use crate::formats::tiff::GenericTiffReader;
 use crate::formats::tiff::reader::TiffReader;
+use crate::imgop::Dim2;
 use crate::imgop::Point;
 use crate::imgop::spline::Spline;
 use crate::lens::LensDescription;
@@ -254,8 +255,7 @@ impl<'a> Decoder for IiqDecoder<'a> {
       .map_err(|ioerr| RawlerError::with_io_error("load IIQ strips", file.path(), ioerr))?;
 
     let mut image = match fmt {
-      IiqCompression::Raw1 => todo!(),
-      IiqCompression::Raw2 => todo!(),
+      IiqCompression::Raw1 | IiqCompression::Raw2 => return Err(RawlerError::unsupported_feature("IIQ", format!("compression {:?}", fmt))),
       IiqCompression::Uncompressed => Self::decode_uncompressed(data, width, height, 14, dummy)?,
       IiqCompression::IIQ_L => Self::decode_compressed(data, strips, width, height, 14, dummy)?,
       IiqCompression::IIQ_L16 => Self::decode_compressed(data, strips, width, height, 16, dummy)?,
@@ -296,6 +296,15 @@ impl<'a> Decoder for IiqDecoder<'a> {
     Ok(mdata)
   }
 
+  fn raw_dimensions(&self, _params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    let (width, height) = self.dimension()?;
+    Ok(Some(Dim2::new(width, height)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(self.camera.mode.clone()))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::IIQ
   }
@@ -309,13 +318,13 @@ impl<'a> IiqDecoder<'a> {
       debug!("Apply defect correction");
 
       for def in defects.iter() {
-        if def.col >= img.width {
+        if def.col < 2 || def.col + 2 >= img.width {
           continue;
         };
         match def.typ {
           131 | 137 => {
             debug!("Correct bad colum: {}", def.col);
-            self.fix_bad_column(img, def);
+            self.fix_bad_column(img, def)?;
           }
           129 => {
             // single bad pixels are ignored, these can be fixed by
@@ -334,10 +343,12 @@ impl<'a> IiqDecoder<'a> {
   fn correct_raw_poly_half(&self, img: &mut PixU16, senscorr: &SensorCalibration) -> Result<()> {
     if let Some(poly) = &senscorr.poly_curve_half {
       debug!("Apply polynom curve half correction");
-      let split_col = self.split_column()?.expect("Must have split column");
+      let split_col = self.split_column()?.ok_or_else(|| RawlerError::malformed("IIQ", "split column is missing"))?;
 
       let mut poly = *poly;
-      let sensor_temp = self.sensor_temp()?.expect("Half polynom curve correction requires the sensor temp.");
+      let sensor_temp = self
+        .sensor_temp()?
+        .ok_or_else(|| RawlerError::malformed("IIQ", "half polynom curve correction requires the sensor temp"))?;
       poly[3] += (sensor_temp - poly[7]) * poly[6] + 1.0;
 
       let mut curve = vec![0_u16; 0x10000];
@@ -376,7 +387,9 @@ impl<'a> IiqDecoder<'a> {
   fn correct_raw_q_linearization(&self, img: &mut PixU16, senscorr: &SensorCalibration) -> Result<()> {
     if let Some(table) = &senscorr.quadrant_linearization {
       debug!("Apply quadrant linearization");
-      let (split_col, split_row) = self.split_offsets()?.expect("Must have split values");
+      let (split_col, split_row) = self
+        .split_offsets()?
+        .ok_or_else(|| RawlerError::malformed("IIQ", "split offsets are missing"))?;
 
       debug!("Split col: {}, row: {}", split_col, split_row);
 
@@ -428,7 +441,9 @@ impl<'a> IiqDecoder<'a> {
   fn correct_raw_q_mul(&self, img: &mut PixU16, senscorr: &SensorCalibration) -> Result<()> {
     if let Some(multipliers) = &senscorr.quadrant_multipliers {
       debug!("Apply quadrant multiplier correction");
-      let (split_col, split_row) = self.split_offsets()?.expect("Must have split values");
+      let (split_col, split_row) = self
+        .split_offsets()?
+        .ok_or_else(|| RawlerError::malformed("IIQ", "split offsets are missing"))?;
       debug!("Split col: {}, row: {}", split_col, split_row);
 
       let mut qmul = [[f32::NAN; 2]; 2];
@@ -451,7 +466,9 @@ impl<'a> IiqDecoder<'a> {
   fn correct_raw_q_combined(&self, img: &mut PixU16, senscorr: &SensorCalibration) -> Result<()> {
     if let Some((coord_x, coord_y)) = &senscorr.quadrant_combined {
       debug!("Apply quadrant combined correction");
-      let (split_col, split_row) = self.split_offsets()?.expect("Must have split values");
+      let (split_col, split_row) = self
+        .split_offsets()?
+        .ok_or_else(|| RawlerError::malformed("IIQ", "split offsets are missing"))?;
 
       debug!("Split col: {}, row: {}", split_col, split_row);
 
@@ -516,8 +533,8 @@ impl<'a> IiqDecoder<'a> {
           debug!("Apply red-blue flat field correction 0x{:x} HEAD: {:?}", flat.typ, flat.head);
           4
         }
-        _ => {
-          panic!("Unsupported flat field typ");
+        typ => {
+          return Err(RawlerError::unsupported_feature("IIQ", format!("flat field type 0x{:x}", typ)));
         }
       };
 
@@ -537,7 +554,9 @@ impl<'a> IiqDecoder<'a> {
       for y in 0..high {
         for x in 0..wide {
           for c in (0..nc).step_by(2) {
-            let num = *pump.next().expect("flat field correction data consumed but tried to get more");
+            let num = *pump
+              .next()
+              .ok_or_else(|| RawlerError::malformed("IIQ", "flat field correction data is too short"))?;
             if y == 0 {
               mrow[c * wide + x] = num;
             } else {
@@ -595,7 +614,9 @@ impl<'a> IiqDecoder<'a> {
     // Remove black level and ajdust individual row/col blacklevel
     if let Some(q_blacklevel) = &calib.q_blacklevel {
       let black = calib.blacklevel as i32;
-      let (split_col, split_row) = self.split_offsets()?.expect("Must have split values");
+      let (split_col, split_row) = self
+        .split_offsets()?
+        .ok_or_else(|| RawlerError::malformed("IIQ", "split offsets are missing"))?;
       let (cblack, rblack) = q_blacklevel;
       debug_assert_eq!(cblack.len(), img.height * 2);
       debug_assert_eq!(rblack.len(), img.width * 2);
@@ -904,11 +925,11 @@ impl<'a> IiqDecoder<'a> {
           sensor_margins,
         })
       }
-      _ => panic!("No sensor calibration data found."),
+      _ => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "No sensor calibration data found")),
     }
   }
 
-  fn fix_bad_column(&self, img: &mut PixU16, defect: &Defect) {
+  fn fix_bad_column(&self, img: &mut PixU16, defect: &Defect) -> Result<()> {
     let col = defect.col;
     for row in 2..img.height - 2 {
       match self.camera.cfa.color_at(row, col) {
@@ -963,11 +984,15 @@ impl<'a> IiqDecoder<'a> {
           // But this is not just averaging, we bias towards the horizontal pixels.
           *img.at_mut(row, col) = (diags as f32 * 0.0732233 + horiz as f32 * 0.3535534).round() as u16;
         }
-        _ => {
-          panic!("Other colors should not appear here");
+        color => {
+          return Err(RawlerError::unsupported_feature(
+            "IIQ",
+            format!("bad column correction for CFA color {}", color),
+          ));
         }
       }
     }
+    Ok(())
   }
 
   /// Extract white balance parameters
@@ -5,7 +5,6 @@ use crate::RawLoader;
 use crate::RawSource;
 use crate::RawlerError;
 use crate::Result;
-use crate::alloc_image;
 use crate::alloc_image_ok;
 use crate::analyze::FormatDump;
 use crate::bits::BEu16;
@@ -74,23 +73,32 @@ impl<'a> Decoder for KdcDecoder<'a> {
       let off = fetch_tiff_tag!(raw, TiffCommonTag::StripOffsets).force_usize(0);
       let mut white = self.camera.whitelevel.clone().expect("KDC needs a whitelevel in camera config")[0];
       let src = file.subview_until_eof(off as u64)?;
-      let image = match fetch_tiff_tag!(raw, TiffCommonTag::Compression).force_usize(0) {
-        1 => Self::decode_dc120(src, width, height, dummy),
-        7 => {
+      let compression = raw.get_entry(TiffCommonTag::Compression).map(|entry| entry.force_usize(0)).unwrap_or(1);
+      let image = match compression {
+        // JPEG compressed modes (good/better/best), old and new style JPEG
+        6 | 7 => {
           white = 0xFF << 1;
           Self::decode_dc120_jpeg(src, width, height, dummy)?
         }
+        // Uncompressed, some firmwares write 0 or vendor values here, but the
+        // data is always 8 bit per pixel (like dcraw).
+        c if src.len() >= width * height => {
+          if c != 1 {
+            log::debug!("KDC: DC120: unknown compression type {}, assuming uncompressed data", c);
+          }
+          Self::decode_dc120(src, width, height, dummy)?
+        }
         c => {
           return Err(RawlerError::unsupported(
             &self.camera,
-            format!("KDC: DC120: Don't know how to handle compression type {}", c),
+            format!("KDC: DC120: Don't know how to handle compression type {} with {} bytes of data", c, src.len()),
           ));
         }
       };
       let cpp = 1;
       let whitelevel = Some(WhiteLevel::new(vec![white; cpp]));
-      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera));
-      let img = RawImage::new(self.camera.clone(), image, cpp, [1.0, 1.0, 1.0, f32::NAN], photometric, None, whitelevel, dummy);
+      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?);
+      let img = RawImage::new(self.camera.clone(), image, cpp, [1.0, 1.0, 1.0, f32::NAN], photometric, None, whitelevel, dummy)?;
       return Ok(img);
     }
 
@@ -115,8 +123,8 @@ impl<'a> Decoder for KdcDecoder<'a> {
       let image = crate::decompressors::radc::decompress(&src, width, height, cbpp, dummy)?;
       let cpp = 1;
       let whitelevel = Some(WhiteLevel::new(vec![white; cpp]));
-      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera));
-      let img = RawImage::new(self.camera.clone(), image, cpp, [1.0, 1.0, 1.0, f32::NAN], photometric, None, whitelevel, dummy);
+      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?);
+      let img = RawImage::new(self.camera.clone(), image, cpp, [1.0, 1.0, 1.0, f32::NAN], photometric, None, whitelevel, dummy)?;
       return Ok(img);
     }
 
@@ -186,8 +194,16 @@ impl<'a> KdcDecoder<'a> {
     }
   }
 
-  pub(crate) fn decode_dc120(src: &[u8], width: usize, height: usize, dummy: bool) -> PixU16 {
-    let mut out = alloc_image!(width, height, dummy);
+  pub(crate) fn decode_dc120(src: &[u8], width: usize, height: usize, dummy: bool) -> Result<PixU16> {
+    if width != 848 || src.len() < width * height {
+      return Err(RawlerError::DecoderFailed(format!(
+        "KDC: DC120: expected 848 pixels per row and {} bytes, got {} and {}",
+        width * height,
+        width,
+        src.len()
+      )));
+    }
+    let mut out = alloc_image_ok!(width, height, dummy);
 
     let mul: [usize; 4] = [162, 192, 187, 92];
     let add: [usize; 4] = [0, 636, 424, 212];
@@ -197,7 +213,7 @@ impl<'a> KdcDecoder<'a> {
         out[row * width + col] = src[row * width + ((col + shift) % 848)] as u16;
       }
     }
-    out
+    Ok(out)
   }
 
   pub(crate) fn decode_dc120_jpeg(src: &[u8], width: usize, height: usize, dummy: bool) -> Result<PixU16> {
@@ -208,10 +224,20 @@ impl<'a> KdcDecoder<'a> {
     let img = image::load_from_memory_with_format(&swapped_src, image::ImageFormat::Jpeg)
       .map_err(|err| RawlerError::DecoderFailed(format!("Failed to read JPEG image: {:?}", err)))?;
 
-    assert_eq!(width, img.width() as usize);
-    assert_eq!(height, img.height() as usize * 2);
+    if width != img.width() as usize || height != img.height() as usize * 2 {
+      return Err(RawlerError::DecoderFailed(format!(
+        "KDC: DC120: JPEG has dimension {}x{}, expected {}x{}",
+        img.width(),
+        img.height(),
+        width,
+        height / 2
+      )));
+    }
     let buf = img.as_flat_samples_u8().ok_or("KDC: failed to get u8 samples from JPEG")?;
     let jpeg = buf.as_slice();
+    if jpeg.len() < width * img.height() as usize * 3 {
+      return Err(RawlerError::DecoderFailed("KDC: DC120: JPEG is not a 3 channel image".to_string()));
+    }
 
     for irow in 0..img.height() as usize {
       let row = irow * 2;
@@ -37,7 +37,9 @@ use crate::decompressors::deflate::DeflateDecompressor;
 use crate::decompressors::jpeg::JpegDecompressor;
 use crate::decompressors::jpegxl::JpegXLDecompressor;
 use crate::decompressors::ljpeg::LJpegDecompressor;
+use crate::decompressors::lzw::LzwDecompressor;
 use crate::decompressors::packed::PackedDecompressor;
+use crate::dng::auxiliary::AuxiliaryImage;
 use crate::exif::Exif;
 use crate::formats::ciff;
 use crate::formats::jfif;
@@ -57,14 +59,16 @@ use crate::lens::LensDescription;
 use crate::pixarray::Pix2D;
 use crate::pixarray::PixU16;
 use crate::pixarray::SubPixel;
-use crate::pixarray::deinterleave2x2;
+use crate::pixarray::deinterleave;
 use crate::rawsource::RawSource;
 use crate::tags::DngTag;
 use crate::tiles::TilesMut;
 
 macro_rules! fetch_ciff_tag {
   ($tiff:expr, $tag:expr) => {
-    $tiff.find_entry($tag).ok_or(format!("Couldn't find tag {}", stringify!($tag)).to_string())?
+    $tiff
+      .find_entry($tag)
+      .ok_or_else(|| crate::RawlerError::malformed("CIFF", format!("Couldn't find tag {}", stringify!($tag))))?
   };
 }
 
@@ -73,7 +77,7 @@ macro_rules! fetch_tiff_tag {
     $ifd
       .get_entry($tag)
       .map(|entry| &entry.value)
-      .ok_or(format!("Couldn't find tag {}", stringify!($tag)))?
+      .ok_or_else(|| crate::RawlerError::malformed("TIFF", format!("Couldn't find tag {}", stringify!($tag))))?
   };
 }
 
@@ -83,11 +87,14 @@ macro_rules! fetch_tiff_tag_variant {
     if let $variant(tmp) = $ifd
       .get_entry($tag)
       .map(|entry| &entry.value)
-      .ok_or(format!("Couldn't find tag {}", stringify!($tag)))?
+      .ok_or_else(|| crate::RawlerError::malformed("TIFF", format!("Couldn't find tag {}", stringify!($tag))))?
     {
       tmp
     } else {
-      return Err(format!("fetch_tiff_tag_variant!(): tag {} has unepxected datatype", stringify!($tag)).into());
+      return Err(crate::RawlerError::malformed(
+        "TIFF",
+        format!("fetch_tiff_tag_variant!(): tag {} has unepxected datatype", stringify!($tag)),
+      ));
     }
   };
 }
@@ -163,6 +170,42 @@ pub struct RawDecodeParams {
   pub image_index: usize,
 }
 
+/// Role of a raw image inside a container with multiple raw images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RawImageKind {
+  /// Full resolution raw image
+  Main,
+  /// Raw image with reduced resolution (e.g. small or medium raw)
+  Reduced,
+  /// Enhanced raw image, like DNG 1.6 enhanced or semantic subframes
+  Enhanced,
+  /// Single frame of a pixel shift capture, starting with 0
+  ShiftFrame(usize),
+}
+
+/// Position of a raw image in a pixel shift capture, from vendor metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelShiftShot {
+  /// Shot number in capture order, starting with 0
+  pub shot: usize,
+  /// Total number of shots of the capture
+  pub shots: usize,
+  /// Identifier shared by all shots of a capture, if recorded
+  pub group: Option<u32>,
+}
+
+impl std::fmt::Display for RawImageKind {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::Main => write!(f, "main"),
+      Self::Reduced => write!(f, "reduced"),
+      Self::Enhanced => write!(f, "enhanced"),
+      Self::ShiftFrame(n) => write!(f, "shift{}", n),
+    }
+  }
+}
+
 #[derive(Default, Debug, Clone)]
 struct DecoderCache<T>
 where
@@ -326,6 +369,43 @@ pub trait Decoder: Send {
     Ok(1)
   }
 
+  /// Kind of the raw image at `index`, see [`RawDecodeParams::image_index`]
+  fn raw_image_kind(&self, index: usize) -> Result<RawImageKind> {
+    let count = self.raw_image_count()?;
+    if index < count {
+      Ok(RawImageKind::Main)
+    } else {
+      Err(RawlerError::DecoderFailed(format!("Raw image index {} out of range ({})", index, count)))
+    }
+  }
+
+  /// Dimension of the raw image in pixels, if it's known without
+  /// reading the pixel data. This is used for probing, see [`crate::probe`].
+  fn raw_dimensions(&self, _params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    Ok(None)
+  }
+
+  /// Position of the raw image in a pixel shift capture
+  ///
+  /// By default, this is derived from [`RawImageKind::ShiftFrame`] for
+  /// containers which store all shots in one file.
+  fn pixel_shift_shot(&self, _file: &RawSource, params: &RawDecodeParams) -> Result<Option<PixelShiftShot>> {
+    Ok(match self.raw_image_kind(params.image_index)? {
+      RawImageKind::ShiftFrame(shot) => Some(PixelShiftShot {
+        shot,
+        shots: self.raw_image_count()?,
+        group: None,
+      }),
+      _ => None,
+    })
+  }
+
+  /// Camera mode of the raw image, if it's known without reading the
+  /// pixel data. This is used for probing, see [`crate::probe`].
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(None)
+  }
+
   /// Gives the metadata for a Raw. This is not the original data but
   /// a generalized set of metadata attributes.
   fn raw_metadata(&self, file: &RawSource, params: &RawDecodeParams) -> Result<RawMetadata>;
@@ -350,6 +430,11 @@ pub trait Decoder: Send {
     Ok(None)
   }
 
+  /// Depth maps and masks stored next to the raw image
+  fn auxiliary_images(&self, _file: &RawSource) -> Result<Vec<AuxiliaryImage>> {
+    Ok(Vec::new())
+  }
+
   fn format_dump(&self) -> FormatDump;
 
   fn ifd(&self, _wk_ifd: WellKnownIFD) -> Result<Option<Rc<IFD>>> {
@@ -452,16 +537,16 @@ impl Orientation {
 
 pub(crate) fn ok_cfa_image(camera: Camera, cpp: usize, wb_coeffs: [f32; 4], image: PixU16, dummy: bool) -> Result<RawImage> {
   assert_eq!(cpp, 1);
-  Ok(RawImage::new(
+  RawImage::new(
     camera.clone(),
     image,
     cpp,
     wb_coeffs,
-    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)),
+    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?),
     None,
     None,
     dummy,
-  ))
+  )
 }
 
 pub(crate) fn ok_cfa_image_with_blacklevels(camera: Camera, cpp: usize, wb_coeffs: [f32; 4], blacks: [u32; 4], image: PixU16, dummy: bool) -> Result<RawImage> {
@@ -472,11 +557,11 @@ pub(crate) fn ok_cfa_image_with_blacklevels(camera: Camera, cpp: usize, wb_coeff
     image,
     cpp,
     wb_coeffs,
-    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)),
+    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?),
     Some(blacklevel),
     None,
     dummy,
-  );
+  )?;
   Ok(img)
 }
 
@@ -490,11 +575,11 @@ pub(crate) fn ok_cfa_image_with_black_white(camera: Camera, cpp: usize, wb_coeff
     image,
     cpp,
     wb_coeffs,
-    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)),
+    RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?),
     Some(blacklevel),
     Some(whitelevel),
     dummy,
-  );
+  )?;
   Ok(img)
 }
    */
@@ -525,7 +610,7 @@ pub(crate) fn dynamic_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result
             ImageBuffer::<Rgb<u8>, Vec<u8>>::from_raw(tiff_width as u32, tiff_height as u32, buf.to_vec())
               .ok_or(RawlerError::DecoderFailed(format!("Create RGB thumbnail from strip failed")))?,
           )),
-          _ => unimplemented!(),
+          _ => Err(RawlerError::unsupported_feature("TIFF", format!("8 bit image with {} samples per pixel", cpp))),
         }
       }
       9..=16 => {
@@ -543,10 +628,13 @@ pub(crate) fn dynamic_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result
             ImageBuffer::<Rgb<u16>, Vec<u16>>::from_raw((tiff_width) as u32, tiff_height as u32, samples)
               .ok_or(RawlerError::DecoderFailed(format!("Create RGB image failed")))?,
           )),
-          _ => unimplemented!(),
+          _ => Err(RawlerError::unsupported_feature(
+            "TIFF",
+            format!("{} bit image with {} samples per pixel", bits, cpp),
+          )),
         }
       }
-      _ => unimplemented!(),
+      _ => Err(RawlerError::unsupported_feature("TIFF", format!("{} bit integer image", bits))),
     },
     RawImageData::Float(samples) => match cpp {
       3 => Ok(DynamicImage::ImageRgb32F(
@@ -554,7 +642,7 @@ pub(crate) fn dynamic_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result
         ImageBuffer::<Rgb<f32>, Vec<f32>>::from_raw(tiff_width as u32, tiff_height as u32, samples)
           .ok_or(RawlerError::DecoderFailed(format!("Create RGB image failed")))?,
       )),
-      _ => unimplemented!(),
+      _ => Err(RawlerError::unsupported_feature("TIFF", format!("float image with {} samples per pixel", cpp))),
     },
   }
 }
@@ -568,10 +656,9 @@ pub(crate) fn dynamic_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result
 /// padding), and optional post-processing steps are applied:
 ///
 /// * **Linearisation** — if a `Linearization` tag is present its lookup table
-///   is applied to every `u16` pixel.
-/// * **Deinterleaving** — if DNG 1.7.1 `RowInterleaveFactor` /
-///   `ColumnInterleaveFactor` tags indicate 2×2 interleaving, pixels are
-///   reordered.
+///   is applied to every pixel (interpolated for floating point data).
+/// * **Deinterleaving** — if `RowInterleaveFactor` / `ColumnInterleaveFactor`
+///   tags are present, rows and columns are reordered.
 ///
 /// # Supported combinations
 ///
@@ -581,8 +668,10 @@ pub(crate) fn dynamic_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result
 /// | `Uint` (u16)  | ModernJPEG (lossy or lossless) | Strips / Tiles |
 /// | `Uint` (u16)  | LossyJPEG                      | Strips / Tiles |
 /// | `Uint` (u16)  | JPEG-XL                        | Strips / Tiles |
+/// | `Uint` (u16)  | Deflate / LZW                  | Strips / Tiles |
 /// | `IEEEFP` (f32)| None                           | Strips / Tiles |
-/// | `IEEEFP` (f32)| Deflate                        | Tiles          |
+/// | `IEEEFP` (f32)| Deflate / LZW                  | Strips / Tiles |
+/// | `IEEEFP` (f32)| JPEG-XL                        | Strips / Tiles |
 ///
 /// All other combinations return an error.
 ///
@@ -605,6 +694,10 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
   let sample_format = SampleFormat::extract(ifd)?.unwrap_or(SampleFormat::Uint);
   let compression = CompressionMethod::extract(ifd)?.unwrap_or(CompressionMethod::None);
   let pi = PhotometricInterpretation::extract(ifd)?.unwrap_or(PhotometricInterpretation::BlackIsZero);
+  let predictor = ifd.get_entry(TiffCommonTag::Predictor).map(|tag| tag.force_u16(0)).unwrap_or(1);
+  // DNG 1.7.1 may store JPEG-XL data in interleaved format
+  let col_ilf = ifd.get_entry(DngTag::ColumnInterleaveFactor).map(|tag| tag.force_usize(0)).unwrap_or(1);
+  let row_ilf = ifd.get_entry(DngTag::RowInterleaveFactor).map(|tag| tag.force_usize(0)).unwrap_or(1);
 
   log::debug!(
     "plain_image_from_ifd(): {}x{}, cpp={}, bits={}, compression={:?}, sample_format={:?}, data_mode={:?}, endian={:?}",
@@ -624,14 +717,14 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
         (CompressionMethod::None, DataMode::Strips) => decode_strips::<u16>(rawsource, ifd, PackedDecompressor::new(bits, endian), dummy)?,
         (CompressionMethod::None, DataMode::Tiles) => decode_tiles::<u16>(rawsource, ifd, PackedDecompressor::new(bits, endian), dummy)?,
         (CompressionMethod::ModernJPEG, DataMode::Strips) => {
-          if (pi == PhotometricInterpretation::YCbCr && bits == 8) || (pi == PhotometricInterpretation::BlackIsZero && bits == 8) {
+          if bits == 8 && pi.is_baseline_jpeg() {
             decode_strips::<u16>(rawsource, ifd, JpegDecompressor::new(), dummy)?
           } else {
             decode_strips::<u16>(rawsource, ifd, LJpegDecompressor::new(), dummy)?
           }
         }
         (CompressionMethod::ModernJPEG, DataMode::Tiles) => {
-          if (pi == PhotometricInterpretation::YCbCr && bits == 8) || (pi == PhotometricInterpretation::BlackIsZero && bits == 8) {
+          if bits == 8 && pi.is_baseline_jpeg() {
             decode_tiles::<u16>(rawsource, ifd, JpegDecompressor::new(), dummy)?
           } else {
             decode_tiles::<u16>(rawsource, ifd, LJpegDecompressor::new(), dummy)?
@@ -641,12 +734,19 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
         (CompressionMethod::LossyJPEG, DataMode::Tiles) => decode_tiles::<u16>(rawsource, ifd, JpegDecompressor::new(), dummy)?,
         (CompressionMethod::JPEGXL, DataMode::Strips) => decode_strips::<u16>(rawsource, ifd, JpegXLDecompressor::new(bits), dummy)?,
         (CompressionMethod::JPEGXL, DataMode::Tiles) => decode_tiles::<u16>(rawsource, ifd, JpegXLDecompressor::new(bits), dummy)?,
+        (CompressionMethod::Deflate, DataMode::Strips) => decode_strips::<u16>(rawsource, ifd, DeflateDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::Deflate, DataMode::Tiles) => decode_tiles::<u16>(rawsource, ifd, DeflateDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::LZW, DataMode::Strips) => decode_strips::<u16>(rawsource, ifd, LzwDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::LZW, DataMode::Tiles) => decode_tiles::<u16>(rawsource, ifd, LzwDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::VC5, _) => {
+          // Decoding requires the VC-5 run length codebook, which is not available yet.
+          return Err(RawlerError::unsupported_feature("DNG", "VC-5 compression (GoPro GPR)"));
+        }
         _ => {
-          return Err(RawlerError::DecoderFailed(format!(
-            "Unsupported compression method: {:?}, storage: {:?}",
-            compression,
-            ifd.data_mode()?
-          )));
+          return Err(RawlerError::unsupported_feature(
+            "TIFF",
+            format!("compression method {:?} with {:?} storage", compression, ifd.data_mode()?),
+          ));
         }
       };
 
@@ -655,20 +755,10 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
       pixbuf = pixbuf.into_crop(Rect::new(Point::zero(), Dim2::new(tiff_width * cpp, tiff_height)));
 
       if let Some(lintable) = ifd.get_entry(TiffCommonTag::Linearization) {
-        apply_linearization(&mut pixbuf, &lintable.value, bits);
+        apply_linearization(&mut pixbuf, &lintable.value, bits)?;
       }
 
-      // DNG 1.7.1 may store JPEG-XL data in interleaved format
-      let col_ilf = ifd.get_entry(DngTag::ColumnInterleaveFactor).map(|tag| tag.force_u16(0)).unwrap_or(1);
-      let row_ilf = ifd.get_entry(DngTag::RowInterleaveFactor).map(|tag| tag.force_u16(0)).unwrap_or(1);
-      match (row_ilf, col_ilf) {
-        (1, 1) => {}
-        (2, 2) => {
-          // Make sure pixbuf is properly cropped (e.g. LJPEG padding)
-          pixbuf = deinterleave2x2(&pixbuf)?;
-        }
-        _ => todo!(),
-      }
+      pixbuf = deinterleave(&pixbuf, cpp, row_ilf, col_ilf)?;
       return Ok(RawImageData::Integer(pixbuf.into_inner()));
     }
     // Floating Point (IEEE) storage
@@ -677,16 +767,17 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
       let mut pixbuf = match (compression, ifd.data_mode()?) {
         (CompressionMethod::None, DataMode::Strips) => decode_strips::<f32>(rawsource, ifd, PackedDecompressor::new(bits, endian), dummy)?,
         (CompressionMethod::None, DataMode::Tiles) => decode_tiles::<f32>(rawsource, ifd, PackedDecompressor::new(bits, endian), dummy)?,
-        (CompressionMethod::Deflate, DataMode::Tiles) => {
-          let predictor = fetch_tiff_tag!(ifd, TiffCommonTag::Predictor).force_u16(0);
-          decode_tiles::<f32>(rawsource, ifd, DeflateDecompressor::new(cpp, predictor, bits), dummy)?
-        }
+        (CompressionMethod::Deflate, DataMode::Strips) => decode_strips::<f32>(rawsource, ifd, DeflateDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::Deflate, DataMode::Tiles) => decode_tiles::<f32>(rawsource, ifd, DeflateDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::LZW, DataMode::Strips) => decode_strips::<f32>(rawsource, ifd, LzwDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::LZW, DataMode::Tiles) => decode_tiles::<f32>(rawsource, ifd, LzwDecompressor::new(cpp, predictor, bits, endian), dummy)?,
+        (CompressionMethod::JPEGXL, DataMode::Strips) => decode_strips::<f32>(rawsource, ifd, JpegXLDecompressor::new(bits), dummy)?,
+        (CompressionMethod::JPEGXL, DataMode::Tiles) => decode_tiles::<f32>(rawsource, ifd, JpegXLDecompressor::new(bits), dummy)?,
         _ => {
-          return Err(RawlerError::DecoderFailed(format!(
-            "Unsupported compression method: {:?}, storage: {:?}",
-            compression,
-            ifd.data_mode()?
-          )));
+          return Err(RawlerError::unsupported_feature(
+            "TIFF",
+            format!("compression method {:?} with {:?} storage", compression, ifd.data_mode()?),
+          ));
         }
       };
 
@@ -694,11 +785,15 @@ pub fn plain_image_from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<RawImage
       // Padded pixels on output by LJPEG compression will corrupt deinterleave.
       pixbuf = pixbuf.into_crop(Rect::new(Point::zero(), Dim2::new(tiff_width * cpp, tiff_height)));
 
-      // TODO: other corrections (see u16 code above)?
+      if let Some(lintable) = ifd.get_entry(TiffCommonTag::Linearization) {
+        apply_linearization_f32(&mut pixbuf, &lintable.value)?;
+      }
+
+      pixbuf = deinterleave(&pixbuf, cpp, row_ilf, col_ilf)?;
 
       return Ok(RawImageData::Float(pixbuf.into_inner()));
     }
-    _ => unimplemented!("other sample-formats"),
+    _ => Err(RawlerError::unsupported_feature("TIFF", format!("sample format {:?}", sample_format))),
   }
 }
 
@@ -801,11 +896,11 @@ where
   Ok(pixbuf)
 }
 
-pub(crate) fn apply_linearization(image: &mut PixU16, tbl: &Value, bits: u32) {
+pub(crate) fn apply_linearization(image: &mut PixU16, tbl: &Value, bits: u32) -> Result<()> {
   match tbl {
     Value::Short(points) => {
       if points.is_empty() {
-        return;
+        return Ok(());
       }
       let table = LookupTable::new_with_bits(points, bits);
       image.par_pixel_rows_mut().for_each(|row| {
@@ -814,10 +909,41 @@ pub(crate) fn apply_linearization(image: &mut PixU16, tbl: &Value, bits: u32) {
           *p = table.dither(*p, &mut random);
         })
       });
+      Ok(())
     }
-    _ => {
-      panic!("Unsupported linear table");
+    _ => Err(RawlerError::unsupported_feature(
+      "DNG",
+      format!("linearization table of type {}", tbl.value_type_name()),
+    )),
+  }
+}
+
+/// Apply a linearization table to floating point data.
+///
+/// Samples are used as (fractional) index into the table and
+/// the table is interpolated linearly.
+pub(crate) fn apply_linearization_f32(image: &mut Pix2D<f32>, tbl: &Value) -> Result<()> {
+  match tbl {
+    Value::Short(points) => {
+      if points.is_empty() {
+        return Ok(());
+      }
+      let last = (points.len() - 1) as f32;
+      image.par_pixel_rows_mut().for_each(|row| {
+        row.iter_mut().for_each(|p| {
+          let pos = p.clamp(0.0, last);
+          let idx = pos.floor() as usize;
+          let next = (idx + 1).min(points.len() - 1);
+          let frac = pos - idx as f32;
+          *p = points[idx] as f32 * (1.0 - frac) + points[next] as f32 * frac;
+        })
+      });
+      Ok(())
     }
+    _ => Err(RawlerError::unsupported_feature(
+      "DNG",
+      format!("linearization table of type {}", tbl.value_type_name()),
+    )),
   }
 }
 
@@ -1039,8 +1165,7 @@ impl RawLoader {
     }
 
     // If all else fails see if we match by filesize to one of those CHDK style files
-    let data = rawfile.as_vec()?;
-    if let Some(cam) = self.naked.get(&data.len()) {
+    if let Some(cam) = self.naked.get(&rawfile.len()) {
       return Ok(Box::new(nkd::NakedDecoder::new(cam.clone(), self)?));
     }
 
@@ -1087,7 +1212,7 @@ impl RawLoader {
 
     match panic::catch_unwind(AssertUnwindSafe(|| self.decode_unsafe(rawfile, params, dummy))) {
       Ok(val) => val,
-      Err(_) => Err(RawlerError::DecoderFailed(format!("Caught a panic while decoding.{}", BUG))),
+      Err(payload) => Err(panic_to_error(payload)),
     }
   }
 
@@ -1104,13 +1229,243 @@ impl RawLoader {
     decoder.raw_image_count()
   }
 
+  /// Kinds of all raw images in a file, ordered by image index
+  pub fn raw_image_kinds_file(&self, path: &Path) -> Result<Vec<RawImageKind>> {
+    let rawfile = RawSource::new(path).map_err(|err| RawlerError::with_io_error("raw_image_kinds_file()", path, err))?;
+    let decoder = self.get_decoder(&rawfile)?;
+    (0..decoder.raw_image_count()?).map(|index| decoder.raw_image_kind(index)).collect()
+  }
+
   // Decodes an unwrapped input (just the image data with minimal metadata) into a RawImage
   // This is only useful for fuzzing really
   #[doc(hidden)]
   pub fn decode_unwrapped(&self, rawfile: &RawSource) -> Result<RawImageData> {
     match panic::catch_unwind(AssertUnwindSafe(|| unwrapped::decode_unwrapped(rawfile))) {
       Ok(val) => val,
-      Err(_) => Err(RawlerError::DecoderFailed(format!("Caught a panic while decoding.{}", BUG))),
+      Err(payload) => Err(panic_to_error(payload)),
+    }
+  }
+}
+
+/// Convert a caught panic into an error, keeping the panic message
+/// so the failing code path is still visible to the caller.
+fn panic_to_error(payload: Box<dyn std::any::Any + Send>) -> RawlerError {
+  let msg = if let Some(msg) = payload.downcast_ref::<&str>() {
+    msg.to_string()
+  } else if let Some(msg) = payload.downcast_ref::<String>() {
+    msg.clone()
+  } else {
+    String::from("unknown panic")
+  };
+  RawlerError::DecoderFailed(format!("Caught a panic while decoding: {}.{}", msg, BUG))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{Cursor, Write};
+
+  use libflate::zlib::Encoder;
+
+  use super::*;
+  use crate::{
+    dng::writer::encode_fp_deflate_tile,
+    formats::tiff::writer::{DirectoryWriter, TiffWriter},
+  };
+
+  const WIDTH: usize = 10;
+  const HEIGHT: usize = 6;
+
+  /// Storage layout for synthetic test images
+  enum Layout {
+    Strips { rows: usize },
+    Tiles { width: usize, height: usize },
+  }
+
+  /// Write a single IFD TIFF with the given encoded blocks and return
+  /// the decoded image.
+  fn decode_synthetic(layout: Layout, blocks: Vec<Vec<u8>>, tags: impl FnOnce(&mut DirectoryWriter)) -> Result<RawImageData> {
+    let mut buf = Cursor::new(Vec::new());
+    let mut tiff = TiffWriter::new(&mut buf)?;
+    let mut ifd = tiff.new_directory();
+    let mut offsets = Vec::new();
+    let mut counts = Vec::new();
+    for block in &blocks {
+      offsets.push(tiff.write_data(block)?);
+      counts.push(block.len() as u32);
+    }
+    match layout {
+      Layout::Strips { rows } => {
+        ifd.add_tag(TiffCommonTag::StripOffsets, &offsets[..]);
+        ifd.add_tag(TiffCommonTag::StripByteCounts, &counts[..]);
+        ifd.add_tag(TiffCommonTag::RowsPerStrip, rows as u32);
+      }
+      Layout::Tiles { width, height } => {
+        ifd.add_tag(TiffCommonTag::TileOffsets, &offsets[..]);
+        ifd.add_tag(TiffCommonTag::TileByteCounts, &counts[..]);
+        ifd.add_tag(TiffCommonTag::TileWidth, width as u32);
+        ifd.add_tag(TiffCommonTag::TileLength, height as u32);
+      }
+    }
+    ifd.add_tag(TiffCommonTag::ImageWidth, WIDTH as u32);
+    ifd.add_tag(TiffCommonTag::ImageLength, HEIGHT as u32);
+    ifd.add_tag(TiffCommonTag::SamplesPerPixel, 1_u16);
+    ifd.add_tag(TiffCommonTag::PhotometricInt, PhotometricInterpretation::BlackIsZero as u16);
+    tags(&mut ifd);
+    tiff.build(ifd)?;
+
+    let data = buf.into_inner();
+    let tiff = GenericTiffReader::new_with_buffer(&data, 0, 0, None)?;
+    plain_image_from_ifd(tiff.root_ifd(), &RawSource::new_from_slice(&data))
+  }
+
+  fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = Encoder::new(Vec::new()).expect("zlib encoder");
+    encoder.write_all(data).expect("zlib write");
+    encoder.finish().into_result().expect("zlib finish")
+  }
+
+  fn lzw(data: &[u8]) -> Vec<u8> {
+    weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+      .encode(data)
+      .expect("lzw encode")
+  }
+
+  /// Encode rows of 16 bit samples with horizontal differencing
+  fn predict_u16(samples: &[u16], line_width: usize, stride: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(samples.len() * 2);
+    for row in samples.chunks_exact(line_width) {
+      for (col, v) in row.iter().enumerate() {
+        let diff = if col >= stride { v.wrapping_sub(row[col - stride]) } else { *v };
+        out.extend_from_slice(&diff.to_ne_bytes());
+      }
+    }
+    out
+  }
+
+  fn integer(data: RawImageData) -> Vec<u16> {
+    match data {
+      RawImageData::Integer(data) => data,
+      RawImageData::Float(_) => panic!("Expected integer data"),
+    }
+  }
+
+  fn test_pattern() -> Vec<u16> {
+    (0..WIDTH * HEIGHT).map(|i| ((i * 997) % 4096) as u16).collect()
+  }
+
+  fn int_tags(compression: CompressionMethod, predictor: u16) -> impl FnOnce(&mut DirectoryWriter) {
+    move |ifd| {
+      ifd.add_tag(TiffCommonTag::BitsPerSample, 16_u16);
+      ifd.add_tag(TiffCommonTag::Compression, compression as u16);
+      ifd.add_tag(TiffCommonTag::Predictor, predictor);
+    }
+  }
+
+  #[test]
+  fn integer_deflate_strips_with_predictors() -> Result<()> {
+    let samples = test_pattern();
+    for (predictor, stride) in [(2, 1), (34892, 2), (34893, 4)] {
+      let blocks = samples.chunks(WIDTH * 4).map(|strip| zlib(&predict_u16(strip, WIDTH, stride))).collect();
+      let decoded = decode_synthetic(Layout::Strips { rows: 4 }, blocks, int_tags(CompressionMethod::Deflate, predictor))?;
+      assert_eq!(integer(decoded), samples, "predictor: {}", predictor);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn integer_deflate_tiles() -> Result<()> {
+    let samples = test_pattern();
+    let (tw, th) = (16, 16);
+    let mut tile = vec![0_u16; tw * th];
+    for row in 0..HEIGHT {
+      tile[row * tw..row * tw + WIDTH].copy_from_slice(&samples[row * WIDTH..(row + 1) * WIDTH]);
+    }
+    let blocks = vec![zlib(&predict_u16(&tile, tw, 1))];
+    let decoded = decode_synthetic(Layout::Tiles { width: tw, height: th }, blocks, int_tags(CompressionMethod::Deflate, 2))?;
+    assert_eq!(integer(decoded), samples);
+    Ok(())
+  }
+
+  #[test]
+  fn integer_lzw_strips() -> Result<()> {
+    let samples = test_pattern();
+    for predictor in [1, 2] {
+      let blocks = samples
+        .chunks(WIDTH * 4)
+        .map(|strip| {
+          let stride = if predictor == 2 { 1 } else { usize::MAX };
+          lzw(&predict_u16(strip, WIDTH, stride))
+        })
+        .collect();
+      let decoded = decode_synthetic(Layout::Strips { rows: 4 }, blocks, int_tags(CompressionMethod::LZW, predictor))?;
+      assert_eq!(integer(decoded), samples, "predictor: {}", predictor);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn float_deflate_and_lzw_strips() -> Result<()> {
+    let samples: Vec<f32> = test_pattern().iter().map(|v| *v as f32 / 4095.0).collect();
+    for compression in [CompressionMethod::Deflate, CompressionMethod::LZW] {
+      for predictor in [1, 3] {
+        let blocks = samples
+          .chunks(WIDTH * 4)
+          .map(|strip| {
+            let data = if predictor == 3 {
+              encode_fp_deflate_tile(strip, WIDTH, 1, 32).map(|deflated| inflate_for_test(&deflated))
+            } else {
+              Ok(strip.iter().flat_map(|v| v.to_ne_bytes()).collect())
+            };
+            data.map(|data| if compression == CompressionMethod::LZW { lzw(&data) } else { zlib(&data) })
+          })
+          .collect::<std::result::Result<Vec<_>, _>>()?;
+        let decoded = decode_synthetic(Layout::Strips { rows: 4 }, blocks, |ifd| {
+          ifd.add_tag(TiffCommonTag::BitsPerSample, 32_u16);
+          ifd.add_tag(TiffCommonTag::SampleFormat, SampleFormat::IEEEFP as u16);
+          ifd.add_tag(TiffCommonTag::Compression, compression as u16);
+          ifd.add_tag(TiffCommonTag::Predictor, predictor);
+        })?;
+        assert_eq!(decoded.as_f32().as_slice(), samples.as_slice(), "{:?}, predictor: {}", compression, predictor);
+      }
+    }
+    Ok(())
+  }
+
+  fn inflate_for_test(data: &[u8]) -> Vec<u8> {
+    let mut decoder = libflate::zlib::Decoder::new(data).expect("zlib decoder");
+    let mut out = Vec::new();
+    std::io::Read::read_to_end(&mut decoder, &mut out).expect("zlib read");
+    out
+  }
+
+  #[test]
+  fn float_linearization() -> Result<()> {
+    let samples = [0.0_f32, 0.5, 1.0, 1.25, 2.0, 3.5];
+    let mut pixbuf = Pix2D::new_with(samples.to_vec(), 3, 2);
+    apply_linearization_f32(&mut pixbuf, &Value::Short(vec![0, 100, 300]))?;
+    assert_eq!(pixbuf.pixels(), &[0.0, 50.0, 100.0, 150.0, 300.0, 300.0]);
+    Ok(())
+  }
+
+  #[test]
+  fn generic_interleave_factors() -> Result<()> {
+    let samples = test_pattern();
+    for (row_ilf, col_ilf) in [(2_usize, 2_usize), (3, 1), (1, 4), (3, 2)] {
+      // Store fields one after another, first for rows then for columns
+      let rows: Vec<usize> = (0..row_ilf).flat_map(|f| (f..HEIGHT).step_by(row_ilf)).collect();
+      let cols: Vec<usize> = (0..col_ilf).flat_map(|f| (f..WIDTH).step_by(col_ilf)).collect();
+      let stored: Vec<u16> = rows
+        .iter()
+        .flat_map(|row| cols.iter().map(|col| samples[row * WIDTH + col]).collect::<Vec<_>>())
+        .collect();
+      let blocks = vec![zlib(&predict_u16(&stored, WIDTH, usize::MAX))];
+      let decoded = decode_synthetic(Layout::Strips { rows: HEIGHT }, blocks, |ifd| {
+        int_tags(CompressionMethod::Deflate, 1)(ifd);
+        ifd.add_tag(DngTag::RowInterleaveFactor, row_ilf as u16);
+        ifd.add_tag(DngTag::ColumnInterleaveFactor, col_ilf as u16);
+      })?;
+      assert_eq!(integer(decoded), samples, "interleave: {}x{}", row_ilf, col_ilf);
     }
+    Ok(())
   }
 }
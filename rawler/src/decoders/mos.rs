@@ -5,8 +5,10 @@ use crate::Result;
 use crate::alloc_image_ok;
 use crate::analyze::FormatDump;
 use crate::decompressors::ljpeg::LjpegDecompressor;
+use crate::decompressors::packed::decompress_8bit;
 use crate::decompressors::packed::decompress_16be;
 use crate::decompressors::packed::decompress_16le;
+use crate::decompressors::packed::decompress_generic_msb;
 use crate::exif::Exif;
 use crate::formats::tiff::GenericTiffReader;
 use crate::formats::tiff::reader::TiffReader;
@@ -42,24 +44,32 @@ impl<'a> MosDecoder<'a> {
 
 impl<'a> Decoder for MosDecoder<'a> {
   fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
-    let raw = self
-      .tiff
-      .find_first_ifd_with_tag(TiffCommonTag::TileOffsets)
-      .ok_or_else(|| RawlerError::DecoderFailed(format!("Failed to find a IFD with TileOffsets tag")))?;
+    // Newer backs store the image in tiles, older ones in strips
+    let (raw, offset_tag, counts_tag) = match self.tiff.find_first_ifd_with_tag(TiffCommonTag::TileOffsets) {
+      Some(raw) => (raw, TiffCommonTag::TileOffsets, TiffCommonTag::TileByteCounts),
+      None => (
+        self
+          .tiff
+          .find_ifds_with_tag(TiffCommonTag::StripOffsets)
+          .into_iter()
+          .find(|ifd| ifd.get_entry(TiffCommonTag::NewSubFileType).map(|entry| entry.force_u32(0)).unwrap_or(0) == 0)
+          .ok_or_else(|| RawlerError::DecoderFailed("Failed to find a IFD with TileOffsets or StripOffsets tag".to_string()))?,
+        TiffCommonTag::StripOffsets,
+        TiffCommonTag::StripByteCounts,
+      ),
+    };
     let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
-    let offset = fetch_tiff_tag!(raw, TiffCommonTag::TileOffsets).force_usize(0);
+    let offset = fetch_tiff_tag!(raw, offset_tag).force_usize(0);
     let compression = fetch_tiff_tag!(raw, TiffCommonTag::Compression).force_usize(0);
+    let bits = raw.get_entry(TiffCommonTag::BitsPerSample).map(|entry| entry.force_u32(0)).unwrap_or(16);
+    let size = raw
+      .get_entry(counts_tag)
+      .map(|counts| (0..counts.count()).map(|i| counts.force_usize(i as usize)).sum::<usize>());
     let src = file.subview_until_eof(offset as u64)?;
 
     let image = match compression {
-      1 => {
-        if self.tiff.little_endian() {
-          decompress_16le(src, width, height, dummy)?
-        } else {
-          decompress_16be(src, width, height, dummy)?
-        }
-      }
+      1 => self.decode_uncompressed(src, width, height, bits, size, dummy)?,
       7 | 99 => self.decode_compressed(&self.camera, src, width, height, dummy)?,
       x => return Err(RawlerError::unsupported(&self.camera, format!("MOS: unsupported compression {}", x))),
     };
@@ -117,6 +127,29 @@ impl<'a> MosDecoder<'a> {
     Ok(xmp[start + tag.len() + 7..end].to_string())
   }
 
+  /// Decode uncompressed data, samples are either packed to `bits` (big-endian bit order)
+  /// or stored in 16 bit words with the byte order of the TIFF container.
+  fn decode_uncompressed(&self, src: &[u8], width: usize, height: usize, bits: u32, size: Option<usize>, dummy: bool) -> Result<PixU16> {
+    let unpacked = size.map(|size| size >= width * height * 2).unwrap_or(bits > 12);
+    match bits {
+      8 => Ok(decompress_8bit(src, width, height, dummy)?),
+      9..=16 if unpacked => {
+        let mut image = if self.tiff.little_endian() {
+          decompress_16le(src, width, height, dummy)?
+        } else {
+          decompress_16be(src, width, height, dummy)?
+        };
+        if bits < 16 {
+          let mask = ((1_u32 << bits) - 1) as u16;
+          image.pixels_mut().iter_mut().for_each(|p| *p &= mask);
+        }
+        Ok(image)
+      }
+      9..=16 => Ok(decompress_generic_msb(src, width, height, bits, dummy)?),
+      x => Err(RawlerError::unsupported(&self.camera, format!("MOS: unsupported bits per sample {}", x))),
+    }
+  }
+
   pub fn decode_compressed(&self, cam: &Camera, src: &[u8], width: usize, height: usize, dummy: bool) -> Result<PixU16> {
     let interlaced = cam.find_hint("interlaced");
     Self::do_decode(src, interlaced, width, height, dummy)
@@ -15,6 +15,7 @@ use crate::formats::jfif::Jfif;
 use crate::formats::jfif::Segment;
 use crate::formats::jfif::is_exif;
 use crate::formats::tiff::IFD;
+use crate::imgop::Dim2;
 use crate::rawsource::RawSource;
 use crate::tags::TiffCommonTag;
 use std::io::Cursor;
@@ -79,7 +80,7 @@ impl<'a> MrwDecoder<'a> {
     if let Some(makernotes) = ifd.get_entry_recursive(TiffCommonTag::Makernote) {
       debug_assert_eq!(makernotes.get_data()[0..3], [b'M', b'L', b'Y']);
       if makernotes.get_data().get(0..3) == Some(b"MLY") {
-        let mut buf = Cursor::new(rawfile.as_vec()?);
+        let mut buf = Cursor::new(rawfile.subview(0, data_offset.min(rawfile.len() as u64))?);
         let mut wb = [0_u16; 4];
         let mut cam_mul = [1_u16; 4];
 
@@ -145,15 +146,16 @@ impl<'a> MrwDecoder<'a> {
   }
 
   fn new_mrw(file: &RawSource, rawloader: &'a RawLoader) -> Result<MrwDecoder<'a>> {
-    let full = file.as_vec()?;
-    let buf = &full;
-    let data_offset: usize = (BEu32(buf, 4) + 8) as usize;
+    // Only the blocks in front of the raw data are required
+    let data_offset: usize = (BEu32(file.subview(0, 8)?, 4) + 8) as usize;
+    let buf = file.subview(0, data_offset as u64)?;
     let mut raw_height: usize = 0;
     let mut raw_width: usize = 0;
     let bits = 12;
     let mut packed = false;
     let mut wb_vals: [u16; 4] = [0; 4];
     let mut tiffpos: usize = 0;
+    let mut tifflen: usize = 0;
 
     let mut currpos: usize = 8;
     // At most we read 20 bytes from currpos so check we don't step outside that
@@ -179,13 +181,14 @@ impl<'a> MrwDecoder<'a> {
           // Base value for offsets needs to be at the beginning of the
           // TIFF block, not the file
           tiffpos = currpos + 8;
+          tifflen = len as usize;
         }
         _ => {}
       }
       currpos += (len + 8) as usize;
     }
 
-    let tiff_data = file.subview_until_eof(tiffpos as u64)?;
+    let tiff_data = file.subview(tiffpos as u64, tifflen as u64)?;
     let tiff = IFD::new(&mut Cursor::new(tiff_data), 8, 0, 0, crate::bits::Endian::Big, &[])?;
 
     let camera = rawloader.check_supported(&tiff)?;
@@ -235,6 +238,14 @@ impl<'a> Decoder for MrwDecoder<'a> {
     Ok(mdata)
   }
 
+  fn raw_dimensions(&self, _params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    Ok(Some(Dim2::new(self.raw_width, self.raw_height)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(self.camera.mode.clone()))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::MRW
   }
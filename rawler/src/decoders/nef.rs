@@ -50,6 +50,7 @@ use super::Camera;
 use super::Decoder;
 use super::FormatHint;
 use super::RawDecodeParams;
+use super::RawImageKind;
 use super::RawMetadata;
 
 mod decrypt;
@@ -151,8 +152,9 @@ impl<'a> NefDecoder<'a> {
       .ok_or_else(|| RawlerError::DecoderFailed(format!("Failed to find a suitable IFD in NEF decoder")))?;
     let bps = fetch_tiff_tag!(raw, TiffCommonTag::BitsPerSample).force_usize(0);
 
-    // Make sure we always use a 12/14 bit mode to get correct white/blackpoints
-    let mode = format!("{}bit", bps);
+    // Make sure we always use a 12/14 bit mode to get correct white/blackpoints.
+    // Small NEF files (sNEF) are tagged as 8 bit, but contain 12 bit YCbCr data.
+    let mode = if bps == 8 { "12bit".to_string() } else { format!("{}bit", bps) };
     let camera = rawloader.check_supported_with_mode(tiff.root_ifd(), &mode)?;
 
     let makernote = if let Some(exif) = tiff.find_first_ifd_with_tag(ExifTag::MakerNotes) {
@@ -175,12 +177,8 @@ impl<'a> NefDecoder<'a> {
 }
 
 impl<'a> Decoder for NefDecoder<'a> {
-  fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
-    let raw = self
-      .tiff
-      .find_first_ifd_with_tag(TiffCommonTag::CFAPattern)
-      .or_else(|| self.tiff.find_ifd_with_new_subfile_type(0))
-      .ok_or_else(|| RawlerError::DecoderFailed(format!("Failed to find a suitable IFD in NEF decoder")))?;
+  fn raw_image(&self, file: &RawSource, params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
+    let (raw, _) = self.raw_ifd(params.image_index)?;
     let mut width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
     let bps = fetch_tiff_tag!(raw, TiffCommonTag::BitsPerSample).force_usize(0);
@@ -247,6 +245,10 @@ impl<'a> Decoder for NefDecoder<'a> {
       } else {
         decompress_16be(&src, width, height, dummy)?
       }
+    } else if Self::is_snef(raw, nef_compression)? {
+      debug!("NEF small raw (sNEF) with YCbCr data");
+      cpp = 3;
+      Self::decode_snef_compressed(&src, coeffs, width, height, dummy)?
     } else if let Some(padding) = self.is_uncompressed(raw)? {
       debug!("NEF uncompressed row padding: {}, little-endian: {}", padding, self.tiff.little_endian());
       match bps {
@@ -280,9 +282,6 @@ impl<'a> Decoder for NefDecoder<'a> {
         }
         x => return Err(RawlerError::unsupported(&self.camera, format!("Don't know uncompressed bps {}", x))),
       }
-    } else if size == width * height * 3 {
-      cpp = 3;
-      Self::decode_snef_compressed(&src, coeffs, width, height, dummy)?
     } else if compression == 34713 {
       self.decode_compressed(&src, width, height, bps, dummy)?
     } else {
@@ -299,15 +298,18 @@ impl<'a> Decoder for NefDecoder<'a> {
     let blacklevel = self.get_blacklevel(bps)?;
     let whitelevel = None;
     let photometric = match cpp {
-      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)),
+      1 => RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?),
       3 => RawPhotometricInterpretation::LinearRaw,
-      _ => todo!(),
+      _ => return Err(RawlerError::unsupported_feature("NEF", format!("{} components per pixel", cpp))),
     };
-    let mut img = RawImage::new(self.camera.clone(), image, cpp, coeffs, photometric, blacklevel, whitelevel, dummy);
+    let mut img = RawImage::new(self.camera.clone(), image, cpp, coeffs, photometric, blacklevel, whitelevel, dummy)?;
 
     if let Some(crop) = self.get_crop()? {
       debug!("RAW Crops: {:?}", crop);
-      img.crop_area = Some(crop);
+      // Crops for full size images don't apply to sNEF files
+      if crop.p.x + crop.d.w <= width && crop.p.y + crop.d.h <= height {
+        img.crop_area = Some(crop);
+      }
     }
 
     if cpp == 3 {
@@ -363,12 +365,71 @@ impl<'a> Decoder for NefDecoder<'a> {
     }
   }
 
+  fn raw_image_count(&self) -> Result<usize> {
+    Ok(self.raw_ifds()?.len())
+  }
+
+  fn raw_image_kind(&self, index: usize) -> Result<RawImageKind> {
+    self.raw_ifd(index).map(|(_, kind)| kind)
+  }
+
+  fn raw_dimensions(&self, params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    if self.camera.model == "NIKON D100" {
+      // Real width is only known while decoding
+      return Ok(None);
+    }
+    let (raw, _) = self.raw_ifd(params.image_index)?;
+    let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
+    let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
+    Ok(Some(Dim2::new(width, height)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(self.camera.mode.clone()))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::NEF
   }
 }
 
 impl<'a> NefDecoder<'a> {
+  /// All raw image IFDs, the primary raw image is always first
+  ///
+  /// Besides the full resolution image, a NEF may carry additional
+  /// raw images in a smaller size.
+  fn raw_ifds(&self) -> Result<Vec<(&IFD, RawImageKind)>> {
+    let main = self
+      .tiff
+      .find_first_ifd_with_tag(TiffCommonTag::CFAPattern)
+      .or_else(|| self.tiff.find_ifd_with_new_subfile_type(0))
+      .ok_or_else(|| RawlerError::DecoderFailed("Failed to find a suitable IFD in NEF decoder".to_string()))?;
+    let main_size = fetch_tiff_tag!(main, TiffCommonTag::ImageWidth).force_usize(0) * fetch_tiff_tag!(main, TiffCommonTag::ImageLength).force_usize(0);
+    let mut ifds = vec![(main, RawImageKind::Main)];
+    for ifd in self.tiff.find_ifds_with_filter(|ifd| {
+      ifd.get_new_sub_file_type() == Some(0)
+        && ifd.has_entry(TiffCommonTag::StripOffsets)
+        && ifd
+          .get_entry(TiffCommonTag::Compression)
+          .is_some_and(|entry| matches!(entry.force_u32(0), 1 | 34713))
+    }) {
+      if std::ptr::eq(ifd, main) {
+        continue;
+      }
+      let size = fetch_tiff_tag!(ifd, TiffCommonTag::ImageWidth).force_usize(0) * fetch_tiff_tag!(ifd, TiffCommonTag::ImageLength).force_usize(0);
+      ifds.push((ifd, if size < main_size { RawImageKind::Reduced } else { RawImageKind::Main }));
+    }
+    Ok(ifds)
+  }
+
+  fn raw_ifd(&self, index: usize) -> Result<(&IFD, RawImageKind)> {
+    let ifds = self.raw_ifds()?;
+    ifds
+      .get(index)
+      .copied()
+      .ok_or_else(|| RawlerError::DecoderFailed(format!("Raw image index {} out of range ({})", index, ifds.len())))
+  }
+
   /// For older formats, we use the camera definitions and this here
   /// is useless. But if we found here the levels in makernotes, we
   /// use these instead. For 12 bit images, the blacklevels are still relative to
@@ -525,7 +586,7 @@ impl<'a> NefDecoder<'a> {
     }
   }
 
-  fn create_hufftable(num: usize) -> std::result::Result<HuffTable, String> {
+  pub(crate) fn create_hufftable(num: usize) -> std::result::Result<HuffTable, String> {
     let mut htable = HuffTable::empty();
 
     for i in 0..15 {
@@ -538,6 +599,17 @@ impl<'a> NefDecoder<'a> {
     Ok(htable)
   }
 
+  /// Detect small NEF files (sNEF): 12 bit YCbCr data tagged as 3x8 bit samples.
+  fn is_snef(raw: &IFD, nef_compression: Option<NefCompression>) -> Result<bool> {
+    let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
+    let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
+    let bps = fetch_tiff_tag!(raw, TiffCommonTag::BitsPerSample).force_usize(0);
+    let size = fetch_tiff_tag!(raw, TiffCommonTag::StripByteCounts).force_usize(0);
+    let ycbcr = raw.get_entry(TiffCommonTag::PhotometricInt).map(|entry| entry.force_u16(0)) == Some(6);
+
+    Ok(size == width * height * 3 && (matches!(nef_compression, Some(NefCompression::Small)) || bps == 8 || ycbcr))
+  }
+
   /// The compression flags in some raws are not reliable because of firmware bugs.
   /// We try to figure out the compression by some heuristics.
   /// The return value is None if the file is not uncompressed or Some(x)
@@ -576,7 +648,7 @@ impl<'a> NefDecoder<'a> {
     })
   }
 
-  fn decode_compressed(&self, src: &PaddedBuf, width: usize, height: usize, bps: usize, dummy: bool) -> std::result::Result<PixU16, String> {
+  fn decode_compressed(&self, src: &PaddedBuf, width: usize, height: usize, bps: usize, dummy: bool) -> Result<PixU16> {
     let meta = if let Some(meta) = self.makernote.get_entry(TiffCommonTag::NefMeta2) {
       debug!("Found NefMeta2");
       meta
@@ -584,7 +656,7 @@ impl<'a> NefDecoder<'a> {
       debug!("Fallback NefMeta1");
       fetch_tiff_tag!(self.makernote, TiffCommonTag::NefMeta1)
     };
-    Self::do_decode(src, meta.get_data(), self.makernote.endian, width, height, bps, dummy)
+    Ok(Self::do_decode(src, meta.get_data(), self.makernote.endian, width, height, bps, dummy)?)
   }
 
   pub(crate) fn do_decode(
@@ -692,12 +764,23 @@ impl<'a> NefDecoder<'a> {
   }
 
   // Decodes 12 bit data in an YUY2-like pattern (2 Luma, 1 Chroma per 2 pixels).
-  // We un-apply the whitebalance, so output matches lossless.
+  // Chroma is sampled at the left pixel, so the right pixel is interpolated
+  // from the current and next chroma pair. We un-apply the whitebalance,
+  // so output matches lossless.
   pub(crate) fn decode_snef_compressed(src: &PaddedBuf, coeffs: [f32; 4], width: usize, height: usize, dummy: bool) -> std::result::Result<PixU16, String> {
-    let inv_wb_r = (1024.0 / coeffs[0]) as i32;
-    let inv_wb_b = (1024.0 / coeffs[2]) as i32;
-
-    //println!("Got invwb {} {}", inv_wb_r, inv_wb_b);
+    if !width.is_multiple_of(2) {
+      return Err(format!("sNEF: width {} is not a multiple of 2", width));
+    }
+    if src.len() < width * height * 3 {
+      return Err(format!("sNEF: buffer too short ({} < {})", src.len(), width * height * 3));
+    }
+    // The lower limit prevents overflow of the inverse whitebalance multiplication.
+    let (wb_r, wb_b) = (coeffs[0], coeffs[2]);
+    if !(0.0313..=10.0).contains(&wb_r) || !(0.0313..=10.0).contains(&wb_b) {
+      return Err(format!("sNEF: whitebalance has bad values ({}, {})", wb_r, wb_b));
+    }
+    let inv_wb_r = (1024.0 / wb_r) as i32;
+    let inv_wb_b = (1024.0 / wb_b) as i32;
 
     let snef_curve = {
       let g: f32 = 2.4;
@@ -714,41 +797,44 @@ impl<'a> NefDecoder<'a> {
       LookupTable::new(&curve)
     };
 
+    let chroma = |i: &[u8]| -> (f32, f32) {
+      let (g4, g5, g6) = (i[3] as u16, i[4] as u16, i[5] as u16);
+      ((g4 | ((g5 & 0x0f) << 8)) as f32, ((g5 >> 4) | (g6 << 4)) as f32)
+    };
+
     decompress_lines_fn(
       width * 3,
       height,
       dummy,
       &(|out: &mut [u16], row| {
-        let inb = &src[row * width * 3..];
-        let mut random = BEu32(inb, 0);
-        for (o, i) in out.chunks_exact_mut(6).zip(inb.chunks_exact(6)) {
-          let g1: u16 = i[0] as u16;
-          let g2: u16 = i[1] as u16;
-          let g3: u16 = i[2] as u16;
-          let g4: u16 = i[3] as u16;
-          let g5: u16 = i[4] as u16;
-          let g6: u16 = i[5] as u16;
-
-          let y1 = (g1 | ((g2 & 0x0f) << 8)) as f32;
-          let y2 = ((g2 >> 4) | (g3 << 4)) as f32;
-          let cb = (g4 | ((g5 & 0x0f) << 8)) as f32 - 2048.0;
-          let cr = ((g5 >> 4) | (g6 << 4)) as f32 - 2048.0;
-
-          let r = snef_curve.dither(clampbits((y1 + 1.370705 * cr) as i32, 12), &mut random);
-          let g = snef_curve.dither(clampbits((y1 - 0.337633 * cb - 0.698001 * cr) as i32, 12), &mut random);
-          let b = snef_curve.dither(clampbits((y1 + 1.732446 * cb) as i32, 12), &mut random);
+        let inb = &src[row * width * 3..(row + 1) * width * 3];
+        let mut random = BEu32(&src[row * width * 3..], 0);
+        let mut ycbcr_to_rgb = |y: f32, cb: f32, cr: f32, o: &mut [u16]| {
+          let (cb, cr) = (cb - 2048.0, cr - 2048.0);
+          let r = snef_curve.dither(clampbits((y + 1.370705 * cr) as i32, 12), &mut random);
+          let g = snef_curve.dither(clampbits((y - 0.337633 * cb - 0.698001 * cr) as i32, 12), &mut random);
+          let b = snef_curve.dither(clampbits((y + 1.732446 * cb) as i32, 12), &mut random);
           // invert the white balance
-          o[0] = clampbits((inv_wb_r * r as i32 + (1 << 9)) >> 10, 15);
+          o[0] = clampbits((inv_wb_r * r as i32 + (1 << 9)) >> 10, 16);
           o[1] = g;
-          o[2] = clampbits((inv_wb_b * b as i32 + (1 << 9)) >> 10, 15);
-
-          let r = snef_curve.dither(clampbits((y2 + 1.370705 * cr) as i32, 12), &mut random);
-          let g = snef_curve.dither(clampbits((y2 - 0.337633 * cb - 0.698001 * cr) as i32, 12), &mut random);
-          let b = snef_curve.dither(clampbits((y2 + 1.732446 * cb) as i32, 12), &mut random);
-          // invert the white balance
-          o[3] = clampbits((inv_wb_r * r as i32 + (1 << 9)) >> 10, 15);
-          o[4] = g;
-          o[5] = clampbits((inv_wb_b * b as i32 + (1 << 9)) >> 10, 15);
+          o[2] = clampbits((inv_wb_b * b as i32 + (1 << 9)) >> 10, 16);
+        };
+        for (n, o) in out.chunks_exact_mut(6).enumerate() {
+          let i = &inb[n * 6..(n + 1) * 6];
+          let (g1, g2, g3) = (i[0] as u16, i[1] as u16, i[2] as u16);
+          let y1 = (g1 | ((g2 & 0x0f) << 8)) as f32;
+          let y2 = ((g2 >> 4) | (g3 << 4)) as f32;
+          let (cb, cr) = chroma(i);
+          let (cb2, cr2) = match inb.get((n + 1) * 6..(n + 2) * 6) {
+            Some(next) => {
+              let (next_cb, next_cr) = chroma(next);
+              ((cb + next_cb) * 0.5, (cr + next_cr) * 0.5)
+            }
+            None => (cb, cr),
+          };
+          let (left, right) = o.split_at_mut(3);
+          ycbcr_to_rgb(y1, cb, cr, left);
+          ycbcr_to_rgb(y2, cb2, cr2, right);
         }
         Ok(())
       }),
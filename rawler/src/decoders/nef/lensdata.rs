@@ -1,3 +1,4 @@
+use crate::RawlerError;
 use crate::Result;
 use crate::bits::LEu16;
 use crate::{decoders::nef::NikonMakernote, formats::tiff::IFD};
@@ -59,7 +60,11 @@ pub(super) fn from_makernote(makernote: &IFD) -> Result<Option<NefLensData>> {
 
     let mut version: u32 = 0;
     for i in 0..4 {
-      version = (version << 4) + (buf[i] - b'0') as u32;
+      let digit = buf
+        .get(i)
+        .and_then(|c| (*c as char).to_digit(16))
+        .ok_or_else(|| RawlerError::malformed("NEF", "invalid lens data version"))?;
+      version = (version << 4) + digit;
     }
 
     let lensdata = match version {
@@ -95,7 +100,7 @@ pub(super) fn from_makernote(makernote: &IFD) -> Result<Option<NefLensData>> {
         parse_lensdata_0x800(version, &buf)?
       }
 
-      _ => todo!("Lensdata version: 0x{:x} not implemented", version),
+      _ => return Err(RawlerError::unsupported_feature("NEF", format!("lens data version 0x{:x}", version))),
     };
 
     log::debug!("NEF lens data version: 0x{:x}, lensdata: {:?}", version, lensdata);
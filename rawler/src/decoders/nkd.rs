@@ -21,11 +21,11 @@ impl<'a> NakedDecoder<'a> {
 
 impl<'a> Decoder for NakedDecoder<'a> {
   fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
-    let data = file.as_vec()?;
-    let buffer = &data;
     let width = self.camera.raw_width;
     let height = self.camera.raw_height;
     let size = self.camera.filesize;
+    // Naked files are identified by size and contain only raw data
+    let buffer = file.subview(0, size as u64)?;
     let bits = size * 8 / width / height;
 
     let image = if self.camera.find_hint("12le_16bitaligned") {
@@ -227,8 +227,8 @@ impl<'a> Decoder for OrfDecoder<'a> {
     let cpp = 1;
     let blacklevel = self.get_blacklevel(bps)?;
     let whitelevel = None;
-    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera));
-    let mut img = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, whitelevel, dummy);
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?);
+    let mut img = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, whitelevel, dummy)?;
     if let Some(crop) = self.get_crop()? {
       img.crop_area = Some(crop);
     }
@@ -251,6 +251,22 @@ impl<'a> Decoder for OrfDecoder<'a> {
     Ok(mdata)
   }
 
+  fn raw_dimensions(&self, _params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    let raw = self
+      .tiff
+      .find_first_ifd_with_tag(TiffCommonTag::StripOffsets)
+      .ok_or_else(|| RawlerError::DecoderFailed("Failed to find a IFD with StripOffsets tag".to_string()))?;
+    let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
+    let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
+    Ok(Some(Dim2::new(width, height)))
+  }
+
+  fn raw_mode(&self, params: &RawDecodeParams) -> Result<Option<String>> {
+    // High resolution shots use their own camera definition
+    let highres = self.raw_dimensions(params)?.is_some_and(|dim| dim.w >= self.camera.highres_width);
+    Ok(Some(if highres { String::from("highres") } else { self.camera.mode.clone() }))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::ORF
   }
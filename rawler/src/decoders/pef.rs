@@ -9,6 +9,7 @@ use super::Camera;
 use super::Decoder;
 use super::FormatHint;
 use super::RawDecodeParams;
+use super::RawImageKind;
 use super::RawMetadata;
 use crate::RawImage;
 use crate::RawLoader;
@@ -26,6 +27,7 @@ use crate::formats::tiff::IFD;
 use crate::formats::tiff::Value;
 use crate::formats::tiff::ifd::OffsetMode;
 use crate::formats::tiff::reader::TiffReader;
+use crate::imgop::Dim2;
 use crate::lens::LensDescription;
 use crate::lens::LensResolver;
 use crate::pixarray::PixU16;
@@ -95,7 +97,7 @@ impl<'a> Decoder for PefDecoder<'a> {
     FormatDump::Pef(PefFormat { tiff: self.tiff.clone() })
   }
 
-  fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
+  fn raw_image(&self, file: &RawSource, params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
     //for (i, ifd) in self.tiff.chains().iter().enumerate() {
     //  eprintln!("IFD {}", i);
     //  for line in ifd.dump::<crate::tags::LegacyTiffRootTag>(10) {
@@ -103,10 +105,7 @@ impl<'a> Decoder for PefDecoder<'a> {
     //  }
     //}
 
-    let raw = self
-      .tiff
-      .find_first_ifd_with_tag(TiffCommonTag::StripOffsets)
-      .ok_or_else(|| RawlerError::unsupported(&self.camera, "Unable to find IFD"))?;
+    let (raw, _) = self.raw_ifd(params.image_index)?;
     let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
     let offset = fetch_tiff_tag!(raw, TiffCommonTag::StripOffsets).force_usize(0);
@@ -127,11 +126,11 @@ impl<'a> Decoder for PefDecoder<'a> {
     let whitelevel = None;
     debug!("Found WB: {:?}", wb);
     let photometric = if self.camera.cfa.is_valid() {
-      RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera))
+      RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&self.camera)?)
     } else {
       RawPhotometricInterpretation::LinearRaw
     };
-    Ok(RawImage::new(self.camera.clone(), image, cpp, wb, photometric, blacklevel, whitelevel, dummy))
+    RawImage::new(self.camera.clone(), image, cpp, wb, photometric, blacklevel, whitelevel, dummy)
   }
 
   fn preview_image(&self, file: &RawSource, params: &RawDecodeParams) -> Result<Option<DynamicImage>> {
@@ -193,12 +192,68 @@ impl<'a> Decoder for PefDecoder<'a> {
     Ok(mdata)
   }
 
+  fn raw_image_count(&self) -> Result<usize> {
+    Ok(self.raw_ifds()?.len())
+  }
+
+  fn raw_image_kind(&self, index: usize) -> Result<RawImageKind> {
+    self.raw_ifd(index).map(|(_, kind)| kind)
+  }
+
+  fn raw_dimensions(&self, params: &RawDecodeParams) -> Result<Option<Dim2>> {
+    let (raw, _) = self.raw_ifd(params.image_index)?;
+    let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
+    let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
+    Ok(Some(Dim2::new(width, height)))
+  }
+
+  fn raw_mode(&self, _params: &RawDecodeParams) -> Result<Option<String>> {
+    Ok(Some(self.camera.mode.clone()))
+  }
+
   fn format_hint(&self) -> FormatHint {
     FormatHint::PEF
   }
 }
 
 impl<'a> PefDecoder<'a> {
+  /// All raw image IFDs
+  ///
+  /// Pixel shift captures store each of the four frames in its
+  /// own IFD with identical dimension.
+  fn raw_ifds(&self) -> Result<Vec<(&IFD, RawImageKind)>> {
+    let first = self
+      .tiff
+      .find_first_ifd_with_tag(TiffCommonTag::StripOffsets)
+      .ok_or_else(|| RawlerError::unsupported(&self.camera, "Unable to find IFD"))?;
+    let dim = |ifd: &IFD| {
+      (
+        ifd.get_entry(TiffCommonTag::ImageWidth).map(|e| e.force_usize(0)),
+        ifd.get_entry(TiffCommonTag::ImageLength).map(|e| e.force_usize(0)),
+      )
+    };
+    let frames = self.tiff.find_ifds_with_filter(|ifd| {
+      ifd.has_entry(TiffCommonTag::StripOffsets)
+        && dim(ifd) == dim(first)
+        && ifd
+          .get_entry(TiffCommonTag::Compression)
+          .is_some_and(|entry| matches!(entry.force_u32(0), 1 | 32773 | 65535))
+    });
+    if frames.len() > 1 {
+      Ok(frames.into_iter().enumerate().map(|(i, ifd)| (ifd, RawImageKind::ShiftFrame(i))).collect())
+    } else {
+      Ok(vec![(first, RawImageKind::Main)])
+    }
+  }
+
+  fn raw_ifd(&self, index: usize) -> Result<(&IFD, RawImageKind)> {
+    let ifds = self.raw_ifds()?;
+    ifds
+      .get(index)
+      .copied()
+      .ok_or_else(|| RawlerError::DecoderFailed(format!("Raw image index {} out of range ({})", index, ifds.len())))
+  }
+
   fn get_wb(&self) -> Result<[f32; 4]> {
     match self.makernote.get_entry(PefMakernote::WhitePoint) {
       Some(wb) => {
@@ -253,13 +308,33 @@ impl<'a> PefDecoder<'a> {
     if let Some(huff) = self.makernote.get_entry(PefMakernote::HuffmanTable) {
       match &huff.value {
         Value::Undefined(data) => Self::do_decode(src, Some((data, self.tiff.get_endian())), width, height, dummy),
-        _ => todo!(), // should not happen!
+        _ => Err(RawlerError::DecoderFailed(format!(
+          "PEF: HuffmanTable has unexpected type {}",
+          huff.value.value_type_name()
+        ))),
       }
     } else {
       Self::do_decode(src, None, width, height, dummy)
     }
   }
 
+  /// Huffman table for files without a HuffmanTable makernote tag
+  pub(crate) fn default_hufftable() -> std::result::Result<HuffTable, String> {
+    // Initialize with legacy data
+    let pentax_tree: [u8; 29] = [0, 2, 3, 1, 1, 1, 1, 1, 1, 2, 0, 0, 0, 0, 0, 0, 3, 4, 2, 5, 1, 6, 0, 7, 8, 9, 10, 11, 12];
+    let mut htable = HuffTable::empty();
+    let mut acc: usize = 0;
+    for i in 0..16 {
+      htable.bits[i + 1] = pentax_tree[i] as u32;
+      acc += htable.bits[i + 1] as usize;
+    }
+    for i in 0..acc {
+      htable.huffval[i] = pentax_tree[i + 16] as u32;
+    }
+    htable.initialize()?;
+    Ok(htable)
+  }
+
   pub(crate) fn do_decode(src: &[u8], huff: Option<(&[u8], Endian)>, width: usize, height: usize, dummy: bool) -> Result<PixU16> {
     let mut out = alloc_image_ok!(width, height, dummy);
     let mut htable = HuffTable::empty();
@@ -302,22 +377,12 @@ impl<'a> PefDecoder<'a> {
         htable.huffval[i] = sm_num;
         v2[sm_num as usize] = 0xffffffff;
       }
+      htable.initialize()?;
     } else {
       debug!("Fallback to standard Huffman table");
-      // Initialize with legacy data
-      let pentax_tree: [u8; 29] = [0, 2, 3, 1, 1, 1, 1, 1, 1, 2, 0, 0, 0, 0, 0, 0, 3, 4, 2, 5, 1, 6, 0, 7, 8, 9, 10, 11, 12];
-      let mut acc: usize = 0;
-      for i in 0..16 {
-        htable.bits[i + 1] = pentax_tree[i] as u32;
-        acc += htable.bits[i + 1] as usize;
-      }
-      for i in 0..acc {
-        htable.huffval[i] = pentax_tree[i + 16] as u32;
-      }
+      htable = Self::default_hufftable()?;
     }
 
-    htable.initialize()?;
-
     let mut pump = BitPumpMSB::new(src);
     let mut pred_up1: [i32; 2] = [0, 0];
     let mut pred_up2: [i32; 2] = [0, 0];
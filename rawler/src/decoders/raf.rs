@@ -19,6 +19,7 @@ use crate::analyze::FormatDump;
 use crate::bits::BEu32;
 use crate::bits::Endian;
 use crate::decoders::raf::fuji_decompressor::decompress_fuji;
+use crate::decoders::raf::fuji_decompressor::fuji_compression_lossless;
 use crate::decompressors::packed::*;
 use crate::exif::Exif;
 use crate::formats::jfif::Jfif;
@@ -140,12 +141,27 @@ pub fn parse_raf_format(file: &RawSource, offset: u32) -> Result<IFD> {
   })
 }
 
-// This won't work for many samples, don't use it.
-#[allow(dead_code)]
-fn get_compression(file: &RawSource) -> Result<u32> {
-  let buf = file.subview(0, 0x6c + 4)?;
-  let compression = BEu32(buf, 0);
-  Ok(compression)
+/// Detect the compression mode of the raw data. The returned
+/// value is used as camera mode.
+///
+/// If the raw data can't be read, the mode is unknown. Errors are
+/// left to the raw image decoding, so metadata is still available.
+fn get_compression_mode(file: &RawSource, ifd: &IFD) -> &'static str {
+  let Some(offset) = ifd
+    .find_first_ifd_with_tag(FujiIFD::StripOffsets)
+    .and_then(|raw| Some(raw.base as u64 + raw.get_entry(FujiIFD::StripOffsets)?.force_u64(0)))
+  else {
+    return "";
+  };
+  match file.subview(offset, 3).map(fuji_compression_lossless) {
+    Ok(Some(true)) => "compressed",
+    Ok(Some(false)) => "lossy",
+    Ok(None) => "",
+    Err(err) => {
+      log::debug!("Failed to read RAF compression header: {}", err);
+      ""
+    }
+  }
 }
 
 /// RAF format contains multiple TIFF and TIFF-like structures.
@@ -221,24 +237,20 @@ impl<'a> RafDecoder<'a> {
   pub fn new(file: &RawSource, rawloader: &'a RawLoader) -> Result<RafDecoder<'a>> {
     let ifd = parse_raf(file)?;
 
-    /*
-    let mode = match get_compression(file)? {
-      0 => "uncompressed",
-      1 => "lossess",
-      2 => "lossy",
-      _ => "unknown",
+    let camera = rawloader.check_supported(&ifd)?;
+
+    // Lossy compressed files fall back to the lossless mode config, as
+    // both share the same layout for most models.
+    let mode = get_compression_mode(file, &ifd);
+    log::debug!("RAF compression mode: '{}'", mode);
+    let camera_compressed = match mode {
+      "" => None,
+      "lossy" => rawloader
+        .check_supported_with_mode(&ifd, mode)
+        .or_else(|_| rawloader.check_supported_with_mode(&ifd, "compressed"))
+        .ok(),
+      _ => rawloader.check_supported_with_mode(&ifd, mode).ok(),
     };
-     */
-
-    let camera = match rawloader.check_supported(&ifd) {
-      Ok(camera) => camera,
-      Err(err) => {
-        log::debug!("Camera not found, trying without mode: {:?}", err);
-        rawloader.check_supported(&ifd)?
-      }
-    };
-
-    let camera_compressed = rawloader.check_supported_with_mode(&ifd, "compressed").ok();
 
     let makernotes = if let Some(exif) = ifd.find_first_ifd_with_tag(ExifTag::MakerNotes) {
       exif.parse_makernote(&mut file.reader(), OffsetMode::Absolute, &[])?
@@ -324,7 +336,6 @@ impl<'a> Decoder for RafDecoder<'a> {
       dbp::decode_dbp(&src, width, height, dummy)?
     } else if src.len() < bps * width * height / 8 {
       if !dummy {
-        //if let Some(camera) = rawloader.check_supported(&ifd)
         if let Some(cam_compr) = self.camera_compressed.clone() {
           camera = cam_compr;
         }
@@ -374,7 +385,7 @@ impl<'a> Decoder for RafDecoder<'a> {
       };
 
       camera.cfa = corrected_cfa;
-      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera));
+      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?);
       let mut image = RawImage::new(
         self.camera.clone(),
         rotated,
@@ -384,7 +395,7 @@ impl<'a> Decoder for RafDecoder<'a> {
         blacklevel,
         None,
         dummy,
-      );
+      )?;
       image.fuji_rotation_width = fuji_rot_width;
 
       if rotate_for_dng {
@@ -392,13 +403,14 @@ impl<'a> Decoder for RafDecoder<'a> {
         image.add_dng_tag(DngTag::CFALayout, 2_u16);
         image.add_dng_tag(TiffCommonTag::CFAPattern, &[0_u8, 1, 2, 1, 2, 1, 0, 1][..]);
 
-        todo!();
         //image.add_dng_tag(DngTag::BlackLevel, image.blacklevel[0]);
         //image.add_dng_tag(DngTag::BlackLevelRepeatDim, [1_u16, 1_u16]);
+        return Err(RawlerError::unsupported_feature("RAF", "staggered CFA layout"));
       }
 
       // Reset crops because we have rotated the data.
-      let rotated_dim = fuji_calc_dimension(image.width, fuji_rot_width.expect("fuji_rot_width must be Some when not rotating for DNG"));
+      let fuji_rot_width = fuji_rot_width.ok_or_else(|| RawlerError::malformed("RAF", "rotation width is missing"))?;
+      let rotated_dim = fuji_calc_dimension(image.width, fuji_rot_width);
       log::debug!("Image dimension after final rotation: {:?}", rotated_dim);
       //image.active_area = camera.active_area.map(|area| Rect::new_with_borders(rotated_dim, &area));
       image.crop_area = camera.crop_area.map(|area| Rect::new_with_borders(rotated_dim, &area));
@@ -420,8 +432,8 @@ impl<'a> Decoder for RafDecoder<'a> {
       } else {
         None
       };
-      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera));
-      let mut image = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, whitelevel, dummy);
+      let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?);
+      let mut image = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, whitelevel, dummy)?;
 
       // Overwrite crop if available in metadata
       if let Some(crop) = self.get_crop()? {
@@ -807,3 +819,39 @@ pub fn fuji_raw_rotate(img: &PixU16, dummy: bool) -> PixU16 {
   }
   out
 }
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+  use crate::formats::tiff::reader::TiffReader;
+  use crate::formats::tiff::writer::TiffWriter;
+
+  /// Build a TIFF with a Fuji StripOffsets tag pointing to `header`
+  /// or behind the end of file.
+  fn compression_mode(header: Option<&[u8]>) -> Result<&'static str> {
+    let mut buf = Cursor::new(Vec::new());
+    let mut tiff = TiffWriter::new(&mut buf)?;
+    let mut ifd = tiff.new_directory();
+    let offset = match header {
+      Some(header) => tiff.write_data(header)?,
+      None => 0x10000,
+    };
+    ifd.add_tag(FujiIFD::StripOffsets, offset);
+    tiff.build(ifd)?;
+    let data = buf.into_inner();
+    let reader = GenericTiffReader::new_with_buffer(&data, 0, 0, None)?;
+    Ok(get_compression_mode(&RawSource::new_from_slice(&data), reader.root_ifd()))
+  }
+
+  #[test]
+  fn compression_mode_falls_back_to_unknown() -> Result<()> {
+    assert_eq!(compression_mode(Some(&[0x49, 0x53, 1]))?, "compressed");
+    assert_eq!(compression_mode(Some(&[0x49, 0x53, 0]))?, "lossy");
+    assert_eq!(compression_mode(Some(&[0, 0, 0]))?, "");
+    // Unreadable raw data must not fail the decoder construction
+    assert_eq!(compression_mode(None)?, "");
+    Ok(())
+  }
+}
@@ -163,15 +163,17 @@ impl Header {
 
 impl Strip {
   fn decompress_strip(&self, src: &PaddedBuf, header: &Header, params: &Params, q_bases: Option<&[u8]>, out: &mut PixU16) {
-    let mut info_block = CompressedBlock::new(header, params);
     log::debug!("Fuji strip offset: {}, len: {}", self.offset, self.size);
 
-    let mut pump = if self.offset + self.size == src.len() {
-      BitPumpMSB::new(&src[self.offset..]) // use extra bytes from PaddedBuf
-    } else {
-      let extra_bytes = 16;
-      BitPumpMSB::new(&src[self.offset..self.offset + self.size + extra_bytes])
-    };
+    // Use extra bytes from the following strip or from PaddedBuf
+    let extra_bytes = 16;
+    let mut pump = BitPumpMSB::new(&src[self.offset..(self.offset + self.size + extra_bytes).min(src.len())]);
+    self.code_strip(&mut pump, header, params, q_bases, out);
+  }
+
+  /// Run the line loop of a strip, sample codes are taken from `coder`.
+  fn code_strip<C: SampleCoder>(&self, pump: &mut C, header: &Header, params: &Params, q_bases: Option<&[u8]>, out: &mut PixU16) {
+    let mut info_block = CompressedBlock::new(header, params);
 
     let mtable = [
       (XT_LINE_R0, XT_LINE_R3),
@@ -187,10 +189,9 @@ impl Strip {
     let mut params = params.clone();
 
     for cur_line in 0..self.height() as usize {
-      debug_assert_eq!(header.is_lossless(), q_bases.is_none());
       // init grads and main qtable
-      if !header.is_lossless() {
-        let q_base = q_bases.as_ref().expect("q_bases must be Some for lossy compression")[cur_line] as i32;
+      if let Some(q_bases) = q_bases {
+        let q_base = q_bases[cur_line] as i32;
         if cur_line == 0 || q_base != params.qtables[0].q_base {
           let max_value = (1 << header.raw_bits) - 1; // todo: put into header as function?
           let main_qtable = Params::new_main_qtable(header, max_value, q_base);
@@ -214,9 +215,9 @@ impl Strip {
       }
 
       if header.raw_type == 16 {
-        info_block.fuji_xtrans_decode_block(&mut pump, &params);
+        info_block.fuji_xtrans_decode_block(pump, &params);
       } else {
-        info_block.fuji_bayer_decode_block(&mut pump, &params);
+        info_block.fuji_bayer_decode_block(pump, &params);
       }
 
       // copy data from line buffers and advance
@@ -248,6 +249,16 @@ impl Strip {
   }
 }
 
+/// Check if the buffer starts with a compressed header and
+/// return `Some(true)` for lossless and `Some(false)` for lossy
+/// compressed data.
+pub(super) fn fuji_compression_lossless(buf: &[u8]) -> Option<bool> {
+  match buf {
+    [0x49, 0x53, lossless, ..] => Some(*lossless == 1),
+    _ => None,
+  }
+}
+
 /// We need PaddedBuf here, because the buffer is divided
 /// into multiple strips and each strip is feed into a BitPump.
 /// Each pump need as little bit more overhead at the end.
@@ -271,11 +282,15 @@ pub(super) fn decompress_fuji(buf: &PaddedBuf, width: usize, height: usize, _bps
   if !header.is_valid() {
     return Err("Fuji header is not valid".into());
   }
-  assert_eq!(
-    Dim2::new(width, height),
-    Dim2::new(header.raw_width.into(), header.raw_height.into()),
-    "RAF header specifies different dimensions!"
-  );
+  if Dim2::new(width, height) != Dim2::new(header.raw_width.into(), header.raw_height.into()) {
+    return Err(
+      format!(
+        "RAF header specifies different dimensions: {}x{} vs. {}x{}",
+        header.raw_width, header.raw_height, width, height
+      )
+      .into(),
+    );
+  }
 
   let params = Params::new(&header)?;
   log::debug!("Params: {:?}", params);
@@ -299,6 +314,9 @@ pub(super) fn decompress_fuji(buf: &PaddedBuf, width: usize, height: usize, _bps
   // Global Q bases for all strips
   let q_bases: Option<Vec<u8>> = if !header.is_lossless() {
     let total_q_bases = block_sizes.len() * ((header.total_lines as usize + 0xF) & !0xF);
+    if stream.remaining_bytes() < total_q_bases {
+      return Err("Fuji decompressor: lossy data is too short for Q bases".into());
+    }
     Some(stream.get_bytes(total_q_bases))
   } else {
     None
@@ -307,6 +325,10 @@ pub(super) fn decompress_fuji(buf: &PaddedBuf, width: usize, height: usize, _bps
   //eprintln!("q_bases: {:?}", q_bases);
   //eprintln!("First block: {}", stream.get_pos());
 
+  if block_sizes.iter().sum::<usize>() > stream.remaining_bytes() {
+    return Err("Fuji decompressor: block sizes exceed input data".into());
+  }
+
   // calculating raw block offsets
   let strips: Vec<Strip> = block_sizes
     .iter()
@@ -324,7 +346,9 @@ pub(super) fn decompress_fuji(buf: &PaddedBuf, width: usize, height: usize, _bps
     })
     .collect();
 
-  assert!(stream.remaining_bytes() <= 16);
+  if stream.remaining_bytes() > 16 {
+    log::debug!("Fuji decompressor: {} trailing bytes after last strip", stream.remaining_bytes());
+  }
 
   let out = SharedPix2D::new(PixU16::new(width, height));
 
@@ -332,7 +356,7 @@ pub(super) fn decompress_fuji(buf: &PaddedBuf, width: usize, height: usize, _bps
   strips.par_iter().for_each(|strip| {
     let line_step = (header.total_lines as usize + 0xF) & !0xF;
     // Each strip has it's own q_bases
-    let q_bases_strip = q_bases.as_ref().map(|buf| &buf[strip.n * line_step..]);
+    let q_bases_strip = q_bases.as_ref().map(|buf| &buf[strip.n * line_step..(strip.n + 1) * line_step]);
     // DANGEROUS: We need multiple mut refs here. This should be
     // safe as be only write pixels to pre-allocated memory.
     let outbuf = unsafe { out.inner_mut() };
@@ -480,11 +504,11 @@ impl CompressedBlock {
   }
 
   /// Decode Bayer pattern (RGGB and the like) from block
-  fn fuji_bayer_decode_block(&mut self, pump: &mut BitPumpMSB, params: &Params) {
+  fn fuji_bayer_decode_block<C: SampleCoder>(&mut self, pump: &mut C, params: &Params) {
     let line_width = params.line_width;
     let mut colors = Colors::new();
 
-    let pass_red_green = |colors: &mut Colors, pump: &mut BitPumpMSB, block: &mut CompressedBlock, c0: usize, c1: usize, grad: usize| {
+    let pass_red_green = |colors: &mut Colors, pump: &mut C, block: &mut CompressedBlock, c0: usize, c1: usize, grad: usize| {
       while colors.g().even < line_width || colors.g().odd < line_width {
         if colors.g().even < line_width {
           fuji_decode_sample_even(pump, params, &mut block.linebuf, c0, &mut colors.r().even, &mut block.grad_even[grad]);
@@ -499,7 +523,7 @@ impl CompressedBlock {
       block.fuji_extend_green(line_width);
     };
 
-    let pass_green_blue = |colors: &mut Colors, pump: &mut BitPumpMSB, block: &mut CompressedBlock, c0: usize, c1: usize, grad: usize| {
+    let pass_green_blue = |colors: &mut Colors, pump: &mut C, block: &mut CompressedBlock, c0: usize, c1: usize, grad: usize| {
       while colors.g().even < line_width || colors.g().odd < line_width {
         if colors.g().even < line_width {
           fuji_decode_sample_even(pump, params, &mut block.linebuf, c0, &mut colors.g().even, &mut block.grad_even[grad]);
@@ -537,17 +561,17 @@ impl CompressedBlock {
   }
 
   /// A single X-Trans decoding pass for the given control colors C0 and C1
-  fn fuji_xtrans_pass<F, const C0: usize, const C1: usize>(
+  fn fuji_xtrans_pass<C: SampleCoder, F, const C0: usize, const C1: usize>(
     &mut self,
     params: &Params,
     colors: &mut Colors,
-    pump: &mut BitPumpMSB,
+    pump: &mut C,
     c0: usize,
     c1: usize,
     grad: usize,
     even_func: F,
   ) where
-    F: Fn(&mut CompressedBlock, &mut BitPumpMSB, usize, usize, usize, &mut ColorPos, &mut ColorPos),
+    F: Fn(&mut CompressedBlock, &mut C, usize, usize, usize, &mut ColorPos, &mut ColorPos),
   {
     let line_width = params.line_width;
     while colors.g().even < line_width || colors.g().odd < line_width {
@@ -566,12 +590,12 @@ impl CompressedBlock {
   }
 
   /// Decode X-Trans pattern from block
-  fn fuji_xtrans_decode_block(&mut self, pump: &mut BitPumpMSB, params: &Params) {
+  fn fuji_xtrans_decode_block<C: SampleCoder>(&mut self, pump: &mut C, params: &Params) {
     let mut colors = Colors::new();
     let line_width = params.line_width;
 
     // Pass 1
-    self.fuji_xtrans_pass::<_, { Colors::R }, { Colors::G }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::R }, { Colors::G }>(
       params,
       &mut colors,
       pump,
@@ -588,7 +612,7 @@ impl CompressedBlock {
     colors.g().reset();
 
     // Pass 2
-    self.fuji_xtrans_pass::<_, { Colors::G }, { Colors::B }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::G }, { Colors::B }>(
       params,
       &mut colors,
       pump,
@@ -606,7 +630,7 @@ impl CompressedBlock {
     colors.g().reset();
 
     // Pass 3
-    self.fuji_xtrans_pass::<_, { Colors::R }, { Colors::G }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::R }, { Colors::G }>(
       params,
       &mut colors,
       pump,
@@ -628,7 +652,7 @@ impl CompressedBlock {
     colors.b().reset();
 
     // Pass 4
-    self.fuji_xtrans_pass::<_, { Colors::G }, { Colors::B }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::G }, { Colors::B }>(
       params,
       &mut colors,
       pump,
@@ -651,7 +675,7 @@ impl CompressedBlock {
     colors.g().reset();
 
     // Pass 5
-    self.fuji_xtrans_pass::<_, { Colors::R }, { Colors::G }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::R }, { Colors::G }>(
       params,
       &mut colors,
       pump,
@@ -674,7 +698,7 @@ impl CompressedBlock {
     colors.b().reset();
 
     // Pass 6
-    self.fuji_xtrans_pass::<_, { Colors::G }, { Colors::B }>(
+    self.fuji_xtrans_pass::<_, _, { Colors::G }, { Colors::B }>(
       params,
       &mut colors,
       pump,
@@ -924,18 +948,62 @@ fn read_code(pump: &mut BitPumpMSB, params: &Params, gradient: &mut Gradient, q_
   } else {
     code /= 2;
   }
-  // Update gradient
+  update_gradient(params, gradient, code);
+  code
+}
+
+/// Adapt gradient to the last decoded code
+#[inline(always)]
+fn update_gradient(params: &Params, gradient: &mut Gradient, code: i32) {
   gradient.0 += code.abs();
   if gradient.1 == params.min_value {
     gradient.0 >>= 1;
     gradient.1 >>= 1;
   }
   gradient.1 += 1;
-  code
+}
+
+/// Predicted value for a sample in the line buffer
+struct Prediction {
+  // Position is only required to encode samples
+  #[cfg_attr(not(test), allow(dead_code))]
+  line: usize,
+  #[cfg_attr(not(test), allow(dead_code))]
+  pos: usize,
+  value: i32,
+  /// Code is subtracted from the prediction
+  negative: bool,
+}
+
+impl Prediction {
+  /// Reconstruct sample value from prediction and code
+  fn reconstruct(&self, params: &Params, qtable: &QTable, code: i32) -> u16 {
+    let step = 2 * qtable.q_base + 1;
+    let mut value = if self.negative { self.value - code * step } else { self.value + code * step };
+    if value < -qtable.q_base {
+      value += qtable.total_values * step;
+    } else if value > qtable.q_base + params.max_value {
+      value -= qtable.total_values * step;
+    }
+    value.clamp(0, params.max_value) as u16
+  }
+}
+
+/// Source for sample codes
+trait SampleCoder {
+  /// Get the code for the sample at `pred.line` and `pred.pos`
+  /// and adapt the gradient.
+  fn code(&mut self, params: &Params, gradient: &mut Gradient, qtable: &QTable, pred: &Prediction) -> i32;
+}
+
+impl SampleCoder for BitPumpMSB<'_> {
+  fn code(&mut self, params: &Params, gradient: &mut Gradient, qtable: &QTable, _pred: &Prediction) -> i32 {
+    read_code(self, params, gradient, qtable)
+  }
 }
 
 /// Decode samples for even positions
-fn fuji_decode_sample_even(pump: &mut BitPumpMSB, params: &Params, linebuf: &mut [Vec<u16>], line: usize, pos: &mut usize, grads: &mut GradientList) {
+fn fuji_decode_sample_even<C: SampleCoder>(pump: &mut C, params: &Params, linebuf: &mut [Vec<u16>], line: usize, pos: &mut usize, grads: &mut GradientList) {
   // Line -2 |   | f |   |
   // Line -1 | c | b | d |
   // Line  0 | a | x | g |
@@ -963,7 +1031,7 @@ fn fuji_decode_sample_even(pump: &mut BitPumpMSB, params: &Params, linebuf: &mut
   // Determine gradient
   let grad = qtable.lookup_gradient(params, rb - rf, rc - rb);
 
-  let mut interp_val = if diff_rc_rb > diff_rf_rb && diff_rc_rb > diff_rd_rb {
+  let interp_val = if diff_rc_rb > diff_rf_rb && diff_rc_rb > diff_rd_rb {
     rf + rd + 2 * rb
   } else if diff_rd_rb > diff_rc_rb && diff_rd_rb > diff_rf_rb {
     rf + rc + 2 * rb
@@ -971,33 +1039,20 @@ fn fuji_decode_sample_even(pump: &mut BitPumpMSB, params: &Params, linebuf: &mut
     rd + rc + 2 * rb
   };
 
-  let code = read_code(pump, params, &mut gradients[grad.unsigned_abs() as usize], qtable);
-
-  // Adjustments specific to even positions
-  if grad < 0 {
-    interp_val = (interp_val >> 2) - code * (2 * qtable.q_base as i32 + 1);
-  } else {
-    interp_val = (interp_val >> 2) + code * (2 * qtable.q_base as i32 + 1);
+  let pred = Prediction {
+    line,
+    pos: *pos,
+    value: interp_val >> 2,
+    negative: grad < 0,
   };
-
-  // Generic adjustments
-  if interp_val < -(qtable.q_base as i32) {
-    interp_val += (qtable.total_values * (2 * qtable.q_base + 1)) as i32;
-  } else if interp_val > qtable.q_base as i32 + params.max_value {
-    interp_val -= (qtable.total_values * (2 * qtable.q_base + 1)) as i32;
-  }
-
-  if interp_val >= 0 {
-    linebuf[line][1 + *pos] = interp_val.min(params.max_value) as u16
-  } else {
-    linebuf[line][1 + *pos] = 0;
-  }
+  let code = pump.code(params, &mut gradients[grad.unsigned_abs() as usize], qtable, &pred);
+  linebuf[line][1 + *pos] = pred.reconstruct(params, qtable, code);
 
   *pos += 2;
 }
 
 /// Decode samples for odd positions
-fn fuji_decode_sample_odd(pump: &mut BitPumpMSB, params: &Params, linebuf: &mut [Vec<u16>], line: usize, pos: &mut usize, grads: &mut GradientList) {
+fn fuji_decode_sample_odd<C: SampleCoder>(pump: &mut C, params: &Params, linebuf: &mut [Vec<u16>], line: usize, pos: &mut usize, grads: &mut GradientList) {
   // Line -2 |   | f |   |
   // Line -1 | c | b | d |
   // Line  0 | a | x | g |
@@ -1025,33 +1080,20 @@ fn fuji_decode_sample_odd(pump: &mut BitPumpMSB, params: &Params, linebuf: &mut
   // Determine gradient
   let grad = qtable.lookup_gradient(params, rb - rc, rc - ra);
 
-  let mut interp_val = if (rb > rc && rb > rd) || (rb < rc && rb < rd) {
+  let interp_val = if (rb > rc && rb > rd) || (rb < rc && rb < rd) {
     (rg + ra + 2 * rb) >> 2
   } else {
     (ra + rg) >> 1
   };
 
-  let code = read_code(pump, params, &mut gradients[grad.unsigned_abs() as usize], qtable);
-
-  // Adjustments specific to odd positions
-  if grad < 0 {
-    interp_val -= code * (2 * qtable.q_base as i32 + 1);
-  } else {
-    interp_val += code * (2 * qtable.q_base as i32 + 1);
-  }
-
-  // Generic adjustments
-  if interp_val < -(qtable.q_base as i32) {
-    interp_val += (qtable.total_values * (2 * qtable.q_base + 1)) as i32;
-  } else if interp_val > qtable.q_base as i32 + params.max_value {
-    interp_val -= (qtable.total_values * (2 * qtable.q_base + 1)) as i32;
-  }
-
-  if interp_val >= 0 {
-    linebuf[line][1 + *pos] = interp_val.min(params.max_value) as u16
-  } else {
-    linebuf[line][1 + *pos] = 0;
-  }
+  let pred = Prediction {
+    line,
+    pos: *pos,
+    value: interp_val,
+    negative: grad < 0,
+  };
+  let code = pump.code(params, &mut gradients[grad.unsigned_abs() as usize], qtable, &pred);
+  linebuf[line][1 + *pos] = pred.reconstruct(params, qtable, code);
 
   *pos += 2;
 }
@@ -1102,3 +1144,200 @@ const XT_LINE_B2: usize = 15;
 const XT_LINE_B3: usize = 16;
 const XT_LINE_B4: usize = 17;
 const XT_LINE_TOTAL: usize = 18;
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::buffer::PaddedBuf;
+  use crate::devtools::synthetic::BitWriterMSB;
+
+  /// Build a compressed stream header for a single 768x6 Bayer strip.
+  fn lossy_stream(with_q_bases: bool) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&0x4953_u16.to_be_bytes()); // signature
+    buf.push(0); // lossy
+    buf.push(0); // raw_type: bayer
+    buf.push(14); // raw_bits
+    buf.extend_from_slice(&6_u16.to_be_bytes()); // raw_height
+    buf.extend_from_slice(&0x300_u16.to_be_bytes()); // raw_rounded_width
+    buf.extend_from_slice(&0x300_u16.to_be_bytes()); // raw_width
+    buf.extend_from_slice(&0x300_u16.to_be_bytes()); // block_size
+    buf.push(1); // blocks_in_row
+    buf.extend_from_slice(&1_u16.to_be_bytes()); // total_lines
+    buf.extend_from_slice(&0_u32.to_be_bytes()); // block size of strip 0
+    buf.resize(32, 0); // padding to 16 byte boundary
+    if with_q_bases {
+      buf.extend_from_slice(&[0; 16]);
+    }
+    buf
+  }
+
+  #[test]
+  fn lossy_header_and_qtables() -> crate::Result<()> {
+    let buf = lossy_stream(true);
+    assert_eq!(fuji_compression_lossless(&buf), Some(false));
+    assert_eq!(fuji_compression_lossless(&[0x49, 0x53, 1]), Some(true));
+    assert_eq!(fuji_compression_lossless(&[0, 0, 0]), None);
+
+    let mut stream = ByteStream::new(&buf, Endian::Big);
+    let header = Header {
+      signature: stream.get_u16(),
+      lossless: stream.get_u8(),
+      raw_type: stream.get_u8(),
+      raw_bits: stream.get_u8(),
+      raw_height: stream.get_u16(),
+      raw_rounded_width: stream.get_u16(),
+      raw_width: stream.get_u16(),
+      block_size: stream.get_u16(),
+      blocks_in_row: stream.get_u8(),
+      total_lines: stream.get_u16(),
+    };
+    assert!(header.is_valid());
+    assert!(!header.is_lossless());
+
+    let params = Params::new(&header)?;
+    assert_eq!(params.qtables.len(), 4);
+    let max_value = (1 << 14) - 1;
+    assert_eq!(params.qtables[1].total_values, max_value + 1);
+    assert_eq!(params.qtables[2].total_values, (max_value + 2) / 3 + 1);
+    assert_eq!(params.qtables[3].total_values, (max_value + 4) / 5 + 1);
+
+    // Main table for q_base 1 quantizes in steps of 3
+    let main = Params::new_main_qtable(&header, max_value, 1);
+    assert_eq!(main.total_values, (max_value + 2) / 3 + 1);
+    assert_eq!(main.q_table[max_value as usize], 0);
+    assert_eq!(main.q_table[max_value as usize + 1], 0);
+    assert_eq!(main.q_table[max_value as usize + 2], 1);
+    Ok(())
+  }
+
+  /// Encoder for sample codes, counterpart of `read_code()`
+  struct SampleEncoder<F: Fn(usize, usize) -> u16> {
+    writer: BitWriterMSB,
+    /// Target value for line buffer line and position
+    target: F,
+  }
+
+  impl<F: Fn(usize, usize) -> u16> SampleCoder for SampleEncoder<F> {
+    fn code(&mut self, params: &Params, gradient: &mut Gradient, qtable: &QTable, pred: &Prediction) -> i32 {
+      let target = (self.target)(pred.line, pred.pos) as i32;
+      let step = 2 * qtable.q_base + 1;
+      let diff = if pred.negative { pred.value - target } else { target - pred.value };
+      let nearest = (diff as f64 / step as f64).round() as i32;
+      // Codes wrap around, so the nearest code may not be encodable
+      let index = |code: i32| if code >= 0 { 2 * code } else { -2 * code - 1 };
+      let code = [nearest, nearest - qtable.total_values, nearest + qtable.total_values]
+        .into_iter()
+        .filter(|code| index(*code) < qtable.total_values)
+        .min_by_key(|code| (pred.reconstruct(params, qtable, *code) as i32 - target).abs())
+        .expect("encodable code");
+
+      let index = index(code) as u32;
+      let dec_bits = bit_diff(gradient.0, gradient.1);
+      let limit = (params.max_bits - qtable.raw_bits - 1) as u32;
+      let (zeros, value, bits) = if (index >> dec_bits) < limit {
+        (index >> dec_bits, index & ((1 << dec_bits) - 1), dec_bits)
+      } else {
+        (limit, index - 1, qtable.raw_bits as u32)
+      };
+      (0..zeros).for_each(|_| self.writer.put(0, 1));
+      self.writer.put(1, 1);
+      self.writer.put(value, bits);
+      update_gradient(params, gradient, code);
+      code
+    }
+  }
+
+  #[test]
+  fn lossy_roundtrip() -> crate::Result<()> {
+    let cfa = CFA::new("RGGB");
+    let (width, height) = (0x600, 12);
+    let header = Header {
+      signature: 0x4953,
+      lossless: 0,
+      raw_type: 0,
+      raw_bits: 14,
+      raw_height: height as u16,
+      raw_rounded_width: width as u16,
+      raw_width: width as u16,
+      block_size: 0x300,
+      blocks_in_row: 2,
+      total_lines: 2,
+    };
+    assert!(header.is_valid());
+    let params = Params::new(&header)?;
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&header.signature.to_be_bytes());
+    buf.extend_from_slice(&[header.lossless, header.raw_type, header.raw_bits]);
+    for value in [header.raw_height, header.raw_rounded_width, header.raw_width, header.block_size] {
+      buf.extend_from_slice(&value.to_be_bytes());
+    }
+    buf.push(header.blocks_in_row);
+    buf.extend_from_slice(&header.total_lines.to_be_bytes());
+
+    // Flat areas use the additional lossy tables
+    let target = |line: usize, pos: usize| -> u16 {
+      if pos % 64 < 24 {
+        (1000 + line * 500) as u16
+      } else {
+        (2000 + line * 400 + (pos * 37) % 1500) as u16
+      }
+    };
+    // Q bases for each strip, padded to 16 lines
+    let q_bases = [[1_u8, 3], [0, 2]].map(|q| [q.as_slice(), &[0; 14]].concat());
+
+    let mut expected = PixU16::new(width, height);
+    let mut blocks = Vec::new();
+    for (n, q) in q_bases.iter().enumerate() {
+      let strip = Strip {
+        offset: 0,
+        size: 0,
+        n,
+        header: header.clone(),
+        cfa: std::array::from_fn(|row| std::array::from_fn(|col| cfa.cfa_color_at(row, col))),
+      };
+      let mut encoder = SampleEncoder {
+        writer: BitWriterMSB::new(),
+        target,
+      };
+      strip.code_strip(&mut encoder, &header, &params, Some(q), &mut expected);
+      blocks.push(encoder.writer.finish());
+    }
+
+    blocks.iter().for_each(|block| buf.extend_from_slice(&(block.len() as u32).to_be_bytes()));
+    buf.resize(32, 0);
+    q_bases.iter().for_each(|q| buf.extend_from_slice(q));
+    blocks.iter().for_each(|block| buf.extend_from_slice(block));
+    let padded = PaddedBuf::new_owned([buf.as_slice(), &[0; 16]].concat(), buf.len());
+    let decoded = decompress_fuji(&padded, width, height, 14, &cfa)?;
+    assert_eq!(decoded.pixels(), expected.pixels());
+
+    // Quantization error is limited by the largest q_base
+    for row in 0..height {
+      for col in 0..width {
+        let line = match cfa.cfa_color_at(row, col) {
+          CFAColor::RED => XT_LINE_R2 + ((row % 6) >> 1),
+          CFAColor::GREEN => XT_LINE_G2 + row % 6,
+          _ => XT_LINE_B2 + ((row % 6) >> 1),
+        };
+        let pos = (col % 0x300) >> 1;
+        let diff = (*decoded.at(row, col) as i32 - target(line, pos) as i32).abs();
+        assert!(diff <= 3, "row {}, col {}: {} vs {}", row, col, decoded.at(row, col), target(line, pos));
+      }
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn lossy_invalid_input_fails() -> crate::Result<()> {
+    let cfa = CFA::new("RGGB");
+    let buf = lossy_stream(true);
+    let padded = PaddedBuf::new_owned([buf.as_slice(), &[0; 16]].concat(), buf.len());
+    assert!(decompress_fuji(&padded, 100, 6, 14, &cfa).is_err());
+
+    let buf = lossy_stream(false);
+    let padded = PaddedBuf::new_owned(buf.clone(), buf.len());
+    assert!(decompress_fuji(&padded, 768, 6, 14, &cfa).is_err());
+    Ok(())
+  }
+}
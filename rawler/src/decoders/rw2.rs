@@ -8,6 +8,7 @@ use crate::Result;
 use crate::analyze::FormatDump;
 use crate::decompressors::packed::decompress_12le_unpacked_left_aligned;
 use crate::decompressors::packed::decompress_12le_wcontrol;
+use crate::decompressors::packed::decompress_14le_unpacked;
 use crate::exif::Exif;
 use crate::formats::tiff::Entry;
 use crate::formats::tiff::GenericTiffReader;
@@ -156,7 +157,9 @@ impl<'a> Decoder for Rw2Decoder<'a> {
         log::debug!("StripOffset: {}", offset);
         let src = file.subview_until_eof_padded(offset as u64)?; // TODO add size and check all samples
 
-        if src.len() >= width * height * 2 {
+        if src.len() >= width * height * 2 && bps == 14 {
+          decompress_14le_unpacked(&src, width, height, dummy)?
+        } else if src.len() >= width * height * 2 {
           decompress_12le_unpacked_left_aligned(&src, width, height, dummy)?
         } else if src.len() >= width * height * 3 / 2 {
           decompress_12le_wcontrol(&src, width, height, dummy)?
@@ -182,8 +185,8 @@ impl<'a> Decoder for Rw2Decoder<'a> {
     if let Some(cfa) = self.get_cfa()? {
       camera.cfa = cfa;
     }
-    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera));
-    let mut img = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, None, dummy);
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera)?);
+    let mut img = RawImage::new(camera, image, cpp, normalize_wb(self.get_wb()?), photometric, blacklevel, None, dummy)?;
 
     if let Some(area) = self.get_active_area()? {
       img.active_area = Some(area);
@@ -357,13 +360,12 @@ impl<'a> Rw2Decoder<'a> {
   ) -> Result<PixU16> {
     log::debug!("width: {}, height: {}, bps: {}", width, height, bps);
     Ok(match raw_format {
-      3 => decode_panasonic_v4(buf, width, height, split, dummy),
-      4 => decode_panasonic_v4(buf, width, height, split, dummy),
+      3 | 4 => decode_panasonic_v4(buf, width, height, split, dummy),
       5 => decode_panasonic_v5(buf, width, height, bps, dummy)?,
       6 => decode_panasonic_v6(buf, width, height, bps, dummy)?,
       7 => decode_panasonic_v7(buf, width, height, bps, dummy)?,
       8 => decode_panasonic_v8(file, width, height, bps, ifd, dummy)?,
-      _ => todo!("Format {} is not implemented", raw_format), // TODO: return error
+      _ => return Err(RawlerError::unsupported_feature("RW2", format!("raw format {}", raw_format))),
     })
   }
 }
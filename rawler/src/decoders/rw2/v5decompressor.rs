@@ -19,13 +19,18 @@ pub(crate) fn decode_panasonic_v5(buf: &[u8], width: usize, height: usize, bps:
   let pixels_per_packet = match bps {
     12 => 10,
     14 => 9,
-    _ => unreachable!(),
+    _ => return Err(format!("RW2 V5: unsupported bits per sample: {}", bps)),
   };
   // Pixel count per full block
   let pixels_per_block = V5_PACKETS_PER_BLOCK * pixels_per_packet;
 
   log::debug!("RW2 V5 decoder: pixels per block: {}, bps: {}", pixels_per_block, bps);
 
+  let need = (width * height).div_ceil(pixels_per_block) * V5_BLOCK_SIZE;
+  if buf.len() < need {
+    return Err(format!("RW2 V5: buffer too short ({} < {})", buf.len(), need));
+  }
+
   // We decode chunked at pixels_per_block boundary
   // Each block delivers the same amount of pixels.
   decompress_chunked_fn(
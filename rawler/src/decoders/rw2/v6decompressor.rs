@@ -33,15 +33,17 @@ pub(crate) fn decode_panasonic_v6(buf: &[u8], width: usize, height: usize, bps:
       spix_compare = 0xffff;
       pixel_mask = 0x3fff;
     }
-    _ => {
-      unreachable!()
-    }
+    _ => return Err(format!("RW2 V6: unsupported bits per sample: {}", bps)),
   }
 
+  if !width.is_multiple_of(pixels_per_block) {
+    return Err(format!("RW2 V6: width {} is not a multiple of {}", width, pixels_per_block));
+  }
   let blocks_per_row = width / pixels_per_block;
   let bytes_per_row = V6_BYTES_PER_BLOCK * blocks_per_row;
-
-  assert_eq!(width % pixels_per_block, 0);
+  if buf.len() < bytes_per_row * height {
+    return Err(format!("RW2 V6: buffer too short ({} < {})", buf.len(), bytes_per_row * height));
+  }
 
   //log::debug!("RW2 V5 decoder: pixels per block: {}, bps: {}", pixels_per_block, bps);
 
@@ -98,7 +100,7 @@ pub(crate) fn decode_panasonic_v6(buf: &[u8], width: usize, height: usize, bps:
             pixelbuffer[1] = pump.get_bits(12) as u16;
             pixelbuffer[0] = pump.get_bits(12) as u16;
           }
-          _ => unreachable!(),
+          _ => unreachable!("bps is checked before decoding"),
         }
 
         let mut curr_pixel = 0;
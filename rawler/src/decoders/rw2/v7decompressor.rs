@@ -12,13 +12,16 @@ pub(crate) fn decode_panasonic_v7(buf: &[u8], width: usize, height: usize, bps:
   let pixels_per_block = match bps {
     14 => 9,
     12 => 10,
-    _ => unreachable!(),
+    _ => return Err(format!("RW2 V7: unsupported bits per sample: {}", bps)),
   };
+  if !width.is_multiple_of(pixels_per_block) {
+    return Err(format!("RW2 V7: width {} is not a multiple of {}", width, pixels_per_block));
+  }
   let blocks_per_row = width / pixels_per_block;
-
-  assert_eq!(width % pixels_per_block, 0);
-
   let bytes_per_row = V7_BYTES_PER_BLOCK * blocks_per_row;
+  if buf.len() < bytes_per_row * height {
+    return Err(format!("RW2 V7: buffer too short ({} < {})", buf.len(), bytes_per_row * height));
+  }
 
   decompress_lines_fn(
     width,
@@ -30,7 +33,7 @@ pub(crate) fn decode_panasonic_v7(buf: &[u8], width: usize, height: usize, bps:
         let start = block_id * pixels_per_block;
         let out = &mut out[start..start + pixels_per_block];
         let mut pump = BitPumpLSB::new(block);
-        out.iter_mut().for_each(|pixel| *pixel = pump.get_bits(14) as u16);
+        out.iter_mut().for_each(|pixel| *pixel = pump.get_bits(bps) as u16);
       }
       Ok(())
     }),
@@ -9,7 +9,7 @@ use itertools::Itertools;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 use crate::{
-  alloc_image_ok,
+  RawlerError, alloc_image_ok,
   bits::{Endian, clamp},
   decoders::{Result, rw2::PanasonicTag},
   formats::tiff::IFD,
@@ -232,14 +232,14 @@ impl HuffmanDecoder {
 
   /// Extract Huffman symbol from bitstream pump and
   /// return index into symbol table.
-  fn get_next(&self, pump: &mut dyn BitPump) -> u8 {
+  fn get_next(&self, pump: &mut dyn BitPump) -> std::result::Result<u8, String> {
     let next_bits = pump.peek_bits(16);
     debug_assert_eq!(self.cache.len(), u16::MAX as usize + 1);
     if let Some((bits, ssss)) = unsafe { *self.cache.get_unchecked(next_bits as usize) } {
       pump.consume_bits(bits as u32);
-      ssss
+      Ok(ssss)
     } else {
-      panic!("Input value {:016b} starts not with a valid huffman symbol", next_bits);
+      Err(format!("Input value {:016b} starts not with a valid huffman symbol", next_bits))
     }
   }
 }
@@ -371,36 +371,62 @@ pub(crate) fn decode_panasonic_v8(rawfile: &RawSource, width: usize, height: usi
   let params = CF2Params::new(ifd)?;
   log::debug!("pana8: params: {:?}", params);
 
-  assert_eq!(
-    params.strip_widths.iter().map(|x| *x as i32).sum::<i32>() as usize / params.num_of_strips_v as usize,
-    width
-  );
+  let total_strip_count = params.num_of_strips_h as usize * params.num_of_strips_v as usize;
+  if params.num_of_strips_v == 0 || params.strip_widths.iter().map(|x| *x as usize).sum::<usize>() / params.num_of_strips_v as usize != width {
+    return Err(format!("Pana8: strip widths don't match image width {}", width).into());
+  }
+  if [
+    params.strip_byte_offsets.len(),
+    params.strip_data_size.len(),
+    params.strip_line_offsets.len(),
+    params.strip_widths.len(),
+    params.strip_heights.len(),
+  ]
+  .iter()
+  .any(|len| *len < total_strip_count)
+  {
+    return Err(format!("Pana8: strip parameters for {} strips are incomplete", total_strip_count).into());
+  }
+  // Shiftdown seems to be the count of bits shifted to right during encoding.
+  // It's 0 for all existing samples so far, so we can't verify a decoder for it.
+  if params.huf_shift_down.iter().any(|shift| shift & 0x1F != 0) {
+    return Err(RawlerError::unsupported_feature("RW2", "CF2HufShiftDown"));
+  }
+  // Strips are written into the shared output without further checks.
+  for strip_id in 0..total_strip_count {
+    let offset = &params.strip_line_offsets[strip_id];
+    if offset.cols as usize + params.strip_widths[strip_id] as usize > width || offset.rows as usize + params.strip_heights[strip_id] as usize > height {
+      return Err(format!("Pana8: strip {} exceeds image dimension", strip_id).into());
+    }
+  }
 
   // Shared output buffer, we need to write from multiple rayon threads to output image.
   let shared_pix = SharedPix2D::new(out);
 
-  let total_strip_count = (params.num_of_strips_h * params.num_of_strips_v) as usize;
   let mut bitstreams = Vec::with_capacity(total_strip_count);
   for strip_id in 0..total_strip_count {
     bitstreams.push(rawfile.subview(params.strip_byte_offsets[strip_id] as u64, (params.strip_data_size[strip_id] as u64 + 7) / 8)?);
   }
 
   // Parallel decode multiple strips
-  (0..total_strip_count).into_par_iter().for_each(|strip_id| {
+  (0..total_strip_count).into_par_iter().try_for_each(|strip_id| {
     let buf = &bitstreams[strip_id];
-    decode_strip(buf, &params, strip_id, unsafe { shared_pix.inner_mut() });
-  });
+    decode_strip(buf, &params, strip_id, unsafe { shared_pix.inner_mut() })
+  })?;
   Ok(shared_pix.into_inner())
 }
 
 /// Decode a single strip
-fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU16) {
+fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU16) -> std::result::Result<(), String> {
   let mut pump = BitPumpReverseBitsMSB::new(buf);
   let width = params.strip_widths[strip_id] as usize;
   let height = params.strip_heights[strip_id] as usize;
   let halfheight = height >> 1;
   let halfwidth = width >> 1;
   let doublewidth = halfwidth * 4;
+  if !width.is_multiple_of(2) {
+    return Err(format!("Pana8: strip {} has odd width {}", strip_id, width));
+  }
   let mut linebuf = vec![0_u16; doublewidth];
 
   // for (i, item) in params.huf_table.iter().enumerate() {
@@ -416,12 +442,10 @@ fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU1
     state.current_base = state.line_base;
     for col in 0..doublewidth {
       // Calculate index
-      let ssss = state.huffdec.get_next(&mut pump);
+      let ssss = state.huffdec.get_next(&mut pump)?;
 
-      // Shiftdown seems to be the count of bits shifted to right during encoding.
-      // It's 0 for all existing samples so far, highly interested in samples that has
-      let shift_down: u8 = (params.huf_shift_down[ssss as usize] & 0x1F) as u8;
-      assert_eq!(shift_down, 0, "CF2HufShiftDown samples required");
+      // Non-zero shiftdown values are refused before decoding.
+      let shift_down: u8 = (params.huf_shift_down.get(ssss as usize).copied().unwrap_or(0) & 0x1F) as u8;
 
       // Calculate total required bits to read from bitstream.
       let req_bits: u32 = ssss.saturating_sub(shift_down as u8) as u32;
@@ -431,7 +455,7 @@ fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU1
         debug_assert_ne!(req_bits, 0);
         let rawbits: u32 = pump.get_bits(req_bits as u32); // Get additional bits
         let sign = rawbits >> (req_bits - 1); // Get leading sign bit
-        let val = (rawbits << (params.huf_shift_down[ssss as usize] & 0xFF)) as i32;
+        let val = (rawbits << shift_down) as i32;
 
         if sign == 1 {
           val
@@ -473,7 +497,6 @@ fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU1
 
     // Copy line buffer into output image.
     // Line buffer contains two rows packed into one row with double width.
-    assert_eq!(linebuf.len(), 2 * width);
     for col in (0..width).step_by(2) {
       let row_offset = params.strip_line_offsets[strip_id].rows as usize;
       let left_margin = params.strip_line_offsets[strip_id].cols as usize;
@@ -492,4 +515,5 @@ fn decode_strip(buf: &[u8], params: &CF2Params, strip_id: usize, out: &mut PixU1
       }
     }
   }
+  Ok(())
 }
@@ -6,6 +6,7 @@ use crate::RawLoader;
 use crate::RawlerError;
 use crate::Result;
 use crate::alloc_image;
+use crate::alloc_image_ok;
 use crate::analyze::FormatDump;
 use crate::bits::LEu32;
 use crate::bits::clampbits;
@@ -13,6 +14,9 @@ use crate::decompressors::packed::decompress_12be;
 use crate::decompressors::packed::decompress_12le;
 use crate::decompressors::packed::decompress_12le_unpacked;
 use crate::decompressors::packed::decompress_14le_unpacked;
+use crate::decompressors::packed::decompress_16le;
+use crate::decompressors::packed::decompress_generic_lsb;
+use crate::decompressors::packed::decompress_generic_msb;
 use crate::exif::Exif;
 use crate::formats::tiff::GenericTiffReader;
 use crate::formats::tiff::IFD;
@@ -84,7 +88,7 @@ impl<'a> Decoder for SrwDecoder<'a> {
       32769 => match bits {
         12 => decompress_12le_unpacked(&src, width, height, dummy)?,
         14 => decompress_14le_unpacked(&src, width, height, dummy)?,
-        x => return Err(RawlerError::unsupported(&self.camera, format!("SRW: Don't know how to handle bps {}", x))),
+        _ => self.decode_uncompressed(raw, &src, width, height, bits, false, dummy)?,
       },
       32770 => match raw.get_entry(TiffCommonTag::SrwSensorAreas) {
         None => match bits {
@@ -96,17 +100,20 @@ impl<'a> Decoder for SrwDecoder<'a> {
             }
           }
           14 => decompress_14le_unpacked(&src, width, height, dummy)?,
-          x => return Err(RawlerError::unsupported(&self.camera, format!("SRW: Don't know how to handle bps {}", x))),
+          // Packed data is stored MSB first, like the 12 bit layout
+          _ => self.decode_uncompressed(raw, &src, width, height, bits, !self.camera.find_hint("little_endian"), dummy)?,
         },
         Some(x) => {
           let coffset = x.force_usize(0);
-          assert!(coffset > 0, "Surely this can't be the start of the file");
+          if coffset == 0 {
+            return Err(RawlerError::DecoderFailed("SRW: invalid line offset table position".to_string()));
+          }
           let loffsets = file.subview_until_eof(coffset as u64)?;
-          SrwDecoder::decode_srw1(&src, loffsets, width, height, dummy)
+          SrwDecoder::decode_srw1(&src, loffsets, width, height, dummy)?
         }
       },
       32772 => SrwDecoder::decode_srw2(&src, width, height, dummy),
-      32773 => SrwDecoder::decode_srw3(&src, width, height, dummy),
+      32773 => SrwDecoder::decode_srw3(&src, width, height, dummy)?,
       x => {
         return Err(RawlerError::unsupported(
           &self.camera,
@@ -134,13 +141,47 @@ impl<'a> Decoder for SrwDecoder<'a> {
 }
 
 impl<'a> SrwDecoder<'a> {
-  pub fn decode_srw1(buf: &[u8], loffsets: &[u8], width: usize, height: usize, dummy: bool) -> PixU16 {
-    let mut out = alloc_image!(width, height, dummy);
+  /// Decode uncompressed data for compression 32769 and 32770 in bit depths
+  /// without a known layout
+  ///
+  /// Samples are either packed to `bits` (bit order given by `msb`) or
+  /// stored in 16 bit little-endian words. The layout is detected by the
+  /// strip size. Without strip byte counts, unpacked samples are assumed.
+  fn decode_uncompressed(&self, raw: &IFD, src: &[u8], width: usize, height: usize, bits: u32, msb: bool, dummy: bool) -> Result<PixU16> {
+    if !(8..=16).contains(&bits) {
+      return Err(RawlerError::unsupported(&self.camera, format!("SRW: Don't know how to handle bps {}", bits)));
+    }
+    let unpacked = match raw.get_entry(TiffCommonTag::StripByteCounts) {
+      Some(counts) => (0..counts.count()).map(|i| counts.force_usize(i as usize)).sum::<usize>() >= width * height * 2,
+      None => true,
+    };
+    let image = match (unpacked, msb) {
+      (true, _) => {
+        let mut image = decompress_16le(src, width, height, dummy)?;
+        let mask = ((1_u32 << bits) - 1) as u16;
+        image.pixels_mut().iter_mut().for_each(|p| *p &= mask);
+        image
+      }
+      (false, false) => decompress_generic_lsb(src, width, height, bits, dummy)?,
+      (false, true) => decompress_generic_msb(src, width, height, bits, dummy)?,
+    };
+    Ok(image)
+  }
+
+  pub fn decode_srw1(buf: &[u8], loffsets: &[u8], width: usize, height: usize, dummy: bool) -> Result<PixU16> {
+    let mut out = alloc_image_ok!(width, height, dummy);
+    if loffsets.len() < height * 4 {
+      return Err(RawlerError::DecoderFailed(format!("SRW: line offset table too short for {} rows", height)));
+    }
 
     for row in 0..height {
       let mut len: [u32; 4] = [if row < 2 { 7 } else { 4 }; 4];
       let loffset = LEu32(loffsets, row * 4) as usize;
-      let mut pump = BitPumpMSB32::new(&buf[loffset..]);
+      let mut pump = BitPumpMSB32::new(
+        buf
+          .get(loffset..)
+          .ok_or_else(|| RawlerError::DecoderFailed(format!("SRW: line offset {} for row {} is out of bounds", loffset, row)))?,
+      );
 
       let img = width * row;
       let img_up = width * (cmp::max(1, row) - 1);
@@ -154,10 +195,16 @@ impl<'a> SrwDecoder<'a> {
         for (i, op) in ops.iter().enumerate() {
           match *op {
             3 => len[i] = pump.get_bits(4),
-            2 => len[i] -= 1,
+            2 => len[i] = len[i].wrapping_sub(1),
             1 => len[i] += 1,
             _ => {}
           }
+          if len[i] > 16 {
+            return Err(RawlerError::DecoderFailed(format!(
+              "SRW: invalid difference length {} in row {}",
+              len[i] as i32, row
+            )));
+          }
         }
 
         // First decode even pixels
@@ -204,7 +251,7 @@ impl<'a> SrwDecoder<'a> {
       }
     }
 
-    out
+    Ok(out)
   }
 
   pub fn decode_srw2(buf: &[u8], width: usize, height: usize, dummy: bool) -> PixU16 {
@@ -286,7 +333,7 @@ impl<'a> SrwDecoder<'a> {
     diff
   }
 
-  pub fn decode_srw3(buf: &[u8], width: usize, height: usize, dummy: bool) -> PixU16 {
+  pub fn decode_srw3(buf: &[u8], width: usize, height: usize, dummy: bool) -> Result<PixU16> {
     // Decoder for third generation compressed SRW files (NX1)
     // Seriously Samsung just use lossless jpeg already, it compresses better too :)
 
@@ -294,7 +341,10 @@ impl<'a> SrwDecoder<'a> {
     // and Loring von Palleske (Samsung) for pointing to the open-source code of
     // Samsung's DNG converter at http://opensource.samsung.com/
 
-    let mut out = alloc_image!(width, height, dummy);
+    if !width.is_multiple_of(16) {
+      return Err(RawlerError::DecoderFailed(format!("SRW: width {} is not a multiple of 16", width)));
+    }
+    let mut out = alloc_image_ok!(width, height, dummy);
     let mut pump = BitPumpMSB32::new(buf);
 
     // Process the initial metadata bits, we only really use initVal, width and
@@ -335,7 +385,11 @@ impl<'a> SrwDecoder<'a> {
       if (line_offset & 0x0f) != 0 {
         line_offset += 16 - (line_offset & 0xf);
       }
-      pump = BitPumpMSB32::new(&buf[line_offset..]);
+      pump = BitPumpMSB32::new(
+        buf
+          .get(line_offset..)
+          .ok_or_else(|| RawlerError::DecoderFailed(format!("SRW: data ends before row {}", row)))?,
+      );
 
       let img = width * row;
       let img_up = width * (cmp::max(1, row) - 1);
@@ -370,7 +424,9 @@ impl<'a> SrwDecoder<'a> {
         }
 
         if row < 2 && motion != 7 {
-          panic!("SRW Decoder: At start of image and motion isn't 7. File corrupted?")
+          return Err(RawlerError::DecoderFailed(
+            "SRW: At start of image and motion isn't 7. File corrupted?".to_string(),
+          ));
         }
 
         if motion == 7 {
@@ -381,25 +437,27 @@ impl<'a> SrwDecoder<'a> {
           }
         } else {
           // The complex case, we now need to actually lookup one or two lines above
-          if row < 2 {
-            panic!("SRW: Got a previous line lookup on first two lines. File corrupted?");
-          }
           let motion_offset: [isize; 7] = [-4, -2, -2, 0, 0, 2, 4];
           let motion_average: [i32; 7] = [0, 0, 1, 0, 1, 0, 0];
           let slide_offset = motion_offset[motion];
 
           for i in 0..16 {
-            let refpixel: usize = if ((row + i) & 0x1) != 0 {
+            let refpixel: isize = if ((row + i) & 0x1) != 0 {
               // Red or blue pixels use same color two lines up
-              ((img_up2 + col + i) as isize + slide_offset) as usize
+              (img_up2 + col + i) as isize + slide_offset
             } else {
               // Green pixel N uses Green pixel N from row above (top left or top right)
               if (i % 2) != 0 {
-                ((img_up + col + i - 1) as isize + slide_offset) as usize
+                (img_up + col + i - 1) as isize + slide_offset
               } else {
-                ((img_up + col + i + 1) as isize + slide_offset) as usize
+                (img_up + col + i + 1) as isize + slide_offset
               }
             };
+            // Corrupted motion vectors may point outside of the image
+            let refpixel = usize::try_from(refpixel)
+              .ok()
+              .filter(|p| p + 2 < out.pixels().len())
+              .ok_or_else(|| RawlerError::DecoderFailed(format!("SRW: motion {} points outside of image in row {}", motion, row)))?;
             // In some cases we use as reference interpolation of this pixel and the next
             out[img + col + i] = if motion_average[motion] != 0 {
               (out[refpixel] + out[refpixel + 2] + 1) >> 1
@@ -424,7 +482,7 @@ impl<'a> SrwDecoder<'a> {
                 diff_bits[i] = diff_bits_mode[colornum][0] + 1;
               }
               2 => {
-                diff_bits[i] = diff_bits_mode[colornum][0] - 1;
+                diff_bits[i] = diff_bits_mode[colornum][0].wrapping_sub(1);
               }
               3 => {
                 diff_bits[i] = pump.get_bits(4);
@@ -434,7 +492,7 @@ impl<'a> SrwDecoder<'a> {
             diff_bits_mode[colornum][0] = diff_bits_mode[colornum][1];
             diff_bits_mode[colornum][1] = diff_bits[i];
             if diff_bits[i] > bit_depth + 1 {
-              panic!("SRW Decoder: Too many difference bits. File corrupted?");
+              return Err(RawlerError::DecoderFailed("SRW: Too many difference bits. File corrupted?".to_string()));
             }
           }
         }
@@ -457,7 +515,7 @@ impl<'a> SrwDecoder<'a> {
       }
     }
 
-    out
+    Ok(out)
   }
 
   /// Get lens description by analyzing TIFF tags and makernotes
@@ -87,12 +87,12 @@ pub fn decode_unwrapped(file: &RawSource) -> Result<RawImageData> {
       let loffsets = data;
       let data = &data[height * 4..];
       Ok(RawImageData::Integer(
-        srw::SrwDecoder::decode_srw1(data, loffsets, width, height, false).into_inner(),
+        srw::SrwDecoder::decode_srw1(data, loffsets, width, height, false)?.into_inner(),
       ))
     }
     26 => Ok(RawImageData::Integer(srw::SrwDecoder::decode_srw2(data, width, height, false).into_inner())),
-    27 => Ok(RawImageData::Integer(srw::SrwDecoder::decode_srw3(data, width, height, false).into_inner())),
-    28 => Ok(RawImageData::Integer(kdc::KdcDecoder::decode_dc120(data, width, height, false).into_inner())),
+    27 => Ok(RawImageData::Integer(srw::SrwDecoder::decode_srw3(data, width, height, false)?.into_inner())),
+    28 => Ok(RawImageData::Integer(kdc::KdcDecoder::decode_dc120(data, width, height, false)?.into_inner())),
     29 => Ok(RawImageData::Integer(
       rw2::v4decompressor::decode_panasonic_v4(data, width, height, false, false).into_inner(),
     )),
@@ -1,5 +1,3 @@
-use std::io::Cursor;
-
 use crate::bits::*;
 use crate::decoders::*;
 
@@ -38,20 +36,22 @@ struct X3fImage {
 
 impl X3fFile {
   fn new(file: &RawSource) -> Result<X3fFile> {
-    let buf = file.as_vec()?;
-    let offset = LEu32(&buf, buf.len() - 4) as usize;
-    let data = &buf[offset..];
-    let version = LEu32(data, 4);
+    // Only read the directory and image headers, not the whole file
+    let len = file.len() as u64;
+    let offset = LEu32(file.subview(len.checked_sub(4).ok_or("X3F: File is too short")?, 4)?, 0) as u64;
+    let header = file.subview(offset, 12)?;
+    let version = LEu32(header, 4);
     if version < 0x00020000 {
       return Err(format_args!("X3F: Directory version too old {}", version).into());
     }
-    let entries = LEu32(data, 8) as usize;
+    let entries = LEu32(header, 8) as usize;
+    let data = file.subview(offset, 12 + entries as u64 * 12)?;
     let mut dirs = Vec::new();
     let mut images = Vec::new();
     for i in 0..entries {
       let dir = X3fDirectory::new(data, 12 + i * 12)?;
       if dir.id == "IMA2" {
-        let img = X3fImage::new(&buf, dir.offset)?;
+        let img = X3fImage::new(file.subview(dir.offset as u64, 28)?, dir.offset)?;
         images.push(img);
       }
       dirs.push(dir);
@@ -73,9 +73,8 @@ impl X3fDirectory {
 }
 
 impl X3fImage {
-  fn new(buf: &[u8], offset: usize) -> Result<X3fImage> {
-    let data = &buf[offset..];
-
+  /// Parse the image header, `data` starts at `offset`
+  fn new(data: &[u8], offset: usize) -> Result<X3fImage> {
     Ok(X3fImage {
       typ: LEu32(data, 8) as usize,
       format: LEu32(data, 12) as usize,
@@ -103,7 +102,7 @@ impl<'a> X3fDecoder<'a> {
 
 impl<'a> Decoder for X3fDecoder<'a> {
   fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
-    let buffer = file.as_vec()?;
+    let buffer = file.subview_until_eof(0)?;
     let caminfo = self
       .dir
       .images
@@ -114,7 +113,7 @@ impl<'a> Decoder for X3fDecoder<'a> {
     if data[0..4] != b"Exif"[..] {
       return Err("X3F: Couldn't find EXIF info".into());
     }
-    let tiff = IFD::new(&mut Cursor::new(&buffer), (caminfo.doffset + 12) as u32, 0, 0, Endian::Little, &[])?;
+    let tiff = IFD::new(&mut file.reader(), (caminfo.doffset + 12) as u32, 0, 0, Endian::Little, &[])?;
 
     let camera = self.rawloader.check_supported(&tiff)?;
 
@@ -131,7 +130,7 @@ impl<'a> Decoder for X3fDecoder<'a> {
 
     let cpp = 3;
     let photometric = RawPhotometricInterpretation::LinearRaw;
-    Ok(RawImage::new(camera, image, cpp, self.get_wb()?, photometric, None, None, dummy))
+    RawImage::new(camera, image, cpp, self.get_wb()?, photometric, None, None, dummy)
   }
 
   fn format_dump(&self) -> FormatDump {
@@ -1,7 +1,7 @@
-//! Deflate (zlib) decompressor for floating-point DNG image data.
+//! Deflate (zlib) decompressor for DNG image data.
 //!
-//! DNG tiles and strips that carry IEEE floating-point pixel data are stored
-//! with two layers of encoding on top of the raw sample bytes:
+//! Floating-point tiles and strips are stored with up to three layers of
+//! encoding on top of the raw sample bytes:
 //!
 //! 1. **Byte-plane shuffling** — the bytes of each multi-byte sample are split
 //!    across separate planes within the compressed block. For a 32-bit float
@@ -18,30 +18,35 @@
 //!    compressed with zlib (identified by `CompressionMethod::Deflate` in the
 //!    TIFF IFD).
 //!
-//! Supported bit depths: 16 (half-float), 24 (24-bit float), and 32
-//! (single-precision float). All depths are widened to `f32` on output.
+//! Without a predictor (predictor 1), samples are stored as plain bytes
+//! in file byte order.
+//!
+//! Integer data is either packed like uncompressed data (predictor 1) or
+//! stored with horizontal differencing (TIFF predictor 2, or the DNG
+//! predictors 34892 and 34893 which use a stride of 2 or 4 pixels).
+//! Differencing works for 1 to 16 bits per sample; depths other than 8
+//! and 16 are packed MSB-first with each row starting on a byte boundary.
+//!
+//! Supported floating-point bit depths: 16 (half-float), 24 (24-bit float),
+//! and 32 (single-precision float). All depths are widened to `f32` on output.
+//!
+//! The reverse prediction is shared with the LZW decompressor.
 
 use libflate::zlib::Decoder;
 use std::io::Read;
 
 use crate::{
-  bits::{Binary16, Binary24, Binary32, FloatingPointParameters, extend_binary_floating_point},
-  decompressors::{Decompressor, LineIteratorMut},
+  bits::{Binary16, Binary24, Binary32, Endian, FloatingPointParameters, extend_binary_floating_point},
+  decompressors::{Decompressor, LineIteratorMut, packed::PackedDecompressor},
+  pumps::{BitPump, BitPumpMSB},
 };
 
-/// Decompressor for Deflate-compressed floating-point DNG image data.
+/// Decompressor for Deflate-compressed DNG image data.
 ///
-/// Constructed once per tile or strip.
+/// Constructed once per image and shared by all tiles or strips.
 #[derive(Debug)]
 pub struct DeflateDecompressor {
-  /// Horizontal delta stride in bytes: `cpp × bytes_per_component`.
-  ///
-  /// Determines how far back the delta predictor looks when decoding each
-  /// byte. Equals the number of channels times the byte-width of the
-  /// predictor unit selected by the TIFF `Predictor` tag.
-  pred_factor: usize,
-  /// Bits per sample (16, 24, or 32).
-  bps: u32,
+  predictor: SamplePredictor,
 }
 
 impl DeflateDecompressor {
@@ -50,22 +55,19 @@ impl DeflateDecompressor {
   /// # Arguments
   /// * `cpp` — Channels (samples) per pixel, e.g. 1 for greyscale, 3 for RGB.
   /// * `predictor` — Value of the TIFF `Predictor` tag:
-  ///   - `3` — Horizontal differencing with a 1-byte component stride.
-  ///   - `34894` — DNG floating-point predictor with a 2-byte component stride.
-  ///   - `34895` — DNG floating-point predictor with a 4-byte component stride.
-  /// * `bps` — Bits per sample stored in the compressed stream (16, 24, or 32).
+  ///   - `1` — No prediction.
+  ///   - `2`, `34892`, `34893` — Integer horizontal differencing with a 1, 2
+  ///     or 4 pixel stride.
+  ///   - `3`, `34894`, `34895` — Floating-point predictor with a 1, 2 or 4
+  ///     pixel stride.
+  /// * `bps` — Bits per sample stored in the compressed stream.
+  /// * `endian` — Byte order of the TIFF file.
   ///
-  /// # Panics
-  /// Panics for any `predictor` value other than 3, 34894, and 34895
-  pub fn new(cpp: usize, predictor: u16, bps: u32) -> Self {
-    let pred_factor = cpp
-      * match predictor {
-        3 => 1,
-        34894 => 2,
-        34895 => 4,
-        _ => panic!("DeflateDecompressor: Unsupported predictor {predictor}"),
-      };
-    Self { pred_factor, bps }
+  /// Unsupported predictors are reported on decompression.
+  pub fn new(cpp: usize, predictor: u16, bps: u32, endian: Endian) -> Self {
+    Self {
+      predictor: SamplePredictor::new(cpp, predictor, bps, endian),
+    }
   }
 }
 
@@ -80,38 +82,151 @@ impl<'a> Decompressor<'a, f32> for DeflateDecompressor {
   /// * `line_width` — Width of each line in pixels (not bytes).
   ///
   /// # Errors
-  /// Returns `Err(String)` if zlib inflation fails or if the inflated data
-  /// length does not match `bps/8 × line_width × lines.len()`.
-  ///
-  /// # Panics
-  /// Panics if `bps` is not 16, 24, or 32.
+  /// Returns `Err(String)` if zlib inflation fails, if the predictor or bit depth
+  /// is not supported for floating-point data or if the inflated data length
+  /// does not match `bps/8 × line_width × lines.len()`.
   fn decompress(&self, src: &[u8], skip_rows: usize, lines: impl LineIteratorMut<'a, f32>, line_width: usize) -> std::result::Result<(), String> {
-    let mut decoder = Decoder::new(src).map_err(|err| err.to_string())?;
-    let mut decoded_data = Vec::new();
-    decoder.read_to_end(&mut decoded_data).map_err(|err| err.to_string())?;
+    let mut decoded_data = inflate(src)?;
+    self.predictor.decode_float(&mut decoded_data, skip_rows, lines, line_width)
+  }
+
+  fn can_skip_rows(&self) -> bool {
+    false
+  }
+}
+
+impl<'a> Decompressor<'a, u16> for DeflateDecompressor {
+  /// Decompresses a single zlib-compressed tile or strip into `u16` pixel lines.
+  ///
+  /// # Errors
+  /// Returns `Err(String)` if zlib inflation fails, if the predictor or bit depth
+  /// is not supported for integer data or if the inflated data is too short.
+  fn decompress(&self, src: &[u8], skip_rows: usize, lines: impl LineIteratorMut<'a, u16>, line_width: usize) -> std::result::Result<(), String> {
+    let mut decoded_data = inflate(src)?;
+    self.predictor.decode_integer(&mut decoded_data, skip_rows, lines, line_width)
+  }
+
+  fn can_skip_rows(&self) -> bool {
+    false
+  }
+}
+
+fn inflate(src: &[u8]) -> std::result::Result<Vec<u8>, String> {
+  let mut decoder = Decoder::new(src).map_err(|err| err.to_string())?;
+  let mut decoded_data = Vec::new();
+  decoder.read_to_end(&mut decoded_data).map_err(|err| err.to_string())?;
+  Ok(decoded_data)
+}
+
+/// Reverse prediction for decompressed sample bytes.
+#[derive(Debug, Clone)]
+pub(super) struct SamplePredictor {
+  cpp: usize,
+  predictor: u16,
+  bps: u32,
+  endian: Endian,
+}
 
+impl SamplePredictor {
+  pub(super) fn new(cpp: usize, predictor: u16, bps: u32, endian: Endian) -> Self {
+    Self { cpp, predictor, bps, endian }
+  }
+
+  /// Decode floating-point samples from decompressed bytes.
+  pub(super) fn decode_float<'a>(
+    &self,
+    data: &mut [u8],
+    skip_rows: usize,
+    lines: impl LineIteratorMut<'a, f32>,
+    line_width: usize,
+  ) -> std::result::Result<(), String> {
     let bytesps = self.bps as usize / 8;
-    assert!(bytesps >= 2 && bytesps <= 4);
-    if decoded_data.len() != bytesps * line_width * lines.len() {
-      return Err(format!("DeflateDecompressor: buffer length mismatch"));
+    if !matches!(self.bps, 16 | 24 | 32) {
+      return Err(format!("bps {} not supported for floating point data", self.bps));
+    }
+    let pred_factor = self.cpp
+      * match self.predictor {
+        1 => 0,
+        3 => 1,
+        34894 => 2,
+        34895 => 4,
+        _ => return Err(format!("Unsupported predictor {} for floating point data", self.predictor)),
+      };
+    if data.len() < bytesps * line_width * lines.len() {
+      return Err("buffer length mismatch".to_string());
     }
 
-    for (line, row) in lines.zip(decoded_data.chunks_exact_mut(bytesps as usize * line_width)).skip(skip_rows) {
+    for (line, row) in lines.zip(data.chunks_exact_mut(bytesps * line_width)).skip(skip_rows) {
       debug_assert_eq!(line.len(), line_width);
-      decode_delta_bytes(row, self.pred_factor);
-
-      match self.bps {
-        16 => decode_fp_delta_row::<Binary16>(line, row, line_width),
-        24 => decode_fp_delta_row::<Binary24>(line, row, line_width),
-        32 => decode_fp_delta_row::<Binary32>(line, row, line_width),
-        _ => panic!("DeflateDecompressor: bps {} not supported", self.bps),
+      if pred_factor == 0 {
+        match self.bps {
+          16 => decode_fp_plain_row::<Binary16>(line, row, self.endian),
+          24 => decode_fp_plain_row::<Binary24>(line, row, self.endian),
+          _ => decode_fp_plain_row::<Binary32>(line, row, self.endian),
+        }
+      } else {
+        decode_delta_bytes(row, pred_factor);
+        match self.bps {
+          16 => decode_fp_delta_row::<Binary16>(line, row, line_width),
+          24 => decode_fp_delta_row::<Binary24>(line, row, line_width),
+          _ => decode_fp_delta_row::<Binary32>(line, row, line_width),
+        }
       }
     }
     Ok(())
   }
 
-  fn can_skip_rows(&self) -> bool {
-    false
+  /// Decode integer samples from decompressed bytes.
+  pub(super) fn decode_integer<'a>(
+    &self,
+    data: &mut [u8],
+    skip_rows: usize,
+    lines: impl LineIteratorMut<'a, u16>,
+    line_width: usize,
+  ) -> std::result::Result<(), String> {
+    let stride = self.cpp
+      * match self.predictor {
+        1 => return PackedDecompressor::new(self.bps, self.endian).decompress(data, skip_rows, lines, line_width),
+        2 => 1,
+        34892 => 2,
+        34893 => 4,
+        _ => return Err(format!("Unsupported predictor {} for integer data", self.predictor)),
+      };
+    if !(1..=16).contains(&self.bps) {
+      return Err(format!(
+        "bps {} not supported with horizontal differencing, samples must fit into 16 bits",
+        self.bps
+      ));
+    }
+    // Each row starts on a byte boundary, even for packed bit depths.
+    let row_bytes = (self.bps as usize * line_width).div_ceil(8);
+    if data.len() < row_bytes * lines.len() {
+      return Err("buffer length mismatch".to_string());
+    }
+
+    for (line, row) in lines.zip(data.chunks_exact(row_bytes)).skip(skip_rows) {
+      debug_assert_eq!(line.len(), line_width);
+      match (self.bps, self.endian) {
+        (8, _) => line.iter_mut().zip(row.iter()).for_each(|(p, v)| *p = *v as u16),
+        (16, Endian::Big) => line
+          .iter_mut()
+          .zip(row.chunks_exact(2))
+          .for_each(|(p, v)| *p = u16::from_be_bytes([v[0], v[1]])),
+        (16, Endian::Little) => line
+          .iter_mut()
+          .zip(row.chunks_exact(2))
+          .for_each(|(p, v)| *p = u16::from_le_bytes([v[0], v[1]])),
+        (bps, _) => {
+          let mut pump = BitPumpMSB::new(row);
+          line.iter_mut().for_each(|p| *p = pump.get_bits(bps) as u16);
+        }
+      }
+      let mask = ((1_u32 << self.bps) - 1) as u16;
+      for col in stride..line.len() {
+        line[col] = line[col].wrapping_add(line[col - stride]) & mask;
+      }
+    }
+    Ok(())
   }
 }
 
@@ -133,3 +248,13 @@ fn decode_fp_delta_row<NARROW: FloatingPointParameters>(line: &mut [f32], row: &
     *pix = f32::from_bits(extend_binary_floating_point::<NARROW, Binary32>(value));
   }
 }
+
+fn decode_fp_plain_row<NARROW: FloatingPointParameters>(line: &mut [f32], row: &[u8], endian: Endian) {
+  for (pix, bytes) in line.iter_mut().zip(row.chunks_exact(NARROW::STORAGE_BYTES)) {
+    let value = match endian {
+      Endian::Big => bytes.iter().fold(0_u32, |acc, b| (acc << 8) | *b as u32),
+      Endian::Little => bytes.iter().rev().fold(0_u32, |acc, b| (acc << 8) | *b as u32),
+    };
+    *pix = f32::from_bits(extend_binary_floating_point::<NARROW, Binary32>(value));
+  }
+}
@@ -9,10 +9,10 @@ use crate::decompressors::{Decompressor, LineIteratorMut};
 
 /// Decompressor for JPEG-XL compressed DNG image data.
 ///
-/// Only 8-bit and 16-bit JPEG-XL streams are currently supported. Other
-/// bit depths are rejected because `jxl_oxide` scales samples to the full
-/// storage-type range on decode, which would break black-level calculations
-/// without a rescaling step that is not yet implemented.
+/// `jxl_oxide` scales integer samples to the full range of the storage type
+/// on decode. For streams with a bit depth other than 16, samples are scaled
+/// back to the bit depth of the stream, so black and white levels from the
+/// TIFF IFD stay valid.
 pub struct JpegXLDecompressor {
   /// Bits per sample as declared in the TIFF IFD (not the JXL stream).
   bps: u32,
@@ -30,77 +30,61 @@ impl<'a> Decompressor<'a, u16> for JpegXLDecompressor {
   ///
   /// Renders frame 0 of the JXL image (alpha channel excluded) and writes
   /// samples row by row into `lines`, skipping the first `skip_rows` rows.
-  ///
-  /// * **8 bps** — samples are decoded to `u8` via a temporary buffer and
-  ///   zero-extended to `u16` without scaling.
-  /// * **9–16 bps** — samples are written directly into the `u16` output.
+  /// Samples are rescaled from 16 bit to the bit depth of the JXL stream.
   ///
   /// # Errors
-  /// Returns `Err` if the JXL image cannot be read or rendered.
-  ///
-  /// # Panics
-  /// Panics if the JXL stream's internal bit depth is not 8 or 16, or if
-  /// `bps` is outside the ranges 8 and 9–16.
+  /// Returns `Err` if the JXL image cannot be read or rendered, or if the
+  /// bit depth of the stream or IFD is not in the range 1–16.
   fn decompress(&self, src: &[u8], mut skip_rows: usize, lines: impl LineIteratorMut<'a, u16>, line_width: usize) -> std::result::Result<(), String> {
     let image = JxlImage::builder()
       .read(src)
       .map_err(|err| format!("Failed to read JPEG-XL image: {:?}", err))?;
-    if let Some(header) = image.frame_header(0) {
-      if header.bit_depth.bits_per_sample() != 8 && header.bit_depth.bits_per_sample() != 16 {
-        // jxl_oxide scales the pixels into full range of storage type.
-        // If we get e.g. 12 bit compressed data, output is scaled to 16 bit.
-        // This breaks blacklevel scaling. We need to scale back to given bps (from TIFF).
-        unimplemented!("JPEG-XL bit-depth {} not supported yet", header.bit_depth.bits_per_sample());
-      }
-      //eprintln!("JPEG-XL Bit-Depth: {:?}", header.bit_depth);
+    let stream_bits = image.image_header().metadata.bit_depth.bits_per_sample();
+    if !(1..=16).contains(&stream_bits) || !(1..=16).contains(&self.bps) {
+      return Err(format!("JPEG-XL bit-depth {} (TIFF: {}) not supported", stream_bits, self.bps));
     }
     let frame = image.render_frame(0).map_err(|err| format!("Failed to render JPEG-XL image: {:?}", err))?;
 
     let mut stream = frame.stream_no_alpha();
 
-    match self.bps {
-      8 => {
-        let mut tmp = vec![0_u8; line_width];
-
-        for line in lines.skip(skip_rows) {
-          while skip_rows > 0 {
-            let written = stream.write_to_buffer(&mut tmp);
-            assert_eq!(line.len(), written);
-            skip_rows -= 1;
-          }
-          let written = stream.write_to_buffer(&mut tmp);
-          assert_eq!(line.len(), written);
-          for (p, x) in line.iter_mut().zip(tmp.iter()) {
-            *p = *x as u16; // Only change storage format, you MUST NOT scale up!
-          }
-        }
+    for line in lines.skip(skip_rows) {
+      while skip_rows > 0 {
+        let written = stream.write_to_buffer(line);
+        check_written(line.len(), written)?;
+        skip_rows -= 1;
       }
-      9..=16 => {
-        for line in lines.skip(skip_rows) {
-          while skip_rows > 0 {
-            let written = stream.write_to_buffer(line);
-            assert_eq!(line.len(), written);
-            skip_rows -= 1;
-          }
-          let written = stream.write_to_buffer(line);
-          assert_eq!(line.len(), written);
-        }
+      let written = stream.write_to_buffer(line);
+      check_written(line_width, written)?;
+      if stream_bits < 16 {
+        // Scale back to the range of the stream, black and white levels refer to it
+        line.iter_mut().for_each(|p| *p = scale_from_u16(*p, stream_bits));
       }
-      _ => unimplemented!(),
     }
+    Ok(())
+  }
 
-    /*
-    let all_ch = frame.image_all_channels();
+  fn can_skip_rows(&self) -> bool {
+    false
+  }
+}
 
-    let pixbuf = all_ch.buf();
-    for (line, buf) in lines.zip(pixbuf.chunks_exact(line_width).skip(skip_rows)) {
-      for (p, f) in line.iter_mut().zip(buf.iter()) {
-        //debug_assert!(*f <= (1.0 + f32::EPSILON));
-        // *p = (f * u16::MAX as f32) as u16;
-        // *p = *f as u16;
+impl<'a> Decompressor<'a, f32> for JpegXLDecompressor {
+  /// Decodes a floating-point JPEG-XL buffer into `f32` pixel lines.
+  fn decompress(&self, src: &[u8], mut skip_rows: usize, lines: impl LineIteratorMut<'a, f32>, line_width: usize) -> std::result::Result<(), String> {
+    let image = JxlImage::builder()
+      .read(src)
+      .map_err(|err| format!("Failed to read JPEG-XL image: {:?}", err))?;
+    let frame = image.render_frame(0).map_err(|err| format!("Failed to render JPEG-XL image: {:?}", err))?;
+    let mut stream = frame.stream_no_alpha();
+    for line in lines.skip(skip_rows) {
+      while skip_rows > 0 {
+        let written = stream.write_to_buffer(line);
+        check_written(line.len(), written)?;
+        skip_rows -= 1;
       }
+      let written = stream.write_to_buffer(line);
+      check_written(line_width, written)?;
     }
-    */
     Ok(())
   }
 
@@ -108,3 +92,33 @@ impl<'a> Decompressor<'a, u16> for JpegXLDecompressor {
     false
   }
 }
+
+fn check_written(expected: usize, written: usize) -> std::result::Result<(), String> {
+  if expected != written {
+    return Err(format!("JPEG-XL stream returned {} samples, expected {}", written, expected));
+  }
+  Ok(())
+}
+
+/// Scale a full range 16 bit sample back to `bits`
+fn scale_from_u16(value: u16, bits: u32) -> u16 {
+  let max = (1_u32 << bits) - 1;
+  ((value as u32 * max + 32767) / 65535) as u16
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn scale_jxl_samples_to_stream_bits() {
+    for bits in [8, 10, 12, 14] {
+      let max = (1_u32 << bits) - 1;
+      for value in 0..=max {
+        // jxl_oxide renders to f32 and converts to u16 with rounding
+        let full = ((value as f32 / max as f32) * 65535.0).round() as u16;
+        assert_eq!(scale_from_u16(full, bits) as u32, value, "bits: {}", bits);
+      }
+    }
+  }
+}
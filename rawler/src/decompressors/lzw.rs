@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! LZW decompressor for TIFF/DNG image data.
+//!
+//! TIFF LZW uses MSB-first codes with the "early change" code size switch.
+//! After decompression, samples are decoded with the same predictors as
+//! Deflate compressed data (see [`super::deflate`]).
+
+use crate::{
+  bits::Endian,
+  decompressors::{Decompressor, LineIteratorMut, deflate::SamplePredictor},
+};
+
+/// Decompressor for LZW-compressed DNG image data.
+#[derive(Debug)]
+pub struct LzwDecompressor {
+  predictor: SamplePredictor,
+}
+
+impl LzwDecompressor {
+  /// Creates a new `LzwDecompressor` from IFD metadata.
+  ///
+  /// Arguments are the same as for [`super::deflate::DeflateDecompressor::new`].
+  pub fn new(cpp: usize, predictor: u16, bps: u32, endian: Endian) -> Self {
+    Self {
+      predictor: SamplePredictor::new(cpp, predictor, bps, endian),
+    }
+  }
+}
+
+impl<'a> Decompressor<'a, u16> for LzwDecompressor {
+  fn decompress(&self, src: &[u8], skip_rows: usize, lines: impl LineIteratorMut<'a, u16>, line_width: usize) -> std::result::Result<(), String> {
+    let mut decoded_data = decode_lzw(src)?;
+    self.predictor.decode_integer(&mut decoded_data, skip_rows, lines, line_width)
+  }
+
+  fn can_skip_rows(&self) -> bool {
+    false
+  }
+}
+
+impl<'a> Decompressor<'a, f32> for LzwDecompressor {
+  fn decompress(&self, src: &[u8], skip_rows: usize, lines: impl LineIteratorMut<'a, f32>, line_width: usize) -> std::result::Result<(), String> {
+    let mut decoded_data = decode_lzw(src)?;
+    self.predictor.decode_float(&mut decoded_data, skip_rows, lines, line_width)
+  }
+
+  fn can_skip_rows(&self) -> bool {
+    false
+  }
+}
+
+fn decode_lzw(src: &[u8]) -> std::result::Result<Vec<u8>, String> {
+  let mut decoder = weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8);
+  let mut decoded_data = Vec::new();
+  // Some writers omit the end-of-information code, so an exhausted
+  // input stream is not treated as error.
+  let result = decoder.into_vec(&mut decoded_data).decode(src);
+  match result.status {
+    Ok(_) => Ok(decoded_data),
+    Err(err) => Err(format!("LZW decoding failed: {}", err)),
+  }
+}
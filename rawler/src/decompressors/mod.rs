@@ -46,6 +46,7 @@ pub mod deflate;
 pub mod jpeg;
 pub mod jpegxl;
 pub mod ljpeg;
+pub mod lzw;
 pub mod packed;
 pub mod radc;
 
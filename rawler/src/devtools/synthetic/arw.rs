@@ -0,0 +1,206 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterLSB, IfdBlob, add_camera, add_makernote, build_std_tiff};
+use crate::Result;
+use crate::bits::LookupTable;
+use crate::decoders::arw::{ArwDecoder, ArwMakernoteTag, SR2SubIFD};
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Key for the encrypted SR2 block
+const SONY_KEY: u32 = 0x1d2e_3f40;
+
+/// Linear tone curve, maps 11 bit input to 12 bit output
+pub(crate) const LINEAR_CURVE: [u16; 4] = [0x3ffc; 4];
+
+/// Black level stored in the SR2 block
+pub(crate) const ARW_BLACKLEVEL: u16 = 512;
+
+/// Encode a Sony ARW2 file (compression 32767, 8 bits per pixel)
+///
+/// Pixels must be 11 bit values and the width a multiple of 32.
+/// The file is tagged as ILCE-7M3.
+pub(crate) fn encode_arw2(image: &PixU16) -> Result<Vec<u8>> {
+  encode_arw2_shot(image, None)
+}
+
+/// Encode a Sony ARW2 file as one shot of a pixel shift capture
+///
+/// `shot` is the group id, shot number (starting with 1) and number of
+/// shots, stored as PixelShiftInfo in the makernote.
+pub(crate) fn encode_arw2_shot(image: &PixU16, shot: Option<(u32, u8, u8)>) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(32) {
+    return Err("ARW2 width must be a multiple of 32".into());
+  }
+  let mut data = Vec::with_capacity(image.width * image.height);
+  for row in image.pixels().chunks_exact(image.width) {
+    data.extend_from_slice(&encode_arw2_row(row)?);
+  }
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "SONY", "ILCE-7M3");
+
+    // SR2 block with levels and white balance, encrypted
+    let mut sr2 = IfdBlob::new();
+    sr2.add(SR2SubIFD::SonyRGGB, [2048_u16, 1024, 1024, 1536]);
+    sr2.add(SR2SubIFD::BlackLevel2, [ARW_BLACKLEVEL; 4]);
+    sr2.add(SR2SubIFD::WhiteLevel, [16300_u16; 3]);
+    tiff.pad_word_boundary()?;
+    let sr2_offset = tiff.position()?;
+    let mut sr2 = sr2.build(sr2_offset)?;
+    sr2.resize(sr2.len().next_multiple_of(4), 0);
+    let sr2 = ArwDecoder::sony_decrypt(&sr2, 0, sr2.len(), SONY_KEY)?;
+    tiff.write_data(&sr2)?;
+
+    let mut private = IfdBlob::new();
+    private.add(TiffCommonTag::SonyOffset, sr2_offset);
+    private.add(TiffCommonTag::SonyLength, sr2.len() as u32);
+    private.add_undefined(TiffCommonTag::SonyKey, SONY_KEY.to_le_bytes().to_vec());
+    tiff.pad_word_boundary()?;
+    let private_offset = tiff.position()?;
+    tiff.write_data(&private.build(private_offset)?)?;
+    root.add_tag_undefined(TiffCommonTag::DNGPrivateArea, private_offset.to_le_bytes().to_vec());
+
+    add_makernote(tiff, &mut root, |offset| {
+      let mut makernote = b"SONY DSC \0\0\0".to_vec();
+      let mut ifd = IfdBlob::new();
+      if let Some((group, shot, shots)) = shot {
+        let mut info = group.to_le_bytes().to_vec();
+        info.extend_from_slice(&[shot, shots]);
+        ifd.add_undefined(ArwMakernoteTag::PixelShiftInfo, info);
+      }
+      makernote.extend_from_slice(&ifd.build(offset + makernote.len() as u32)?);
+      Ok(makernote)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, 8_u16);
+    root.add_tag(TiffCommonTag::Compression, 32767_u16);
+    root.add_tag(TiffCommonTag::SonyCurve, LINEAR_CURVE);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    Ok(root)
+  })
+}
+
+/// Encode a single row, each block of 32 pixels is stored as
+/// two interleaved groups of 16 pixels in 32 bytes.
+fn encode_arw2_row(row: &[u16]) -> Result<Vec<u8>> {
+  let mut writer = BitWriterLSB::new();
+  for block in row.chunks_exact(32) {
+    for j in 0..2 {
+      let values: Vec<u32> = (0..16).map(|i| block[j + i * 2] as u32).collect();
+      if values.iter().any(|v| *v > 0x7ff) {
+        return Err("ARW2 pixel values are limited to 11 bits".into());
+      }
+      let max = values.iter().copied().max().unwrap_or_default();
+      let min = values.iter().copied().min().unwrap_or_default();
+      let imax = values.iter().position(|v| *v == max).unwrap_or_default();
+      let imin = values
+        .iter()
+        .position(|v| *v == min)
+        .filter(|i| *i != imax)
+        .unwrap_or(if imax == 0 { 1 } else { 0 });
+      let delta = max - min;
+      let shift = (32 - delta.leading_zeros() as i32 - 7).max(0) as u32;
+      writer.put(max, 11);
+      writer.put(min, 11);
+      writer.put(imax as u32, 4);
+      writer.put(imin as u32, 4);
+      for (i, value) in values.iter().enumerate() {
+        if i == imax || i == imin {
+          continue;
+        }
+        let step = (value - min) >> shift;
+        if (step << shift) + min != *value {
+          return Err(format!("ARW2 value {} is not representable with shift {}", value, shift).into());
+        }
+        writer.put(step, 7);
+      }
+    }
+  }
+  Ok(writer.finish())
+}
+
+/// Expected decoder output for ARW2 data
+///
+/// The decoder dithers each pixel while applying the tone curve. The
+/// random state is seeded by the first 16 bits of each row, so the
+/// output is deterministic and can be reproduced.
+pub(crate) fn expected_arw2(image: &PixU16, encoded_rows: &[u8]) -> PixU16 {
+  let curve_points = LINEAR_CURVE.map(|v| ((v >> 2) & 0xfff) as usize);
+  let curve: LookupTable = ArwDecoder::calculate_curve([0, curve_points[0], curve_points[1], curve_points[2], curve_points[3], 4095]);
+  let mut out = PixU16::new_with(vec![0; image.width * image.height], image.width, image.height);
+  for row in 0..image.height {
+    let src = &encoded_rows[row * image.width..];
+    let mut random = u16::from_le_bytes([src[0], src[1]]) as u32;
+    for block in (0..image.width).step_by(32) {
+      for j in 0..2 {
+        for i in 0..16 {
+          let idx = row * image.width + block + j + i * 2;
+          out.pixels_mut()[idx] = curve.dither(image.pixels()[idx] << 1, &mut random);
+        }
+      }
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::decoders::{PixelShiftShot, RawDecodeParams};
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+  use crate::rawsource::RawSource;
+
+  #[test]
+  fn arw2_roundtrip() -> Result<()> {
+    let pattern = test_pattern(128, 64, 11);
+    let file = encode_arw2(&pattern)?;
+    let rows: Vec<u8> = pattern
+      .pixels()
+      .chunks_exact(pattern.width)
+      .map(encode_arw2_row)
+      .collect::<Result<Vec<_>>>()?
+      .concat();
+    let image = decode(file)?;
+    assert_pixels(&image, &expected_arw2(&pattern, &rows));
+    assert_eq!(image.wb_coeffs[0], 2.0);
+    Ok(())
+  }
+
+  #[test]
+  fn arw2_pixel_shift_shot() -> Result<()> {
+    let pattern = test_pattern(32, 2, 11);
+    let file = encode_arw2_shot(&pattern, Some((0x1234_5678, 3, 4)))?;
+    let source = RawSource::new_from_slice(&file);
+    let decoder = crate::get_decoder(&source)?;
+    let shot = decoder.pixel_shift_shot(&source, &RawDecodeParams::default())?;
+    assert_eq!(
+      shot,
+      Some(PixelShiftShot {
+        shot: 2,
+        shots: 4,
+        group: Some(0x1234_5678)
+      })
+    );
+
+    let file = encode_arw2(&pattern)?;
+    let source = RawSource::new_from_slice(&file);
+    let decoder = crate::get_decoder(&source)?;
+    assert_eq!(decoder.pixel_shift_shot(&source, &RawDecodeParams::default())?, None);
+    Ok(())
+  }
+
+  #[test]
+  fn arw2_rejects_unrepresentable() {
+    let mut pattern = test_pattern(32, 2, 11);
+    pattern.pixels_mut()[2] = 2000;
+    pattern.pixels_mut()[4] = 1001;
+    assert!(encode_arw2(&pattern).is_err());
+  }
+}
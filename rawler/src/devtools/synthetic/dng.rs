@@ -0,0 +1,441 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use std::io::Cursor;
+
+use super::{SyntheticTiff, add_camera, add_makernote, build_std_tiff, pack_msb};
+use crate::Result;
+use crate::dng::dcp::DCP_MAGIC;
+use crate::formats::tiff::writer::{DirectoryWriter, TiffWriter};
+use crate::formats::tiff::{Rational, SRational};
+use crate::ljpeg92::LjpegCompressor;
+use crate::pixarray::PixU16;
+use crate::tags::{DngTag, ExifGpsTag, ExifTag, TiffCommonTag};
+
+/// Root IFD with all tags required for a CFA DNG
+fn dng_root(tiff: &SyntheticTiff, image: &PixU16, bits: u32, compression: u16) -> DirectoryWriter {
+  let mut root = tiff.new_directory();
+  add_camera(&mut root, "Synthetic", "Test Camera");
+  root.add_tag(DngTag::DNGVersion, [1_u8, 4, 0, 0]);
+  root.add_tag(DngTag::UniqueCameraModel, "Synthetic Test Camera");
+  root.add_tag(DngTag::AsShotNeutral, [0.5_f32, 1.0, 0.7]);
+  add_cfa_tags(&mut root, image, bits, compression, 0);
+  root
+}
+
+/// Tags of a single CFA raw image
+fn add_cfa_tags(ifd: &mut DirectoryWriter, image: &PixU16, bits: u32, compression: u16, subfile_type: u32) {
+  ifd.add_tag(TiffCommonTag::NewSubFileType, subfile_type);
+  ifd.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+  ifd.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+  ifd.add_tag(TiffCommonTag::BitsPerSample, bits as u16);
+  ifd.add_tag(TiffCommonTag::SamplesPerPixel, 1_u16);
+  ifd.add_tag(TiffCommonTag::Compression, compression);
+  ifd.add_tag(TiffCommonTag::PhotometricInt, 32803_u16);
+  ifd.add_tag(TiffCommonTag::CFARepeatPatternDim, [2_u16, 2]);
+  ifd.add_tag(TiffCommonTag::CFAPattern, [0_u8, 1, 1, 2]);
+  ifd.add_tag(TiffCommonTag::WhiteLevel, ((1_u32 << bits) - 1) as u16);
+}
+
+/// Write uncompressed 16 bit samples as a single strip
+fn add_strip_16(tiff: &mut SyntheticTiff, ifd: &mut DirectoryWriter, image: &PixU16) -> Result<()> {
+  let data: Vec<u8> = image.pixels().iter().flat_map(|p| p.to_le_bytes()).collect();
+  let offset = tiff.write_data(&data)?;
+  ifd.add_tag(TiffCommonTag::StripOffsets, offset);
+  ifd.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+  ifd.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+  Ok(())
+}
+
+/// Encode an uncompressed DNG with samples packed to `bits`
+pub(crate) fn encode_packed(image: &PixU16, bits: u32) -> Result<Vec<u8>> {
+  build_std_tiff(|tiff| {
+    let data = if bits == 16 {
+      image.pixels().iter().flat_map(|p| p.to_le_bytes()).collect()
+    } else {
+      pack_msb(image.pixels(), bits)
+    };
+    let offset = tiff.write_data(&data)?;
+    let mut root = dng_root(tiff, image, bits, 1);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    root.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+    Ok(root)
+  })
+}
+
+/// Encode an uncompressed 16 bit DNG with additional images in SubIFDs
+///
+/// Each SubIFD is a CFA image with the given NewSubFileType. Besides
+/// these, a RGB preview is added which must not be taken as raw image.
+pub(crate) fn encode_multi(image: &PixU16, subs: &[(&PixU16, u32)]) -> Result<Vec<u8>> {
+  build_std_tiff(|tiff| {
+    let mut offsets = Vec::new();
+    for (sub, subfile_type) in subs {
+      let mut ifd = tiff.new_directory();
+      add_cfa_tags(&mut ifd, sub, 16, 1, *subfile_type);
+      add_strip_16(tiff, &mut ifd, sub)?;
+      offsets.push(ifd.build(tiff)?);
+    }
+    let mut preview = tiff.new_directory();
+    preview.add_tag(TiffCommonTag::NewSubFileType, 1_u32);
+    preview.add_tag(TiffCommonTag::ImageWidth, 4_u32);
+    preview.add_tag(TiffCommonTag::ImageLength, 4_u32);
+    preview.add_tag(TiffCommonTag::BitsPerSample, [8_u16, 8, 8]);
+    preview.add_tag(TiffCommonTag::SamplesPerPixel, 3_u16);
+    preview.add_tag(TiffCommonTag::Compression, 1_u16);
+    preview.add_tag(TiffCommonTag::PhotometricInt, 2_u16);
+    let offset = tiff.write_data(&[0x80; 4 * 4 * 3])?;
+    preview.add_tag(TiffCommonTag::StripOffsets, offset);
+    preview.add_tag(TiffCommonTag::StripByteCounts, 4 * 4 * 3_u32);
+    preview.add_tag(TiffCommonTag::RowsPerStrip, 4_u32);
+    offsets.push(preview.build(tiff)?);
+
+    let mut root = dng_root(tiff, image, 16, 1);
+    add_strip_16(tiff, &mut root, image)?;
+    root.add_tag(TiffCommonTag::SubIFDs, &offsets);
+    Ok(root)
+  })
+}
+
+/// Auxiliary image for [`encode_auxiliary`]
+pub(crate) struct SyntheticAux<'a> {
+  pub subfile_type: u32,
+  pub photometric: u16,
+  pub semantic_name: Option<&'a str>,
+  pub bits: u32,
+  pub width: usize,
+  pub height: usize,
+  pub data: Vec<u16>,
+}
+
+/// Encode an uncompressed 16 bit DNG with depth map and masks in SubIFDs,
+/// like Apple ProRAW files.
+pub(crate) fn encode_auxiliary(image: &PixU16, auxs: &[SyntheticAux], gain_table_map: Option<&[u8]>) -> Result<Vec<u8>> {
+  build_std_tiff(|tiff| {
+    let mut offsets = Vec::new();
+    for aux in auxs {
+      let data: Vec<u8> = if aux.bits == 8 {
+        aux.data.iter().map(|v| *v as u8).collect()
+      } else {
+        aux.data.iter().flat_map(|v| v.to_le_bytes()).collect()
+      };
+      let offset = tiff.write_data(&data)?;
+      let mut ifd = tiff.new_directory();
+      ifd.add_tag(TiffCommonTag::NewSubFileType, aux.subfile_type);
+      ifd.add_tag(TiffCommonTag::ImageWidth, aux.width as u32);
+      ifd.add_tag(TiffCommonTag::ImageLength, aux.height as u32);
+      ifd.add_tag(TiffCommonTag::BitsPerSample, aux.bits as u16);
+      ifd.add_tag(TiffCommonTag::SamplesPerPixel, 1_u16);
+      ifd.add_tag(TiffCommonTag::Compression, 1_u16);
+      ifd.add_tag(TiffCommonTag::PhotometricInt, aux.photometric);
+      if let Some(name) = aux.semantic_name {
+        ifd.add_tag(DngTag::SemanticName, name);
+      }
+      ifd.add_tag(TiffCommonTag::StripOffsets, offset);
+      ifd.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+      ifd.add_tag(TiffCommonTag::RowsPerStrip, aux.height as u32);
+      offsets.push(ifd.build(tiff)?);
+    }
+
+    let mut root = dng_root(tiff, image, 16, 1);
+    add_strip_16(tiff, &mut root, image)?;
+    root.add_tag(DngTag::DepthFormat, 1_u16);
+    root.add_tag(DngTag::DepthNear, Rational::new(1, 2));
+    root.add_tag(DngTag::DepthFar, Rational::new(5, 1));
+    root.add_tag(DngTag::DepthUnits, 1_u16);
+    root.add_tag(DngTag::DepthMeasureType, 1_u16);
+    if let Some(map) = gain_table_map {
+      root.add_tag_undefined(DngTag::ProfileGainTableMap, map.to_vec());
+    }
+    root.add_tag(TiffCommonTag::SubIFDs, &offsets);
+    Ok(root)
+  })
+}
+
+/// Encode an uncompressed 16 bit DNG with tags a converter must preserve
+///
+/// Contains a camera profile, an extra camera profile, opcodes, private
+/// data, a makernote, GPS data and an embedded original raw file.
+pub(crate) fn encode_annotated(image: &PixU16) -> Result<Vec<u8>> {
+  build_std_tiff(|tiff| {
+    let mut root = dng_root(tiff, image, 16, 1);
+    add_strip_16(tiff, &mut root, image)?;
+    add_makernote(tiff, &mut root, |_| Ok(b"SYNTHETIC MAKERNOTE".to_vec()))?;
+
+    let mut gps = tiff.new_directory();
+    gps.add_tag(ExifGpsTag::GPSVersionID, [2_u8, 3, 0, 0]);
+    gps.add_tag(ExifGpsTag::GPSLatitudeRef, "N");
+    root.add_tag(ExifTag::GPSInfo, gps.build(tiff)?);
+
+    let mut profile = Cursor::new(Vec::new());
+    let dcp = TiffWriter::new_with_magic(&mut profile, DCP_MAGIC)?;
+    let mut ifd = dcp.new_directory();
+    ifd.add_tag(DngTag::ProfileName, "Extra Profile");
+    ifd.add_tag(DngTag::CalibrationIlluminant1, 17_u16);
+    ifd.add_tag(DngTag::ColorMatrix1, [SRational::new(1, 1); 9]);
+    dcp.build(ifd)?;
+    root.add_tag(DngTag::ExtraCameraProfiles, [tiff.write_data(profile.get_ref())?]);
+
+    root.add_tag(DngTag::DNGBackwardVersion, [1_u8, 5, 0, 0]);
+    root.add_tag(DngTag::ProfileName, "Synthetic Profile");
+    root.add_tag(DngTag::CalibrationIlluminant1, 21_u16);
+    root.add_tag(
+      DngTag::ColorMatrix1,
+      [SRational::new(7, 10), SRational::new(-2, 10), SRational::new(1, 10)].repeat(3).as_slice(),
+    );
+    root.add_tag(DngTag::MakerNoteSafety, 1_u16);
+    root.add_tag_undefined(DngTag::DNGPrivateData, b"Synthetic\0private data".to_vec());
+    root.add_tag(DngTag::OriginalRawFileName, "ORIGINAL.RAW");
+    root.add_tag_undefined(DngTag::OriginalRawFileData, vec![0xAB; 64]);
+    root.add_tag(DngTag::DefaultScale, [Rational::new(2, 1), Rational::new(1, 1)]);
+    root.add_tag(DngTag::RawImageDigest, [0x11_u8; 16]);
+    root.add_tag(DngTag::NewRawImageDigest, [0x22_u8; 16]);
+    // Left over from an old preview
+    root.add_tag(DngTag::PreviewColorSpace, 3_u32);
+    root.add_tag(DngTag::PreviewSettingsName, "Old Settings");
+    // Empty opcode list with a dummy header
+    root.add_tag_undefined(DngTag::OpcodeList2, vec![0, 0, 0, 0, 0, 0, 0, 0]);
+    Ok(root)
+  })
+}
+
+/// Encode a LZW compressed DNG with horizontal differencing (predictor 2)
+///
+/// Samples other than 16 bit are packed MSB-first, each row starting on
+/// a byte boundary.
+pub(crate) fn encode_lzw(image: &PixU16, bits: u32) -> Result<Vec<u8>> {
+  build_std_tiff(|tiff| {
+    let mask = ((1_u32 << bits) - 1) as u16;
+    let mut data = Vec::new();
+    for row in image.pixels().chunks_exact(image.width) {
+      let diffs: Vec<u16> = (0..row.len())
+        .map(|col| if col > 0 { row[col].wrapping_sub(row[col - 1]) & mask } else { row[col] })
+        .collect();
+      if bits == 16 {
+        data.extend(diffs.iter().flat_map(|p| p.to_le_bytes()));
+      } else {
+        data.extend(pack_msb(&diffs, bits));
+      }
+    }
+    let lzw = weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+      .encode(&data)
+      .map_err(|err| format!("LZW encoding failed: {}", err))?;
+    let offset = tiff.write_data(&lzw)?;
+    let mut root = dng_root(tiff, image, bits, 5);
+    root.add_tag(TiffCommonTag::Predictor, 2_u16);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, lzw.len() as u32);
+    root.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+    Ok(root)
+  })
+}
+
+/// Encode a LJPEG-92 compressed DNG with tiles of the given size
+///
+/// The image dimension must be a multiple of the tile size.
+pub(crate) fn encode_ljpeg(image: &PixU16, bits: u32, tile_width: usize, tile_height: usize) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(tile_width) || !image.height.is_multiple_of(tile_height) {
+    return Err("Image dimension must be a multiple of the tile size".into());
+  }
+  build_std_tiff(|tiff| {
+    let mut offsets = Vec::new();
+    let mut sizes = Vec::new();
+    for ty in (0..image.height).step_by(tile_height) {
+      for tx in (0..image.width).step_by(tile_width) {
+        let tile: Vec<u16> = (ty..ty + tile_height)
+          .flat_map(|row| image.pixels()[row * image.width + tx..row * image.width + tx + tile_width].iter().copied())
+          .collect();
+        let jpeg = LjpegCompressor::new(&tile, tile_width, tile_height, 1, bits as u8, 1, 0, 0)
+          .and_then(|state| state.encode())
+          .map_err(|err| format!("LJPEG encoding failed: {}", err))?;
+        offsets.push(tiff.write_data(&jpeg)?);
+        sizes.push(jpeg.len() as u32);
+      }
+    }
+    let mut root = dng_root(tiff, image, bits, 7);
+    root.add_tag(TiffCommonTag::TileWidth, tile_width as u32);
+    root.add_tag(TiffCommonTag::TileLength, tile_height as u32);
+    root.add_tag(TiffCommonTag::TileOffsets, &offsets);
+    root.add_tag(TiffCommonTag::TileByteCounts, &sizes);
+    Ok(root)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn dng_packed_roundtrip() -> Result<()> {
+    for bits in [10, 12, 14, 16] {
+      let pattern = test_pattern(128, 96, bits);
+      let image = decode(encode_packed(&pattern, bits)?)?;
+      assert_eq!(image.bps, bits as usize);
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn dng_multi_image_selection() -> Result<()> {
+    use crate::decoders::{RawDecodeParams, RawImageKind};
+    use crate::rawsource::RawSource;
+    use std::sync::Arc;
+
+    let main = test_pattern(64, 48, 16);
+    let reduced = test_pattern(32, 24, 16);
+    let enhanced = test_pattern(64, 48, 14);
+    let buf = encode_multi(&main, &[(&reduced, 1), (&enhanced, 0x10)])?;
+    let source = RawSource::new_from_shared_vec(Arc::new(buf));
+    let decoder = crate::get_decoder(&source)?;
+    assert_eq!(decoder.raw_image_count()?, 3);
+    let kinds = (0..3).map(|i| decoder.raw_image_kind(i)).collect::<Result<Vec<_>>>()?;
+    assert_eq!(kinds, [RawImageKind::Main, RawImageKind::Enhanced, RawImageKind::Reduced]);
+    for (index, expected) in [&main, &enhanced, &reduced].into_iter().enumerate() {
+      let image = decoder.raw_image(&source, &RawDecodeParams { image_index: index }, false)?;
+      assert_pixels(&image, expected);
+    }
+    assert!(decoder.raw_image(&source, &RawDecodeParams { image_index: 3 }, false).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn dng_vc5_is_unsupported() -> Result<()> {
+    let pattern = test_pattern(64, 48, 12);
+    let buf = build_std_tiff(|tiff| {
+      let offset = tiff.write_data(&[0; 64])?;
+      let mut root = dng_root(tiff, &pattern, 12, 9);
+      root.add_tag(TiffCommonTag::StripOffsets, offset);
+      root.add_tag(TiffCommonTag::StripByteCounts, 64_u32);
+      root.add_tag(TiffCommonTag::RowsPerStrip, pattern.height as u32);
+      Ok(root)
+    })?;
+    assert!(matches!(decode(buf), Err(crate::RawlerError::UnsupportedFeature { .. })));
+    Ok(())
+  }
+
+  fn prores_auxiliary() -> Vec<SyntheticAux<'static>> {
+    let mask = |name, seed: u16| SyntheticAux {
+      subfile_type: 4,
+      photometric: 52527,
+      semantic_name: Some(name),
+      bits: 8,
+      width: 16,
+      height: 12,
+      data: (0..16 * 12).map(|i| (i as u16 * seed) % 256).collect(),
+    };
+    vec![
+      mask("com.apple.ProRaw.SkinMatte", 3),
+      mask("com.apple.ProRaw.SkyMatte", 7),
+      SyntheticAux {
+        subfile_type: 8,
+        photometric: 51177,
+        semantic_name: None,
+        bits: 16,
+        width: 8,
+        height: 6,
+        data: (0..8 * 6).map(|i| i as u16 * 1000).collect(),
+      },
+    ]
+  }
+
+  fn gain_table_map() -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&1_u32.to_be_bytes());
+    buf.extend_from_slice(&1_u32.to_be_bytes());
+    for v in [1.0_f64, 1.0, 0.0, 0.0] {
+      buf.extend_from_slice(&v.to_be_bytes());
+    }
+    buf.extend_from_slice(&2_u32.to_be_bytes());
+    for v in [0.0_f32, 0.0, 0.0, 0.0, 1.0] {
+      buf.extend_from_slice(&v.to_be_bytes());
+    }
+    for v in [1.5_f32, 2.0] {
+      buf.extend_from_slice(&v.to_be_bytes());
+    }
+    buf
+  }
+
+  fn check_auxiliary(images: &[crate::dng::auxiliary::AuxiliaryImage], expected: &[SyntheticAux]) {
+    use crate::dng::auxiliary::AuxiliaryKind;
+
+    let names: Vec<String> = images.iter().map(|aux| aux.name()).collect();
+    assert_eq!(names, ["mask_skin", "mask_sky", "depth"]);
+    for (aux, synthetic) in images.iter().zip(expected) {
+      assert_eq!((aux.width, aux.height, aux.bits), (synthetic.width, synthetic.height, synthetic.bits));
+      assert_eq!(aux.subfile_type, synthetic.subfile_type);
+      assert_eq!(aux.data, synthetic.data);
+    }
+    assert!(matches!(&images[0].kind, AuxiliaryKind::SemanticMask { name, .. } if name == "com.apple.ProRaw.SkinMatte"));
+    assert_eq!(images[2].kind, AuxiliaryKind::Depth);
+  }
+
+  #[test]
+  fn dng_auxiliary_images() -> Result<()> {
+    use crate::decoders::Decoder;
+    use crate::decoders::dng::DngDecoder;
+    use crate::dng::auxiliary::{DepthFormat, DepthUnits};
+    use crate::formats::tiff::GenericTiffReader;
+    use crate::rawsource::RawSource;
+
+    let main = test_pattern(64, 48, 16);
+    let auxs = prores_auxiliary();
+    let source = RawSource::new_from_slice(&encode_auxiliary(&main, &auxs, Some(&gain_table_map()))?);
+    let tiff = GenericTiffReader::new(&mut source.reader(), 0, 0, None, &[])?;
+    let decoder = DngDecoder::new(&source, tiff, crate::global_loader())?;
+
+    // Masks and depth map are not raw images
+    assert_eq!(decoder.raw_image_count()?, 1);
+    check_auxiliary(&decoder.auxiliary_images(&source)?, &auxs);
+
+    let depth = decoder.depth_info().ok_or("no depth info")?;
+    assert_eq!((depth.format, depth.units), (DepthFormat::Linear, DepthUnits::Meters));
+    assert_eq!((depth.near, depth.far), (Some(0.5), Some(5.0)));
+
+    let map = decoder.profile_gain_table_map()?.ok_or("no gain table map")?;
+    assert_eq!(map.gains_at(0, 0), Some(&[1.5_f32, 2.0][..]));
+    Ok(())
+  }
+
+  #[test]
+  fn dng_auxiliary_images_preserved() -> Result<()> {
+    use crate::dng::convert::{ConvertParams, convert_raw_source};
+    use crate::rawsource::RawSource;
+
+    let main = test_pattern(64, 48, 16);
+    let auxs = prores_auxiliary();
+    let source = RawSource::new_from_slice(&encode_auxiliary(&main, &auxs, None)?);
+    let mut dng = std::io::Cursor::new(Vec::new());
+    convert_raw_source(&source, &mut dng, "IMG_0001.DNG", &ConvertParams::default())?;
+
+    let converted = RawSource::new_from_slice(dng.get_ref());
+    let decoder = crate::get_decoder(&converted)?;
+    assert_eq!(decoder.raw_image_count()?, 1);
+    check_auxiliary(&decoder.auxiliary_images(&converted)?, &auxs);
+    assert_pixels(&decode(dng.into_inner())?, &main);
+    Ok(())
+  }
+
+  #[test]
+  fn dng_ljpeg_roundtrip() -> Result<()> {
+    for bits in [12, 14, 16] {
+      let pattern = test_pattern(128, 96, bits);
+      let image = decode(encode_ljpeg(&pattern, bits, 64, 48)?)?;
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn dng_lzw_roundtrip() -> Result<()> {
+    for bits in [8, 12, 14, 16] {
+      let pattern = test_pattern(128, 96, bits);
+      let image = decode(encode_lzw(&pattern, bits)?)?;
+      assert_eq!(image.bps, bits as usize);
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{add_camera, build_std_tiff};
+use crate::Result;
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Encode an uncompressed Kodak DC120 KDC file
+///
+/// The DC120 stores 848x976 samples of 8 bit, each row is rotated
+/// by a row dependent offset.
+pub(crate) fn encode_dc120(image: &PixU16, compression: u16) -> Result<Vec<u8>> {
+  if image.width != 848 || image.height != 976 {
+    return Err("DC120 image must be 848x976".into());
+  }
+  let width = image.width;
+  let mul: [usize; 4] = [162, 192, 187, 92];
+  let add: [usize; 4] = [0, 636, 424, 212];
+  let mut data = vec![0_u8; width * image.height];
+  for (row, line) in image.pixels().chunks_exact(width).enumerate() {
+    let shift = row * mul[row & 3] + add[row & 3];
+    for (col, p) in line.iter().enumerate() {
+      data[row * width + (col + shift) % width] = u8::try_from(*p).map_err(|_| "DC120 samples must fit into 8 bits")?;
+    }
+  }
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "Eastman Kodak Company", "Kodak DC120 ZOOM Digital Camera");
+    let offset = tiff.write_data(&data)?;
+    let mut raw = tiff.new_directory();
+    raw.add_tag(TiffCommonTag::ImageWidth, width as u32);
+    raw.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    raw.add_tag(TiffCommonTag::Compression, compression);
+    raw.add_tag(TiffCommonTag::CFARepeatPatternDim, [2_u16, 2]);
+    raw.add_tag(TiffCommonTag::CFAPattern, [1_u8, 0, 2, 1]);
+    raw.add_tag(TiffCommonTag::StripOffsets, offset);
+    raw.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    let raw_offset = raw.build(tiff)?;
+    root.add_tag(TiffCommonTag::SubIFDs, [raw_offset]);
+    Ok(root)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn dc120_uncompressed_roundtrip() -> Result<()> {
+    let pattern = test_pattern(848, 976, 8);
+    // Firmware versions differ in the compression tag of uncompressed files
+    for compression in [1, 0] {
+      let image = decode(encode_dc120(&pattern, compression)?)?;
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn dc120_truncated_fails() -> Result<()> {
+    let pattern = test_pattern(848, 976, 8);
+    let mut buf = encode_dc120(&pattern, 1)?;
+    // Cut the file in the middle of the image data
+    buf.truncate(848 * 500);
+    assert!(decode(buf).is_err());
+    Ok(())
+  }
+}
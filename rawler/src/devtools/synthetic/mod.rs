@@ -0,0 +1,345 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Synthetic raw files for offline tests
+//!
+//! The encoders in this module produce minimal files which are just
+//! valid enough to pass the matching decoder. Each encoder takes a known
+//! [`PixU16`] pattern, so decoders can be verified bit-exact without any
+//! sample files from the raw database.
+//!
+//! Encoders are not meant for production use. They only implement the
+//! subset of a format that the decoder needs and return an error for
+//! pixel data that can't be represented exactly.
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use crate::RawImage;
+use crate::Result;
+use crate::decoders::RawDecodeParams;
+use crate::decompressors::ljpeg::huffman::HuffTable;
+use crate::formats::tiff::Value;
+use crate::formats::tiff::writer::{DirectoryWriter, TiffWriter};
+use crate::pixarray::PixU16;
+use crate::rawimage::RawImageData;
+use crate::rawsource::RawSource;
+use crate::tags::{ExifTag, TiffCommonTag, TiffTag};
+
+mod arw;
+pub(crate) mod dng;
+mod kdc;
+mod mos;
+mod mrw;
+mod nef;
+mod orf;
+mod pef;
+mod rw2;
+mod srw;
+
+/// Writer type passed to the TIFF container closures
+pub(crate) type SyntheticTiff<'a> = TiffWriter<&'a mut Cursor<Vec<u8>>>;
+
+/// Generate a smooth test pattern with a small amount of texture.
+///
+/// The pattern has different levels for each CFA position, a gradient
+/// along both axes and a pseudo-random noise of 4 bits. All values are
+/// clamped to `bits` and are never smaller than 16.
+pub(crate) fn test_pattern(width: usize, height: usize, bits: u32) -> PixU16 {
+  let max = (1_u32 << bits) - 1;
+  let mut seed: u32 = 0x2545_f491;
+  let mut data = Vec::with_capacity(width * height);
+  for row in 0..height {
+    for col in 0..width {
+      seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+      let noise = (seed >> 16) & 0xf;
+      let level = 64 + 2 * row as u32 + col as u32 + 40 * (row as u32 & 1) + 20 * (col as u32 & 1);
+      data.push((level + noise).min(max) as u16);
+    }
+  }
+  PixU16::new_with(data, width, height)
+}
+
+/// Build a little-endian TIFF container
+///
+/// The closure writes all data blocks and returns the root IFD.
+pub(crate) fn build_tiff<F>(magic: u16, f: F) -> Result<Vec<u8>>
+where
+  F: FnOnce(&mut SyntheticTiff) -> Result<DirectoryWriter>,
+{
+  let mut buf = Cursor::new(Vec::new());
+  let mut tiff = TiffWriter::new_with_magic(&mut buf, magic)?;
+  let root = f(&mut tiff)?;
+  tiff.build(root)?;
+  Ok(buf.into_inner())
+}
+
+/// Build a standard TIFF container
+pub(crate) fn build_std_tiff<F>(f: F) -> Result<Vec<u8>>
+where
+  F: FnOnce(&mut SyntheticTiff) -> Result<DirectoryWriter>,
+{
+  build_tiff(42, f)
+}
+
+/// Add an EXIF IFD with a makernote to the root IFD
+///
+/// The closure receives the file offset of the makernote data, so
+/// makernotes with absolute offsets can be built.
+pub(crate) fn add_makernote<F>(tiff: &mut SyntheticTiff, root: &mut DirectoryWriter, makernote: F) -> Result<()>
+where
+  F: FnOnce(u32) -> Result<Vec<u8>>,
+{
+  // The makernote is the only value of the EXIF IFD which is
+  // not embedded, so it is written to the next word boundary.
+  tiff.pad_word_boundary()?;
+  let makernote = makernote(tiff.position()?)?;
+  let mut exif = tiff.new_directory();
+  exif.add_tag_undefined(ExifTag::MakerNotes, makernote);
+  let offset = exif.build(tiff)?;
+  root.add_tag(TiffCommonTag::ExifIFDPointer, offset);
+  Ok(())
+}
+
+/// Add Make and Model to the root IFD
+pub(crate) fn add_camera(root: &mut DirectoryWriter, make: &str, model: &str) {
+  root.add_tag(TiffCommonTag::Make, make);
+  root.add_tag(TiffCommonTag::Model, model);
+}
+
+/// Standalone little-endian IFD for structures embedded into
+/// other data blocks, like makernotes.
+///
+/// In contrast to [`DirectoryWriter`], the final position of the
+/// IFD must be known before it is serialized, so offsets for values
+/// which don't fit into the entry itself can be calculated.
+#[derive(Default)]
+pub(crate) struct IfdBlob {
+  entries: BTreeMap<u16, Value>,
+}
+
+impl IfdBlob {
+  pub(crate) fn new() -> Self {
+    Self::default()
+  }
+
+  pub(crate) fn add<T: TiffTag, V: Into<Value>>(&mut self, tag: T, value: V) {
+    self.entries.insert(tag.into(), value.into());
+  }
+
+  pub(crate) fn add_undefined<T: TiffTag>(&mut self, tag: T, data: Vec<u8>) {
+    self.entries.insert(tag.into(), Value::Undefined(data));
+  }
+
+  /// Serialize the IFD, `base` is the offset of the IFD
+  /// as seen by the reader.
+  pub(crate) fn build(&self, base: u32) -> Result<Vec<u8>> {
+    let ifd_len = 2 + self.entries.len() * 12 + 4;
+    let mut ifd = Vec::with_capacity(ifd_len);
+    let mut data = Vec::new();
+    ifd.extend_from_slice(&(self.entries.len() as u16).to_le_bytes());
+    for (tag, value) in &self.entries {
+      ifd.extend_from_slice(&tag.to_le_bytes());
+      ifd.extend_from_slice(&value.value_type().to_le_bytes());
+      ifd.extend_from_slice(&(value.count() as u32).to_le_bytes());
+      if value.byte_size() > 4 {
+        if !data.len().is_multiple_of(2) {
+          data.push(0);
+        }
+        let offset = base + (ifd_len + data.len()) as u32;
+        value.write(&mut data)?;
+        ifd.extend_from_slice(&offset.to_le_bytes());
+      } else {
+        ifd.extend_from_slice(&value.as_embedded()?.to_le_bytes());
+      }
+    }
+    ifd.extend_from_slice(&0_u32.to_le_bytes());
+    ifd.extend_from_slice(&data);
+    Ok(ifd)
+  }
+}
+
+/// Bit writer for MSB-first bitstreams, counterpart of `BitPumpMSB`
+#[derive(Default)]
+pub(crate) struct BitWriterMSB {
+  buf: Vec<u8>,
+  acc: u64,
+  nbits: u32,
+}
+
+impl BitWriterMSB {
+  pub(crate) fn new() -> Self {
+    Self::default()
+  }
+
+  pub(crate) fn put(&mut self, value: u32, nbits: u32) {
+    debug_assert!(nbits <= 32);
+    if nbits == 0 {
+      return;
+    }
+    self.acc = (self.acc << nbits) | (value as u64 & ((1 << nbits) - 1));
+    self.nbits += nbits;
+    while self.nbits >= 8 {
+      self.nbits -= 8;
+      self.buf.push((self.acc >> self.nbits) as u8);
+    }
+    self.acc &= (1 << self.nbits) - 1;
+  }
+
+  /// Flush remaining bits and append zero padding, because
+  /// bit pumps read ahead.
+  pub(crate) fn finish(mut self) -> Vec<u8> {
+    if self.nbits > 0 {
+      self.put(0, 8 - self.nbits);
+    }
+    self.buf.extend_from_slice(&[0; 8]);
+    self.buf
+  }
+}
+
+/// Bit writer for LSB-first bitstreams, counterpart of `BitPumpLSB`
+#[derive(Default)]
+pub(crate) struct BitWriterLSB {
+  buf: Vec<u8>,
+  acc: u64,
+  nbits: u32,
+}
+
+impl BitWriterLSB {
+  pub(crate) fn new() -> Self {
+    Self::default()
+  }
+
+  pub(crate) fn put(&mut self, value: u32, nbits: u32) {
+    debug_assert!(nbits <= 32);
+    self.acc |= (value as u64 & ((1 << nbits) - 1)) << self.nbits;
+    self.nbits += nbits;
+    while self.nbits >= 8 {
+      self.buf.push(self.acc as u8);
+      self.acc >>= 8;
+      self.nbits -= 8;
+    }
+  }
+
+  /// Flush remaining bits without any padding.
+  pub(crate) fn finish(mut self) -> Vec<u8> {
+    if self.nbits > 0 {
+      self.buf.push(self.acc as u8);
+    }
+    self.buf
+  }
+}
+
+/// Huffman encoder for JPEG style difference coding
+///
+/// The codes are derived from the same [`HuffTable`] the decoder
+/// uses, so any table known to a decoder can be used for encoding.
+pub(crate) struct HuffEncoder {
+  /// Code and code length for each SSSS class
+  codes: [Option<(u32, u32)>; 17],
+}
+
+impl HuffEncoder {
+  pub(crate) fn new(table: &HuffTable) -> Self {
+    let mut codes = [None; 17];
+    let mut code = 0_u32;
+    let mut pos = 0;
+    for len in 1..=16 {
+      for _ in 0..table.bits[len] {
+        let ssss = table.huffval[pos] as usize;
+        if ssss < codes.len() {
+          codes[ssss] = Some((code, len as u32));
+        }
+        code += 1;
+        pos += 1;
+      }
+      code <<= 1;
+    }
+    Self { codes }
+  }
+
+  pub(crate) fn encode(&self, writer: &mut BitWriterMSB, diff: i32) -> Result<()> {
+    let ssss = 32 - diff.unsigned_abs().leading_zeros();
+    let (code, len) = self
+      .codes
+      .get(ssss as usize)
+      .copied()
+      .flatten()
+      .ok_or_else(|| format!("Huffman table has no code for difference {}", diff))?;
+    if ssss == 16 {
+      return Err(format!("Difference {} can't be encoded", diff).into());
+    }
+    writer.put(code, len);
+    let bits = if diff < 0 { diff - 1 } else { diff };
+    writer.put(bits as u32, ssss);
+    Ok(())
+  }
+}
+
+/// Pack samples MSB-first with the given bit depth
+pub(crate) fn pack_msb(pixels: &[u16], bits: u32) -> Vec<u8> {
+  let mut writer = BitWriterMSB::new();
+  pixels.iter().for_each(|p| writer.put(*p as u32, bits));
+  let mut buf = writer.finish();
+  buf.truncate((pixels.len() * bits as usize).div_ceil(8));
+  buf
+}
+
+/// Decode a synthetic file with the regular decoder pipeline
+pub(crate) fn decode(buf: Vec<u8>) -> Result<RawImage> {
+  let rawfile = RawSource::new_from_shared_vec(Arc::new(buf));
+  let decoder = crate::get_decoder(&rawfile)?;
+  decoder.raw_image(&rawfile, &RawDecodeParams::default(), false)
+}
+
+/// Extract integer pixels from a decoded image
+pub(crate) fn pixels(image: &RawImage) -> &[u16] {
+  match &image.data {
+    RawImageData::Integer(data) => data,
+    RawImageData::Float(_) => panic!("Expected integer raw data"),
+  }
+}
+
+/// Assert that a decoded image matches the expected pixels
+pub(crate) fn assert_pixels(image: &RawImage, expected: &PixU16) {
+  assert_eq!((image.width, image.height), (expected.width, expected.height), "Dimension mismatch");
+  let actual = pixels(image);
+  if let Some(pos) = actual.iter().zip(expected.pixels()).position(|(a, b)| a != b) {
+    panic!(
+      "Pixel mismatch at row {}, col {}: decoded {}, expected {}",
+      pos / expected.width,
+      pos % expected.width,
+      actual[pos],
+      expected.pixels()[pos]
+    );
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn bitwriters_match_pumps() -> Result<()> {
+    use crate::pumps::{BitPump, BitPumpLSB, BitPumpMSB};
+
+    let values = [(5_u32, 3_u32), (0x3ff, 10), (1, 1), (0x1234, 16), (0, 7), (0x7f, 7)];
+    let mut msb = BitWriterMSB::new();
+    let mut lsb = BitWriterLSB::new();
+    for (value, bits) in values {
+      msb.put(value, bits);
+      lsb.put(value, bits);
+    }
+    let msb = msb.finish();
+    let mut lsb = lsb.finish();
+    lsb.extend_from_slice(&[0; 8]);
+    let mut msb_pump = BitPumpMSB::new(&msb);
+    let mut lsb_pump = BitPumpLSB::new(&lsb);
+    for (value, bits) in values {
+      assert_eq!(msb_pump.get_bits(bits), value);
+      assert_eq!(lsb_pump.get_bits(bits), value);
+    }
+    Ok(())
+  }
+}
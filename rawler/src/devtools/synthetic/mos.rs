@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{build_std_tiff, pack_msb};
+use crate::Result;
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Encode an uncompressed Leaf MOS file
+///
+/// Leaf backs don't write Make and Model, the camera is identified by
+/// the XMP packet and the Software tag. Samples with less than 16 bits
+/// are packed MSB first, if `packed` is set. Data is written into a
+/// single tile for `tiled`, otherwise into a strip.
+pub(crate) fn encode_mos(image: &PixU16, bits: u32, packed: bool, tiled: bool) -> Result<Vec<u8>> {
+  let data = if packed {
+    pack_msb(image.pixels(), bits)
+  } else {
+    image.pixels().iter().flat_map(|p| p.to_le_bytes()).collect()
+  };
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    root.add_tag(TiffCommonTag::Software, "Camera Library");
+    let xmp = "<x:xmpmeta><tiff:Make>Leaf</tiff:Make><tiff:Model>Leaf Aptus 22(LF22)</tiff:Model></x:xmpmeta>";
+    root.add_tag_undefined(TiffCommonTag::Xmp, xmp.as_bytes().to_vec());
+    let mut meta = vec![0_u8; 16];
+    meta.extend_from_slice(b"NeutObj_neutrals");
+    meta.resize(meta.len() + 28, 0);
+    meta.extend_from_slice(b"1024\n512\n1024\n768\n\0");
+    meta.resize(meta.len() + 32, 0);
+    root.add_tag_undefined(TiffCommonTag::LeafMetadata, meta);
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::NewSubFileType, 0_u32);
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, bits as u16);
+    root.add_tag(TiffCommonTag::Compression, 1_u16);
+    if tiled {
+      root.add_tag(TiffCommonTag::TileWidth, image.width as u32);
+      root.add_tag(TiffCommonTag::TileLength, image.height as u32);
+      root.add_tag(TiffCommonTag::TileOffsets, offset);
+      root.add_tag(TiffCommonTag::TileByteCounts, data.len() as u32);
+    } else {
+      root.add_tag(TiffCommonTag::StripOffsets, offset);
+      root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+      root.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+    }
+    Ok(root)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn mos_uncompressed_layouts() -> Result<()> {
+    let cases = [(16, false, true), (16, false, false), (14, false, false), (12, true, false), (14, true, true)];
+    for (bits, packed, tiled) in cases {
+      let pattern = test_pattern(64, 48, bits);
+      let image = decode(encode_mos(&pattern, bits, packed, tiled)?)?;
+      assert_pixels(&image, &pattern);
+      assert_eq!(image.wb_coeffs[0], 2.0, "{} bits, packed: {}, tiled: {}", bits, packed, tiled);
+    }
+    Ok(())
+  }
+}
@@ -0,0 +1,95 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::pack_msb;
+use crate::Result;
+use crate::pixarray::PixU16;
+
+/// Encode a Minolta DiMAGE 7 MRW file with 12 bit samples
+///
+/// The file has a PRD, WBG and TTW block in front of the raw data.
+/// Samples are either packed or stored as big-endian 16 bit words.
+pub(crate) fn encode_mrw(image: &PixU16, packed: bool) -> Result<Vec<u8>> {
+  if image.pixels().iter().any(|p| *p > 0x0fff) {
+    return Err("MRW samples must fit into 12 bits".into());
+  }
+  let data = if packed {
+    pack_msb(image.pixels(), 12)
+  } else {
+    image.pixels().iter().flat_map(|p| p.to_be_bytes()).collect()
+  };
+
+  let mut prd = vec![0_u8; 24];
+  prd[8..10].copy_from_slice(&(image.height as u16).to_be_bytes());
+  prd[10..12].copy_from_slice(&(image.width as u16).to_be_bytes());
+  prd[12..14].copy_from_slice(&(image.height as u16).to_be_bytes());
+  prd[14..16].copy_from_slice(&(image.width as u16).to_be_bytes());
+  prd[16] = if packed { 12 } else { 16 };
+  prd[17] = 12;
+
+  let mut wbg = vec![0_u8; 4];
+  [512_u16, 256, 256, 384].iter().for_each(|wb| wbg.extend_from_slice(&wb.to_be_bytes()));
+
+  let mut blocks = Vec::new();
+  for (tag, block) in [(b"\0PRD", prd), (b"\0WBG", wbg), (b"\0TTW", camera_tiff("Minolta Co., Ltd.", "DiMAGE 7"))] {
+    blocks.extend_from_slice(tag);
+    blocks.extend_from_slice(&(block.len() as u32).to_be_bytes());
+    blocks.extend_from_slice(&block);
+  }
+
+  let mut buf = Vec::with_capacity(8 + blocks.len() + data.len());
+  buf.extend_from_slice(b"\0MRM");
+  buf.extend_from_slice(&(blocks.len() as u32).to_be_bytes());
+  buf.extend_from_slice(&blocks);
+  buf.extend_from_slice(&data);
+  Ok(buf)
+}
+
+/// Big-endian TIFF with Make and Model only, as stored in the TTW block
+fn camera_tiff(make: &str, model: &str) -> Vec<u8> {
+  let values: Vec<Vec<u8>> = [make, model].iter().map(|s| s.bytes().chain([0]).collect()).collect();
+  let mut tiff = b"MM\0\x2a\0\0\0\x08".to_vec();
+  tiff.extend_from_slice(&(values.len() as u16).to_be_bytes());
+  let mut offset = 8 + 2 + values.len() * 12 + 4;
+  for (tag, value) in [0x010f_u16, 0x0110].iter().zip(values.iter()) {
+    tiff.extend_from_slice(&tag.to_be_bytes());
+    tiff.extend_from_slice(&2_u16.to_be_bytes());
+    tiff.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    tiff.extend_from_slice(&(offset as u32).to_be_bytes());
+    offset += value.len();
+  }
+  tiff.extend_from_slice(&[0; 4]);
+  values.iter().for_each(|value| tiff.extend_from_slice(value));
+  tiff
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+  use crate::imgop::Dim2;
+  use crate::probe::{DEFAULT_PROBE_BUDGET, ProbeParams, probe};
+  use crate::rawsource::RawSource;
+
+  #[test]
+  fn mrw_roundtrip() -> Result<()> {
+    let pattern = test_pattern(64, 48, 12);
+    for packed in [false, true] {
+      let image = decode(encode_mrw(&pattern, packed)?)?;
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn mrw_probe_reads_only_blocks() -> Result<()> {
+    let data = encode_mrw(&test_pattern(1024, 768, 12), false)?;
+    assert!(data.len() as u64 > DEFAULT_PROBE_BUDGET);
+    let result = probe(&RawSource::new_from_slice(&data), &ProbeParams::default())?;
+    assert_eq!(result.dim, Some(Dim2::new(1024, 768)));
+    assert_eq!(result.model, "DiMAGE 7");
+    assert!(result.mode.is_some());
+    assert!(result.bytes_read < data.len() as u64 / 4);
+    Ok(())
+  }
+}
@@ -0,0 +1,306 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterMSB, HuffEncoder, IfdBlob, add_camera, add_makernote, build_std_tiff};
+use crate::Result;
+use crate::bits::LookupTable;
+use crate::decoders::nef::{NefDecoder, NikonMakernote};
+use crate::formats::tiff::{Rational, Value};
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Encode a Nikon NEF file with lossless Huffman compression (34713)
+///
+/// Pixels must be 12 bit values. The makernote contains a version 70
+/// metadata block without a linearization curve. The file is tagged
+/// as D750.
+pub(crate) fn encode_nef(image: &PixU16) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(2) {
+    return Err("NEF width must be even".into());
+  }
+  let data = encode_nef_data(image)?;
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "NIKON CORPORATION", "NIKON D750");
+    add_makernote(tiff, &mut root, |_| {
+      // Version 70 selects the lossless 12 bit table, all initial
+      // predictors are zero and the curve is empty.
+      let mut meta = vec![70_u8, 0x30];
+      meta.extend_from_slice(&[0; 4 * 2 + 2]);
+      let mut makernote = IfdBlob::new();
+      makernote.add_undefined(NikonMakernote::NefMeta2, meta);
+      build_makernote(&makernote)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, 12_u16);
+    root.add_tag(TiffCommonTag::Compression, 34713_u16);
+    root.add_tag(TiffCommonTag::CFAPattern, [0_u8, 1, 1, 2]);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    root.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+    Ok(root)
+  })
+}
+
+/// Encode a Nikon NEF file with uncompressed, packed 12 bit data
+///
+/// This is the reduced 12 bit mode (NEF compression 6) with
+/// little-endian packing and no row padding.
+pub(crate) fn encode_nef_uncompressed12(image: &PixU16) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(2) {
+    return Err("NEF width must be even".into());
+  }
+  if image.pixels().iter().any(|p| *p > 0xfff) {
+    return Err("NEF pixel values are limited to 12 bits".into());
+  }
+  let data: Vec<u8> = image
+    .pixels()
+    .chunks_exact(2)
+    .flat_map(|p| [p[0] as u8, ((p[0] >> 8) as u8 & 0x0f) | ((p[1] as u8 & 0x0f) << 4), (p[1] >> 4) as u8])
+    .collect();
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "NIKON CORPORATION", "NIKON D750");
+    add_makernote(tiff, &mut root, |_| {
+      let mut makernote = IfdBlob::new();
+      makernote.add(NikonMakernote::NefCompression, 6_u16);
+      build_makernote(&makernote)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, 12_u16);
+    root.add_tag(TiffCommonTag::Compression, 1_u16);
+    root.add_tag(TiffCommonTag::CFAPattern, [0_u8, 1, 1, 2]);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    root.add_tag(TiffCommonTag::RowsPerStrip, image.height as u32);
+    Ok(root)
+  })
+}
+
+/// A pair of pixels in a sNEF file: two luma values sharing
+/// one set of chroma values, all 12 bit.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SnefPair {
+  pub(crate) y1: u16,
+  pub(crate) y2: u16,
+  pub(crate) cb: u16,
+  pub(crate) cr: u16,
+}
+
+/// Encode a Nikon small raw (sNEF) file with YCbCr data
+///
+/// `pairs` contains `width / 2` pairs for each row. The as shot
+/// whitebalance is stored as `NefWB0` in the makernote.
+pub(crate) fn encode_snef(pairs: &[SnefPair], width: usize, height: usize, wb: (f32, f32)) -> Result<Vec<u8>> {
+  if !width.is_multiple_of(2) || pairs.len() != width / 2 * height {
+    return Err("sNEF pairs don't match dimension".into());
+  }
+  let data: Vec<u8> = pairs
+    .iter()
+    .flat_map(|p| {
+      let bits = p.y1 as u64 | (p.y2 as u64) << 12 | (p.cb as u64) << 24 | (p.cr as u64) << 36;
+      bits.to_le_bytes()[0..6].to_vec()
+    })
+    .collect();
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "NIKON CORPORATION", "NIKON D750");
+    add_makernote(tiff, &mut root, |_| {
+      let mut makernote = IfdBlob::new();
+      makernote.add(NikonMakernote::NefCompression, 8_u16);
+      makernote.add(
+        TiffCommonTag::NefWB0,
+        Value::Rational(vec![Rational::new((wb.0 * 1000.0) as u32, 1000), Rational::new((wb.1 * 1000.0) as u32, 1000)]),
+      );
+      build_makernote(&makernote)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::NewSubFileType, 0_u32);
+    root.add_tag(TiffCommonTag::ImageWidth, width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, [8_u16, 8, 8]);
+    root.add_tag(TiffCommonTag::Compression, 1_u16);
+    root.add_tag(TiffCommonTag::PhotometricInt, 6_u16);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    root.add_tag(TiffCommonTag::RowsPerStrip, height as u32);
+    Ok(root)
+  })
+}
+
+/// Wrap a makernote IFD into the Nikon type 2 container
+fn build_makernote(makernote: &IfdBlob) -> Result<Vec<u8>> {
+  // Offsets are relative to the embedded TIFF header
+  let mut data = b"Nikon\0\x02\x10\0\0II\x2a\0".to_vec();
+  data.extend_from_slice(&8_u32.to_le_bytes());
+  data.extend_from_slice(&makernote.build(8)?);
+  Ok(data)
+}
+
+/// Huffman encoded bitstream, the counterpart of [`NefDecoder::do_decode`]
+fn encode_nef_data(image: &PixU16) -> Result<Vec<u8>> {
+  if image.pixels().iter().any(|p| *p > 0xfff) {
+    return Err("NEF pixel values are limited to 12 bits".into());
+  }
+  let encoder = HuffEncoder::new(&NefDecoder::create_hufftable(2)?);
+  let mut writer = BitWriterMSB::new();
+  let mut pred_up: [[i32; 2]; 2] = [[0; 2]; 2];
+  for (row, line) in image.pixels().chunks_exact(image.width).enumerate() {
+    for (c, pred) in pred_up[row & 1].iter_mut().enumerate() {
+      encoder.encode(&mut writer, line[c] as i32 - *pred)?;
+      *pred = line[c] as i32;
+    }
+    for col in 2..image.width {
+      encoder.encode(&mut writer, line[col] as i32 - line[col - 2] as i32)?;
+    }
+  }
+  Ok(writer.finish())
+}
+
+/// Expected decoder output for NEF data
+///
+/// The decoder dithers each pixel while applying the (identity) curve.
+/// The random state is seeded by the first 24 bits of the bitstream.
+pub(crate) fn expected_nef(image: &PixU16, data: &[u8]) -> PixU16 {
+  let points: Vec<u16> = (0..1 << 12).collect();
+  let curve = LookupTable::new(&points);
+  let mut random = u32::from_be_bytes([0, data[0], data[1], data[2]]);
+  let pixels = image.pixels().iter().map(|p| curve.dither(*p, &mut random)).collect();
+  PixU16::new_with(pixels, image.width, image.height)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, pixels, test_pattern};
+  use crate::rawimage::RawPhotometricInterpretation;
+
+  #[test]
+  fn nef_lossless_roundtrip() -> Result<()> {
+    let pattern = test_pattern(128, 96, 12);
+    let image = decode(encode_nef(&pattern)?)?;
+    assert_pixels(&image, &expected_nef(&pattern, &encode_nef_data(&pattern)?));
+    assert_eq!(image.wb_coeffs[0], 1.0);
+    Ok(())
+  }
+
+  #[test]
+  fn nef_uncompressed_12bit_roundtrip() -> Result<()> {
+    let pattern = test_pattern(128, 96, 12);
+    let image = decode(encode_nef_uncompressed12(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    assert_eq!(image.cpp, 1);
+    Ok(())
+  }
+
+  /// Linear RGB value as decoded from sNEF without dithering
+  fn snef_linear(y: f32, cb: f32, cr: f32, wb: (f32, f32)) -> [f32; 3] {
+    let (cb, cr) = (cb - 2048.0, cr - 2048.0);
+    let linear = |v: f32| {
+      let v = v.clamp(0.0, 4095.0) / 4095.0;
+      let res = if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) };
+      (res * 65535.0 * 4.0).min(65535.0)
+    };
+    [
+      linear(y + 1.370705 * cr) / wb.0,
+      linear(y - 0.337633 * cb - 0.698001 * cr),
+      linear(y + 1.732446 * cb) / wb.1,
+    ]
+  }
+
+  fn assert_rgb(actual: &[u16], expected: [f32; 3]) {
+    for (a, e) in actual.iter().zip(expected) {
+      assert!((*a as f32 - e).abs() <= e * 0.01 + 2.0, "decoded {:?}, expected {:?}", actual, expected);
+    }
+  }
+
+  #[test]
+  fn snef_neutral_unapplies_wb() -> Result<()> {
+    let (width, height) = (32, 24);
+    let wb = (2.0, 1.5);
+    let pair = SnefPair {
+      y1: 1500,
+      y2: 1400,
+      cb: 2048,
+      cr: 2048,
+    };
+    let image = decode(encode_snef(&vec![pair; width / 2 * height], width, height, wb)?)?;
+    assert_eq!(image.cpp, 3);
+    assert_eq!((image.width, image.height), (width, height));
+    assert!(matches!(image.photometric, RawPhotometricInterpretation::LinearRaw));
+    assert_eq!(image.wb_coeffs[0], 2.0);
+    assert_eq!(image.wb_coeffs[2], 1.5);
+
+    let data = pixels(&image);
+    assert_rgb(&data[0..3], snef_linear(1500.0, 2048.0, 2048.0, wb));
+    assert_rgb(&data[3..6], snef_linear(1400.0, 2048.0, 2048.0, wb));
+    Ok(())
+  }
+
+  #[test]
+  fn snef_interpolates_chroma() -> Result<()> {
+    let (width, height) = (32, 24);
+    let wb = (1.0, 1.0);
+    let pairs: Vec<SnefPair> = (0..width / 2 * height)
+      .map(|i| SnefPair {
+        y1: 1200,
+        y2: 1200,
+        cb: 2048 + 20 * (i % (width / 2)) as u16,
+        cr: 2048 - 10 * (i % (width / 2)) as u16,
+      })
+      .collect();
+    let image = decode(encode_snef(&pairs, width, height, wb)?)?;
+    let row = &pixels(&image)[0..width * 3];
+    // Left pixel uses the chroma of its own pair
+    assert_rgb(&row[6..9], snef_linear(1200.0, 2068.0, 2038.0, wb));
+    // Right pixel interpolates between this and the next pair
+    assert_rgb(&row[9..12], snef_linear(1200.0, 2078.0, 2033.0, wb));
+    // Last pixel has no successor and keeps its chroma
+    assert_rgb(&row[93..96], snef_linear(1200.0, 2348.0, 1898.0, wb));
+    Ok(())
+  }
+
+  #[test]
+  fn snef_converts_to_linear_dng() -> Result<()> {
+    use crate::dng::convert::{ConvertParams, convert_raw_source};
+    use crate::rawsource::RawSource;
+
+    let pair = SnefPair {
+      y1: 1000,
+      y2: 1100,
+      cb: 2100,
+      cr: 2000,
+    };
+    let source = RawSource::new_from_slice(&encode_snef(&vec![pair; 16 * 24], 32, 24, (2.0, 1.5))?);
+    let mut dng = std::io::Cursor::new(Vec::new());
+    convert_raw_source(&source, &mut dng, "DSC_0001.NEF", &ConvertParams::default())?;
+
+    let image = decode(dng.into_inner())?;
+    assert_eq!(image.cpp, 3);
+    assert!(matches!(image.photometric, RawPhotometricInterpretation::LinearRaw));
+    assert_eq!((image.width, image.height), (32, 24));
+    Ok(())
+  }
+
+  #[test]
+  fn snef_bad_wb_fails() -> Result<()> {
+    let pair = SnefPair {
+      y1: 1000,
+      y2: 1000,
+      cb: 2048,
+      cr: 2048,
+    };
+    assert!(decode(encode_snef(&vec![pair; 16 * 24], 32, 24, (0.0, 1.0))?).is_err());
+    Ok(())
+  }
+}
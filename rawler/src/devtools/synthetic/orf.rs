@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterMSB, IfdBlob, add_camera, add_makernote, build_std_tiff};
+use crate::Result;
+use crate::decoders::orf::OrfMakernotes;
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Encode an Olympus ORF file with the adaptive compression scheme
+///
+/// Pixels must be 12 bit values. The file is tagged as E-M10.
+pub(crate) fn encode_orf(image: &PixU16) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(2) {
+    return Err("ORF width must be even".into());
+  }
+  let data = encode_orf_data(image)?;
+  if data.len() >= image.width * image.height * 12 / 8 {
+    // The decoder selects the compression by the data size
+    return Err("ORF compressed data is too large".into());
+  }
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "OLYMPUS IMAGING CORP.", "E-M10");
+    add_makernote(tiff, &mut root, |offset| {
+      // Makernote IFD offsets are absolute
+      let mut makernote = IfdBlob::new();
+      makernote.add(OrfMakernotes::OlympusRedMul, 512_u16);
+      makernote.add(OrfMakernotes::OlympusBlueMul, 384_u16);
+      let mut data = b"OLYMPUS\0II\x03\0".to_vec();
+      data.extend_from_slice(&makernote.build(offset + data.len() as u32)?);
+      Ok(data)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, 12_u16);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    Ok(root)
+  })
+}
+
+/// Compressed bitstream, the counterpart of `OrfDecoder::decode_compressed`
+///
+/// The encoder tracks the same adaptive state and predictors as the decoder.
+fn encode_orf_data(image: &PixU16) -> Result<Vec<u8>> {
+  let (width, pixels) = (image.width, image.pixels());
+  if pixels.iter().any(|p| *p > 0xfff) {
+    return Err("ORF pixel values are limited to 12 bits".into());
+  }
+  let mut writer = BitWriterMSB::new();
+  // Header bytes skipped by the decoder
+  (0..7).for_each(|_| writer.put(0, 8));
+  let mut left: [i32; 2] = [0; 2];
+  let mut nw: [i32; 2] = [0; 2];
+  for row in 0..image.height {
+    let mut acarry: [[i32; 3]; 2] = [[0; 3]; 2];
+    for col in (0..width).step_by(2) {
+      for s in 0..2 {
+        let value = pixels[row * width + col + s] as i32;
+        let pred = if row < 2 && col < 2 {
+          0
+        } else if row < 2 {
+          left[s]
+        } else if col < 2 {
+          nw[s] = pixels[(row - 2) * width + col + s] as i32;
+          nw[s]
+        } else {
+          let up = pixels[(row - 2) * width + col + s] as i32;
+          let left_minus_nw = left[s] - nw[s];
+          let up_minus_nw = up - nw[s];
+          let pred = if left_minus_nw * up_minus_nw < 0 {
+            if left_minus_nw.abs() > 32 || up_minus_nw.abs() > 32 {
+              left[s] + up_minus_nw
+            } else {
+              (left[s] + up) >> 1
+            }
+          } else if left_minus_nw.abs() > up_minus_nw.abs() {
+            left[s]
+          } else {
+            up
+          };
+          nw[s] = up;
+          pred
+        };
+        left[s] = value;
+
+        let i = if acarry[s][2] < 3 { 2 } else { 0 };
+        let mut nbits = 2 + i;
+        while ((acarry[s][0] >> (nbits + i)) & 0xffff) > 0 {
+          nbits += 1;
+        }
+        let nbits = nbits.min(16) as u32;
+
+        let delta = value - pred;
+        let low = delta & 3;
+        let diff = delta >> 2;
+        let m = diff - acarry[s][1];
+        let (sign, carry) = if m >= 0 { (0, m) } else { (1, !m) };
+        let high = carry >> nbits;
+        writer.put(sign, 1);
+        writer.put(low as u32, 2);
+        if high < 12 {
+          writer.put(1, high as u32 + 1);
+        } else {
+          if nbits >= 15 || high >= 1 << (15 - nbits) {
+            return Err(format!("ORF difference {} is not representable", delta).into());
+          }
+          writer.put(0, 12);
+          writer.put((high << 1) as u32, 16 - nbits);
+        }
+        writer.put(carry as u32, nbits);
+
+        acarry[s][0] = carry;
+        acarry[s][1] = (diff * 3 + acarry[s][1]) >> 5;
+        acarry[s][2] = if carry > 16 { 0 } else { acarry[s][2] + 1 };
+      }
+    }
+  }
+  Ok(writer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn orf_compressed_roundtrip() -> Result<()> {
+    let pattern = test_pattern(128, 96, 12);
+    let image = decode(encode_orf(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    assert_eq!(image.wb_coeffs[0], 2.0);
+    Ok(())
+  }
+
+  #[test]
+  fn orf_large_steps_roundtrip() -> Result<()> {
+    // Hard edges force the escape code for large differences
+    let mut pattern = test_pattern(64, 32, 12);
+    pattern
+      .pixels_mut()
+      .iter_mut()
+      .enumerate()
+      .filter(|(i, _)| (32..36).contains(&(i % 64)))
+      .for_each(|(_, p)| *p = 4000);
+    let image = decode(encode_orf(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    Ok(())
+  }
+}
@@ -0,0 +1,75 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterMSB, HuffEncoder, IfdBlob, add_camera, add_makernote, build_std_tiff};
+use crate::Result;
+use crate::decoders::pef::{PefDecoder, PefMakernote};
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Black level stored in the makernote
+pub(crate) const PEF_BLACKLEVEL: u16 = 64;
+
+/// Encode a Pentax PEF file with Huffman compression (65535)
+///
+/// The data is encoded with the default Pentax Huffman table, so
+/// no HuffmanTable tag is written. The file is tagged as K-1.
+pub(crate) fn encode_pef(image: &PixU16) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(2) {
+    return Err("PEF width must be even".into());
+  }
+  let encoder = HuffEncoder::new(&PefDecoder::default_hufftable()?);
+  let mut writer = BitWriterMSB::new();
+  let mut pred_up: [[i32; 2]; 2] = [[0; 2]; 2];
+  for (row, line) in image.pixels().chunks_exact(image.width).enumerate() {
+    // First two pixels are predicted from the same column two rows above,
+    // all others from the left pixel of the same color.
+    for (c, pred) in pred_up[row & 1].iter_mut().enumerate() {
+      encoder.encode(&mut writer, line[c] as i32 - *pred)?;
+      *pred = line[c] as i32;
+    }
+    for col in 2..image.width {
+      encoder.encode(&mut writer, line[col] as i32 - line[col - 2] as i32)?;
+    }
+  }
+  let data = writer.finish();
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "RICOH IMAGING COMPANY, LTD.", "PENTAX K-1");
+    add_makernote(tiff, &mut root, |offset| {
+      // Makernote IFD offsets are absolute
+      let mut makernote = IfdBlob::new();
+      makernote.add(PefMakernote::WhitePoint, [8192_u16, 4096, 4096, 6144]);
+      makernote.add(PefMakernote::BlackPoint, [PEF_BLACKLEVEL; 4]);
+      let mut data = b"AOC\0II".to_vec();
+      data.extend_from_slice(&makernote.build(offset + data.len() as u32)?);
+      Ok(data)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, 14_u16);
+    root.add_tag(TiffCommonTag::Compression, 65535_u16);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    Ok(root)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn pef_huffman_roundtrip() -> Result<()> {
+    let pattern = test_pattern(96, 64, 14);
+    let image = decode(encode_pef(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    assert_eq!(image.blacklevel.levels[0].as_f32(), PEF_BLACKLEVEL as f32);
+    assert_eq!(image.wb_coeffs[0], 2.0);
+    Ok(())
+  }
+}
@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterLSB, add_camera, build_tiff};
+use crate::Result;
+use crate::decoders::rw2::PanasonicTag;
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// TIFF magic for RW2 files
+const RW2_MAGIC: u16 = 0x55;
+
+/// Size of data blocks in RW2 v4 and v5
+const BLOCK_SIZE: usize = 0x4000;
+
+/// Black level stored in the RW2 IFD
+pub(crate) const RW2_BLACKLEVEL: u16 = 128;
+
+/// Encode a Panasonic RW2 file in raw format 4
+///
+/// Pixels must be 12 bit values, the width a multiple of 14. The first
+/// two pixels of each 14 pixel block must be at least 16. The file is
+/// tagged as DC-GH5.
+pub(crate) fn encode_rw2_v4(image: &PixU16) -> Result<Vec<u8>> {
+  if !image.width.is_multiple_of(14) {
+    return Err("RW2 v4 width must be a multiple of 14".into());
+  }
+  let mut writer = PanasonicBitWriter::new(true);
+  for block in image.pixels().chunks_exact(14) {
+    encode_v4_block(&mut writer, block)?;
+  }
+  build_rw2(image, 4, 12, &writer.finish())
+}
+
+/// Encode a Panasonic RW2 file in raw format 5 with 12 bits
+///
+/// The total pixel count must be a multiple of 10.
+pub(crate) fn encode_rw2_v5(image: &PixU16) -> Result<Vec<u8>> {
+  const SPLIT_OFFSET: usize = 0x2008;
+  const PIXELS_PER_PACKET: usize = 10;
+  if !image.pixels().len().is_multiple_of(PIXELS_PER_PACKET) {
+    return Err("RW2 v5 pixel count must be a multiple of 10".into());
+  }
+  let pixels_per_block = BLOCK_SIZE / 16 * PIXELS_PER_PACKET;
+  let mut data = Vec::new();
+  for chunk in image.pixels().chunks(pixels_per_block) {
+    let mut block = Vec::with_capacity(BLOCK_SIZE);
+    for packet in chunk.chunks_exact(PIXELS_PER_PACKET) {
+      let mut writer = BitWriterLSB::new();
+      packet.iter().for_each(|p| writer.put(*p as u32, 12));
+      let mut packet = writer.finish();
+      packet.resize(16, 0);
+      block.extend_from_slice(&packet);
+    }
+    block.resize(BLOCK_SIZE, 0);
+    // Each block is stored with both sections swapped
+    data.extend_from_slice(&block[SPLIT_OFFSET..]);
+    data.extend_from_slice(&block[..SPLIT_OFFSET]);
+  }
+  build_rw2(image, 5, 12, &data)
+}
+
+/// Encode a Panasonic RW2 file in raw format 7 with 12 or 14 bits
+///
+/// The width must be a multiple of 10 (12 bits) or 9 (14 bits).
+pub(crate) fn encode_rw2_v7(image: &PixU16, bps: u32) -> Result<Vec<u8>> {
+  let pixels_per_block = match bps {
+    12 => 10,
+    14 => 9,
+    _ => return Err("RW2 v7 supports only 12 and 14 bits".into()),
+  };
+  if !image.width.is_multiple_of(pixels_per_block) {
+    return Err("RW2 v7 width must be a multiple of the block size".into());
+  }
+  let mut data = Vec::new();
+  for block in image.pixels().chunks_exact(pixels_per_block) {
+    let mut writer = BitWriterLSB::new();
+    block.iter().for_each(|p| writer.put(*p as u32, bps));
+    let mut block = writer.finish();
+    block.resize(16, 0);
+    data.extend_from_slice(&block);
+  }
+  build_rw2(image, 7, bps as u16, &data)
+}
+
+/// Encode an uncompressed RW2 file with one sample per 16 bit word
+///
+/// 12 bit samples are stored left aligned, 14 bit samples right aligned.
+/// The data is referenced by StripOffsets instead of PanaOffsets.
+pub(crate) fn encode_rw2_unpacked(image: &PixU16, bps: u32) -> Result<Vec<u8>> {
+  let shift = match bps {
+    12 => 4,
+    14 => 0,
+    _ => return Err("RW2 unpacked data supports only 12 and 14 bits".into()),
+  };
+  let data: Vec<u8> = image.pixels().iter().flat_map(|p| (p << shift).to_le_bytes()).collect();
+  build_rw2_with(image, 4, bps as u16, &data, TiffCommonTag::StripOffsets)
+}
+
+/// Build a RW2 file with arbitrary raw data
+pub(crate) fn build_rw2(image: &PixU16, raw_format: u16, bps: u16, data: &[u8]) -> Result<Vec<u8>> {
+  build_rw2_with(image, raw_format, bps, data, TiffCommonTag::PanaOffsets)
+}
+
+/// Build a RW2 file, the raw data is referenced by `offset_tag`
+fn build_rw2_with(image: &PixU16, raw_format: u16, bps: u16, data: &[u8], offset_tag: TiffCommonTag) -> Result<Vec<u8>> {
+  build_tiff(RW2_MAGIC, |tiff| {
+    let offset = tiff.write_data(data)?;
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "Panasonic", "DC-GH5");
+    root.add_tag(PanasonicTag::PanaWidth, image.width as u16);
+    root.add_tag(PanasonicTag::PanaLength, image.height as u16);
+    root.add_tag(PanasonicTag::BitsPerSample, bps);
+    root.add_tag(PanasonicTag::RawFormat, raw_format);
+    root.add_tag(PanasonicTag::PanaWBsR, 512_u16);
+    root.add_tag(PanasonicTag::PanaWBsB, 384_u16);
+    root.add_tag(PanasonicTag::BlackLevelRed, RW2_BLACKLEVEL);
+    root.add_tag(PanasonicTag::BlackLevelGreen, RW2_BLACKLEVEL);
+    root.add_tag(PanasonicTag::BlackLevelBlue, RW2_BLACKLEVEL);
+    root.add_tag(offset_tag, offset);
+    Ok(root)
+  })
+}
+
+/// Encode 14 pixels into 128 bits
+///
+/// The first pixel of each color is stored as absolute value, all other
+/// pixels as 8 bit offsets to the previous pixel of the same color. The
+/// scale of the offsets is selected for each group of three pixels.
+fn encode_v4_block(writer: &mut PanasonicBitWriter, block: &[u16]) -> Result<()> {
+  let mut pred: [i32; 2] = [0, 0];
+  for i in 0..2 {
+    let value = block[i] as i32;
+    if value >> 4 == 0 || value > 0xfff {
+      return Err(format!("RW2 v4 start value {} is out of range", value).into());
+    }
+    writer.put((value >> 4) as u32, 8)?;
+    writer.put((value & 0xf) as u32, 4)?;
+    pred[i] = value;
+  }
+  for group in (2..14).step_by(3) {
+    let (code, steps) = (0..4)
+      .find_map(|code| {
+        let sh = 4 >> (3 - code);
+        let mut trial = pred;
+        let steps = (group..group + 3)
+          .map(|i| {
+            let step = v4_step(trial[i & 1], block[i] as i32, sh)?;
+            trial[i & 1] = block[i] as i32;
+            Some(step)
+          })
+          .collect::<Option<Vec<u32>>>()?;
+        Some((code, steps))
+      })
+      .ok_or_else(|| format!("RW2 v4 block is not representable: {:?}", &block[group..group + 3]))?;
+    writer.put(code, 2)?;
+    for (i, step) in (group..group + 3).zip(steps) {
+      writer.put(step, 8)?;
+      pred[i & 1] = block[i] as i32;
+    }
+  }
+  Ok(())
+}
+
+/// Find the 8 bit offset that turns `pred` into `value`
+fn v4_step(pred: i32, value: i32, sh: i32) -> Option<u32> {
+  if pred == value {
+    return Some(0);
+  }
+  (1..256)
+    .find(|j| {
+      let mut next = pred - (0x80 << sh);
+      if next < 0 || sh == 4 {
+        next &= !(-1 << sh);
+      }
+      next + (j << sh) == value
+    })
+    .map(|j| j as u32)
+}
+
+/// Bit writer for the Panasonic bitstream
+///
+/// The bits are written to exactly the positions the decoder reads
+/// them from, including the swapped sections of each block.
+struct PanasonicBitWriter {
+  buf: Vec<u8>,
+  /// Mask of bits already written
+  written: Vec<u8>,
+  pos: usize,
+  nbits: u32,
+  split: bool,
+}
+
+impl PanasonicBitWriter {
+  fn new(split: bool) -> Self {
+    Self {
+      buf: Vec::new(),
+      written: Vec::new(),
+      pos: 0,
+      nbits: 0,
+      split,
+    }
+  }
+
+  fn put(&mut self, value: u32, num: u32) -> Result<()> {
+    if num > self.nbits {
+      self.nbits += BLOCK_SIZE as u32 * 8;
+      self.pos += BLOCK_SIZE;
+      self.buf.resize(self.pos + 2, 0);
+      self.written.resize(self.pos + 2, 0);
+    }
+    let mut byte = ((self.nbits - num) >> 3) ^ 0x3ff0;
+    if self.split {
+      byte = (byte + 0x4000 - 0x2008) % 0x4000;
+    }
+    let start = byte as usize + self.pos - BLOCK_SIZE;
+    let shift = (self.nbits - num) & 7;
+    for t in 0..num {
+      let idx = start + ((shift + t) / 8) as usize;
+      let mask = 1 << ((shift + t) % 8);
+      let bit = if (value >> t) & 1 == 1 { mask } else { 0 };
+      if self.written[idx] & mask != 0 && self.buf[idx] & mask != bit {
+        return Err("RW2 bitstream conflict".into());
+      }
+      self.written[idx] |= mask;
+      self.buf[idx] |= bit;
+    }
+    self.nbits -= num;
+    Ok(())
+  }
+
+  fn finish(self) -> Vec<u8> {
+    self.buf
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn rw2_v4_roundtrip() -> Result<()> {
+    let pattern = test_pattern(112, 84, 12);
+    let image = decode(encode_rw2_v4(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    assert_eq!(image.blacklevel.levels[0].as_f32(), RW2_BLACKLEVEL as f32);
+    Ok(())
+  }
+
+  #[test]
+  fn rw2_v7_roundtrip() -> Result<()> {
+    for (width, bps) in [(120, 12), (117, 14)] {
+      let pattern = test_pattern(width, 90, bps);
+      let image = decode(encode_rw2_v7(&pattern, bps)?)?;
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn rw2_unpacked_roundtrip() -> Result<()> {
+    for bps in [12, 14] {
+      let pattern = test_pattern(120, 90, bps);
+      let image = decode(encode_rw2_unpacked(&pattern, bps)?)?;
+      assert_pixels(&image, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn rw2_unsupported_layouts_fail() -> Result<()> {
+    let pattern = test_pattern(120, 90, 12);
+    assert!(decode(build_rw2(&pattern, 7, 12, &vec![0; 120 * 90 * 2])?).is_ok());
+    let data = vec![0; 120 * 90];
+    // Unknown raw format, bit depth not supported by the bitstream,
+    // truncated data: all must return an error.
+    assert!(decode(build_rw2(&pattern, 99, 12, &data)?).is_err());
+    assert!(decode(build_rw2(&pattern, 1, 12, &data)?).is_err());
+    assert!(decode(build_rw2(&pattern, 7, 10, &data)?).is_err());
+    assert!(decode(build_rw2(&pattern, 7, 12, &data[..100])?).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn rw2_v5_roundtrip() -> Result<()> {
+    // More than one block of 0x4000 bytes
+    let pattern = test_pattern(140, 105, 12);
+    let image = decode(encode_rw2_v5(&pattern)?)?;
+    assert_pixels(&image, &pattern);
+    Ok(())
+  }
+}
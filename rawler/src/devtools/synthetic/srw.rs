@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use super::{BitWriterLSB, IfdBlob, add_camera, add_makernote, build_std_tiff, pack_msb};
+use crate::Result;
+use crate::decoders::srw::SrwMakernote;
+use crate::pixarray::PixU16;
+use crate::tags::TiffCommonTag;
+
+/// Storage layout of uncompressed SRW data
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum SrwLayout {
+  /// Samples packed to the bit depth, MSB first
+  PackedMSB,
+  /// Samples packed to the bit depth, LSB first
+  PackedLSB,
+  /// One sample per 16 bit little-endian word
+  Unpacked,
+}
+
+/// Encode an uncompressed Samsung SRW file
+///
+/// The file is tagged as NX100, which has no byte order hints.
+pub(crate) fn encode_srw(image: &PixU16, bits: u32, compression: u16, layout: SrwLayout) -> Result<Vec<u8>> {
+  build_srw(image, bits, compression, layout, true)
+}
+
+fn build_srw(image: &PixU16, bits: u32, compression: u16, layout: SrwLayout, byte_counts: bool) -> Result<Vec<u8>> {
+  let data = match layout {
+    SrwLayout::PackedMSB => pack_msb(image.pixels(), bits),
+    SrwLayout::PackedLSB => {
+      let mut writer = BitWriterLSB::new();
+      image.pixels().iter().for_each(|p| writer.put(*p as u32, bits));
+      let mut data = writer.finish();
+      data.truncate((image.pixels().len() * bits as usize).div_ceil(8));
+      data
+    }
+    SrwLayout::Unpacked => image.pixels().iter().flat_map(|p| p.to_le_bytes()).collect(),
+  };
+
+  build_std_tiff(|tiff| {
+    let mut root = tiff.new_directory();
+    add_camera(&mut root, "SAMSUNG", "NX100");
+    add_makernote(tiff, &mut root, |_| {
+      // Makernote offsets are relative to the makernote
+      let mut makernote = IfdBlob::new();
+      makernote.add(SrwMakernote::SrwRGGBLevels, [4096_u32 + 8192, 4096 + 4096, 4096 + 4096, 4096 + 6144]);
+      makernote.add(SrwMakernote::SrwRGGBBlacks, [4096_u32; 4]);
+      makernote.build(0)
+    })?;
+
+    let offset = tiff.write_data(&data)?;
+    root.add_tag(TiffCommonTag::ImageWidth, image.width as u32);
+    root.add_tag(TiffCommonTag::ImageLength, image.height as u32);
+    root.add_tag(TiffCommonTag::BitsPerSample, bits as u16);
+    root.add_tag(TiffCommonTag::Compression, compression);
+    root.add_tag(TiffCommonTag::StripOffsets, offset);
+    if byte_counts {
+      root.add_tag(TiffCommonTag::StripByteCounts, data.len() as u32);
+    }
+    Ok(root)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::decoders::srw::SrwDecoder;
+  use crate::devtools::synthetic::{assert_pixels, decode, test_pattern};
+
+  #[test]
+  fn srw_uncompressed_layouts() -> Result<()> {
+    let cases = [
+      (12, 32769, SrwLayout::Unpacked),
+      (14, 32769, SrwLayout::Unpacked),
+      (16, 32769, SrwLayout::Unpacked),
+      (10, 32769, SrwLayout::PackedLSB),
+      (12, 32770, SrwLayout::PackedMSB),
+      (14, 32770, SrwLayout::Unpacked),
+      (10, 32770, SrwLayout::PackedMSB),
+      (16, 32770, SrwLayout::Unpacked),
+    ];
+    for (bits, compression, layout) in cases {
+      let pattern = test_pattern(128, 96, bits);
+      let image = decode(encode_srw(&pattern, bits, compression, layout)?)?;
+      assert_pixels(&image, &pattern);
+      assert_eq!(image.wb_coeffs[0], 2.0, "{} bits, compression {}, {:?}", bits, compression, layout);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn srw_known_layouts_ignore_byte_counts() -> Result<()> {
+    // 12 and 14 bit layouts are fixed per compression, even if the strip
+    // size is missing or suggests another layout.
+    let cases = [
+      (12, 32769, SrwLayout::Unpacked),
+      (14, 32769, SrwLayout::Unpacked),
+      (12, 32770, SrwLayout::PackedMSB),
+      (14, 32770, SrwLayout::Unpacked),
+    ];
+    for (bits, compression, layout) in cases {
+      let pattern = test_pattern(128, 96, bits);
+      assert_pixels(&decode(build_srw(&pattern, bits, compression, layout, false)?)?, &pattern);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn srw_unknown_compression_fails() -> Result<()> {
+    let pattern = test_pattern(128, 96, 12);
+    assert!(decode(encode_srw(&pattern, 12, 32771, SrwLayout::Unpacked)?).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn srw_corrupted_data_fails() {
+    // Line offsets point behind the data
+    let loffsets: Vec<u8> = (0..32_u32).flat_map(|_| 0x1000_u32.to_le_bytes()).collect();
+    assert!(SrwDecoder::decode_srw1(&[0; 256], &loffsets, 32, 32, false).is_err());
+    // Line offset table is too short
+    assert!(SrwDecoder::decode_srw1(&[0; 256], &loffsets[..16], 32, 32, false).is_err());
+    // Compressed NX1 data requires a width multiple of 16
+    assert!(SrwDecoder::decode_srw3(&[0; 256], 24, 4, false).is_err());
+  }
+}
@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Auxiliary images stored next to the raw image in DNG files.
+//!
+//! DNG 1.6 allows additional sub-IFDs for depth maps and semantic
+//! masks. Apple ProRAW uses them for segmentation mattes (skin, sky,
+//! hair, teeth, ...) and a depth map. Local tone mapping is described by
+//! the `ProfileGainTableMap` tag.
+
+use byteorder::{BigEndian, ByteOrder};
+use image::{DynamicImage, ImageBuffer, Luma};
+
+use crate::{
+  RawImageData, RawlerError, Result,
+  decoders::plain_image_from_ifd,
+  formats::tiff::{IFD, PhotometricInterpretation},
+  rawsource::RawSource,
+  tags::{DngTag, TiffCommonTag},
+};
+
+/// NewSubFileType bit for transparency masks
+const SUBFILE_MASK: u32 = 0x4;
+/// NewSubFileType bit for depth maps
+const SUBFILE_DEPTH: u32 = 0x8;
+
+/// Kind of an auxiliary image
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuxiliaryKind {
+  /// Semantic mask (DNG 1.6), identified by `SemanticName`
+  SemanticMask { name: String, instance_id: Option<String> },
+  /// Depth map (DNG 1.5)
+  Depth,
+  /// Plain transparency mask
+  TransparencyMask,
+}
+
+/// Single channel image stored in its own sub-IFD
+#[derive(Debug, Clone)]
+pub struct AuxiliaryImage {
+  pub kind: AuxiliaryKind,
+  /// Original NewSubFileType value
+  pub subfile_type: u32,
+  pub width: usize,
+  pub height: usize,
+  /// Bits per sample, 8 or 16
+  pub bits: u32,
+  pub data: Vec<u16>,
+  /// Area of the main image covered by this mask (top, left, bottom, right)
+  pub mask_sub_area: Option<[u32; 4]>,
+}
+
+impl AuxiliaryImage {
+  /// Returns true if the IFD contains a depth map or a mask
+  pub fn is_auxiliary_ifd(ifd: &IFD) -> bool {
+    let photometric = ifd.get_entry(TiffCommonTag::PhotometricInt).map(|entry| entry.force_u16(0));
+    let subfile_type = ifd.get_entry(TiffCommonTag::NewSubFileType).map(|entry| entry.force_u32(0)).unwrap_or(0);
+    match photometric {
+      Some(pi) if pi == PhotometricInterpretation::Depth as u16 || pi == PhotometricInterpretation::SemanticMask as u16 => true,
+      Some(pi) if pi == PhotometricInterpretation::TransparencyMask as u16 => true,
+      // Some writers use BlackIsZero together with the subfile type
+      _ => subfile_type & (SUBFILE_MASK | SUBFILE_DEPTH) != 0 && !ifd.has_entry(DngTag::PreviewColorSpace),
+    }
+  }
+
+  /// Decode an auxiliary image from its IFD
+  pub fn from_ifd(ifd: &IFD, rawsource: &RawSource) -> Result<Self> {
+    let dimension = |tag: TiffCommonTag| {
+      ifd
+        .get_entry(tag)
+        .map(|entry| entry.force_usize(0))
+        .ok_or_else(|| RawlerError::DecoderFailed(format!("Auxiliary image has no {:?} tag", tag)))
+    };
+    let width = dimension(TiffCommonTag::ImageWidth)?;
+    let height = dimension(TiffCommonTag::ImageLength)?;
+    let cpp = ifd.get_entry(TiffCommonTag::SamplesPerPixel).map(|entry| entry.force_usize(0)).unwrap_or(1);
+    let bits = ifd.get_entry(TiffCommonTag::BitsPerSample).map(|entry| entry.force_u32(0)).unwrap_or(8);
+    let subfile_type = ifd.get_entry(TiffCommonTag::NewSubFileType).map(|entry| entry.force_u32(0)).unwrap_or(0);
+    let photometric = ifd.get_entry(TiffCommonTag::PhotometricInt).map(|entry| entry.force_u16(0)).unwrap_or(1);
+
+    if cpp != 1 {
+      return Err(RawlerError::unsupported_feature(
+        "DNG",
+        format!("auxiliary image with {} samples per pixel", cpp),
+      ));
+    }
+    if !(1..=16).contains(&bits) {
+      return Err(RawlerError::unsupported_feature(
+        "DNG",
+        format!("auxiliary image with {} bits per sample", bits),
+      ));
+    }
+
+    let kind = if let Some(name) = ifd.get_entry(DngTag::SemanticName).and_then(|entry| entry.as_string()) {
+      AuxiliaryKind::SemanticMask {
+        name: name.clone(),
+        instance_id: ifd.get_entry(DngTag::SemanticInstanceID).and_then(|entry| entry.as_string()).cloned(),
+      }
+    } else if photometric == PhotometricInterpretation::Depth as u16 || subfile_type & SUBFILE_DEPTH != 0 {
+      AuxiliaryKind::Depth
+    } else {
+      AuxiliaryKind::TransparencyMask
+    };
+
+    let mask_sub_area = ifd
+      .get_entry(DngTag::MaskSubArea)
+      .filter(|entry| entry.count() == 4)
+      .map(|entry| [entry.force_u32(0), entry.force_u32(1), entry.force_u32(2), entry.force_u32(3)]);
+
+    let data = match plain_image_from_ifd(ifd, rawsource)? {
+      RawImageData::Integer(data) => data,
+      RawImageData::Float(_) => return Err(RawlerError::unsupported_feature("DNG", "floating point auxiliary image")),
+    };
+    if data.len() != width * height {
+      return Err(RawlerError::DecoderFailed(format!(
+        "Auxiliary image has {} samples, expected {}x{}",
+        data.len(),
+        width,
+        height
+      )));
+    }
+
+    Ok(Self {
+      kind,
+      subfile_type,
+      width,
+      height,
+      bits,
+      data,
+      mask_sub_area,
+    })
+  }
+
+  /// Short name, usable as filename suffix (e.g. `mask_skin`, `depth`)
+  pub fn name(&self) -> String {
+    match &self.kind {
+      AuxiliaryKind::SemanticMask { name, instance_id } => {
+        // Apple uses names like "com.apple.ProRaw.SkinMatte"
+        let short = name.rsplit(['.', ':']).next().unwrap_or(name).trim_end_matches("Matte").to_lowercase();
+        let short: String = short.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        match instance_id {
+          Some(id) if !id.is_empty() => format!("mask_{}_{}", short, id),
+          _ => format!("mask_{}", short),
+        }
+      }
+      AuxiliaryKind::Depth => String::from("depth"),
+      AuxiliaryKind::TransparencyMask => String::from("transparency"),
+    }
+  }
+
+  /// Convert into a grayscale image, 8 bit images are kept as 8 bit,
+  /// everything else is scaled to 16 bit.
+  pub fn to_dynamic_image(&self) -> Option<DynamicImage> {
+    if self.bits <= 8 {
+      let max = (1_u32 << self.bits) - 1;
+      let data = self.data.iter().map(|v| ((*v as u32).min(max) * 255 / max) as u8).collect();
+      ImageBuffer::<Luma<u8>, Vec<u8>>::from_raw(self.width as u32, self.height as u32, data).map(DynamicImage::ImageLuma8)
+    } else {
+      let max = (1_u32 << self.bits) - 1;
+      let data = self.data.iter().map(|v| ((*v as u32).min(max) * 65535 / max) as u16).collect();
+      ImageBuffer::<Luma<u16>, Vec<u16>>::from_raw(self.width as u32, self.height as u32, data).map(DynamicImage::ImageLuma16)
+    }
+  }
+}
+
+/// Encoding of the depth map values (DepthFormat tag)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, enumn::N)]
+#[repr(u16)]
+pub enum DepthFormat {
+  Unknown = 0,
+  Linear = 1,
+  Inverse = 2,
+}
+
+/// Unit of DepthNear and DepthFar (DepthUnits tag)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, enumn::N)]
+#[repr(u16)]
+pub enum DepthUnits {
+  Unknown = 0,
+  Meters = 1,
+}
+
+/// How the depth is measured (DepthMeasureType tag)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, enumn::N)]
+#[repr(u16)]
+pub enum DepthMeasureType {
+  Unknown = 0,
+  OpticalAxis = 1,
+  OpticalRay = 2,
+}
+
+/// Depth map parameters from the root IFD
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthInfo {
+  pub format: DepthFormat,
+  pub near: Option<f32>,
+  pub far: Option<f32>,
+  pub units: DepthUnits,
+  pub measure_type: DepthMeasureType,
+}
+
+impl DepthInfo {
+  /// Read depth parameters, returns `None` if DepthFormat is missing
+  pub fn from_ifd(ifd: &IFD) -> Option<Self> {
+    let format = ifd.get_entry(DngTag::DepthFormat)?.force_u16(0);
+    let short = |tag: DngTag| ifd.get_entry(tag).map(|entry| entry.force_u16(0)).unwrap_or(0);
+    Some(Self {
+      format: DepthFormat::n(format).unwrap_or(DepthFormat::Unknown),
+      near: ifd.get_entry(DngTag::DepthNear).map(|entry| entry.force_f32(0)),
+      far: ifd.get_entry(DngTag::DepthFar).map(|entry| entry.force_f32(0)),
+      units: DepthUnits::n(short(DngTag::DepthUnits)).unwrap_or(DepthUnits::Unknown),
+      measure_type: DepthMeasureType::n(short(DngTag::DepthMeasureType)).unwrap_or(DepthMeasureType::Unknown),
+    })
+  }
+}
+
+/// Local tone mapping gain table (ProfileGainTableMap tag, DNG 1.6)
+///
+/// The table is a grid of `points_v` x `points_h` cells, each with
+/// `points_n` gains, indexed by a weighted sum of the input pixel channels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileGainTableMap {
+  pub points_v: usize,
+  pub points_h: usize,
+  pub spacing_v: f64,
+  pub spacing_h: f64,
+  pub origin_v: f64,
+  pub origin_h: f64,
+  pub points_n: usize,
+  /// Weights for R, G, B, min(R,G,B) and max(R,G,B)
+  pub input_weights: [f32; 5],
+  /// Gains in row-major order, `points_n` values per grid point
+  pub gains: Vec<f32>,
+}
+
+impl ProfileGainTableMap {
+  const HEADER_SIZE: usize = 4 * 2 + 8 * 4 + 4 + 4 * 5;
+
+  /// Parse the tag payload, which is always stored big-endian
+  pub fn parse(buf: &[u8]) -> Result<Self> {
+    if buf.len() < Self::HEADER_SIZE {
+      return Err(RawlerError::DecoderFailed(format!("ProfileGainTableMap is too short: {} bytes", buf.len())));
+    }
+    let points_v = BigEndian::read_u32(&buf[0..]) as usize;
+    let points_h = BigEndian::read_u32(&buf[4..]) as usize;
+    let spacing_v = BigEndian::read_f64(&buf[8..]);
+    let spacing_h = BigEndian::read_f64(&buf[16..]);
+    let origin_v = BigEndian::read_f64(&buf[24..]);
+    let origin_h = BigEndian::read_f64(&buf[32..]);
+    let points_n = BigEndian::read_u32(&buf[40..]) as usize;
+    let mut input_weights = [0.0; 5];
+    for (i, weight) in input_weights.iter_mut().enumerate() {
+      *weight = BigEndian::read_f32(&buf[44 + i * 4..]);
+    }
+    let count = points_v
+      .checked_mul(points_h)
+      .and_then(|v| v.checked_mul(points_n))
+      .ok_or_else(|| RawlerError::DecoderFailed(format!("ProfileGainTableMap has invalid size {}x{}x{}", points_v, points_h, points_n)))?;
+    let payload = &buf[Self::HEADER_SIZE..];
+    if payload.len() < count * 4 {
+      return Err(RawlerError::DecoderFailed(format!(
+        "ProfileGainTableMap needs {} gains, but only {} bytes are left",
+        count,
+        payload.len()
+      )));
+    }
+    let gains = payload.chunks_exact(4).take(count).map(BigEndian::read_f32).collect();
+    Ok(Self {
+      points_v,
+      points_h,
+      spacing_v,
+      spacing_h,
+      origin_v,
+      origin_h,
+      points_n,
+      input_weights,
+      gains,
+    })
+  }
+
+  /// Gains for grid point (`row`, `col`)
+  pub fn gains_at(&self, row: usize, col: usize) -> Option<&[f32]> {
+    if row >= self.points_v || col >= self.points_h {
+      return None;
+    }
+    let start = (row * self.points_h + col) * self.points_n;
+    self.gains.get(start..start + self.points_n)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_gain_table_map() -> Result<()> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(&2_u32.to_be_bytes());
+    buf.extend_from_slice(&3_u32.to_be_bytes());
+    for v in [0.5_f64, 1.0 / 3.0, 0.0, 0.0] {
+      buf.extend_from_slice(&v.to_be_bytes());
+    }
+    buf.extend_from_slice(&2_u32.to_be_bytes());
+    for v in [0.0_f32, 0.0, 0.0, 0.0, 1.0] {
+      buf.extend_from_slice(&v.to_be_bytes());
+    }
+    for i in 0..12 {
+      buf.extend_from_slice(&(1.0_f32 + i as f32 / 8.0).to_be_bytes());
+    }
+    let map = ProfileGainTableMap::parse(&buf)?;
+    assert_eq!((map.points_v, map.points_h, map.points_n), (2, 3, 2));
+    assert_eq!(map.spacing_v, 0.5);
+    assert_eq!(map.input_weights, [0.0, 0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(map.gains_at(1, 2), Some(&[2.25_f32, 2.375][..]));
+    assert_eq!(map.gains_at(2, 0), None);
+
+    buf.truncate(buf.len() - 4);
+    assert!(ProfileGainTableMap::parse(&buf).is_err());
+    Ok(())
+  }
+}
@@ -1,18 +1,23 @@
 use std::{
   ffi::OsStr,
-  io::{Cursor, Seek, Write},
+  io::{Seek, Write},
   path::Path,
-  sync::Arc,
-  thread::JoinHandle,
   time::SystemTime,
 };
 
 use image::DynamicImage;
 
 use crate::{
-  RawImage, RawImageData,
-  decoders::{Decoder, RawDecodeParams, RawPhotometricInterpretation, WellKnownIFD, WhiteLevel},
-  dng::{DNG_VERSION_V1_4, PREVIEW_JPEG_QUALITY, original::OriginalCompressed, writer::DngWriter},
+  RawImage, RawImageData, RawlerError,
+  decoders::{Decoder, FormatHint, RawDecodeParams, RawMetadata, RawPhotometricInterpretation, WellKnownIFD, WhiteLevel},
+  dng::{
+    DNG_VERSION_V1_4, DNG_VERSION_V1_6,
+    dcp::DcpProfile,
+    original::OriginalCompressed,
+    preview::{PreviewParams, PreviewSource, trim_letterbox},
+    recompress::recompress_dng,
+    writer::{DEFAULT_TILE_SIZE, DngWriter},
+  },
   formats::tiff::Entry,
   imgop::{
     develop::RawDevelop,
@@ -24,7 +29,7 @@ use crate::{
   tags::{DngTag, ExifTag, TiffCommonTag},
 };
 
-use super::{CropMode, DngCompression, DngPhotometricConversion};
+use super::{CropMode, DeflatePredictor, DngCompression, DngPhotometricConversion};
 
 /// Parameters for DNG conversion
 #[derive(Clone, Debug)]
@@ -35,12 +40,22 @@ pub struct ConvertParams {
   pub apply_scaling: bool,
   pub crop: CropMode,
   pub predictor: u8,
+  /// Predictor for Deflate compression
+  pub deflate_predictor: DeflatePredictor,
   pub preview: bool,
   pub thumbnail: bool,
   pub artist: Option<String>,
   pub software: String,
   pub index: usize,
   pub keep_mtime: bool,
+  /// Camera profile to embed instead of the built-in color matrices
+  pub profile: Option<DcpProfile>,
+  /// Settings for preview and thumbnail images
+  pub preview_params: PreviewParams,
+  /// Tile width and height for compressed raw images, a multiple of 16
+  pub tile_size: usize,
+  /// Version written as DNGVersion, must not be older than the backward version
+  pub dng_version: [u8; 4],
 }
 
 /// Information surfaced from a completed conversion.
@@ -63,16 +78,37 @@ impl Default for ConvertParams {
       apply_scaling: false,
       crop: CropMode::Best,
       predictor: 1,
+      deflate_predictor: DeflatePredictor::default(),
       preview: true,
       thumbnail: true,
       artist: None,
       software: "DNGLab".into(),
       index: 0,
       keep_mtime: false,
+      profile: None,
+      preview_params: PreviewParams::default(),
+      tile_size: DEFAULT_TILE_SIZE,
+      dng_version: DNG_VERSION_V1_6,
     }
   }
 }
 
+/// Create a DNG writer with the version from `params`
+pub(crate) fn dng_writer<W>(dng: W, backward_version: [u8; 4], params: &ConvertParams) -> crate::Result<DngWriter<W>>
+where
+  W: Write + Seek,
+{
+  if params.dng_version < backward_version {
+    return Err(RawlerError::unsupported_feature(
+      "DNG",
+      format!("version {:?} is older than backward version {:?}", params.dng_version, backward_version),
+    ));
+  }
+  let mut dng = DngWriter::new(dng, backward_version)?;
+  dng.set_version(params.dng_version);
+  Ok(dng)
+}
+
 /// Convert a raw input file into DNG
 ///
 /// We don't accept a DNG file path here, because we don't know
@@ -83,16 +119,9 @@ pub fn convert_raw_file<W: Write + Seek + Send>(raw: &Path, dng: &mut W, params:
   //let raw_stream = BufReader::new(File::open(raw)?); // TODO: add path hint to error?
   //let rawfile = RawFile::new(PathBuf::from(raw), raw_stream);
 
-  let rawfile = Arc::new(RawSource::new(raw)?);
-
-  let original_compress_thread = if params.embedded {
-    let orig_source = rawfile.clone();
-    Some(std::thread::spawn(move || OriginalCompressed::compress(&mut orig_source.reader())))
-  } else {
-    None
-  };
+  let rawfile = RawSource::new(raw)?;
 
-  internal_convert(&rawfile, dng, original_filename, original_compress_thread, params)
+  internal_convert(&rawfile, dng, original_filename, params)
 }
 
 /// Convert a raw input file into DNG
@@ -100,37 +129,41 @@ pub fn convert_raw_source<W>(raw_source: &RawSource, dng: &mut W, original_filen
 where
   W: Write + Seek + Send,
 {
-  let original_compress_thread = if params.embedded {
-    let mut original_stream = Cursor::new(raw_source.as_vec()?);
-    Some(std::thread::spawn(move || OriginalCompressed::compress(&mut original_stream)))
-  } else {
-    None
-  };
-
-  internal_convert(raw_source, dng, original_filename, original_compress_thread, params)
+  internal_convert(raw_source, dng, original_filename, params)
 }
 
-fn internal_convert<W>(
-  rawfile: &RawSource,
-  dng: &mut W,
-  original_filename: impl AsRef<str>,
-  original_compress_thread: Option<JoinHandle<Result<OriginalCompressed, std::io::Error>>>,
-  params: &ConvertParams,
-) -> crate::Result<ConvertInfo>
+fn internal_convert<W>(rawfile: &RawSource, dng: &mut W, original_filename: impl AsRef<str>, params: &ConvertParams) -> crate::Result<ConvertInfo>
 where
   W: Write + Seek + Send,
 {
   let decoder = crate::get_decoder(rawfile)?;
+
+  // DNG input is re-encoded without losing any tags. Other images
+  // than the main image are converted like any other raw file.
+  if decoder.format_hint() == FormatHint::DNG && params.index == 0 {
+    return recompress_dng(rawfile, decoder.as_ref(), dng, params);
+  }
+
+  let original_compress_thread = if params.embedded {
+    // Sources are shared, so this doesn't copy the data
+    let orig_source = rawfile.clone();
+    Some(std::thread::spawn(move || OriginalCompressed::compress(&mut orig_source.reader())))
+  } else {
+    None
+  };
+
   let raw_params = RawDecodeParams { image_index: params.index };
   let mut rawimage = decoder.raw_image(rawfile, &raw_params, false)?;
   let metadata = decoder.raw_metadata(rawfile, &raw_params)?;
 
   log::info!(
-    "DNG conversion: '{}', make: {}, model: {}, raw-image-count: {}",
+    "DNG conversion: '{}', make: {}, model: {}, raw-image-count: {}, image: {} ({})",
     original_filename.as_ref(),
     rawimage.clean_make,
     rawimage.clean_model,
-    decoder.raw_image_count()?
+    decoder.raw_image_count()?,
+    params.index,
+    decoder.raw_image_kind(params.index)?
   );
   log::debug!("Raw image WB coeff: {:?}", rawimage.wb_coeffs);
 
@@ -158,12 +191,14 @@ where
     rawimage.apply_scaling()?;
   }
 
-  let mut dng = DngWriter::new(dng, DNG_VERSION_V1_4)?;
+  let mut dng = dng_writer(dng, DNG_VERSION_V1_4, params)?;
 
   // Write RAW image for subframe type 0
   // If no thumbnail should be written to root IFD, we need to put the raw image into
   // root IFD instead.
   let mut raw = if params.thumbnail { dng.subframe(0) } else { dng.subframe_on_root(0) };
+  raw.set_deflate_predictor(params.deflate_predictor);
+  raw.set_tile_size(params.tile_size)?;
   raw.raw_image(&rawimage, params.crop, params.compression, params.photometric_conversion, params.predictor)?;
   // Check for DNG raw IFD related tags
   if let Some(dng_raw_ifd) = decoder.ifd(WellKnownIFD::VirtualDngRawTags)? {
@@ -171,19 +206,20 @@ where
   }
   raw.finalize()?;
 
+  // Keep depth maps and semantic masks
+  match decoder.auxiliary_images(rawfile) {
+    Ok(images) => {
+      for aux in &images {
+        dng.auxiliary_image(aux)?;
+      }
+    }
+    Err(err) => log::warn!("Failed to read auxiliary images, continue anyway: {:?}", err),
+  }
+
   // Write preview and thumbnail if requested
   if params.preview || params.thumbnail {
-    match generate_preview(rawfile, decoder.as_ref(), &rawimage, &raw_params) {
-      Ok(image) => {
-        if params.preview {
-          let mut preview = dng.subframe(1);
-          preview.preview(&image, PREVIEW_JPEG_QUALITY)?;
-          preview.finalize()?;
-        }
-        if params.thumbnail {
-          dng.thumbnail(&image)?;
-        }
-      }
+    match generate_preview(rawfile, decoder.as_ref(), &rawimage, &raw_params, params) {
+      Ok(image) => write_previews(&mut dng, &image, params)?,
       Err(err) => log::warn!("Failed to get review image, continue anyway: {:?}", err),
     }
   }
@@ -236,6 +272,10 @@ where
     dng.original_file(&original, original_filename)?;
   }
 
+  if let Some(profile) = &params.profile {
+    embed_profile(&mut dng, &rawimage, profile)?;
+  }
+
   if let Some(artist) = &params.artist {
     dng.root_ifd_mut().add_tag(TiffCommonTag::Artist, artist);
   }
@@ -258,24 +298,136 @@ where
   Ok(ConvertInfo { last_modified })
 }
 
-fn generate_preview(rawfile: &RawSource, decoder: &dyn Decoder, rawimage: &RawImage, params: &RawDecodeParams) -> crate::Result<DynamicImage> {
-  match decoder.preview_image(rawfile, params)? {
-    Some(image) => Ok(image),
-    None => {
-      log::warn!("Preview image not found, try to generate sRGB from RAW");
-      let dev = RawDevelop::default();
-      let image = dev.develop_intermediate(rawimage)?;
-      /*
-      let params = rawimage.develop_params()?;
-      let (srgbf, dim) = develop_raw_srgb(&rawimage.data, &params)?;
-      let output = convert_from_f32_scaled_u16(&srgbf, 0, u16::MAX);
-      let image = if srgbf.len() == dim.w * dim.h {
-        DynamicImage::ImageLuma16(ImageBuffer::from_raw(dim.w as u32, dim.h as u32, output).expect("Invalid ImageBuffer size"))
-      } else {
-        DynamicImage::ImageRgb16(ImageBuffer::from_raw(dim.w as u32, dim.h as u32, output).expect("Invalid ImageBuffer size"))
-      };
-       */
-      Ok(image.to_dynamic_image().ok_or("failed to convert to dynamic image")?)
+/// Write a raw image which is not backed by a single input file into DNG
+///
+/// This is used for images composed from multiple frames, so
+/// there is no original file to embed.
+pub fn convert_raw_image<W>(rawimage: &RawImage, metadata: &RawMetadata, dng: &mut W, params: &ConvertParams) -> crate::Result<()>
+where
+  W: Write + Seek + Send,
+{
+  let mut dng = dng_writer(dng, DNG_VERSION_V1_4, params)?;
+
+  let mut raw = if params.thumbnail { dng.subframe(0) } else { dng.subframe_on_root(0) };
+  raw.set_deflate_predictor(params.deflate_predictor);
+  raw.set_tile_size(params.tile_size)?;
+  raw.raw_image(rawimage, params.crop, params.compression, params.photometric_conversion, params.predictor)?;
+  raw.finalize()?;
+
+  if params.preview || params.thumbnail {
+    match develop_preview(rawimage, params.profile.as_ref()) {
+      Ok(image) => write_previews(&mut dng, &image, params)?,
+      Err(err) => log::warn!("Failed to develop review image, continue anyway: {:?}", err),
+    }
+  }
+
+  dng.load_base_tags(rawimage)?;
+  dng.load_metadata(metadata)?;
+  // Tag overrides from composition, like BaselineExposure for HDR images
+  dng.root_ifd_mut().copy_with_override(rawimage.dng_tags.iter());
+  if !dng.root_ifd().contains(ExifTag::Orientation) {
+    dng.root_ifd_mut().add_tag(ExifTag::Orientation, rawimage.orientation.to_u16());
+  }
+
+  if let Some(profile) = &params.profile {
+    embed_profile(&mut dng, rawimage, profile)?;
+  }
+
+  if let Some(artist) = &params.artist {
+    dng.root_ifd_mut().add_tag(TiffCommonTag::Artist, artist);
+  }
+  dng.root_ifd_mut().add_tag(TiffCommonTag::Software, &params.software);
+
+  dng
+    .root_ifd_mut()
+    .add_tag(ExifTag::ModifyDate, chrono::Local::now().format("%Y:%m:%d %H:%M:%S").to_string());
+
+  dng.close()?;
+  Ok(())
+}
+
+/// Embed camera profile into root IFD, replacing the built-in color matrices
+pub(super) fn embed_profile<W>(dng: &mut DngWriter<W>, rawimage: &RawImage, profile: &DcpProfile) -> crate::Result<()>
+where
+  W: Write + Seek + Send,
+{
+  let camera_model = format!("{} {}", rawimage.clean_make, rawimage.clean_model);
+  if let Some(profile_model) = &profile.unique_camera_model
+    && !profile_model.eq_ignore_ascii_case(&camera_model)
+  {
+    log::warn!("Camera profile is made for '{}', but image is from '{}'", profile_model, camera_model);
+  }
+  profile.embed(dng.root_ifd_mut())
+}
+
+/// Write preview and thumbnail images as requested by `params`
+pub(super) fn write_previews<W>(dng: &mut DngWriter<W>, image: &DynamicImage, params: &ConvertParams) -> crate::Result<()>
+where
+  W: Write + Seek + Send,
+{
+  if params.preview {
+    let mut preview = dng.subframe(1);
+    preview.preview_with_params(image, &params.preview_params)?;
+    preview.ifd_mut().add_tag(DngTag::PreviewSettingsDigest, preview_settings_digest(params));
+    preview.ifd_mut().add_tag(
+      DngTag::PreviewDateTime,
+      chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+    );
+    preview.finalize()?;
+  }
+  if params.thumbnail {
+    dng.thumbnail_with_params(image, &params.preview_params)?;
+  }
+  Ok(())
+}
+
+/// MD5 digest of all settings which influence the preview rendering
+fn preview_settings_digest(params: &ConvertParams) -> [u8; 16] {
+  let settings = format!("{:?};{:?}", params.preview_params, params.profile.as_ref().map(|profile| &profile.name));
+  md5::compute(settings).0
+}
+
+pub(super) fn generate_preview(
+  rawfile: &RawSource,
+  decoder: &dyn Decoder,
+  rawimage: &RawImage,
+  raw_params: &RawDecodeParams,
+  params: &ConvertParams,
+) -> crate::Result<DynamicImage> {
+  if params.preview_params.source == PreviewSource::Camera {
+    match decoder.preview_image(rawfile, raw_params)? {
+      Some(image) => {
+        let raw_dim = rawimage.crop_area.or(rawimage.active_area).map(|area| area.d).unwrap_or_else(|| rawimage.dim());
+        let image = trim_letterbox(&image, raw_dim.w as f32 / raw_dim.h as f32).unwrap_or(image);
+        if params
+          .preview_params
+          .accept_size(image.width(), image.height(), raw_dim.w as u32, raw_dim.h as u32)
+        {
+          return Ok(image);
+        }
+        log::info!("Preview image is too small ({}x{}), generate sRGB from RAW", image.width(), image.height());
+      }
+      None => log::warn!("Preview image not found, try to generate sRGB from RAW"),
     }
   }
+  develop_preview(rawimage, params.profile.as_ref())
+}
+
+fn develop_preview(rawimage: &RawImage, profile: Option<&DcpProfile>) -> crate::Result<DynamicImage> {
+  let mut dev = RawDevelop::default();
+  if let Some(profile) = profile {
+    dev = dev.with_profile(profile.clone());
+  }
+  let image = dev.develop_intermediate(rawimage)?;
+  /*
+  let params = rawimage.develop_params()?;
+  let (srgbf, dim) = develop_raw_srgb(&rawimage.data, &params)?;
+  let output = convert_from_f32_scaled_u16(&srgbf, 0, u16::MAX);
+  let image = if srgbf.len() == dim.w * dim.h {
+    DynamicImage::ImageLuma16(ImageBuffer::from_raw(dim.w as u32, dim.h as u32, output).expect("Invalid ImageBuffer size"))
+  } else {
+    DynamicImage::ImageRgb16(ImageBuffer::from_raw(dim.w as u32, dim.h as u32, output).expect("Invalid ImageBuffer size"))
+  };
+   */
+  Ok(image.to_dynamic_image().ok_or("failed to convert to dynamic image")?)
 }
@@ -0,0 +1,713 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! DNG Camera Profiles (DCP)
+//!
+//! A DCP file is a TIFF file with the magic `IIRC` (or `MMCR` for big endian)
+//! and a single IFD which contains the DNG camera profile tags. The same tags
+//! are used when a profile is embedded into the root IFD of a DNG file.
+
+use std::{
+  collections::HashMap,
+  fs::File,
+  io::{BufReader, BufWriter, Cursor, Read, Seek, Write},
+  path::Path,
+};
+
+use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use rayon::prelude::*;
+
+use crate::{
+  RawlerError, Result,
+  bits::Endian,
+  formats::tiff::{DirectoryWriter, IFD, SRational, TiffWriter, Value},
+  imgop::{
+    matrix::{multiply, multiply_row1, pseudo_inverse},
+    srgb::{srgb_apply_gamma, srgb_invert_gamma},
+    xyz::{FlatColorMatrix, Illuminant, XYZ_TO_PROFOTORGB_D50},
+  },
+  pixarray::Color2D,
+  tags::DngTag,
+};
+
+/// Magic marker of DCP files, instead of 42 for TIFF
+pub const DCP_MAGIC: u16 = 0x4352;
+
+/// Linear sRGB (D65) to XYZ (D50), Bradford adapted
+/// http://www.brucelindbloom.com/Eqn_RGB_XYZ_Matrix.html
+#[allow(clippy::excessive_precision)]
+const SRGB_TO_XYZ_D50: [[f32; 3]; 3] = [
+  [0.4360747, 0.3850649, 0.1430804],
+  [0.2225045, 0.7168786, 0.0606169],
+  [0.0139322, 0.0971045, 0.7141733],
+];
+
+/// Tags which belong to a camera profile. These are removed from
+/// a DNG IFD before a profile is embedded.
+const PROFILE_TAGS: [DngTag; 29] = [
+  DngTag::CalibrationIlluminant1,
+  DngTag::CalibrationIlluminant2,
+  DngTag::CalibrationIlluminant3,
+  DngTag::IlluminantData1,
+  DngTag::IlluminantData2,
+  DngTag::IlluminantData3,
+  DngTag::ColorMatrix1,
+  DngTag::ColorMatrix2,
+  DngTag::ColorMatrix3,
+  DngTag::ForwardMatrix1,
+  DngTag::ForwardMatrix2,
+  DngTag::ForwardMatrix3,
+  DngTag::ReductionMatrix1,
+  DngTag::ReductionMatrix2,
+  DngTag::ProfileName,
+  DngTag::ProfileCopyright,
+  DngTag::ProfileEmbedPolicy,
+  DngTag::ProfileCalibrationSignature,
+  DngTag::ProfileHueSatMapDims,
+  DngTag::ProfileHueSatMapData1,
+  DngTag::ProfileHueSatMapData2,
+  DngTag::ProfileHueSatMapData3,
+  DngTag::ProfileHueSatMapEncoding,
+  DngTag::ProfileLookTableDims,
+  DngTag::ProfileLookTableData,
+  DngTag::ProfileLookTableEncoding,
+  DngTag::ProfileToneCurve,
+  DngTag::BaselineExposureOffset,
+  DngTag::DefaultBlackRender,
+];
+
+/// Usage rules for a profile (`ProfileEmbedPolicy`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProfileEmbedPolicy {
+  /// Profile can be embedded and copied to other files
+  #[default]
+  AllowCopying = 0,
+  /// Profile can be embedded, but not copied to other files
+  EmbedIfUsed = 1,
+  /// Profile must not be embedded
+  EmbedNever = 2,
+  /// No restrictions
+  NoRestrictions = 3,
+}
+
+impl TryFrom<u32> for ProfileEmbedPolicy {
+  type Error = RawlerError;
+
+  fn try_from(value: u32) -> Result<Self> {
+    Ok(match value {
+      0 => Self::AllowCopying,
+      1 => Self::EmbedIfUsed,
+      2 => Self::EmbedNever,
+      3 => Self::NoRestrictions,
+      _ => return Err(RawlerError::malformed("DCP", format!("Unknown ProfileEmbedPolicy: {}", value))),
+    })
+  }
+}
+
+/// Hue/saturation/value mapping table, used for `ProfileHueSatMap`
+/// and `ProfileLookTable`.
+///
+/// Each entry consists of a hue shift (in degrees), a saturation
+/// scale and a value scale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HsvTable {
+  pub hue_divisions: u32,
+  pub sat_divisions: u32,
+  pub val_divisions: u32,
+  pub data: Vec<f32>,
+  /// Value divisions are sRGB gamma encoded (encoding 1) instead of linear (encoding 0)
+  pub srgb_encoding: bool,
+}
+
+impl HsvTable {
+  fn dims(&self) -> [u32; 3] {
+    [self.hue_divisions, self.sat_divisions, self.val_divisions]
+  }
+
+  fn check(&self) -> Result<()> {
+    let expected = self.hue_divisions as usize * self.sat_divisions as usize * self.val_divisions.max(1) as usize * 3;
+    if self.hue_divisions == 0 || self.sat_divisions < 2 || self.data.len() != expected {
+      return Err(RawlerError::malformed(
+        "DCP",
+        format!(
+          "HSV table with dimension {:?} has {} values, expected {}",
+          self.dims(),
+          self.data.len(),
+          expected
+        ),
+      ));
+    }
+    Ok(())
+  }
+
+  fn entry(&self, h: usize, s: usize, v: usize) -> &[f32] {
+    let idx = ((v * self.hue_divisions as usize + h) * self.sat_divisions as usize + s) * 3;
+    &self.data[idx..idx + 3]
+  }
+
+  /// Lookup (interpolated) modification for hue in range 0..6,
+  /// saturation and value in range 0..1.
+  fn lookup(&self, hue: f32, sat: f32, val: f32) -> [f32; 3] {
+    let (hd, sd, vd) = (self.hue_divisions as usize, self.sat_divisions as usize, self.val_divisions.max(1) as usize);
+    let val = if self.srgb_encoding { srgb_apply_gamma(val.clamp(0.0, 1.0)) } else { val };
+
+    let hf = if hd < 2 { 0.0 } else { hue * hd as f32 / 6.0 };
+    let h0 = (hf.floor() as usize) % hd;
+    let h1 = (h0 + 1) % hd;
+    let fh = hf - hf.floor();
+
+    let sf = sat.clamp(0.0, 1.0) * (sd - 1) as f32;
+    let s0 = (sf.floor() as usize).min(sd - 1);
+    let s1 = (s0 + 1).min(sd - 1);
+    let fs = sf - s0 as f32;
+
+    let vf = val.clamp(0.0, 1.0) * (vd - 1) as f32;
+    let v0 = (vf.floor() as usize).min(vd - 1);
+    let v1 = (v0 + 1).min(vd - 1);
+    let fv = vf - v0 as f32;
+
+    let mut result = [0.0; 3];
+    for (v, wv) in [(v0, 1.0 - fv), (v1, fv)] {
+      for (h, wh) in [(h0, 1.0 - fh), (h1, fh)] {
+        for (s, ws) in [(s0, 1.0 - fs), (s1, fs)] {
+          let weight = wv * wh * ws;
+          if weight > 0.0 {
+            for (r, e) in result.iter_mut().zip(self.entry(h, s, v)) {
+              *r += weight * e;
+            }
+          }
+        }
+      }
+    }
+    result
+  }
+
+  /// Apply table to a linear RGB pixel
+  pub(crate) fn apply(&self, rgb: [f32; 3]) -> [f32; 3] {
+    let (hue, sat, val) = rgb_to_hsv(rgb);
+    let [hue_shift, sat_scale, val_scale] = self.lookup(hue, sat, val);
+    let hue = (hue + hue_shift * 6.0 / 360.0).rem_euclid(6.0);
+    let sat = (sat * sat_scale).clamp(0.0, 1.0);
+    let val = val * val_scale;
+    hsv_to_rgb(hue, sat, val)
+  }
+}
+
+/// Matrices and tables for a single calibration illuminant
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileCalibration {
+  pub illuminant: Illuminant,
+  /// XYZ to camera matrix
+  pub color_matrix: Option<FlatColorMatrix>,
+  /// White balanced camera to XYZ (D50) matrix
+  pub forward_matrix: Option<FlatColorMatrix>,
+  pub hue_sat_map: Option<HsvTable>,
+  /// Raw `IlluminantData` for [`Illuminant::Other`], in the byte order of the source profile
+  pub illuminant_data: Option<Vec<u8>>,
+}
+
+/// DNG camera profile
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DcpProfile {
+  pub name: Option<String>,
+  pub unique_camera_model: Option<String>,
+  pub copyright: Option<String>,
+  pub calibration_signature: Option<String>,
+  pub embed_policy: ProfileEmbedPolicy,
+  /// Up to three calibrations, in order of `CalibrationIlluminant1..3`
+  pub calibrations: Vec<ProfileCalibration>,
+  pub look_table: Option<HsvTable>,
+  /// Tone curve as (input, output) pairs in range 0..1
+  pub tone_curve: Option<Vec<(f32, f32)>>,
+  pub baseline_exposure_offset: Option<f32>,
+  pub default_black_render: Option<u32>,
+}
+
+impl DcpProfile {
+  /// Read a DCP file
+  pub fn read_file(path: &Path) -> Result<Self> {
+    let mut reader = BufReader::new(File::open(path).map_err(|err| RawlerError::with_io_error("DcpProfile::read_file()", path, err))?);
+    let mut data = Vec::new();
+    reader
+      .read_to_end(&mut data)
+      .map_err(|err| RawlerError::with_io_error("DcpProfile::read_file()", path, err))?;
+    Self::from_bytes(&data)
+  }
+
+  /// Parse a DCP file from a buffer
+  pub fn from_bytes(data: &[u8]) -> Result<Self> {
+    let mut cursor = Cursor::new(data);
+    let (endian, magic, offset) = match data.get(0..2) {
+      Some(b"II") => (
+        Endian::Little,
+        read_u16_at::<LittleEndian>(&mut cursor, 2)?,
+        read_u32_at::<LittleEndian>(&mut cursor, 4)?,
+      ),
+      Some(b"MM") => (
+        Endian::Big,
+        read_u16_at::<BigEndian>(&mut cursor, 2)?,
+        read_u32_at::<BigEndian>(&mut cursor, 4)?,
+      ),
+      _ => return Err(RawlerError::malformed("DCP", "Invalid byte order marker")),
+    };
+    if magic != DCP_MAGIC {
+      return Err(RawlerError::malformed("DCP", format!("Invalid magic marker: 0x{:x}", magic)));
+    }
+    let ifd = IFD::new(&mut cursor, offset, 0, 0, endian, &[])?;
+    Self::from_ifd(&ifd)
+  }
+
+  /// Read profile from an IFD (DCP root IFD or DNG root IFD)
+  pub fn from_ifd(ifd: &IFD) -> Result<Self> {
+    let string = |tag: DngTag| -> Option<String> {
+      ifd.get_entry(tag).and_then(|entry| match &entry.value {
+        Value::Ascii(ascii) => ascii.first().cloned(),
+        Value::Byte(data) | Value::Undefined(data) => Some(String::from_utf8_lossy(data).trim_end_matches('\0').to_string()),
+        _ => None,
+      })
+    };
+    let floats = |tag: DngTag| -> Option<Vec<f32>> {
+      ifd
+        .get_entry(tag)
+        .map(|entry| (0..entry.count() as usize).map(|i| entry.value.force_f32(i)).collect())
+    };
+
+    let hue_sat_dims = ifd.get_entry(DngTag::ProfileHueSatMapDims).map(|entry| entry.value.clone());
+    let hue_sat_srgb = ifd
+      .get_entry(DngTag::ProfileHueSatMapEncoding)
+      .map(|entry| entry.force_u32(0) == 1)
+      .unwrap_or(false);
+    let hsv_table = |dims: &Value, data: Vec<f32>, srgb_encoding: bool| -> Result<HsvTable> {
+      let table = HsvTable {
+        hue_divisions: dims.force_u32(0),
+        sat_divisions: dims.force_u32(1),
+        val_divisions: if dims.count() > 2 { dims.force_u32(2) } else { 1 },
+        data,
+        srgb_encoding,
+      };
+      table.check()?;
+      Ok(table)
+    };
+
+    let mut calibrations = Vec::new();
+    for (illu_tag, cm_tag, fm_tag, hsm_tag, data_tag) in [
+      (
+        DngTag::CalibrationIlluminant1,
+        DngTag::ColorMatrix1,
+        DngTag::ForwardMatrix1,
+        DngTag::ProfileHueSatMapData1,
+        DngTag::IlluminantData1,
+      ),
+      (
+        DngTag::CalibrationIlluminant2,
+        DngTag::ColorMatrix2,
+        DngTag::ForwardMatrix2,
+        DngTag::ProfileHueSatMapData2,
+        DngTag::IlluminantData2,
+      ),
+      (
+        DngTag::CalibrationIlluminant3,
+        DngTag::ColorMatrix3,
+        DngTag::ForwardMatrix3,
+        DngTag::ProfileHueSatMapData3,
+        DngTag::IlluminantData3,
+      ),
+    ] {
+      let color_matrix = floats(cm_tag);
+      let forward_matrix = floats(fm_tag);
+      let hue_sat_map = match (&hue_sat_dims, floats(hsm_tag)) {
+        (Some(dims), Some(data)) => Some(hsv_table(dims, data, hue_sat_srgb)?),
+        _ => None,
+      };
+      if color_matrix.is_none() && forward_matrix.is_none() && hue_sat_map.is_none() {
+        continue;
+      }
+      for matrix in [&color_matrix, &forward_matrix].into_iter().flatten() {
+        if matrix.len() % 3 != 0 || matrix.len() > 12 {
+          return Err(RawlerError::malformed(
+            "DCP",
+            format!("Invalid matrix with {} values for {:?}", matrix.len(), illu_tag),
+          ));
+        }
+      }
+      let illuminant_value = ifd.get_entry(illu_tag).map(|entry| entry.force_u16(0)).unwrap_or(Illuminant::Unknown.into());
+      let illuminant = Illuminant::try_from(illuminant_value).map_err(|err| RawlerError::unsupported_feature("DCP", err))?;
+      calibrations.push(ProfileCalibration {
+        illuminant,
+        color_matrix,
+        forward_matrix,
+        hue_sat_map,
+        illuminant_data: ifd.get_entry(data_tag).and_then(|entry| match &entry.value {
+          Value::Byte(data) | Value::Undefined(data) => Some(data.clone()),
+          _ => None,
+        }),
+      });
+    }
+
+    let look_table = match (ifd.get_entry(DngTag::ProfileLookTableDims), floats(DngTag::ProfileLookTableData)) {
+      (Some(dims), Some(data)) => {
+        let srgb = ifd
+          .get_entry(DngTag::ProfileLookTableEncoding)
+          .map(|entry| entry.force_u32(0) == 1)
+          .unwrap_or(false);
+        Some(hsv_table(&dims.value, data, srgb)?)
+      }
+      _ => None,
+    };
+
+    let tone_curve = floats(DngTag::ProfileToneCurve).map(|points| points.chunks_exact(2).map(|p| (p[0], p[1])).collect());
+
+    Ok(Self {
+      name: string(DngTag::ProfileName),
+      unique_camera_model: string(DngTag::UniqueCameraModel),
+      copyright: string(DngTag::ProfileCopyright),
+      calibration_signature: string(DngTag::ProfileCalibrationSignature),
+      embed_policy: match ifd.get_entry(DngTag::ProfileEmbedPolicy) {
+        Some(entry) => ProfileEmbedPolicy::try_from(entry.force_u32(0))?,
+        None => ProfileEmbedPolicy::default(),
+      },
+      calibrations,
+      look_table,
+      tone_curve,
+      baseline_exposure_offset: ifd.get_entry(DngTag::BaselineExposureOffset).map(|entry| entry.value.force_f32(0)),
+      default_black_render: ifd.get_entry(DngTag::DefaultBlackRender).map(|entry| entry.force_u32(0)),
+    })
+  }
+
+  /// Write profile as DCP file
+  pub fn write<W: Write + Seek>(&self, writer: W) -> Result<()> {
+    let dcp = TiffWriter::new_with_magic(writer, DCP_MAGIC)?;
+    let mut ifd = dcp.new_directory();
+    if let Some(model) = &self.unique_camera_model {
+      ifd.add_tag(DngTag::UniqueCameraModel, model.as_str());
+    }
+    self.write_tags(&mut ifd);
+    dcp.build(ifd)?;
+    Ok(())
+  }
+
+  /// Write profile as DCP file to path
+  pub fn write_file(&self, path: &Path) -> Result<()> {
+    let file = File::create(path).map_err(|err| RawlerError::with_io_error("DcpProfile::write_file()", path, err))?;
+    let mut writer = BufWriter::new(file);
+    self.write(&mut writer)?;
+    writer
+      .flush()
+      .map_err(|err| RawlerError::with_io_error("DcpProfile::write_file()", path, err))?;
+    Ok(())
+  }
+
+  /// Add all profile tags to an IFD
+  pub fn write_tags(&self, ifd: &mut DirectoryWriter) {
+    let illuminant_tags = [DngTag::CalibrationIlluminant1, DngTag::CalibrationIlluminant2, DngTag::CalibrationIlluminant3];
+    let cm_tags = [DngTag::ColorMatrix1, DngTag::ColorMatrix2, DngTag::ColorMatrix3];
+    let fm_tags = [DngTag::ForwardMatrix1, DngTag::ForwardMatrix2, DngTag::ForwardMatrix3];
+    let hsm_tags = [DngTag::ProfileHueSatMapData1, DngTag::ProfileHueSatMapData2, DngTag::ProfileHueSatMapData3];
+    let data_tags = [DngTag::IlluminantData1, DngTag::IlluminantData2, DngTag::IlluminantData3];
+    for (i, calibration) in self.calibrations.iter().take(3).enumerate() {
+      ifd.add_tag(illuminant_tags[i], u16::from(calibration.illuminant));
+      if let Some(matrix) = &calibration.color_matrix {
+        ifd.add_tag(cm_tags[i], matrix_to_srational(matrix).as_slice());
+      }
+      if let Some(matrix) = &calibration.forward_matrix {
+        ifd.add_tag(fm_tags[i], matrix_to_srational(matrix).as_slice());
+      }
+      if let Some(table) = &calibration.hue_sat_map {
+        ifd.add_tag(DngTag::ProfileHueSatMapDims, &table.dims()[..]);
+        ifd.add_tag(DngTag::ProfileHueSatMapEncoding, table.srgb_encoding as u32);
+        ifd.add_tag(hsm_tags[i], table.data.as_slice());
+      }
+      if let Some(data) = &calibration.illuminant_data {
+        ifd.add_tag_undefined(data_tags[i], data.clone());
+      }
+    }
+    if let Some(name) = &self.name {
+      ifd.add_tag(DngTag::ProfileName, name.as_str());
+    }
+    if let Some(copyright) = &self.copyright {
+      ifd.add_tag(DngTag::ProfileCopyright, copyright.as_str());
+    }
+    if let Some(signature) = &self.calibration_signature {
+      ifd.add_tag(DngTag::ProfileCalibrationSignature, signature.as_str());
+    }
+    ifd.add_tag(DngTag::ProfileEmbedPolicy, self.embed_policy as u32);
+    if let Some(table) = &self.look_table {
+      ifd.add_tag(DngTag::ProfileLookTableDims, &table.dims()[..]);
+      ifd.add_tag(DngTag::ProfileLookTableEncoding, table.srgb_encoding as u32);
+      ifd.add_tag(DngTag::ProfileLookTableData, table.data.as_slice());
+    }
+    if let Some(curve) = &self.tone_curve {
+      let points: Vec<f32> = curve.iter().flat_map(|(x, y)| [*x, *y]).collect();
+      ifd.add_tag(DngTag::ProfileToneCurve, points.as_slice());
+    }
+    if let Some(offset) = self.baseline_exposure_offset {
+      ifd.add_tag(DngTag::BaselineExposureOffset, SRational::new((offset * 100.0).round() as i32, 100));
+    }
+    if let Some(render) = self.default_black_render {
+      ifd.add_tag(DngTag::DefaultBlackRender, render);
+    }
+  }
+
+  /// Embed profile into a DNG root IFD, replacing any existing
+  /// color matrices and profile tags.
+  pub fn embed(&self, ifd: &mut DirectoryWriter) -> Result<()> {
+    if self.embed_policy == ProfileEmbedPolicy::EmbedNever {
+      return Err(RawlerError::unsupported_feature(
+        "DCP",
+        format!("embedding of profile '{}' (policy: embed never)", self.name.as_deref().unwrap_or_default()),
+      ));
+    }
+    if self.calibrations.iter().all(|calibration| calibration.color_matrix.is_none()) {
+      return Err(RawlerError::malformed("DCP", "Profile has no color matrix"));
+    }
+    for tag in PROFILE_TAGS {
+      ifd.remove_tag(tag);
+    }
+    self.write_tags(ifd);
+    Ok(())
+  }
+
+  /// Color matrices (XYZ to camera) for all calibration illuminants
+  pub fn color_matrices(&self) -> HashMap<Illuminant, FlatColorMatrix> {
+    self
+      .calibrations
+      .iter()
+      .filter_map(|calibration| calibration.color_matrix.clone().map(|matrix| (calibration.illuminant, matrix)))
+      .collect()
+  }
+
+  /// Returns true if profile contains tables or curves
+  /// which modify the rendering after color matrix transformation.
+  pub fn has_rendering_tables(&self) -> bool {
+    self.look_table.is_some() || self.tone_curve.is_some() || self.calibrations.iter().any(|calibration| calibration.hue_sat_map.is_some())
+  }
+
+  /// Apply hue/sat map (for the given illuminant, or the first available),
+  /// look table and tone curve to linear sRGB pixels.
+  ///
+  /// DNG defines these operations in linear ProPhoto RGB space, so pixels are
+  /// converted forth and back.
+  pub fn apply_rendering(&self, pixels: &mut Color2D<f32, 3>, illuminant: Illuminant) {
+    let hue_sat_map = self
+      .calibrations
+      .iter()
+      .find(|calibration| calibration.illuminant == illuminant && calibration.hue_sat_map.is_some())
+      .or_else(|| self.calibrations.iter().find(|calibration| calibration.hue_sat_map.is_some()))
+      .and_then(|calibration| calibration.hue_sat_map.as_ref());
+    let tone_curve = self.tone_curve.as_ref().map(|curve| ToneCurve::new(curve));
+    let srgb_to_prophoto = multiply(&XYZ_TO_PROFOTORGB_D50, &SRGB_TO_XYZ_D50);
+    let prophoto_to_srgb = pseudo_inverse(srgb_to_prophoto);
+
+    pixels.data.par_iter_mut().for_each(|pix| {
+      let mut rgb = multiply_row1(&srgb_to_prophoto, pix);
+      if let Some(table) = hue_sat_map {
+        rgb = table.apply(rgb);
+      }
+      if let Some(table) = &self.look_table {
+        rgb = table.apply(rgb);
+      }
+      if let Some(curve) = &tone_curve {
+        rgb = rgb.map(|v| curve.apply(v));
+      }
+      *pix = multiply_row1(&prophoto_to_srgb, &rgb).map(|v| v.clamp(0.0, 1.0));
+    });
+  }
+}
+
+/// Piecewise linear tone curve
+struct ToneCurve {
+  points: Vec<(f32, f32)>,
+}
+
+impl ToneCurve {
+  fn new(points: &[(f32, f32)]) -> Self {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Self { points }
+  }
+
+  fn apply(&self, v: f32) -> f32 {
+    // Curve is defined on gamma encoded values
+    let x = srgb_apply_gamma(v.clamp(0.0, 1.0));
+    let y = match self.points.iter().position(|p| p.0 >= x) {
+      None => self.points.last().map(|p| p.1).unwrap_or(x),
+      Some(0) => self.points[0].1,
+      Some(i) => {
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        if b.0 > a.0 { a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0) } else { b.1 }
+      }
+    };
+    srgb_invert_gamma(y)
+  }
+}
+
+fn matrix_to_srational(matrix: &[f32]) -> Vec<SRational> {
+  matrix.iter().map(|v| SRational::new((v * 10_000.0).round() as i32, 10_000)).collect()
+}
+
+fn read_u16_at<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>, pos: u64) -> Result<u16> {
+  cursor.set_position(pos);
+  cursor.read_u16::<E>().map_err(|_| RawlerError::malformed("DCP", "File too short"))
+}
+
+fn read_u32_at<E: byteorder::ByteOrder>(cursor: &mut Cursor<&[u8]>, pos: u64) -> Result<u32> {
+  cursor.set_position(pos);
+  cursor.read_u32::<E>().map_err(|_| RawlerError::malformed("DCP", "File too short"))
+}
+
+/// RGB to HSV with hue in range 0..6
+pub(crate) fn rgb_to_hsv([r, g, b]: [f32; 3]) -> (f32, f32, f32) {
+  let max = r.max(g).max(b);
+  let min = r.min(g).min(b);
+  let delta = max - min;
+  if max <= 0.0 || delta <= 0.0 {
+    return (0.0, 0.0, max);
+  }
+  let hue = if r == max {
+    (g - b) / delta
+  } else if g == max {
+    2.0 + (b - r) / delta
+  } else {
+    4.0 + (r - g) / delta
+  };
+  (hue.rem_euclid(6.0), delta / max, max)
+}
+
+pub(crate) fn hsv_to_rgb(hue: f32, sat: f32, val: f32) -> [f32; 3] {
+  let sector = hue.floor();
+  let f = hue - sector;
+  let p = val * (1.0 - sat);
+  let q = val * (1.0 - sat * f);
+  let t = val * (1.0 - sat * (1.0 - f));
+  match sector as i32 {
+    0 => [val, t, p],
+    1 => [q, val, p],
+    2 => [p, val, t],
+    3 => [p, q, val],
+    4 => [t, p, val],
+    _ => [val, p, q],
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::formats::tiff::GenericTiffReader;
+
+  fn sample_profile() -> DcpProfile {
+    DcpProfile {
+      name: Some("Studio Profile".into()),
+      unique_camera_model: Some("Test Camera".into()),
+      copyright: Some("Copyright Studio".into()),
+      calibration_signature: None,
+      embed_policy: ProfileEmbedPolicy::NoRestrictions,
+      calibrations: vec![
+        ProfileCalibration {
+          illuminant: Illuminant::A,
+          color_matrix: Some(vec![1.1, -0.2, 0.05, -0.3, 1.2, 0.1, 0.0, 0.1, 0.6]),
+          forward_matrix: Some(vec![0.7, 0.1, 0.15, 0.3, 0.7, 0.0, 0.0, 0.05, 0.8]),
+          hue_sat_map: Some(HsvTable {
+            hue_divisions: 6,
+            sat_divisions: 2,
+            val_divisions: 1,
+            data: [0.0, 1.0, 1.0].repeat(12),
+            srgb_encoding: false,
+          }),
+          illuminant_data: None,
+        },
+        ProfileCalibration {
+          illuminant: Illuminant::D65,
+          color_matrix: Some(vec![0.9, -0.25, 0.0, -0.4, 1.3, 0.1, -0.05, 0.2, 0.7]),
+          forward_matrix: None,
+          hue_sat_map: Some(HsvTable {
+            hue_divisions: 6,
+            sat_divisions: 2,
+            val_divisions: 1,
+            data: [5.0, 1.1, 1.0].repeat(12),
+            srgb_encoding: false,
+          }),
+          illuminant_data: None,
+        },
+        ProfileCalibration {
+          illuminant: Illuminant::Other,
+          color_matrix: Some(vec![1.0, -0.3, 0.0, -0.45, 1.35, 0.1, -0.1, 0.25, 0.75]),
+          forward_matrix: None,
+          hue_sat_map: None,
+          // Chromaticity x = 0.3, y = 0.32
+          illuminant_data: Some([0, 0, 0, 0, 30, 0, 0, 0, 100, 0, 0, 0, 32, 0, 0, 0, 100, 0, 0, 0].to_vec()),
+        },
+      ],
+      look_table: None,
+      tone_curve: Some(vec![(0.0, 0.0), (0.5, 0.6), (1.0, 1.0)]),
+      baseline_exposure_offset: Some(0.25),
+      default_black_render: None,
+    }
+  }
+
+  #[test]
+  fn dcp_write_read_roundtrip() -> Result<()> {
+    let profile = sample_profile();
+    let mut buf = Cursor::new(Vec::new());
+    profile.write(&mut buf)?;
+    let data = buf.into_inner();
+    #[cfg(target_endian = "little")]
+    assert_eq!(&data[0..4], b"IIRC");
+    let parsed = DcpProfile::from_bytes(&data)?;
+    assert_eq!(parsed, profile);
+    Ok(())
+  }
+
+  #[test]
+  fn dcp_rejects_tiff_magic() {
+    let mut buf = Cursor::new(Vec::new());
+    let tiff = TiffWriter::new(&mut buf).expect("TIFF writer");
+    let mut ifd = tiff.new_directory();
+    ifd.add_tag(DngTag::ProfileName, "x");
+    tiff.build(ifd).expect("TIFF build");
+    assert!(DcpProfile::from_bytes(buf.get_ref()).is_err());
+  }
+
+  #[test]
+  fn embed_replaces_color_matrices() -> Result<()> {
+    let mut buf = Cursor::new(Vec::new());
+    let tiff = TiffWriter::new(&mut buf)?;
+    let mut ifd = tiff.new_directory();
+    ifd.add_tag(DngTag::CalibrationIlluminant1, u16::from(Illuminant::D50));
+    ifd.add_tag(DngTag::ColorMatrix1, [SRational::new(1, 1); 9].as_slice());
+    ifd.add_tag(DngTag::CalibrationIlluminant3, u16::from(Illuminant::D55));
+    ifd.add_tag(DngTag::ColorMatrix3, [SRational::new(1, 1); 9].as_slice());
+    ifd.add_tag_undefined(DngTag::IlluminantData1, vec![1, 2, 3, 4]);
+    let profile = sample_profile();
+    profile.embed(&mut ifd)?;
+    tiff.build(ifd)?;
+
+    let tiff = GenericTiffReader::new_with_buffer(buf.get_ref(), 0, 0, None)?;
+    let embedded = DcpProfile::from_ifd(crate::formats::tiff::reader::TiffReader::root_ifd(&tiff))?;
+    assert_eq!(embedded.calibrations, profile.calibrations);
+    assert_eq!(embedded.name, profile.name);
+
+    let never = DcpProfile {
+      embed_policy: ProfileEmbedPolicy::EmbedNever,
+      ..profile
+    };
+    assert!(never.embed(&mut DirectoryWriter::new()).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn hsv_table_and_tone_curve() {
+    let profile = sample_profile();
+    let mut pixels = Color2D::<f32, 3>::new_with(vec![[0.5, 0.25, 0.1], [0.2, 0.2, 0.2]], 2, 1);
+    let original = pixels.data.clone();
+    profile.apply_rendering(&mut pixels, Illuminant::A);
+    // Identity table for illuminant A, neutral pixel stays neutral but brighter by tone curve
+    let grey = pixels.data[1];
+    assert!((grey[0] - grey[1]).abs() < 1.0e-4 && (grey[1] - grey[2]).abs() < 1.0e-4);
+    assert!(grey[0] > original[1][0]);
+
+    let rgb = [0.5, 0.25, 0.1];
+    let (h, s, v) = rgb_to_hsv(rgb);
+    let back = hsv_to_rgb(h, s, v);
+    assert!(rgb.iter().zip(back.iter()).all(|(a, b)| (a - b).abs() < 1.0e-6));
+  }
+}
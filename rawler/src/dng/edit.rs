@@ -0,0 +1,365 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! In-place metadata editing of DNG files
+//!
+//! Only the root IFD is rewritten. The new IFD and all changed values are
+//! appended to the end of the file, unchanged values keep their location.
+//! Image data, SubIFDs and the embedded original raw file are never touched.
+//! The TIFF header is updated as the last step, so an interrupted edit
+//! leaves the previous state of the file intact.
+
+use std::{
+  fs::OpenOptions,
+  io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+  path::Path,
+};
+
+use byteorder::{NativeEndian, ReadBytesExt};
+
+use crate::{
+  RawlerError, Result,
+  bits::Endian,
+  decoders::Orientation,
+  formats::tiff::{DirectoryWriter, IFD, Rational, TiffWriter, Value},
+  tags::{DngTag, ExifGpsTag, ExifTag, TiffCommonTag, TiffTag},
+};
+
+use super::dcp::DcpProfile;
+
+/// TIFF type for IFD offsets
+const TYPE_IFD: u16 = 13;
+
+/// GPS position in decimal degrees
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpsPosition {
+  pub latitude: f64,
+  pub longitude: f64,
+  /// Altitude in meters above sea level
+  pub altitude: Option<f64>,
+}
+
+/// Metadata changes for an existing DNG
+///
+/// Fields set to `None` are left unchanged. Empty strings remove
+/// the corresponding tag.
+#[derive(Clone, Debug, Default)]
+pub struct DngMetadataEdit {
+  pub artist: Option<String>,
+  pub copyright: Option<String>,
+  pub image_description: Option<String>,
+  pub orientation: Option<Orientation>,
+  pub gps: Option<GpsPosition>,
+  /// Remove GPS information, ignored if `gps` is set
+  pub remove_gps: bool,
+  /// Neutral white balance values (one per color plane)
+  pub as_shot_neutral: Option<Vec<f32>>,
+  /// XMP packet
+  pub xmp: Option<Vec<u8>>,
+  /// Camera profile which replaces all existing profile tags
+  pub profile: Option<DcpProfile>,
+}
+
+impl DngMetadataEdit {
+  /// Check if no change is requested
+  pub fn is_empty(&self) -> bool {
+    self.artist.is_none()
+      && self.copyright.is_none()
+      && self.image_description.is_none()
+      && self.orientation.is_none()
+      && self.gps.is_none()
+      && !self.remove_gps
+      && self.as_shot_neutral.is_none()
+      && self.xmp.is_none()
+      && self.profile.is_none()
+  }
+
+  /// Apply changes to the root IFD of the DNG
+  fn apply<W: Write + Seek>(&self, tiff: &mut TiffWriter<W>, root: &IFD, ifd: &mut DirectoryWriter) -> Result<()> {
+    set_or_remove(ifd, ExifTag::Artist, &self.artist);
+    set_or_remove(ifd, ExifTag::Copyright, &self.copyright);
+    set_or_remove(ifd, ExifTag::ImageDescription, &self.image_description);
+
+    if let Some(orientation) = self.orientation {
+      if orientation == Orientation::Unknown {
+        return Err(RawlerError::unsupported_feature("DNG", "Orientation must be known"));
+      }
+      ifd.add_tag(TiffCommonTag::Orientation, orientation.to_u16());
+    }
+
+    if let Some(neutral) = &self.as_shot_neutral {
+      if neutral.is_empty() || neutral.len() > 4 || neutral.iter().any(|v| !v.is_finite() || *v <= 0.0) {
+        return Err(RawlerError::unsupported_feature("DNG", format!("AsShotNeutral values {:?}", neutral)));
+      }
+      let values: Vec<Rational> = neutral.iter().map(|v| Rational::new_f32(*v, 100000)).collect();
+      // AsShotNeutral and AsShotWhiteXY are mutually exclusive
+      ifd.remove_tag(DngTag::AsShotWhiteXY);
+      ifd.add_tag(DngTag::AsShotNeutral, values.as_slice());
+    }
+
+    if let Some(xmp) = &self.xmp {
+      ifd.add_tag(ExifTag::ApplicationNotes, xmp.as_slice());
+    }
+
+    if let Some(profile) = &self.profile {
+      let camera_model = root
+        .get_entry(DngTag::UniqueCameraModel)
+        .and_then(|entry| entry.value.as_string().cloned())
+        .unwrap_or_default();
+      if let Some(profile_model) = &profile.unique_camera_model
+        && !profile_model.eq_ignore_ascii_case(&camera_model)
+      {
+        log::warn!("Camera profile is made for '{}', but image is from '{}'", profile_model, camera_model);
+      }
+      profile.embed(ifd)?;
+    }
+
+    if let Some(gps) = &self.gps {
+      let offset = gps_ifd(gps)?.build(tiff)?;
+      ifd.add_tag(ExifTag::GPSInfo, offset);
+    } else if self.remove_gps {
+      ifd.remove_tag(ExifTag::GPSInfo);
+    }
+    Ok(())
+  }
+}
+
+/// Apply metadata changes to a DNG stream in place
+///
+/// The stream must contain a complete DNG file in native byte order.
+pub fn edit_dng<F>(file: &mut F, edit: &DngMetadataEdit) -> Result<()>
+where
+  F: Read + Write + Seek,
+{
+  let root = read_root_ifd(file)?;
+  append_root_ifd(&root, file, edit)
+}
+
+/// Apply metadata changes to a DNG file in place
+pub fn edit_dng_file(path: &Path, edit: &DngMetadataEdit) -> Result<()> {
+  let file = OpenOptions::new()
+    .read(true)
+    .write(true)
+    .open(path)
+    .map_err(|err| RawlerError::with_io_error("edit_dng_file()", path, err))?;
+  let root = read_root_ifd(&mut BufReader::new(&file))?;
+  let mut writer = BufWriter::new(&file);
+  append_root_ifd(&root, &mut writer, edit)?;
+  writer.flush().map_err(|err| RawlerError::with_io_error("edit_dng_file()", path, err))?;
+  Ok(())
+}
+
+/// Read root IFD and check if the file can be edited
+fn read_root_ifd<R: Read + Seek>(file: &mut R) -> Result<IFD> {
+  file.seek(SeekFrom::Start(0))?;
+  let mut root = IFD::new_root_with_correction(file, 0, 0, 0, 0, &[])?;
+  if !root.has_entry(DngTag::DNGVersion) {
+    return Err(RawlerError::unsupported_feature("DNG", "File is not a DNG"));
+  }
+  let native = if cfg!(target_endian = "little") { Endian::Little } else { Endian::Big };
+  if root.endian != native {
+    return Err(RawlerError::unsupported_feature(
+      "DNG",
+      format!("editing files in {:?} endian byte order", root.endian),
+    ));
+  }
+  // Offsets of type IFD are parsed as unknown bytes, convert them to LONG
+  for entry in root.entries.values_mut() {
+    if let Value::Unknown(TYPE_IFD, ref data) = entry.value {
+      let count = data.len();
+      let mut offset = entry.offset().unwrap_or_default() as u64;
+      if (2..=4).contains(&count) {
+        // Parser assumed inline data, but it's a pointer
+        file.seek(SeekFrom::Start(offset))?;
+        offset = file.read_u32::<NativeEndian>()? as u64;
+      }
+      let mut offsets = vec![0; count];
+      file.seek(SeekFrom::Start(offset))?;
+      file.read_u32_into::<NativeEndian>(&mut offsets)?;
+      entry.value = Value::Long(offsets);
+      entry.embedded = Some(offset as u32);
+    }
+  }
+  Ok(root)
+}
+
+/// Write edited copy of the root IFD to the end of the file and
+/// make it the new root IFD
+fn append_root_ifd<W: Write + Seek>(root: &IFD, file: W, edit: &DngMetadataEdit) -> Result<()> {
+  let mut ifd = DirectoryWriter::new();
+  for (tag, entry) in root.entries() {
+    match (&entry.value, entry.offset()) {
+      // Strings may change their size when rewritten, so never reuse them
+      (Value::Ascii(_), _) | (_, None) => ifd.add_untyped_tag(*tag, entry.value.clone()),
+      (value, Some(offset)) => ifd.add_stored_tag(*tag, value.clone(), offset as u32),
+    }
+  }
+  ifd.set_next_ifd(root.next_ifd());
+
+  let mut tiff = TiffWriter::new_append(file)?;
+  edit.apply(&mut tiff, root, &mut ifd)?;
+  tiff.build(ifd)?;
+  Ok(())
+}
+
+/// Set string tag, remove it for empty strings
+fn set_or_remove<T: TiffTag>(ifd: &mut DirectoryWriter, tag: T, value: &Option<String>) {
+  match value.as_deref() {
+    Some("") => ifd.remove_tag(tag),
+    Some(value) => ifd.add_tag(tag, value),
+    None => {}
+  }
+}
+
+/// Build GPS IFD for position
+fn gps_ifd(gps: &GpsPosition) -> Result<DirectoryWriter> {
+  if !(-90.0..=90.0).contains(&gps.latitude) || !(-180.0..=180.0).contains(&gps.longitude) {
+    return Err(RawlerError::unsupported_feature(
+      "DNG",
+      format!("GPS position {}, {} is out of range", gps.latitude, gps.longitude),
+    ));
+  }
+  let mut ifd = DirectoryWriter::new();
+  ifd.add_tag(ExifGpsTag::GPSVersionID, [2_u8, 3, 0, 0]);
+  ifd.add_tag(ExifGpsTag::GPSLatitudeRef, if gps.latitude < 0.0 { "S" } else { "N" });
+  ifd.add_tag(ExifGpsTag::GPSLatitude, degrees_to_dms(gps.latitude));
+  ifd.add_tag(ExifGpsTag::GPSLongitudeRef, if gps.longitude < 0.0 { "W" } else { "E" });
+  ifd.add_tag(ExifGpsTag::GPSLongitude, degrees_to_dms(gps.longitude));
+  if let Some(altitude) = gps.altitude {
+    ifd.add_tag(ExifGpsTag::GPSAltitudeRef, if altitude < 0.0 { 1_u8 } else { 0 });
+    ifd.add_tag(ExifGpsTag::GPSAltitude, Rational::new((altitude.abs() * 100.0).round() as u32, 100));
+  }
+  Ok(ifd)
+}
+
+/// Convert decimal degrees to degrees, minutes and seconds
+fn degrees_to_dms(value: f64) -> [Rational; 3] {
+  let value = value.abs();
+  let degrees = value.trunc();
+  let minutes = ((value - degrees) * 60.0).trunc();
+  let seconds = ((value - degrees) * 60.0 - minutes) * 60.0;
+  [
+    Rational::new(degrees as u32, 1),
+    Rational::new(minutes as u32, 1),
+    Rational::new((seconds * 10000.0).round() as u32, 10000),
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+  use crate::devtools::synthetic::{add_camera, assert_pixels, build_std_tiff, decode, dng::encode_multi, test_pattern};
+
+  fn root_string(root: &IFD, tag: ExifTag) -> Option<String> {
+    root.get_entry(tag).and_then(|entry| entry.value.as_string().cloned())
+  }
+
+  #[test]
+  fn edit_dng_metadata() -> Result<()> {
+    let pattern = test_pattern(64, 48, 16);
+    let preview = test_pattern(32, 24, 16);
+    let original = encode_multi(&pattern, &[(&preview, 1)])?;
+    let mut file = Cursor::new(original.clone());
+
+    let edit = DngMetadataEdit {
+      artist: Some("Jane Doe".into()),
+      copyright: Some("CC-BY 4.0".into()),
+      image_description: Some("Harbour".into()),
+      orientation: Some(Orientation::Rotate90),
+      gps: Some(GpsPosition {
+        latitude: -33.8568,
+        longitude: 151.2153,
+        altitude: Some(-12.5),
+      }),
+      as_shot_neutral: Some(vec![0.5, 1.0, 0.8]),
+      xmp: Some(b"<x:xmpmeta/>".to_vec()),
+      ..Default::default()
+    };
+    edit_dng(&mut file, &edit)?;
+
+    // Existing data is never overwritten, besides the root IFD offset
+    let edited = file.get_ref();
+    assert_eq!(edited[8..original.len()], original[8..]);
+
+    let root = read_root_ifd(&mut file)?;
+    assert_eq!(root_string(&root, ExifTag::Artist).as_deref(), Some("Jane Doe"));
+    assert_eq!(root_string(&root, ExifTag::Copyright).as_deref(), Some("CC-BY 4.0"));
+    assert_eq!(root_string(&root, ExifTag::ImageDescription).as_deref(), Some("Harbour"));
+    assert_eq!(
+      root.get_entry(ExifTag::ApplicationNotes).map(|entry| entry.value.clone()),
+      Some(Value::Byte(b"<x:xmpmeta/>".to_vec()))
+    );
+    let gps_offset = root.get_entry(ExifTag::GPSInfo).map(|entry| entry.force_u32(0)).expect("GPS IFD is missing");
+    let gps = IFD::new(&mut file, gps_offset, 0, 0, root.endian, &[])?;
+    assert_eq!(
+      gps.get_entry(ExifGpsTag::GPSLatitudeRef).and_then(|e| e.value.as_string().cloned()).as_deref(),
+      Some("S")
+    );
+    assert_eq!(
+      gps.get_entry(ExifGpsTag::GPSLongitudeRef).and_then(|e| e.value.as_string().cloned()).as_deref(),
+      Some("E")
+    );
+    assert_eq!(
+      gps.get_entry(ExifGpsTag::GPSLatitude).map(|entry| entry.value.clone()),
+      Some(Value::Rational(vec![Rational::new(33, 1), Rational::new(51, 1), Rational::new(244800, 10000)]))
+    );
+    assert_eq!(gps.get_entry(ExifGpsTag::GPSAltitudeRef).map(|entry| entry.force_u32(0)), Some(1));
+
+    let image = decode(file.get_ref().clone())?;
+    assert_pixels(&image, &pattern);
+    assert_eq!(image.orientation, Orientation::Rotate90);
+    assert_eq!(image.wb_coeffs[..3], [2.0, 1.0, 1.25]);
+
+    // Edit again, remove tags and keep the others
+    let edit = DngMetadataEdit {
+      artist: Some(String::new()),
+      remove_gps: true,
+      ..Default::default()
+    };
+    edit_dng(&mut file, &edit)?;
+    let root = read_root_ifd(&mut file)?;
+    assert_eq!(root_string(&root, ExifTag::Artist), None);
+    assert_eq!(root_string(&root, ExifTag::Copyright).as_deref(), Some("CC-BY 4.0"));
+    assert!(!root.has_entry(ExifTag::GPSInfo));
+    assert_eq!(root.get_entry(TiffCommonTag::SubIFDs).map(|entry| entry.count()), Some(2));
+    assert_pixels(&decode(file.into_inner())?, &pattern);
+    Ok(())
+  }
+
+  #[test]
+  fn edit_rejects_invalid_input() -> Result<()> {
+    let pattern = test_pattern(64, 48, 16);
+    let edit = DngMetadataEdit {
+      artist: Some("Jane Doe".into()),
+      ..Default::default()
+    };
+    let tiff = build_std_tiff(|tiff| {
+      let mut root = tiff.new_directory();
+      add_camera(&mut root, "Synthetic", "Test Camera");
+      Ok(root)
+    })?;
+    assert!(edit_dng(&mut Cursor::new(tiff), &edit).is_err());
+
+    let original = encode_multi(&pattern, &[])?;
+    let mut file = Cursor::new(original.clone());
+    let edit = DngMetadataEdit {
+      as_shot_neutral: Some(vec![0.0, 1.0, 1.0]),
+      ..Default::default()
+    };
+    assert!(edit_dng(&mut file, &edit).is_err());
+    let edit = DngMetadataEdit {
+      gps: Some(GpsPosition {
+        latitude: 91.0,
+        longitude: 0.0,
+        altitude: None,
+      }),
+      ..Default::default()
+    };
+    assert!(edit_dng(&mut file, &edit).is_err());
+    // Header still points to the original root IFD
+    assert_eq!(file.get_ref()[..8], original[..8]);
+    Ok(())
+  }
+}
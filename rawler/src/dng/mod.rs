@@ -1,8 +1,13 @@
 // SPDX-License-Identifier: LGPL-2.1
 // Copyright 2023 Daniel Vogelbacher <daniel@chaospixel.com>
 
+pub mod auxiliary;
 pub mod convert;
+pub mod dcp;
+pub mod edit;
 pub mod original;
+pub mod preview;
+mod recompress;
 pub mod writer;
 
 use crate::imgop::Rect;
@@ -41,13 +46,14 @@ pub fn rect_to_dng_area(area: &Rect) -> [u16; 4] {
 #[cfg(feature = "clap")]
 impl clap::ValueEnum for DngCompression {
   fn value_variants<'a>() -> &'a [Self] {
-    &[Self::Lossless, Self::Uncompressed]
+    &[Self::Lossless, Self::Uncompressed, Self::Deflate]
   }
 
   fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
     Some(match self {
       Self::Uncompressed => clap::builder::PossibleValue::new("uncompressed"),
       Self::Lossless => clap::builder::PossibleValue::new("lossless"),
+      Self::Deflate => clap::builder::PossibleValue::new("deflate"),
     })
   }
 }
@@ -110,5 +116,78 @@ pub enum DngCompression {
   Uncompressed,
   /// Lossless JPEG-92 compression
   Lossless,
+  /// Deflate (zlib) compression for integer and floating point data
+  Deflate,
   // Lossy
 }
+
+/// Predictor for Deflate compressed DNG
+///
+/// The TIFF predictor value depends on the sample format:
+/// integer data uses horizontal differencing (2, 34892, 34893),
+/// floating point data the floating point predictor (3, 34894, 34895).
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DeflatePredictor {
+  /// No prediction (1)
+  None,
+  /// Difference to the previous pixel (2 or 3)
+  #[default]
+  Horizontal,
+  /// Difference to the pixel two columns left (34892 or 34894)
+  HorizontalX2,
+  /// Difference to the pixel four columns left (34893 or 34895)
+  HorizontalX4,
+}
+
+impl DeflatePredictor {
+  /// All available predictors
+  pub fn iter() -> impl Iterator<Item = Self> {
+    [Self::None, Self::Horizontal, Self::HorizontalX2, Self::HorizontalX4].into_iter()
+  }
+
+  /// Pixel distance used for differencing, 0 if no prediction is applied
+  pub fn stride(&self) -> usize {
+    match self {
+      Self::None => 0,
+      Self::Horizontal => 1,
+      Self::HorizontalX2 => 2,
+      Self::HorizontalX4 => 4,
+    }
+  }
+
+  /// TIFF predictor value for integer data
+  pub fn integer_tag(&self) -> u16 {
+    match self {
+      Self::None => 1,
+      Self::Horizontal => 2,
+      Self::HorizontalX2 => 34892,
+      Self::HorizontalX4 => 34893,
+    }
+  }
+
+  /// TIFF predictor value for floating point data
+  pub fn float_tag(&self) -> u16 {
+    match self {
+      Self::None => 1,
+      Self::Horizontal => 3,
+      Self::HorizontalX2 => 34894,
+      Self::HorizontalX4 => 34895,
+    }
+  }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for DeflatePredictor {
+  fn value_variants<'a>() -> &'a [Self] {
+    &[Self::None, Self::Horizontal, Self::HorizontalX2, Self::HorizontalX4]
+  }
+
+  fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+    Some(match self {
+      Self::None => clap::builder::PossibleValue::new("none"),
+      Self::Horizontal => clap::builder::PossibleValue::new("horizontal"),
+      Self::HorizontalX2 => clap::builder::PossibleValue::new("horizontal2"),
+      Self::HorizontalX4 => clap::builder::PossibleValue::new("horizontal4"),
+    })
+  }
+}
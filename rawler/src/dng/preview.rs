@@ -0,0 +1,202 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+use image::{DynamicImage, imageops::FilterType};
+
+use super::PREVIEW_JPEG_QUALITY;
+
+/// Default maximum width or height of preview images
+pub const PREVIEW_DEFAULT_SIZE: u32 = 1024;
+
+/// Default maximum width or height of thumbnail images
+pub const THUMBNAIL_DEFAULT_SIZE: u32 = 256;
+
+/// Pixel values up to this limit are treated as black border.
+/// JPEG artifacts prevent a strict test for zero.
+const LETTERBOX_THRESHOLD: u8 = 16;
+
+/// Maximum relative aspect ratio deviation of a trimmed preview
+const LETTERBOX_ASPECT_TOLERANCE: f32 = 0.03;
+
+/// Resampling filter for preview and thumbnail downscaling
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PreviewFilter {
+  Nearest,
+  Triangle,
+  #[default]
+  CatmullRom,
+  Lanczos3,
+}
+
+impl From<PreviewFilter> for FilterType {
+  fn from(filter: PreviewFilter) -> Self {
+    match filter {
+      PreviewFilter::Nearest => FilterType::Nearest,
+      PreviewFilter::Triangle => FilterType::Triangle,
+      PreviewFilter::CatmullRom => FilterType::CatmullRom,
+      PreviewFilter::Lanczos3 => FilterType::Lanczos3,
+    }
+  }
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for PreviewFilter {
+  fn value_variants<'a>() -> &'a [Self] {
+    &[Self::Nearest, Self::Triangle, Self::CatmullRom, Self::Lanczos3]
+  }
+
+  fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+    Some(match self {
+      Self::Nearest => clap::builder::PossibleValue::new("nearest"),
+      Self::Triangle => clap::builder::PossibleValue::new("triangle"),
+      Self::CatmullRom => clap::builder::PossibleValue::new("catmullrom"),
+      Self::Lanczos3 => clap::builder::PossibleValue::new("lanczos3"),
+    })
+  }
+}
+
+/// Source for preview and thumbnail images
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PreviewSource {
+  /// Use the preview embedded by the camera, render from raw
+  /// if it is missing or too small.
+  #[default]
+  Camera,
+  /// Always render from raw data
+  Raw,
+}
+
+#[cfg(feature = "clap")]
+impl clap::ValueEnum for PreviewSource {
+  fn value_variants<'a>() -> &'a [Self] {
+    &[Self::Camera, Self::Raw]
+  }
+
+  fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+    Some(match self {
+      Self::Camera => clap::builder::PossibleValue::new("camera"),
+      Self::Raw => clap::builder::PossibleValue::new("raw"),
+    })
+  }
+}
+
+/// Parameters for preview and thumbnail generation
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewParams {
+  /// Maximum width or height of the preview, `None` for full size
+  pub max_size: Option<u32>,
+  /// Maximum width or height of the thumbnail
+  pub thumbnail_size: u32,
+  /// Filter for downscaling
+  pub filter: PreviewFilter,
+  /// JPEG quality of the preview (0.0 - 1.0)
+  pub quality: f32,
+  /// Source of the preview image
+  pub source: PreviewSource,
+}
+
+impl Default for PreviewParams {
+  fn default() -> Self {
+    Self {
+      max_size: Some(PREVIEW_DEFAULT_SIZE),
+      thumbnail_size: THUMBNAIL_DEFAULT_SIZE,
+      filter: PreviewFilter::default(),
+      quality: PREVIEW_JPEG_QUALITY,
+      source: PreviewSource::default(),
+    }
+  }
+}
+
+impl PreviewParams {
+  /// Check if a camera preview of the given dimension is large enough
+  /// for a preview of these settings. Previews smaller than half of
+  /// the requested size are rejected.
+  pub fn accept_size(&self, width: u32, height: u32, raw_width: u32, raw_height: u32) -> bool {
+    let raw_long = raw_width.max(raw_height);
+    let target = self.max_size.map(|size| size.min(raw_long)).unwrap_or(raw_long);
+    width.max(height) >= target / 2
+  }
+}
+
+/// Scale image to fit into a `max_size` x `max_size` box, preserving
+/// the aspect ratio. Images are never upscaled.
+pub fn scale_to_fit(img: &DynamicImage, max_size: u32, filter: PreviewFilter) -> DynamicImage {
+  if img.width() <= max_size && img.height() <= max_size {
+    img.clone()
+  } else {
+    img.resize(max_size, max_size, filter.into())
+  }
+}
+
+/// Remove black borders from camera previews.
+///
+/// Some cameras embed previews with a different aspect ratio than the
+/// raw image and pad them with black bars. The bars are removed if the
+/// trimmed image matches the expected `aspect` ratio (width / height).
+pub fn trim_letterbox(img: &DynamicImage, aspect: f32) -> Option<DynamicImage> {
+  let luma = img.to_luma8();
+  let (width, height) = luma.dimensions();
+  if width == 0 || height == 0 {
+    return None;
+  }
+  let row_is_black = |y: u32| (0..width).all(|x| luma.get_pixel(x, y).0[0] <= LETTERBOX_THRESHOLD);
+  let col_is_black = |x: u32| (0..height).all(|y| luma.get_pixel(x, y).0[0] <= LETTERBOX_THRESHOLD);
+
+  let top = (0..height).take_while(|y| row_is_black(*y)).count() as u32;
+  if top == height {
+    return None; // Completely black
+  }
+  let bottom = (0..height).rev().take_while(|y| row_is_black(*y)).count() as u32;
+  let left = (0..width).take_while(|x| col_is_black(*x)).count() as u32;
+  let right = (0..width).rev().take_while(|x| col_is_black(*x)).count() as u32;
+  if top + bottom + left + right == 0 {
+    return None;
+  }
+
+  let (trim_w, trim_h) = (width - left - right, height - top - bottom);
+  let deviation = |w: u32, h: u32| ((w as f32 / h as f32) / aspect - 1.0).abs();
+  if deviation(trim_w, trim_h) <= LETTERBOX_ASPECT_TOLERANCE && deviation(trim_w, trim_h) < deviation(width, height) {
+    Some(img.crop_imm(left, top, trim_w, trim_h))
+  } else {
+    None
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::Result;
+  use image::{GenericImageView, Rgb, RgbImage};
+
+  fn letterboxed(width: u32, height: u32, bar: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |_, y| {
+      if y < bar || y >= height - bar { Rgb([3, 2, 4]) } else { Rgb([120, 140, 90]) }
+    }))
+  }
+
+  #[test]
+  fn trim_letterbox_bars() -> Result<()> {
+    // 3:2 image in a 4:3 frame
+    let img = letterboxed(240, 180, 10);
+    let trimmed = trim_letterbox(&img, 1.5).expect("letterbox bars must be detected");
+    assert_eq!(trimmed.dimensions(), (240, 160));
+    // Bars which don't match the raw aspect ratio are preserved
+    assert!(trim_letterbox(&img, 4.0 / 3.0).is_none());
+    assert!(trim_letterbox(&letterboxed(240, 160, 0), 1.5).is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn scale_preview_bounds() -> Result<()> {
+    let img = DynamicImage::ImageRgb8(RgbImage::new(600, 400));
+    assert_eq!(scale_to_fit(&img, 300, PreviewFilter::Lanczos3).dimensions(), (300, 200));
+    assert_eq!(scale_to_fit(&img, 1024, PreviewFilter::Nearest).dimensions(), (600, 400));
+
+    let params = PreviewParams::default();
+    assert!(params.accept_size(1024, 683, 6000, 4000));
+    assert!(!params.accept_size(160, 120, 6000, 4000));
+    // Small raw images don't require large previews
+    assert!(params.accept_size(320, 240, 640, 480));
+    Ok(())
+  }
+}
@@ -0,0 +1,412 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Lossless DNG to DNG conversion
+//!
+//! The raw image is decoded and written with new compression, tiling,
+//! preview and thumbnail. All other tags are copied from the input, so
+//! camera profiles, opcodes, private data, makernotes and the embedded
+//! original raw file survive even if rawler doesn't know about them.
+
+use std::io::{Cursor, Seek, Write};
+
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+
+use crate::{
+  RawlerError, Result,
+  bits::Endian,
+  decoders::{Decoder, RawDecodeParams, WellKnownIFD},
+  dng::{DNG_VERSION_V1_4, dcp::DCP_MAGIC, writer::DngWriter},
+  formats::tiff::{DirectoryWriter, IFD, TiffWriter, Value},
+  rawsource::RawSource,
+  tags::{DngTag, ExifTag, TiffCommonTag},
+};
+
+use super::convert::{ConvertInfo, ConvertParams, dng_writer, embed_profile, generate_preview, write_previews};
+
+/// Tags which the writer derives from the raw image and its storage
+const WRITER_TAGS: [u16; 34] = [
+  TiffCommonTag::NewSubFileType as u16,
+  TiffCommonTag::ImageWidth as u16,
+  TiffCommonTag::ImageLength as u16,
+  TiffCommonTag::BitsPerSample as u16,
+  TiffCommonTag::Compression as u16,
+  TiffCommonTag::PhotometricInt as u16,
+  TiffCommonTag::StripOffsets as u16,
+  TiffCommonTag::SamplesPerPixel as u16,
+  TiffCommonTag::RowsPerStrip as u16,
+  TiffCommonTag::StripByteCounts as u16,
+  ExifTag::PlanarConfiguration as u16,
+  TiffCommonTag::Predictor as u16,
+  TiffCommonTag::TileWidth as u16,
+  TiffCommonTag::TileLength as u16,
+  TiffCommonTag::TileOffsets as u16,
+  TiffCommonTag::TileByteCounts as u16,
+  TiffCommonTag::SampleFormat as u16,
+  ExifTag::JPEGTables as u16,
+  ExifTag::YCbCrCoefficients as u16,
+  ExifTag::YCbCrSubSampling as u16,
+  ExifTag::YCbCrPositioning as u16,
+  ExifTag::ReferenceBlackWhite as u16,
+  TiffCommonTag::CFARepeatPatternDim as u16,
+  TiffCommonTag::CFAPattern as u16,
+  DngTag::CFAPlaneColor as u16,
+  DngTag::CFALayout as u16,
+  // Linearization is applied by the decoder
+  DngTag::LinearizationTable as u16,
+  DngTag::BlackLevelRepeatDim as u16,
+  DngTag::BlackLevel as u16,
+  DngTag::WhiteLevel as u16,
+  DngTag::DefaultCropOrigin as u16,
+  DngTag::DefaultCropSize as u16,
+  DngTag::ActiveArea as u16,
+  DngTag::MaskedAreas as u16,
+];
+
+/// Tags which are written to a new location or replaced by the writer
+const ROOT_TAGS: [u16; 8] = [
+  TiffCommonTag::SubIFDs as u16,
+  TiffCommonTag::ExifIFDPointer as u16,
+  ExifTag::GPSInfo as u16,
+  DngTag::DNGVersion as u16,
+  DngTag::DNGBackwardVersion as u16,
+  DngTag::ExtraCameraProfiles as u16,
+  // Digests of the original raw data don't match the re-encoded image
+  DngTag::RawImageDigest as u16,
+  DngTag::NewRawImageDigest as u16,
+];
+
+/// Tags which describe the preview or thumbnail, these are set
+/// by the writer for the new images only
+const PREVIEW_TAGS: [u16; 6] = [
+  DngTag::PreviewApplicationName as u16,
+  DngTag::PreviewApplicationVersion as u16,
+  DngTag::PreviewSettingsName as u16,
+  DngTag::PreviewSettingsDigest as u16,
+  DngTag::PreviewColorSpace as u16,
+  DngTag::PreviewDateTime as u16,
+];
+
+/// Tags which belong to the raw IFD, even if it is the root IFD
+const RAW_IFD_TAGS: [u16; 14] = [
+  DngTag::BlackLevelDeltaH as u16,
+  DngTag::BlackLevelDeltaV as u16,
+  DngTag::DefaultScale as u16,
+  DngTag::BestQualityScale as u16,
+  DngTag::DefaultUserCrop as u16,
+  DngTag::BayerGreenSplit as u16,
+  DngTag::ChromaBlurRadius as u16,
+  DngTag::AntiAliasStrength as u16,
+  DngTag::NoiseReductionApplied as u16,
+  DngTag::NoiseProfile as u16,
+  DngTag::OpcodeList1 as u16,
+  DngTag::OpcodeList2 as u16,
+  DngTag::OpcodeList3 as u16,
+  DngTag::ProfileGainTableMap as u16,
+];
+
+/// Re-encode the main raw image of a DNG file
+///
+/// There is no original file embedded for DNG input, an existing
+/// original raw file is copied instead.
+pub(crate) fn recompress_dng<W>(rawfile: &RawSource, decoder: &dyn Decoder, dng: &mut W, params: &ConvertParams) -> Result<ConvertInfo>
+where
+  W: Write + Seek + Send,
+{
+  let raw_params = RawDecodeParams { image_index: params.index };
+  let mut rawimage = decoder.raw_image(rawfile, &raw_params, false)?;
+  if params.apply_scaling {
+    rawimage.apply_scaling()?;
+  }
+  let root = decoder.ifd(WellKnownIFD::Root)?.ok_or("DNG has no root IFD")?;
+  let raw_ifd = decoder.ifd(WellKnownIFD::Raw)?.ok_or("DNG has no raw IFD")?;
+  // Raw tags are mixed with other tags if the raw image is stored in root IFD
+  let raw_on_root = raw_ifd.offset == root.offset;
+
+  log::info!(
+    "DNG recompression: make: {}, model: {}, compression: {:?}",
+    rawimage.clean_make,
+    rawimage.clean_model,
+    params.compression
+  );
+
+  let mut dng = dng_writer(dng, backward_version(&root), params)?;
+
+  let mut raw = if params.thumbnail { dng.subframe(0) } else { dng.subframe_on_root(0) };
+  raw.set_deflate_predictor(params.deflate_predictor);
+  raw.set_tile_size(params.tile_size)?;
+  raw.raw_image(&rawimage, params.crop, params.compression, params.photometric_conversion, params.predictor)?;
+  // Original values win over the defaults from the decoded image
+  raw.ifd_mut().copy_with_override(
+    raw_ifd
+      .value_iter()
+      .filter(|(tag, _)| !WRITER_TAGS.contains(tag) && (!raw_on_root || RAW_IFD_TAGS.contains(tag))),
+  );
+  raw.finalize()?;
+
+  match decoder.auxiliary_images(rawfile) {
+    Ok(images) => {
+      for aux in &images {
+        dng.auxiliary_image(aux)?;
+      }
+    }
+    Err(err) => log::warn!("Failed to read auxiliary images, continue anyway: {:?}", err),
+  }
+
+  if params.preview || params.thumbnail {
+    match generate_preview(rawfile, decoder, &rawimage, &raw_params, params) {
+      Ok(image) => write_previews(&mut dng, &image, params)?,
+      Err(err) => log::warn!("Failed to get review image, continue anyway: {:?}", err),
+    }
+  }
+
+  dng.root_ifd_mut().copy_with_override(
+    root
+      .value_iter()
+      .filter(|(tag, _)| !WRITER_TAGS.contains(tag) && !ROOT_TAGS.contains(tag) && !PREVIEW_TAGS.contains(tag))
+      .filter(|(tag, _)| !raw_on_root || !RAW_IFD_TAGS.contains(tag)),
+  );
+
+  if let Some(exif) = decoder.ifd(WellKnownIFD::Exif)? {
+    // Interoperability IFD is not copied
+    dng
+      .exif_ifd_mut()
+      .copy_with_override(exif.value_iter().filter(|(tag, _)| **tag != ExifTag::InteropOffset as u16));
+    // Same rule as for other raw files, unsafe makernotes are dropped
+    if root.get_entry(DngTag::MakerNoteSafety).map(|entry| entry.force_u16(0)).unwrap_or(0) == 0 {
+      dng.exif_ifd_mut().remove_tag(ExifTag::MakerNotes);
+    }
+  }
+
+  if let Some(gps) = decoder.ifd(WellKnownIFD::ExifGps)? {
+    let mut gps_ifd = DirectoryWriter::new();
+    gps_ifd.copy(gps.value_iter());
+    if !gps_ifd.is_empty() {
+      let offset = gps_ifd.build(&mut dng.dng)?;
+      dng.root_ifd_mut().add_tag(ExifTag::GPSInfo, offset);
+    }
+  }
+
+  copy_extra_profiles(rawfile, &root, &mut dng)?;
+
+  if let Some(profile) = &params.profile {
+    embed_profile(&mut dng, &rawimage, profile)?;
+  }
+
+  if let Some(artist) = &params.artist {
+    dng.root_ifd_mut().add_tag(TiffCommonTag::Artist, artist);
+  }
+  dng.root_ifd_mut().add_tag(TiffCommonTag::Software, &params.software);
+  dng
+    .root_ifd_mut()
+    .add_tag(ExifTag::ModifyDate, chrono::Local::now().format("%Y:%m:%d %H:%M:%S").to_string());
+
+  dng.close()?;
+
+  let last_modified = match decoder.raw_metadata(rawfile, &raw_params).and_then(|metadata| metadata.last_modified()) {
+    Ok(last_modified) => last_modified,
+    Err(err) => {
+      log::warn!("Failed to get last-modified: {:?}", err);
+      None
+    }
+  };
+  Ok(ConvertInfo { last_modified })
+}
+
+/// Backward version of the input, but at least the version
+/// required by our writer
+fn backward_version(root: &IFD) -> [u8; 4] {
+  match root.get_entry(DngTag::DNGBackwardVersion).map(|entry| &entry.value) {
+    Some(Value::Byte(version)) if version.len() == 4 => [version[0], version[1], version[2], version[3]].max(DNG_VERSION_V1_4),
+    _ => DNG_VERSION_V1_4,
+  }
+}
+
+/// Copy extra camera profiles
+///
+/// Each profile is a small TIFF structure with offsets relative to
+/// the start of the profile, so it can be rebuilt independently.
+fn copy_extra_profiles<W>(rawfile: &RawSource, root: &IFD, dng: &mut DngWriter<W>) -> Result<()>
+where
+  W: Write + Seek,
+{
+  let Some(entry) = root.get_entry(DngTag::ExtraCameraProfiles) else {
+    return Ok(());
+  };
+  let mut offsets = Vec::with_capacity(entry.count() as usize);
+  for i in 0..entry.count() as usize {
+    match read_extra_profile(rawfile, entry.force_u32(i)) {
+      Ok(profile) => {
+        let mut buf = Cursor::new(Vec::new());
+        let tiff = TiffWriter::new_with_magic(&mut buf, DCP_MAGIC)?;
+        tiff.build(profile)?;
+        offsets.push(dng.dng.write_data(buf.get_ref())?);
+      }
+      Err(err) => log::warn!("Failed to read extra camera profile {}, ignoring it: {}", i, err),
+    }
+  }
+  if !offsets.is_empty() {
+    dng.root_ifd_mut().add_tag(DngTag::ExtraCameraProfiles, &offsets);
+  }
+  Ok(())
+}
+
+/// Read extra camera profile at `offset`
+fn read_extra_profile(rawfile: &RawSource, offset: u32) -> Result<DirectoryWriter> {
+  let header = rawfile.subview(offset as u64, 8)?;
+  let (endian, magic, ifd_offset) = match &header[0..2] {
+    b"II" => (Endian::Little, LittleEndian::read_u16(&header[2..]), LittleEndian::read_u32(&header[4..])),
+    b"MM" => (Endian::Big, BigEndian::read_u16(&header[2..]), BigEndian::read_u32(&header[4..])),
+    _ => return Err(RawlerError::malformed("DNG", "Invalid byte order marker for camera profile")),
+  };
+  if magic != DCP_MAGIC && magic != 42 {
+    return Err(RawlerError::malformed("DNG", format!("Invalid magic marker for camera profile: 0x{:x}", magic)));
+  }
+  let ifd = IFD::new(&mut rawfile.reader(), ifd_offset, offset, 0, endian, &[])?;
+  let mut profile = DirectoryWriter::new();
+  profile.copy(ifd.value_iter());
+  Ok(profile)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+  use crate::{
+    devtools::synthetic::{assert_pixels, dng::encode_annotated, test_pattern},
+    dng::{DNG_VERSION_V1_5, DngCompression, convert::convert_raw_source, dcp::DcpProfile},
+    formats::tiff::CompressionMethod,
+    tags::{ExifGpsTag, TiffTag},
+  };
+
+  fn value<T: TiffTag>(ifd: &IFD, tag: T) -> Option<Value> {
+    ifd.get_entry(tag).map(|entry| entry.value.clone())
+  }
+
+  #[test]
+  fn recompress_dng_keeps_tags() -> Result<()> {
+    let pattern = test_pattern(64, 48, 16);
+    let input = encode_annotated(&pattern)?;
+    let input_source = RawSource::new_from_slice(&input);
+    let input_decoder = crate::get_decoder(&input_source)?;
+    let input_root = input_decoder.ifd(WellKnownIFD::Root)?.ok_or("no root IFD")?;
+
+    // Raw image moves between root IFD and SubIFD, depending on the thumbnail
+    let cases = [
+      (DngCompression::Lossless, true, CompressionMethod::ModernJPEG),
+      (DngCompression::Deflate, false, CompressionMethod::Deflate),
+      (DngCompression::Uncompressed, true, CompressionMethod::None),
+    ];
+    let mut file = input.clone();
+    for (compression, thumbnail, method) in cases {
+      let params = ConvertParams {
+        compression,
+        thumbnail,
+        preview: false,
+        ..Default::default()
+      };
+      let mut output = Cursor::new(Vec::new());
+      convert_raw_source(&RawSource::new_from_slice(&file), &mut output, "INPUT.DNG", &params)?;
+      file = output.into_inner();
+
+      let source = RawSource::new_from_slice(&file);
+      let decoder = crate::get_decoder(&source)?;
+      assert_pixels(&decoder.raw_image(&source, &RawDecodeParams::default(), false)?, &pattern);
+
+      let root = decoder.ifd(WellKnownIFD::Root)?.ok_or("no root IFD")?;
+      let raw = decoder.ifd(WellKnownIFD::Raw)?.ok_or("no raw IFD")?;
+      assert_eq!(raw.get_entry(TiffCommonTag::Compression).map(|entry| entry.force_u16(0)), Some(method as u16));
+      assert_eq!(raw.offset == root.offset, !thumbnail, "{:?}", compression);
+      for tag in [DngTag::OpcodeList2, DngTag::DefaultScale] {
+        assert_eq!(value(&raw, tag), value(&input_root, tag), "{:?}, {:?}", compression, tag);
+        if thumbnail {
+          assert!(!root.has_entry(tag), "{:?} must not be in root IFD", tag);
+        }
+      }
+      for tag in [
+        DngTag::ProfileName,
+        DngTag::ColorMatrix1,
+        DngTag::CalibrationIlluminant1,
+        DngTag::AsShotNeutral,
+        DngTag::DNGPrivateData,
+        DngTag::DNGBackwardVersion,
+        // The input DNG is not embedded as original
+        DngTag::OriginalRawFileName,
+        DngTag::OriginalRawFileData,
+      ] {
+        assert_eq!(value(&root, tag), value(&input_root, tag), "{:?}, {:?}", compression, tag);
+      }
+
+      for tag in [
+        DngTag::RawImageDigest,
+        DngTag::NewRawImageDigest,
+        DngTag::PreviewColorSpace,
+        DngTag::PreviewSettingsName,
+      ] {
+        assert!(input_root.has_entry(tag));
+        assert!(!root.has_entry(tag), "{:?}, {:?} must not be copied", compression, tag);
+      }
+
+      let exif = decoder.ifd(WellKnownIFD::Exif)?.ok_or("no EXIF IFD")?;
+      assert_eq!(value(&exif, ExifTag::MakerNotes), Some(Value::Undefined(b"SYNTHETIC MAKERNOTE".to_vec())));
+      let gps = decoder.ifd(WellKnownIFD::ExifGps)?.ok_or("no GPS IFD")?;
+      assert_eq!(
+        gps
+          .get_entry(ExifGpsTag::GPSLatitudeRef)
+          .and_then(|entry| entry.value.as_string().cloned())
+          .as_deref(),
+        Some("N")
+      );
+
+      let offsets = root.get_entry(DngTag::ExtraCameraProfiles).ok_or("no extra profiles")?;
+      assert_eq!(offsets.count(), 1);
+      let profile = DcpProfile::from_bytes(&file[offsets.force_usize(0)..])?;
+      assert_eq!(profile.name.as_deref(), Some("Extra Profile"));
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn recompress_dng_tile_size_and_version() -> Result<()> {
+    let pattern = test_pattern(64, 48, 16);
+    let input = encode_annotated(&pattern)?;
+    for compression in [DngCompression::Lossless, DngCompression::Deflate] {
+      let params = ConvertParams {
+        compression,
+        tile_size: 32,
+        dng_version: DNG_VERSION_V1_5,
+        preview: false,
+        ..Default::default()
+      };
+      let mut output = Cursor::new(Vec::new());
+      convert_raw_source(&RawSource::new_from_slice(&input), &mut output, "INPUT.DNG", &params)?;
+      let file = output.into_inner();
+
+      let source = RawSource::new_from_slice(&file);
+      let decoder = crate::get_decoder(&source)?;
+      assert_pixels(&decoder.raw_image(&source, &RawDecodeParams::default(), false)?, &pattern);
+      let root = decoder.ifd(WellKnownIFD::Root)?.ok_or("no root IFD")?;
+      let raw = decoder.ifd(WellKnownIFD::Raw)?.ok_or("no raw IFD")?;
+      assert_eq!(raw.get_entry(TiffCommonTag::TileWidth).map(|entry| entry.force_usize(0)), Some(32));
+      assert_eq!(raw.get_entry(TiffCommonTag::TileLength).map(|entry| entry.force_usize(0)), Some(32));
+      assert_eq!(value(&root, DngTag::DNGVersion), Some(Value::Byte(DNG_VERSION_V1_5.to_vec())));
+    }
+
+    // Input requires DNG 1.5 readers
+    for params in [
+      ConvertParams {
+        dng_version: DNG_VERSION_V1_4,
+        ..Default::default()
+      },
+      ConvertParams {
+        tile_size: 100,
+        ..Default::default()
+      },
+    ] {
+      let mut output = Cursor::new(Vec::new());
+      assert!(convert_raw_source(&RawSource::new_from_slice(&input), &mut output, "INPUT.DNG", &params).is_err());
+    }
+    Ok(())
+  }
+}
@@ -5,12 +5,14 @@ use std::{
   time::Instant,
 };
 
-use image::{DynamicImage, codecs::jpeg::JpegEncoder, imageops::FilterType};
+use image::{DynamicImage, codecs::jpeg::JpegEncoder};
+use libflate::zlib::Encoder;
 use log::debug;
 use rayon::prelude::*;
 
 use crate::{
   CFA, RawImage, RawImageData,
+  bits::{Binary16, Binary24, Binary32, narrow_binary_floating_point},
   decoders::{Camera, RawMetadata},
   dng::rect_to_dng_area,
   envparams::{rawler_dng_multistrip_threshold, rawler_dng_rows_per_strip},
@@ -31,12 +33,20 @@ use crate::{
   tags::{DngTag, TiffCommonTag},
 };
 
-use super::{CropMode, DNG_VERSION_V1_6, DngCompression, DngPhotometricConversion, original::OriginalCompressed};
+use super::{
+  CropMode, DNG_VERSION_V1_6, DeflatePredictor, DngCompression, DngPhotometricConversion,
+  auxiliary::{AuxiliaryImage, AuxiliaryKind},
+  original::OriginalCompressed,
+  preview::{PreviewParams, scale_to_fit},
+};
 
 pub type DngError = TiffError;
 
 pub type Result<T> = std::result::Result<T, DngError>;
 
+/// Default width and height of tiles for compressed raw images
+pub const DEFAULT_TILE_SIZE: usize = 256;
+
 pub struct DngWriter<B>
 where
   B: Write + Seek,
@@ -55,6 +65,8 @@ where
 {
   writer: &'w mut DngWriter<B>,
   ifd: Option<DirectoryWriter>,
+  deflate_predictor: DeflatePredictor,
+  tile_size: usize,
 }
 
 impl<'w, B> SubFrameWriter<'w, B>
@@ -70,7 +82,28 @@ where
       ifd.add_tag(TiffCommonTag::NewSubFileType, subtype);
       Some(ifd)
     };
-    Self { ifd, writer }
+    Self {
+      ifd,
+      writer,
+      deflate_predictor: DeflatePredictor::default(),
+      tile_size: DEFAULT_TILE_SIZE,
+    }
+  }
+
+  /// Set predictor for Deflate compressed raw images
+  pub fn set_deflate_predictor(&mut self, predictor: DeflatePredictor) {
+    self.deflate_predictor = predictor;
+  }
+
+  /// Set tile width and height for compressed raw images
+  ///
+  /// TIFF requires the tile size to be a multiple of 16.
+  pub fn set_tile_size(&mut self, size: usize) -> Result<()> {
+    if size == 0 || !size.is_multiple_of(16) || size > u16::MAX as usize {
+      return Err(DngError::General(format!("Invalid tile size {}, must be a multiple of 16", size)));
+    }
+    self.tile_size = size;
+    Ok(())
   }
 
   pub fn ifd(&mut self) -> &DirectoryWriter {
@@ -91,7 +124,8 @@ where
     let blacklevel = Some(BlackLevel::new(&[0_u32, 0, 0], 1, 1, 3));
     let whitelevel = Some(WhiteLevel::new_bits(8, cpp));
     let photometric = RawPhotometricInterpretation::LinearRaw;
-    let rawimage = RawImage::new(cam, rawimagedata, cpp, wb_coeffs, photometric, blacklevel, whitelevel, false);
+    let rawimage =
+      RawImage::new(cam, rawimagedata, cpp, wb_coeffs, photometric, blacklevel, whitelevel, false).map_err(|err| TiffError::General(err.to_string()))?;
     self.raw_image(&rawimage, CropMode::None, compression, DngPhotometricConversion::Original, predictor)
   }
 
@@ -105,7 +139,8 @@ where
     let blacklevel = Some(BlackLevel::new(&[0_u32, 0, 0], 1, 1, 3));
     let whitelevel = Some(WhiteLevel::new_bits(16, cpp));
     let photometric = RawPhotometricInterpretation::LinearRaw;
-    let rawimage = RawImage::new(cam, rawimagedata, cpp, wb_coeffs, photometric, blacklevel, whitelevel, false);
+    let rawimage =
+      RawImage::new(cam, rawimagedata, cpp, wb_coeffs, photometric, blacklevel, whitelevel, false).map_err(|err| TiffError::General(err.to_string()))?;
     self.raw_image(&rawimage, CropMode::None, compression, DngPhotometricConversion::Original, predictor)
   }
 
@@ -304,6 +339,11 @@ where
         self.ifd_mut().add_tag(TiffCommonTag::Compression, CompressionMethod::ModernJPEG);
         dng_put_raw_ljpeg(self, &rawimage, predictor)?;
       }
+      DngCompression::Deflate => {
+        self.ifd_mut().add_tag(TiffCommonTag::Compression, CompressionMethod::Deflate);
+        let predictor = self.deflate_predictor;
+        dng_put_raw_deflate(self, &rawimage, predictor)?;
+      }
     }
 
     /*
@@ -315,12 +355,17 @@ where
     Ok(())
   }
 
+  /// Write JPEG preview with default size and filter
   pub fn preview(&mut self, img: &DynamicImage, quality: f32) -> Result<()> {
+    self.preview_with_params(img, &PreviewParams { quality, ..Default::default() })
+  }
+
+  /// Write JPEG preview, scaled down according to `params`
+  pub fn preview_with_params(&mut self, img: &DynamicImage, params: &PreviewParams) -> Result<()> {
     let now = Instant::now();
-    let preview_img = if img.width() > 1024 {
-      DynamicImage::ImageRgb8(img.resize(1024, 768, FilterType::Nearest).to_rgb8())
-    } else {
-      DynamicImage::ImageRgb8(img.to_rgb8())
+    let preview_img = match params.max_size {
+      Some(max_size) => DynamicImage::ImageRgb8(scale_to_fit(img, max_size, params.filter).to_rgb8()),
+      None => DynamicImage::ImageRgb8(img.to_rgb8()),
     };
     debug!("preview downscale: {} s", now.elapsed().as_secs_f32());
 
@@ -341,7 +386,7 @@ where
     let now = Instant::now();
     let offset = self.writer.dng.position()?;
     // TODO: improve offsets?
-    let jpeg_encoder = JpegEncoder::new_with_quality(&mut self.writer.dng.writer, (quality * 100.0).clamp(0.0, 100.0) as u8);
+    let jpeg_encoder = JpegEncoder::new_with_quality(&mut self.writer.dng.writer, (params.quality * 100.0).clamp(1.0, 100.0) as u8);
     preview_img
       .write_with_encoder(jpeg_encoder)
       .map_err(|err| io::Error::new(io::ErrorKind::Other, format!("Failed to write jpeg preview: {:?}", err)))?;
@@ -385,6 +430,11 @@ where
     })
   }
 
+  /// Override the DNG version, defaults to 1.6
+  pub fn set_version(&mut self, version: [u8; 4]) {
+    self.root_ifd.add_tag(DngTag::DNGVersion, version);
+  }
+
   pub fn as_shot_neutral(&mut self, wb: impl AsRef<[Rational]>) {
     // Only write tag if wb is valid
     if wb.as_ref()[0].n != 0 {
@@ -459,6 +509,48 @@ where
     Ok(())
   }
 
+  /// Write depth map or mask into a separate SubIFD
+  ///
+  /// Images are stored with 8 or 16 bits, other bit depths are scaled
+  /// to the full range of the stored samples.
+  pub fn auxiliary_image(&mut self, aux: &AuxiliaryImage) -> Result<()> {
+    let photometric = match aux.kind {
+      AuxiliaryKind::SemanticMask { .. } => PhotometricInterpretation::SemanticMask,
+      AuxiliaryKind::Depth => PhotometricInterpretation::Depth,
+      AuxiliaryKind::TransparencyMask => PhotometricInterpretation::TransparencyMask,
+    };
+    let max = (1_u32 << aux.bits.clamp(1, 16)) - 1;
+    let mut frame = self.subframe(aux.subfile_type);
+    let (offset, size, bits) = if aux.bits <= 8 {
+      let data: Vec<u8> = aux.data.iter().map(|v| ((*v as u32).min(max) * 255 / max) as u8).collect();
+      (frame.writer.dng.write_data(&data)?, data.len(), 8_u16)
+    } else {
+      let data: Vec<u16> = aux.data.iter().map(|v| ((*v as u32).min(max) * 65535 / max) as u16).collect();
+      (frame.writer.dng.write_data_u16_le(&data)?, data.len() * size_of::<u16>(), 16_u16)
+    };
+    let ifd = frame.ifd_mut();
+    ifd.add_tag(TiffCommonTag::ImageWidth, aux.width as u32);
+    ifd.add_tag(TiffCommonTag::ImageLength, aux.height as u32);
+    ifd.add_tag(TiffCommonTag::Compression, CompressionMethod::None);
+    ifd.add_tag(TiffCommonTag::BitsPerSample, bits);
+    ifd.add_tag(TiffCommonTag::SampleFormat, 1_u16);
+    ifd.add_tag(TiffCommonTag::SamplesPerPixel, 1_u16);
+    ifd.add_tag(TiffCommonTag::PhotometricInt, photometric);
+    ifd.add_tag(TiffCommonTag::StripOffsets, offset);
+    ifd.add_tag(TiffCommonTag::StripByteCounts, size as u32);
+    ifd.add_tag(TiffCommonTag::RowsPerStrip, aux.height as u32);
+    if let AuxiliaryKind::SemanticMask { name, instance_id } = &aux.kind {
+      ifd.add_tag(DngTag::SemanticName, name.as_str());
+      if let Some(instance_id) = instance_id {
+        ifd.add_tag(DngTag::SemanticInstanceID, instance_id.as_str());
+      }
+    }
+    if let Some(area) = aux.mask_sub_area {
+      ifd.add_tag(DngTag::MaskSubArea, area);
+    }
+    frame.finalize()
+  }
+
   pub fn subframe(&mut self, id: u32) -> SubFrameWriter<'_, B> {
     SubFrameWriter::new(self, id, false)
   }
@@ -469,7 +561,12 @@ where
 
   /// Write thumbnail image into DNG
   pub fn thumbnail(&mut self, img: &DynamicImage) -> Result<()> {
-    let thumb_img = img.resize(240, 120, FilterType::Nearest).to_rgb8();
+    self.thumbnail_with_params(img, &PreviewParams::default())
+  }
+
+  /// Write thumbnail image into DNG, scaled down according to `params`
+  pub fn thumbnail_with_params(&mut self, img: &DynamicImage, params: &PreviewParams) -> Result<()> {
+    let thumb_img = scale_to_fit(img, params.thumbnail_size, params.filter).to_rgb8();
     self.root_ifd.add_tag(TiffCommonTag::NewSubFileType, 1_u32);
     self.root_ifd.add_tag(TiffCommonTag::ImageWidth, thumb_img.width() as u32);
     self.root_ifd.add_tag(TiffCommonTag::ImageLength, thumb_img.height() as u32);
@@ -570,8 +667,8 @@ fn dng_put_raw_ljpeg<W>(subframe: &mut SubFrameWriter<W>, rawimage: &RawImage, p
 where
   W: Seek + Write,
 {
-  let tile_w = 256 & !0b111; // ensure div 16
-  let tile_h = 256 & !0b111;
+  let tile_w = subframe.tile_size;
+  let tile_h = subframe.tile_size;
 
   let lj92_data = match rawimage.data {
     RawImageData::Integer(ref data) => {
@@ -642,6 +739,135 @@ where
   Ok(())
 }
 
+/// Compress RAW image with Deflate
+///
+/// Data is split into multiple tiles. Integer samples are stored with 8 bits
+/// (if `bps` allows it) or 16 bits and encoded with horizontal differencing.
+/// Floating point samples are stored with 16, 24 or 32 bits (taken from `bps`,
+/// 32 bits for any other value) and encoded with the floating point predictor:
+/// each row is split into byte planes, followed by horizontal byte differencing.
+fn dng_put_raw_deflate<W>(subframe: &mut SubFrameWriter<W>, rawimage: &RawImage, predictor: DeflatePredictor) -> Result<()>
+where
+  W: Seek + Write,
+{
+  let tile_w = subframe.tile_size;
+  let tile_h = subframe.tile_size;
+  let line_width = tile_w * rawimage.cpp;
+  let pred_factor = predictor.stride() * rawimage.cpp;
+
+  let (tiles_compr, bps, sample_format, predictor_tag) = match rawimage.data {
+    RawImageData::Integer(ref data) => {
+      let bps = if rawimage.bps <= 8 { 8 } else { 16 };
+      debug!("Deflate compression: integer bit depth: {}, predictor: {:?}", bps, predictor);
+      let tiler = ImageTiler::new(data, rawimage.width, rawimage.height, rawimage.cpp, tile_w, tile_h);
+      let tiles: Vec<Vec<u8>> = (0..tiler.tile_count())
+        .into_par_iter()
+        .map(|idx| encode_int_deflate_tile(&tiler.build_tile(idx), line_width, pred_factor, bps))
+        .collect::<Result<Vec<_>>>()?;
+      (tiles, bps, 1_u16, predictor.integer_tag()) // Unsigned Integer
+    }
+    RawImageData::Float(ref data) => {
+      let bps = match rawimage.bps {
+        16 | 24 => rawimage.bps,
+        _ => 32,
+      };
+      debug!("Deflate compression: float bit depth: {}, predictor: {:?}", bps, predictor);
+      let tiler = ImageTiler::new(data, rawimage.width, rawimage.height, rawimage.cpp, tile_w, tile_h);
+      let tiles: Vec<Vec<u8>> = (0..tiler.tile_count())
+        .into_par_iter()
+        .map(|idx| encode_fp_deflate_tile(&tiler.build_tile(idx), line_width, pred_factor, bps))
+        .collect::<Result<Vec<_>>>()?;
+      (tiles, bps, 3_u16, predictor.float_tag()) // IEEE Float
+    }
+  };
+
+  let mut tile_offsets: Vec<u32> = Vec::new();
+  let mut tile_sizes: Vec<u32> = Vec::new();
+
+  for tile in tiles_compr.iter() {
+    let offs = subframe.writer.dng.write_data(tile)?;
+    tile_offsets.push(offs);
+    tile_sizes.push(tile.len() as u32);
+  }
+
+  subframe.ifd_mut().add_tag(TiffCommonTag::BitsPerSample, &vec![bps as u16; rawimage.cpp]);
+  subframe.ifd_mut().add_tag(TiffCommonTag::SampleFormat, &vec![sample_format; rawimage.cpp]);
+  subframe.ifd_mut().add_tag(TiffCommonTag::Predictor, predictor_tag);
+  subframe.ifd_mut().add_tag(TiffCommonTag::TileOffsets, &tile_offsets);
+  subframe.ifd_mut().add_tag(TiffCommonTag::TileByteCounts, &tile_sizes);
+  subframe.ifd_mut().add_tag(TiffCommonTag::TileWidth, tile_w as u16);
+  subframe.ifd_mut().add_tag(TiffCommonTag::TileLength, tile_h as u16);
+
+  Ok(())
+}
+
+/// Encode a single integer tile with horizontal differencing and zlib
+///
+/// Each sample is subtracted from the sample `pred_factor` positions to
+/// its left, a `pred_factor` of 0 disables prediction.
+/// Samples are stored in the native byte order of the TIFF writer.
+pub(crate) fn encode_int_deflate_tile(tile: &[u16], line_width: usize, pred_factor: usize, bps: usize) -> Result<Vec<u8>> {
+  let bytesps = bps.div_ceil(8);
+  let mut buf = Vec::with_capacity(tile.len() * bytesps);
+  for line in tile.chunks_exact(line_width) {
+    for (col, sample) in line.iter().enumerate() {
+      let value = if pred_factor > 0 && col >= pred_factor {
+        sample.wrapping_sub(line[col - pred_factor])
+      } else {
+        *sample
+      };
+      match bytesps {
+        1 => buf.push(value as u8),
+        _ => buf.extend_from_slice(&value.to_ne_bytes()),
+      }
+    }
+  }
+  deflate(&buf)
+}
+
+/// Encode a single tile with floating point predictor and zlib
+///
+/// Bytes are differenced `pred_factor` positions to the left inside
+/// each byte plane. If `pred_factor` is 0, samples are stored unshuffled
+/// in the native byte order of the TIFF writer.
+pub(crate) fn encode_fp_deflate_tile(tile: &[f32], line_width: usize, pred_factor: usize, bps: usize) -> Result<Vec<u8>> {
+  let bytesps = bps / 8;
+  let mut buf = vec![0_u8; tile.len() * bytesps];
+  for (line, row) in tile.chunks_exact(line_width).zip(buf.chunks_exact_mut(line_width * bytesps)) {
+    for (col, sample) in line.iter().enumerate() {
+      let bits = match bps {
+        16 => narrow_binary_floating_point::<Binary32, Binary16>(sample.to_bits()) << 16,
+        24 => narrow_binary_floating_point::<Binary32, Binary24>(sample.to_bits()) << 8,
+        _ => sample.to_bits(),
+      };
+      let bytes = bits.to_be_bytes();
+      if pred_factor == 0 {
+        let dst = &mut row[col * bytesps..(col + 1) * bytesps];
+        dst.copy_from_slice(&bytes[..bytesps]);
+        if cfg!(target_endian = "little") {
+          dst.reverse();
+        }
+      } else {
+        for (c, byte) in bytes.iter().take(bytesps).enumerate() {
+          row[col + c * line_width] = *byte;
+        }
+      }
+    }
+    if pred_factor > 0 {
+      for i in (pred_factor..row.len()).rev() {
+        row[i] = row[i].wrapping_sub(row[i - pred_factor]);
+      }
+    }
+  }
+  deflate(&buf)
+}
+
+fn deflate(buf: &[u8]) -> Result<Vec<u8>> {
+  let mut encoder = Encoder::new(Vec::with_capacity(buf.len())).map_err(DngError::Io)?;
+  encoder.write_all(buf).map_err(DngError::Io)?;
+  encoder.finish().into_result().map_err(DngError::Io)
+}
+
 /// Write RAW uncompressed into DNG
 ///
 /// This uses unsigned 16 bit values for storage
@@ -718,6 +944,152 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn auxiliary_image_scaled_to_stored_bits() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::{
+      dng::auxiliary::AuxiliaryImage,
+      formats::tiff::{GenericTiffReader, reader::TiffReader},
+      rawsource::RawSource,
+    };
+
+    let aux = |bits: u32, data: Vec<u16>| AuxiliaryImage {
+      kind: AuxiliaryKind::Depth,
+      subfile_type: 8,
+      width: 2,
+      height: 2,
+      bits,
+      data,
+      mask_sub_area: None,
+    };
+    for (input, expected_bits, expected) in [
+      (aux(1, vec![0, 1, 1, 0]), 8, vec![0, 255, 255, 0]),
+      (aux(8, vec![0, 1, 128, 255]), 8, vec![0, 1, 128, 255]),
+      (aux(12, vec![0, 1, 2048, 4095]), 16, vec![0, 16, 32775, 65535]),
+      (aux(16, vec![0, 1, 32768, 65535]), 16, vec![0, 1, 32768, 65535]),
+    ] {
+      let mut buf = Cursor::new(Vec::new());
+      let mut dng = DngWriter::new(&mut buf, DNG_VERSION_V1_4)?;
+      dng.auxiliary_image(&input)?;
+      dng.close()?;
+
+      let source = RawSource::new_from_slice(buf.get_ref());
+      let tiff = GenericTiffReader::new_with_buffer(buf.get_ref(), 0, 0, None)?;
+      let ifd = tiff.find_first_ifd_with_tag(TiffCommonTag::StripOffsets).ok_or("no auxiliary IFD")?;
+      let decoded = AuxiliaryImage::from_ifd(ifd, &source)?;
+      assert_eq!(decoded.bits, expected_bits);
+      assert_eq!(decoded.data, expected, "{} bits", input.bits);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn deflate_float_cfa_roundtrip() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::{decoders::RawDecodeParams, rawimage::CFAConfig, rawsource::RawSource};
+
+    let (width, height) = (300, 20);
+    let data: Vec<f32> = (0..width * height).map(|i| (i % 1000) as f32 / 999.0 * 4.0).collect();
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    cam.make = "Test".into();
+    cam.model = "Float".into();
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)?);
+
+    for (bps, predictor) in [16, 24, 32]
+      .into_iter()
+      .flat_map(|bps| DeflatePredictor::iter().map(move |predictor| (bps, predictor)))
+    {
+      let mut rawimage = RawImage::new_with_data(
+        cam.clone(),
+        RawImageData::Float(data.clone()),
+        width,
+        height,
+        1,
+        [1.0, 1.0, 1.0, f32::NAN],
+        photometric.clone(),
+        Some(BlackLevel::zero(1, 1, 1)),
+        Some(WhiteLevel::new([1])),
+        false,
+      )?;
+      rawimage.bps = bps;
+
+      let mut buf = Cursor::new(Vec::new());
+      let mut dng = DngWriter::new(&mut buf, DNG_VERSION_V1_4)?;
+      let mut raw = dng.subframe_on_root(0);
+      raw.set_deflate_predictor(predictor);
+      raw.raw_image(&rawimage, CropMode::None, DngCompression::Deflate, DngPhotometricConversion::Original, 1)?;
+      raw.finalize()?;
+      dng.load_base_tags(&rawimage)?;
+      dng.close()?;
+
+      let source = RawSource::new_from_slice(buf.get_ref());
+      let decoder = crate::get_decoder(&source)?;
+      let decoded = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+      let decoded = decoded.data.as_f32();
+      let tolerance = match bps {
+        16 => 4.0 / 1024.0,
+        24 => 4.0 / 65536.0,
+        _ => 0.0,
+      };
+      assert_eq!(decoded.len(), data.len());
+      for (a, b) in decoded.iter().zip(data.iter()) {
+        assert!((a - b).abs() <= tolerance, "bps: {}, {:?}: {} != {}", bps, predictor, a, b);
+      }
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn deflate_integer_roundtrip() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    use crate::{decoders::RawDecodeParams, rawimage::CFAConfig, rawsource::RawSource};
+
+    let (width, height) = (300, 20);
+    let mut cam = Camera::new();
+    cam.cfa = CFA::new("RGGB");
+    cam.make = "Test".into();
+    cam.model = "Deflate".into();
+    let cfa = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&cam)?);
+
+    for (cpp, photometric) in [(1, cfa), (3, RawPhotometricInterpretation::LinearRaw)] {
+      for bps in [8, 16] {
+        for predictor in DeflatePredictor::iter() {
+          let max = (1_u32 << bps) - 1;
+          let data: Vec<u16> = (0..width * height * cpp).map(|i| ((i * 7919) as u32 % (max + 1)) as u16).collect();
+          let mut rawimage = RawImage::new_with_data(
+            cam.clone(),
+            RawImageData::Integer(data.clone()),
+            width * cpp,
+            height,
+            cpp,
+            [1.0, 1.0, 1.0, f32::NAN],
+            photometric.clone(),
+            Some(BlackLevel::zero(1, 1, cpp)),
+            Some(WhiteLevel::new(vec![max; cpp])),
+            false,
+          )?;
+          rawimage.bps = bps;
+
+          let mut buf = Cursor::new(Vec::new());
+          let mut dng = DngWriter::new(&mut buf, DNG_VERSION_V1_4)?;
+          let mut raw = dng.subframe_on_root(0);
+          raw.set_deflate_predictor(predictor);
+          raw.raw_image(&rawimage, CropMode::None, DngCompression::Deflate, DngPhotometricConversion::Original, 1)?;
+          raw.finalize()?;
+          dng.load_base_tags(&rawimage)?;
+          dng.close()?;
+
+          let source = RawSource::new_from_slice(buf.get_ref());
+          let decoder = crate::get_decoder(&source)?;
+          let decoded = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+          match decoded.data {
+            RawImageData::Integer(decoded) => assert_eq!(decoded, data, "cpp: {}, bps: {}, {:?}", cpp, bps, predictor),
+            RawImageData::Float(_) => panic!("Integer data expected"),
+          }
+        }
+      }
+    }
+    Ok(())
+  }
+
   #[cfg(feature = "rawdb")]
   #[test]
   fn convert_canon_cr3_to_dng() -> std::result::Result<(), Box<dyn std::error::Error>> {
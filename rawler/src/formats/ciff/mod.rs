@@ -47,9 +47,10 @@ pub fn is_ciff(file: &RawSource) -> bool {
 
 impl CiffIFD {
   pub fn new_file(file: &RawSource) -> Result<CiffIFD, String> {
-    let data = file.as_vec().map_err(|err| format!("Failed to read whole file: {:?}", err))?;
+    // The directory of the root heap is located at the end of the file
+    let data = file.buf().map_err(|err| format!("Failed to read whole file: {:?}", err))?;
 
-    CiffIFD::new(&data, LEu32(&data, 2) as usize, data.len(), 1)
+    CiffIFD::new(data, LEu32(data, 2) as usize, data.len(), 1)
   }
 
   pub fn new(buf: &[u8], start: usize, end: usize, depth: u32) -> Result<CiffIFD, String> {
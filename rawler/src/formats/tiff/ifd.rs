@@ -101,7 +101,7 @@ impl IFD {
   }
 
   pub fn new<R: Read + Seek>(reader: &mut R, offset: u32, base: u32, corr: i32, endian: Endian, sub_tags: &[u16]) -> Result<IFD> {
-    reader.seek(SeekFrom::Start((base + offset) as u64))?;
+    reader.seek(SeekFrom::Start(base as u64 + offset as u64))?;
     let mut sub_ifd_offsets = HashMap::new();
     let mut reader = EndianReader::new(reader, endian);
     let entry_count = reader.read_u16()?;
@@ -153,7 +153,7 @@ impl IFD {
               Value::Long(offsets) => {
                 sub_ifd_offsets.insert(tag, offsets.clone());
               }
-              Value::Unknown(tag, offsets) => {
+              Value::Unknown(tag, offsets) if !offsets.is_empty() => {
                 sub_ifd_offsets.insert(*tag, vec![offsets[0] as u32]);
               }
               Value::Undefined(_) => {
@@ -238,55 +238,6 @@ impl IFD {
     self.entries().iter().map(|(tag, entry)| (tag, &entry.value))
   }
 
-  /*
-  pub fn new<R: Read + Seek>(reader: &mut R, offset: u32, base: u32, corr: i32, endian: Endian, sub_tags: &[u16]) -> Result<Self> {
-    reader.seek(SeekFrom::Start((base + offset) as u64))?;
-    let mut sub_ifd_offsets = Vec::new();
-    let mut reader = EndianReader::new(reader, endian);
-    let entry_count = reader.read_u16()?;
-    let mut entries = BTreeMap::new();
-    let mut sub = Vec::new();
-    for _ in 0..entry_count {
-      //let embedded = reader.read_u32()?;
-      let tag = reader.read_u16()?;
-      if tag == LegacyTiffRootTag::SubIFDs.into() || sub_tags.contains(&tag) {
-        let entry = Entry::parse(&mut reader, base, corr, tag)?;
-        match entry.value {
-          Value::Long(offsets) => {
-            sub_ifd_offsets.extend_from_slice(&offsets);
-          }
-          _ => {
-            todo!()
-          }
-        }
-      } else {
-        let entry = Entry::parse(&mut reader, base, corr, tag)?;
-        entries.insert(entry.tag, entry);
-      }
-    }
-    let next_ifd = reader.read_u32()?;
-
-    // Process SubIFDs
-    let pos = reader.position()?;
-    let reader = reader.into_inner();
-    for offset in sub_ifd_offsets {
-      let ifd = IFD::new(reader, apply_corr(offset, corr), base, corr, endian, sub_tags)?;
-      sub.push(ifd);
-    }
-    EndianReader::new(reader, endian).goto(pos)?; // restore
-
-    Ok(Self {
-      offset,
-      base,
-      corr,
-      next_ifd: if next_ifd == 0 { 0 } else { apply_corr(next_ifd, corr) },
-      entries,
-      endian,
-      sub,
-    })
-  }
-   */
-
   /// Extend the IFD with sub-IFDs from a specific tag.
   /// The IFD corrections are used from current IFD.
   pub fn extend_sub_ifds<R: Read + Seek>(&mut self, reader: &mut R, tag: u16) -> Result<Option<&Vec<Self>>> {
@@ -301,10 +252,7 @@ impl IFD {
           self.sub.insert(tag, subs);
           Ok(self.sub.get(&tag))
         }
-        val => {
-          debug!("Found IFD offset tag, but type mismatch: {:?}", val);
-          todo!()
-        }
+        val => Err(TiffError::General(format!("Found IFD offset tag 0x{:X}, but type mismatch: {:?}", tag, val))),
       }
     } else {
       Ok(None)
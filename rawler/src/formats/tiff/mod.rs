@@ -33,6 +33,8 @@ pub enum CompressionMethod {
   // "Extended JPEG" or "new JPEG" style
   ModernJPEG = 7,
   Deflate = 8,
+  // SMPTE VC-5, used by GoPro GPR files
+  VC5 = 9,
   OldDeflate = 0x80B2,
   PackBits = 0x8005,
   // DNG Extensions
@@ -75,6 +77,18 @@ pub enum PhotometricInterpretation {
   // Defined by DNG
   CFA = 32803,
   LinearRaw = 34892,
+  Depth = 51177,
+  SemanticMask = 52527,
+}
+
+impl PhotometricInterpretation {
+  /// 8 bit JPEG data with this interpretation is baseline JPEG, not lossless JPEG
+  pub fn is_baseline_jpeg(&self) -> bool {
+    matches!(
+      self,
+      Self::YCbCr | Self::BlackIsZero | Self::TransparencyMask | Self::Depth | Self::SemanticMask
+    )
+  }
 }
 
 impl ExtractFromIFD for PhotometricInterpretation {
@@ -372,4 +386,30 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn sub_ifd_type_mismatch_is_error() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let mut output = Cursor::new(Vec::new());
+    let tiff = TiffWriter::new(&mut output)?;
+    let mut dir = tiff.new_directory();
+    dir.add_tag(TiffCommonTag::ExifIFDPointer, [8_u16]);
+    tiff.build(dir)?;
+
+    output.seek(SeekFrom::Start(0))?;
+    let reader = GenericTiffReader::new(&mut output, 0, 0, None, &[])?;
+    let mut root = reader.root_ifd().clone();
+    assert!(root.extend_sub_ifds(&mut output, TiffCommonTag::ExifIFDPointer.into()).is_err());
+    Ok(())
+  }
+
+  /// Inputs from the fuzz corpus which crashed the parser before
+  #[test]
+  fn fuzz_corpus_regressions() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus/fuzz_tiff_parse");
+    for entry in std::fs::read_dir(corpus)? {
+      let data = std::fs::read(entry?.path())?;
+      let _ = GenericTiffReader::new_with_buffer(&data, 0, 0, Some(10));
+    }
+    Ok(())
+  }
 }
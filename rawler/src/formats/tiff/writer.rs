@@ -29,23 +29,42 @@ where
   W: Write + Seek,
 {
   pub fn new(writer: W) -> Result<Self> {
+    Self::new_with_magic(writer, TIFF_MAGIC)
+  }
+
+  /// Create writer for TIFF based formats with a different magic
+  /// marker, like DNG camera profiles.
+  pub fn new_with_magic(writer: W, magic: u16) -> Result<Self> {
     let mut tmp = Self { writer, ifd_location: 0 };
-    tmp.write_header()?;
+    tmp.write_header(magic)?;
     Ok(tmp)
   }
 
+  /// Create writer which appends to an existing TIFF file
+  ///
+  /// The file must use the native byte order. The header is only
+  /// updated when `build()` writes the new root IFD, so the file stays
+  /// valid until then.
+  pub fn new_append(mut writer: W) -> Result<Self> {
+    let end = writer.seek(SeekFrom::End(0))?;
+    if end > u32::MAX as u64 {
+      return Err(TiffError::General("TIFF file is too large to append data".to_string()));
+    }
+    Ok(Self { writer, ifd_location: 4 })
+  }
+
   pub fn new_directory(&self) -> DirectoryWriter {
     DirectoryWriter::new()
   }
 
-  fn write_header(&mut self) -> Result<()> {
+  fn write_header(&mut self, magic: u16) -> Result<()> {
     #[cfg(target_endian = "little")]
     let boi: u8 = 0x49;
     #[cfg(not(target_endian = "little"))]
     let boi: u8 = 0x4d;
 
     self.writer.write_all(&[boi, boi])?;
-    self.writer.write_u16::<NativeEndian>(TIFF_MAGIC)?;
+    self.writer.write_u16::<NativeEndian>(magic)?;
     self.ifd_location = self.writer.stream_position()?;
     self.writer.write_u32::<NativeEndian>(0_u32)?;
 
@@ -192,6 +211,25 @@ impl DirectoryWriter {
     }
   }
 
+  /// Add a value that is already stored in the file at `offset`
+  ///
+  /// The value is not written again when the directory is built.
+  /// Values that fit into the entry itself are embedded as usual.
+  pub fn add_stored_tag(&mut self, tag: u16, value: Value, offset: u32) {
+    self.entries.insert(
+      tag,
+      Entry {
+        tag,
+        value,
+        embedded: Some(offset),
+      },
+    );
+  }
+
+  pub fn set_next_ifd(&mut self, offset: u32) {
+    self.next_ifd = offset;
+  }
+
   pub fn add_tag_undefined<T: TiffTag>(&mut self, tag: T, data: Vec<u8>) {
     let tag: u16 = tag.into();
     //let data = data.as_ref();
@@ -242,6 +280,10 @@ impl DirectoryWriter {
       let data_bytes = 4;
 
       if value.byte_size() > data_bytes {
+        if embedded.is_some() {
+          // Value is already stored in file
+          continue;
+        }
         tiff.pad_word_boundary()?;
         let offset = tiff.position()?;
         value.write(&mut tiff.writer)?;
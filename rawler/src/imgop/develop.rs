@@ -6,9 +6,9 @@ use std::{
 use image::{DynamicImage, ImageBuffer};
 
 use crate::{
-  RawImage,
+  RawImage, RawlerError,
   decoders::{RawDecodeParams, RawMetadata},
-  dng::CropMode,
+  dng::{CropMode, dcp::DcpProfile},
   formats::tiff::{DirectoryWriter, TiffWriter},
   imgop::{
     chromatic_adaption::adapt_bradford,
@@ -38,6 +38,7 @@ pub struct RawProcessingParams {
   pub software: String,
   pub index: usize,
   pub keep_mtime: bool,
+  pub profile: Option<DcpProfile>,
 }
 
 impl Default for RawProcessingParams {
@@ -49,6 +50,7 @@ impl Default for RawProcessingParams {
       software: "DNGLab".into(),
       index: 0,
       keep_mtime: false,
+      profile: None,
     }
   }
 }
@@ -59,7 +61,10 @@ pub fn process_raw_image<W: Write + Seek + Send>(raw: &Path, image_file: &mut W,
   let raw_params = RawDecodeParams { image_index: params.index };
   let rawimage = decoder.raw_image(&rawfile, &raw_params, false)?;
   let metadata = decoder.raw_metadata(&rawfile, &raw_params)?;
-  let develop = RawDevelop::default();
+  let mut develop = RawDevelop::default();
+  if let Some(profile) = &params.profile {
+    develop = develop.with_profile(profile.clone());
+  }
   develop.develop(&rawimage, &metadata, image_file)?;
   Ok(())
 }
@@ -123,6 +128,8 @@ impl Intermediate {
 #[derive(Clone)]
 pub struct RawDevelop {
   pub steps: Vec<ProcessingStep>,
+  /// Camera profile used instead of the color matrices of the raw image
+  pub profile: Option<DcpProfile>,
 }
 
 impl Default for RawDevelop {
@@ -138,13 +145,23 @@ impl Default for RawDevelop {
         ProcessingStep::CropDefault,
         ProcessingStep::SRgb,
       ],
+      profile: None,
     }
   }
 }
 
 impl RawDevelop {
   pub fn new_with(steps: &[ProcessingStep]) -> Self {
-    Self { steps: Vec::from(steps) }
+    Self {
+      steps: Vec::from(steps),
+      profile: None,
+    }
+  }
+
+  /// Use camera profile for color calibration
+  pub fn with_profile(mut self, profile: DcpProfile) -> Self {
+    self.profile = Some(profile);
+    self
   }
 
   /*
@@ -166,6 +183,14 @@ impl RawDevelop {
   /// has only one color channel.
   pub fn develop_intermediate(&self, rawimage: &RawImage) -> crate::Result<Intermediate> {
     let mut rawimage = rawimage.clone();
+    if let Some(profile) = &self.profile {
+      let matrices = profile.color_matrices();
+      if matrices.is_empty() {
+        log::warn!("Camera profile has no color matrices, using matrices from raw image");
+      } else {
+        rawimage.color_matrix = matrices;
+      }
+    }
     if self.steps.contains(&ProcessingStep::Rescale) {
       rawimage.apply_scaling()?;
     }
@@ -182,7 +207,7 @@ impl RawDevelop {
         rawimage.width,
         rawimage.height,
       )),
-      _ => todo!(),
+      cpp => return Err(RawlerError::unsupported_feature("RawDevelop", format!("{} components per pixel", cpp))),
     };
 
     if self.steps.contains(&ProcessingStep::Demosaic) {
@@ -217,7 +242,10 @@ impl RawDevelop {
               let xtrans_demosaic = XTransBilinearDemosaic::new();
               Intermediate::ThreeColor(xtrans_demosaic.demosaic(&pixels, &config.cfa, &config.colors, roi))
             } else {
-              todo!()
+              return Err(RawlerError::unsupported_feature(
+                "RawDevelop",
+                format!("demosaic for CFA '{}' on {:?} sensor", config.cfa.name, config.sensor),
+              ));
             }
           } else {
             intermediate
@@ -283,6 +311,12 @@ impl RawDevelop {
         Intermediate::ThreeColor(pixels) => Intermediate::ThreeColor(map_3ch_to_rgb(&pixels, &wb, xyz2cam)),
         Intermediate::FourColor(pixels) => Intermediate::ThreeColor(map_4ch_to_rgb(&pixels, &wb, xyz2cam)),
       };
+
+      if let (Some(profile), Intermediate::ThreeColor(pixels)) = (&self.profile, &mut intermediate)
+        && profile.has_rendering_tables()
+      {
+        profile.apply_rendering(pixels, illu);
+      }
     }
 
     if self.steps.contains(&ProcessingStep::CropDefault) {
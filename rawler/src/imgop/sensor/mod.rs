@@ -2,7 +2,7 @@
 // Copyright 2021 Daniel Vogelbacher <daniel@chaospixel.com>
 
 use crate::{
-  CFA,
+  CFA, RawlerError, Result,
   cfa::PlaneColor,
   imgop::Rect,
   pixarray::{Color2D, Pix2D, SubPixel},
@@ -32,16 +32,19 @@ impl SensorType {
   /// - 2x2 with at least 3 unique colors → [`SensorType::Bayer`]
   /// - 6x6 → [`SensorType::Xtrans`]
   ///
-  /// # Panics
-  /// Panics (via `unimplemented!()`) for CFA patterns that do not match
-  /// either a Bayer or X-Trans layout.
-  pub fn from_cfa(cfa: &CFA) -> Self {
+  /// # Errors
+  /// Returns [`RawlerError::UnsupportedFeature`] for CFA patterns that do not
+  /// match either a Bayer or X-Trans layout.
+  pub fn from_cfa(cfa: &CFA) -> Result<Self> {
     if cfa.width == 2 && cfa.height == 2 && cfa.unique_colors() >= 3 {
-      Self::Bayer
+      Ok(Self::Bayer)
     } else if cfa.width == 6 && cfa.height == 6 {
-      Self::Xtrans
+      Ok(Self::Xtrans)
     } else {
-      unimplemented!()
+      Err(RawlerError::unsupported_feature(
+        "CFA",
+        format!("{}x{} pattern '{}'", cfa.width, cfa.height, cfa.name),
+      ))
     }
   }
 }
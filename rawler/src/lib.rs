@@ -67,6 +67,7 @@
 use decoders::Camera;
 use decoders::Decoder;
 use decoders::RawDecodeParams;
+use decoders::RawImageKind;
 use formats::jfif::JfifError;
 use lazy_static::lazy_static;
 
@@ -74,7 +75,9 @@ pub mod analyze;
 pub mod bitarray;
 pub mod bits;
 pub mod buffer;
+pub mod calibration;
 pub mod cfa;
+pub mod compose;
 pub mod decoders;
 pub mod decompressors;
 pub mod devtools;
@@ -86,6 +89,8 @@ pub mod imgop;
 pub mod lens;
 pub mod ljpeg92;
 pub mod pixarray;
+pub mod probe;
+pub mod profiling;
 pub mod pumps;
 pub mod rawimage;
 pub mod rawsource;
@@ -106,6 +111,7 @@ lazy_static! {
   static ref LOADER: RawLoader = decoders::RawLoader::new();
 }
 
+use serde::{Deserialize, Serialize};
 use std::io::Read;
 use std::io::Seek;
 use std::path::Path;
@@ -122,10 +128,74 @@ pub enum RawlerError {
   #[error("Error: {}, model '{}', make: '{}', mode: '{}'", what, model, make, mode)]
   Unsupported { what: String, model: String, make: String, mode: String },
 
+  #[error("Unsupported {} feature: {}", format, feature)]
+  UnsupportedFeature { format: String, feature: String },
+
+  #[error("File is truncated in context '{}'{}", context, fmt_offset(.offset))]
+  Truncated {
+    context: String,
+    offset: Option<u64>,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("Malformed {} data{}: {}", format, fmt_offset(.offset), what)]
+  Malformed {
+    format: String,
+    what: String,
+    offset: Option<u64>,
+    #[source]
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+  },
+
+  #[error("I/O error in context '{}': {}", context, source)]
+  Io {
+    context: String,
+    #[source]
+    source: std::io::Error,
+  },
+
   #[error("Failed to decode image, possibly corrupt image: {}", _0)]
   DecoderFailed(String),
 }
 
+fn fmt_offset(offset: &Option<u64>) -> String {
+  offset.map(|offset| format!(" at offset 0x{:x}", offset)).unwrap_or_default()
+}
+
+/// Stable error codes for [`RawlerError`]
+///
+/// The numeric values are part of the public API. They are never changed
+/// or reused, so they can be stored, compared or used for exit status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u16)]
+pub enum ErrorCode {
+  /// Generic decoder failure, usually corrupt pixel data
+  DecoderFailed = 1,
+  /// Camera make, model or mode is unknown
+  UnsupportedCamera = 2,
+  /// Format feature (compression, layout...) is not implemented
+  UnsupportedFeature = 3,
+  /// Data is behind the end of the file
+  Truncated = 4,
+  /// Container structure or metadata is invalid
+  Malformed = 5,
+  /// Any other I/O error
+  Io = 6,
+}
+
+impl ErrorCode {
+  pub fn value(self) -> u16 {
+    self as u16
+  }
+}
+
+impl std::fmt::Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "E{:03}", self.value())
+  }
+}
+
 pub type Result<T> = std::result::Result<T, RawlerError>;
 
 impl RawlerError {
@@ -138,21 +208,72 @@ impl RawlerError {
     }
   }
 
+  /// Error for a valid but not implemented feature of a format,
+  /// like a compression method or sample layout.
+  pub fn unsupported_feature(format: impl AsRef<str>, feature: impl AsRef<str>) -> Self {
+    Self::UnsupportedFeature {
+      format: format.as_ref().to_string(),
+      feature: feature.as_ref().to_string(),
+    }
+  }
+
+  /// Error for invalid structures or metadata, like missing required tags.
+  pub fn malformed(format: impl AsRef<str>, what: impl AsRef<str>) -> Self {
+    Self::Malformed {
+      format: format.as_ref().to_string(),
+      what: what.as_ref().to_string(),
+      offset: None,
+      source: None,
+    }
+  }
+
   pub fn with_io_error(context: impl AsRef<str>, path: impl AsRef<Path>, error: std::io::Error) -> Self {
-    Self::DecoderFailed(format!(
-      "I/O error in context '{}', {} on file: {}",
-      context.as_ref(),
-      error,
-      path.as_ref().display()
-    ))
+    Self::from_io(format!("{}, file: {}", context.as_ref(), path.as_ref().display()), error)
+  }
+
+  /// Convert I/O error, reads behind EOF are reported as truncated file.
+  fn from_io(context: String, error: std::io::Error) -> Self {
+    if error.kind() == std::io::ErrorKind::UnexpectedEof {
+      let offset = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rawsource::OutOfBounds>())
+        .map(|oob| oob.offset);
+      Self::Truncated {
+        context,
+        offset,
+        source: error,
+      }
+    } else {
+      Self::Io { context, source: error }
+    }
+  }
+
+  /// Get the stable error code
+  pub fn code(&self) -> ErrorCode {
+    match self {
+      Self::Unsupported { .. } => ErrorCode::UnsupportedCamera,
+      Self::UnsupportedFeature { .. } => ErrorCode::UnsupportedFeature,
+      Self::Truncated { .. } => ErrorCode::Truncated,
+      Self::Malformed { .. } => ErrorCode::Malformed,
+      Self::Io { .. } => ErrorCode::Io,
+      Self::DecoderFailed(_) => ErrorCode::DecoderFailed,
+    }
+  }
+
+  /// File offset related to this error, if known
+  pub fn offset(&self) -> Option<u64> {
+    match self {
+      Self::Truncated { offset, .. } | Self::Malformed { offset, .. } => *offset,
+      _ => None,
+    }
   }
 }
 
 impl From<std::io::Error> for RawlerError {
   fn from(err: std::io::Error) -> Self {
-    log::error!("I/O error: {}", err.to_string());
+    log::error!("I/O error: {}", err);
     log::error!("Backtrace:\n{:?}", backtrace::Backtrace::new());
-    Self::DecoderFailed(format!("I/O Error without context: {}", err))
+    Self::from_io(String::from("unknown"), err)
   }
 }
 
@@ -182,13 +303,29 @@ impl From<String> for RawlerError {
 
 impl From<TiffError> for RawlerError {
   fn from(err: TiffError) -> Self {
-    Self::DecoderFailed(err.to_string())
+    match err {
+      TiffError::Io(err) => Self::from_io(String::from("TIFF"), err),
+      err => Self::Malformed {
+        format: String::from("TIFF"),
+        what: err.to_string(),
+        offset: None,
+        source: Some(Box::new(err)),
+      },
+    }
   }
 }
 
 impl From<JfifError> for RawlerError {
   fn from(err: JfifError) -> Self {
-    Self::DecoderFailed(err.to_string())
+    match err {
+      JfifError::Io(err) => Self::from_io(String::from("JFIF"), err),
+      err => Self::Malformed {
+        format: String::from("JFIF"),
+        what: err.to_string(),
+        offset: None,
+        source: Some(Box::new(err)),
+      },
+    }
   }
 }
 
@@ -246,6 +383,10 @@ pub fn raw_image_count_file<P: AsRef<Path>>(path: P) -> Result<usize> {
   LOADER.raw_image_count_file(path.as_ref())
 }
 
+pub fn raw_image_kinds_file<P: AsRef<Path>>(path: P) -> Result<Vec<RawImageKind>> {
+  LOADER.raw_image_kinds_file(path.as_ref())
+}
+
 pub fn global_loader() -> &'static RawLoader {
   &LOADER
 }
@@ -254,3 +395,32 @@ pub fn global_loader() -> &'static RawLoader {
 pub(crate) fn init_test_logger() {
   let _ = env_logger::builder().is_test(true).filter_level(log::LevelFilter::Debug).try_init();
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::error::Error;
+
+  #[test]
+  fn truncated_error_has_offset() {
+    let source = RawSource::new_from_slice(&[0; 16]);
+    let err: RawlerError = source.subview(10, 100).expect_err("subview must fail").into();
+    assert_eq!(err.code(), ErrorCode::Truncated);
+    assert_eq!(err.offset(), Some(10));
+    assert!(err.source().is_some());
+  }
+
+  #[test]
+  fn tiff_error_is_malformed() {
+    let err: RawlerError = TiffError::FormatMismatch("bad magic".into()).into();
+    assert_eq!(err.code(), ErrorCode::Malformed);
+    assert!(err.source().is_some());
+    let err: RawlerError = TiffError::Io(std::io::Error::new(std::io::ErrorKind::PermissionDenied, "denied")).into();
+    assert_eq!(err.code(), ErrorCode::Io);
+  }
+
+  #[test]
+  fn error_code_format() {
+    assert_eq!(ErrorCode::UnsupportedFeature.to_string(), "E003");
+  }
+}
@@ -231,48 +231,43 @@ where
   output
 }
 
-#[multiversion(targets("x86_64+avx+avx2", "x86+sse", "aarch64+neon"))]
-pub(crate) fn deinterleave2x2<T>(pixbuf: &Pix2D<T>) -> std::result::Result<Pix2D<T>, String>
+/// Reorder interleaved rows and columns (DNG `RowInterleaveFactor` and
+/// `ColumnInterleaveFactor`).
+///
+/// Interleaved images store all rows (or columns) of the first field, then all
+/// of the second field and so on. Field `f` holds the rows `f, f + factor, ...`.
+/// `cpp` is the number of samples per pixel, columns are interleaved per pixel.
+pub(crate) fn deinterleave<T>(pixbuf: &Pix2D<T>, cpp: usize, row_factor: usize, col_factor: usize) -> std::result::Result<Pix2D<T>, String>
 where
   T: SubPixel,
 {
-  if pixbuf.initialized {
-    let line_width = pixbuf.width;
-
-    let half_width = line_width / 2;
-    let line_distance = line_width;
-    let ch0 = &pixbuf[..];
-    let ch1 = &pixbuf[half_width..];
-    let ch2 = &pixbuf[pixbuf.len() / 2..];
-    let ch3 = &pixbuf[pixbuf.len() / 2 + half_width..];
-
-    decompress_lines_fn(pixbuf.width, pixbuf.height, false, &|line, row| {
-      let src_row = row / 2;
-      let offset = src_row * line_distance;
-      let ch_a;
-      let ch_b;
-      if row & 1 == 0 {
-        // For even rows, we take top-left and top-right channel data.
-        ch_a = &ch0[offset..offset + half_width];
-        ch_b = &ch1[offset..offset + half_width];
-      } else {
-        // For odd rows, we take bottom-left and bottom-right channel data.
-        ch_a = &ch2[offset..offset + half_width];
-        ch_b = &ch3[offset..offset + half_width];
-      }
-
-      debug_assert_eq!(ch_a.len(), ch_b.len());
-      debug_assert_eq!(ch_a.len() + ch_b.len(), line.len());
-      debug_assert_eq!(line_width, line.len());
-      line.chunks_exact_mut(2).zip(ch_a.iter().zip(ch_b.iter())).for_each(|(dst, (a, b))| {
-        dst[0] = *a;
-        dst[1] = *b;
-      });
-      Ok(())
-    })
-  } else {
-    Ok(pixbuf.clone())
-  }
+  if row_factor == 0 || col_factor == 0 || cpp == 0 || !pixbuf.width.is_multiple_of(cpp) {
+    return Err(format!(
+      "Invalid interleave factor {}x{} for image of width {} (cpp: {})",
+      row_factor, col_factor, pixbuf.width, cpp
+    ));
+  }
+  if !pixbuf.initialized || (row_factor == 1 && col_factor == 1) {
+    return Ok(pixbuf.clone());
+  }
+  let width = pixbuf.width / cpp;
+  let cols: Vec<usize> = (0..width).map(|col| interleaved_position(col, width, col_factor)).collect();
+
+  decompress_lines_fn(pixbuf.width, pixbuf.height, false, &|line, row| {
+    let src = pixbuf.row(interleaved_position(row, pixbuf.height, row_factor));
+    for (dst, src_col) in line.chunks_exact_mut(cpp).zip(cols.iter()) {
+      dst.copy_from_slice(&src[src_col * cpp..src_col * cpp + cpp]);
+    }
+    Ok(())
+  })
+}
+
+/// Position of `pos` in storage order, for `len` elements
+/// split into `factor` fields.
+fn interleaved_position(pos: usize, len: usize, factor: usize) -> usize {
+  let field = pos % factor;
+  let preceding: usize = (0..field).map(|f| (len - f).div_ceil(factor)).sum();
+  preceding + pos / factor
 }
 
 /*
@@ -0,0 +1,248 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Fast metadata-only probing
+//!
+//! Probing identifies a raw file and reads its capture metadata without
+//! decoding any pixel data. All reads go through a lazy source with a
+//! fixed byte budget, so the amount of I/O per file is guaranteed. If a
+//! decoder needs more data than the budget allows, the probe fails with
+//! a [`BudgetExceeded`] I/O error instead of reading on.
+
+use std::{
+  fs::File,
+  path::Path,
+  sync::{
+    Arc,
+    atomic::{AtomicU64, Ordering},
+  },
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  RawlerError, Result,
+  decoders::{FormatHint, RawDecodeParams, RawMetadata},
+  formats::tiff::{GenericTiffReader, IFD, reader::TiffReader},
+  imgop::Dim2,
+  rawsource::{RawSource, RawSourceBackend},
+  tags::{ExifTag, TiffCommonTag, TiffTag},
+};
+
+/// Default byte budget for probing a single file
+pub const DEFAULT_PROBE_BUDGET: u64 = 1024 * 1024;
+
+/// Error payload for reads exceeding the probe budget
+///
+/// This is wrapped into an [`std::io::Error`].
+#[derive(Debug, thiserror::Error)]
+#[error("Probe byte budget of {} bytes exceeded by read of {}+{}", budget, offset, size)]
+pub struct BudgetExceeded {
+  pub budget: u64,
+  pub offset: u64,
+  pub size: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct ProbeParams {
+  /// Maximum number of bytes read from the source
+  pub byte_budget: u64,
+  pub decode_params: RawDecodeParams,
+}
+
+impl Default for ProbeParams {
+  fn default() -> Self {
+    Self {
+      byte_budget: DEFAULT_PROBE_BUDGET,
+      decode_params: RawDecodeParams::default(),
+    }
+  }
+}
+
+/// Location of an embedded preview or thumbnail image
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviewLocation {
+  pub offset: u64,
+  pub size: u64,
+  /// Dimension, if stored in the container
+  pub dim: Option<Dim2>,
+  /// TIFF compression, 6 or 7 is JPEG
+  pub compression: Option<u16>,
+}
+
+/// Result of [`probe`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProbeResult {
+  pub format: FormatHint,
+  pub make: String,
+  pub model: String,
+  /// Camera mode, if it can be determined within the budget
+  pub mode: Option<String>,
+  /// Raw image dimension, if it can be determined within the budget
+  pub dim: Option<Dim2>,
+  pub metadata: RawMetadata,
+  pub previews: Vec<PreviewLocation>,
+  /// Number of bytes read from the source
+  pub bytes_read: u64,
+}
+
+/// Backend which serves reads from another source until the budget is used up
+struct BudgetBackend {
+  source: RawSource,
+  budget: u64,
+  used: Arc<AtomicU64>,
+}
+
+impl RawSourceBackend for BudgetBackend {
+  fn len(&self) -> u64 {
+    self.source.len() as u64
+  }
+
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+    let size = buf.len() as u64;
+    self
+      .used
+      .fetch_update(Ordering::AcqRel, Ordering::Acquire, |used| {
+        used.checked_add(size).filter(|total| *total <= self.budget)
+      })
+      .map_err(|_| {
+        std::io::Error::other(BudgetExceeded {
+          budget: self.budget,
+          offset,
+          size,
+        })
+      })?;
+    buf.copy_from_slice(self.source.subview(offset, size)?);
+    Ok(())
+  }
+}
+
+/// Probe a raw file
+///
+/// The file is read on demand, so only the probed ranges are loaded
+/// from disk.
+pub fn probe_file<P: AsRef<Path>>(path: P, params: &ProbeParams) -> Result<ProbeResult> {
+  let path = path.as_ref();
+  let file = File::open(path).map_err(|err| RawlerError::with_io_error("probe_file()", path, err))?;
+  let source = RawSource::new_from_reader(file)
+    .map_err(|err| RawlerError::with_io_error("probe_file()", path, err))?
+    .with_path(path);
+  probe(&source, params)
+}
+
+/// Probe a raw source for format, camera, dimension, metadata and previews
+pub fn probe(source: &RawSource, params: &ProbeParams) -> Result<ProbeResult> {
+  let used = Arc::new(AtomicU64::default());
+  let budget_source = RawSource::new_from_backend(BudgetBackend {
+    source: source.clone(),
+    budget: params.byte_budget,
+    used: used.clone(),
+  })
+  .with_path(source.path());
+
+  let decoder = crate::get_decoder(&budget_source)?;
+  let metadata = decoder.raw_metadata(&budget_source, &params.decode_params)?;
+
+  let mut mode = decoder.raw_mode(&params.decode_params)?;
+  let mut dim = decoder.raw_dimensions(&params.decode_params)?;
+  if dim.is_none() {
+    // Dummy decoding doesn't decompress, but may touch the pixel data.
+    // This only succeeds if it fits into the remaining budget.
+    match decoder.raw_image(&budget_source, &params.decode_params, true) {
+      Ok(image) => {
+        dim = Some(image.dim());
+        mode = Some(image.camera.mode.clone());
+      }
+      Err(err) => log::debug!("Probe: raw dimension not available: {}", err),
+    }
+  }
+
+  Ok(ProbeResult {
+    format: decoder.format_hint(),
+    make: metadata.make.clone(),
+    model: metadata.model.clone(),
+    mode,
+    dim,
+    metadata,
+    previews: preview_locations(&budget_source),
+    bytes_read: used.load(Ordering::Acquire),
+  })
+}
+
+/// Find embedded previews in TIFF based containers
+///
+/// The TIFF structure is already cached by the decoder, so this needs
+/// no additional reads in most cases.
+fn preview_locations(source: &RawSource) -> Vec<PreviewLocation> {
+  let Ok(tiff) = GenericTiffReader::new(&mut source.reader(), 0, 0, None, &[]) else {
+    return Vec::new();
+  };
+  let mut previews: Vec<PreviewLocation> = Vec::new();
+  for ifd in tiff.find_ifds_with_filter(|_| true) {
+    let location = if let (Some(offset), Some(size)) = (
+      first_u64(ifd, ExifTag::JPEGInterchangeFormat),
+      first_u64(ifd, ExifTag::JPEGInterchangeFormatLength),
+    ) {
+      PreviewLocation {
+        offset,
+        size,
+        dim: None,
+        compression: Some(6),
+      }
+    } else if first_u64(ifd, TiffCommonTag::NewSubFileType).is_some_and(|typ| typ & 1 == 1) {
+      let (Some(offset), Some(size)) = (first_u64(ifd, TiffCommonTag::StripOffsets), first_u64(ifd, TiffCommonTag::StripByteCounts)) else {
+        continue;
+      };
+      let dim = first_u64(ifd, TiffCommonTag::ImageWidth).zip(first_u64(ifd, TiffCommonTag::ImageLength));
+      PreviewLocation {
+        offset,
+        size,
+        dim: dim.map(|(w, h)| Dim2::new(w as usize, h as usize)),
+        compression: first_u64(ifd, TiffCommonTag::Compression).map(|c| c as u16),
+      }
+    } else {
+      continue;
+    };
+    if location.size > 0 && !previews.iter().any(|p| p.offset == location.offset) {
+      previews.push(location);
+    }
+  }
+  previews
+}
+
+fn first_u64<T: TiffTag>(ifd: &IFD, tag: T) -> Option<u64> {
+  ifd.get_entry(tag).and_then(|entry| entry.value.get_u64(0).ok().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::devtools::synthetic::{dng, test_pattern};
+
+  #[test]
+  fn probe_reads_only_metadata() -> Result<()> {
+    let data = dng::encode_packed(&test_pattern(1024, 768, 16), 16)?;
+    let len = data.len() as u64;
+    let result = probe(&RawSource::new_from_shared_vec(Arc::new(data)), &ProbeParams::default())?;
+    assert_eq!(result.format, FormatHint::DNG);
+    assert_eq!(result.dim, Some(Dim2::new(1024, 768)));
+    assert_eq!(result.make, "Synthetic");
+    assert_eq!(result.mode.as_deref(), Some("dng"));
+    assert!(result.bytes_read <= DEFAULT_PROBE_BUDGET);
+    assert!(result.bytes_read < len / 4);
+    Ok(())
+  }
+
+  #[test]
+  fn probe_respects_budget() -> Result<()> {
+    let source = RawSource::new_from_shared_vec(Arc::new(dng::encode_packed(&test_pattern(128, 96, 16), 16)?));
+    let params = ProbeParams {
+      byte_budget: 16,
+      ..Default::default()
+    };
+    assert!(probe(&source, &params).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,991 @@
+// SPDX-License-Identifier: LGPL-2.1
+// Copyright 2025 Daniel Vogelbacher <daniel@chaospixel.com>
+
+//! Camera profile generation from a color target shot
+//!
+//! The camera response of each patch of a ColorChecker target is sampled
+//! from the raw image and matched against the reference CIELab (D50) values
+//! of the target:
+//!
+//! * A camera to XYZ (D50) matrix is solved by least squares in CIELab.
+//! * The camera neutral for the illuminant of the shot is the camera
+//!   response to a perfect white reflector under this matrix.
+//! * Forward matrix (white balanced camera to XYZ D50) and color matrix
+//!   (XYZ to camera) are derived from both.
+//! * Optionally, a hue/saturation map corrects the residual errors
+//!   of the matrix.
+//!
+//! Patches are located by the centers of the four corner patches. If these
+//! are not given, an axis aligned chart is searched in the image.
+
+use rayon::prelude::*;
+
+use crate::{
+  RawImage, RawlerError, Result,
+  dng::dcp::{DcpProfile, HsvTable, ProfileCalibration, rgb_to_hsv},
+  imgop::{
+    chromatic_adaption::bradford_adaption_matrix,
+    cielab::{XYZ_to_lab, lab_to_XYZ},
+    develop::{Intermediate, ProcessingStep, RawDevelop},
+    matrix::{multiply, multiply_row1, pseudo_inverse, transform_2d},
+    xyz::{CIE_1931_TRISTIMULUS_D50, Illuminant, XYZ_TO_PROFOTORGB_D50},
+  },
+  pixarray::Color2D,
+};
+
+/// Patches with a channel above this value (relative to white level) are clipped
+const CLIPPING_THRESHOLD: f32 = 0.97;
+
+/// Maximum dimension of the downscaled image for chart detection
+const DETECT_SIZE: usize = 256;
+
+/// Maximum ratio of noise within patches to the difference between patches
+const MAX_DETECT_SCORE: f64 = 0.2;
+
+/// Number of chart positions verified by a fit
+const MAX_DETECT_CANDIDATES: usize = 8;
+
+/// Illuminants which can be used for the color matrix
+pub const PROFILE_ILLUMINANTS: [Illuminant; 9] = [
+  Illuminant::A,
+  Illuminant::B,
+  Illuminant::C,
+  Illuminant::D50,
+  Illuminant::D55,
+  Illuminant::D65,
+  Illuminant::D75,
+  Illuminant::Daylight,
+  Illuminant::Flash,
+];
+
+/// Reference values for the ColorChecker Classic (CIELab D50, X-Rite formulation after November 2014)
+const COLORCHECKER24_LAB: [[f32; 3]; 24] = [
+  [37.54, 14.37, 14.92],
+  [64.66, 19.27, 17.50],
+  [49.32, -3.82, -22.54],
+  [43.46, -12.74, 22.72],
+  [54.94, 9.61, -24.79],
+  [70.48, -32.26, -0.37],
+  [62.73, 35.83, 56.50],
+  [39.43, 10.75, -45.17],
+  [50.57, 48.64, 16.67],
+  [30.10, 22.54, -20.87],
+  [71.77, -24.13, 58.19],
+  [71.51, 18.24, 67.37],
+  [28.37, 15.42, -49.80],
+  [54.38, -39.72, 32.27],
+  [42.43, 51.05, 28.62],
+  [81.80, 2.67, 80.41],
+  [50.63, 51.28, -14.12],
+  [49.57, -29.71, -28.32],
+  [95.19, -1.03, 2.93],
+  [81.29, -0.57, 0.44],
+  [66.89, -0.75, -0.06],
+  [50.76, -0.13, 0.14],
+  [35.63, -0.46, -0.48],
+  [20.64, 0.07, -0.46],
+];
+
+/// Supported color targets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetChart {
+  /// ColorChecker Classic with 24 patches
+  ColorChecker24,
+  /// ColorChecker Digital SG with 140 patches
+  ColorCheckerSG,
+}
+
+impl TargetChart {
+  pub fn new_from_str(s: &str) -> Result<Self> {
+    match s {
+      "colorchecker24" => Ok(Self::ColorChecker24),
+      "colorchecker-sg" => Ok(Self::ColorCheckerSG),
+      _ => Err(RawlerError::unsupported_feature("profiling", format!("color target '{}'", s))),
+    }
+  }
+
+  /// Number of patch columns and rows, in landscape orientation
+  pub fn grid(&self) -> (usize, usize) {
+    match self {
+      Self::ColorChecker24 => (6, 4),
+      Self::ColorCheckerSG => (14, 10),
+    }
+  }
+
+  pub fn patch_count(&self) -> usize {
+    let (cols, rows) = self.grid();
+    cols * rows
+  }
+}
+
+/// Reference values of a color target
+#[derive(Clone, Debug)]
+pub struct TargetReference {
+  pub chart: TargetChart,
+  /// CIELab (D50) values in row-major order, starting at the top-left patch
+  pub lab: Vec<[f32; 3]>,
+}
+
+impl TargetReference {
+  /// Built-in reference for the ColorChecker Classic
+  pub fn colorchecker24() -> Self {
+    Self {
+      chart: TargetChart::ColorChecker24,
+      lab: COLORCHECKER24_LAB.to_vec(),
+    }
+  }
+
+  /// Parse reference values from a CGATS text file.
+  ///
+  /// CIELab (`LAB_L`, `LAB_A`, `LAB_B`) or XYZ (`XYZ_X`, `XYZ_Y`, `XYZ_Z`) fields
+  /// are supported. If all sample IDs are in the form `A1` (letter for column,
+  /// number for row), they define the patch positions. Otherwise the values
+  /// must be in row-major order.
+  pub fn from_cgats(chart: TargetChart, text: &str) -> Result<Self> {
+    let mut fields: Vec<&str> = Vec::new();
+    let mut rows: Vec<Vec<&str>> = Vec::new();
+    let mut section = None;
+    for line in text.lines().map(str::trim) {
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      match line {
+        "BEGIN_DATA_FORMAT" | "BEGIN_DATA" | "END_DATA_FORMAT" | "END_DATA" => {
+          section = line.strip_prefix("BEGIN_");
+          continue;
+        }
+        _ => {}
+      }
+      match section {
+        Some("DATA_FORMAT") => fields.extend(line.split_whitespace()),
+        Some("DATA") => rows.push(line.split_whitespace().collect()),
+        _ => {}
+      }
+    }
+
+    let field = |name: &str| fields.iter().position(|f| f.eq_ignore_ascii_case(name));
+    let value = |row: &[&str], idx: usize| -> Result<f32> {
+      row
+        .get(idx)
+        .and_then(|v| v.parse::<f32>().ok())
+        .ok_or_else(|| RawlerError::malformed("CGATS", format!("Invalid value in data row: {}", row.join(" "))))
+    };
+    let lab: Vec<[f32; 3]> = match (field("LAB_L"), field("LAB_A"), field("LAB_B"), field("XYZ_X"), field("XYZ_Y"), field("XYZ_Z")) {
+      (Some(l), Some(a), Some(b), _, _, _) => rows
+        .iter()
+        .map(|row| Ok([value(row, l)?, value(row, a)?, value(row, b)?]))
+        .collect::<Result<_>>()?,
+      (_, _, _, Some(x), Some(y), Some(z)) => rows
+        .iter()
+        .map(|row| {
+          let xyz = [value(row, x)? / 100.0, value(row, y)? / 100.0, value(row, z)? / 100.0];
+          Ok(XYZ_to_lab(&xyz, &CIE_1931_TRISTIMULUS_D50))
+        })
+        .collect::<Result<_>>()?,
+      _ => return Err(RawlerError::malformed("CGATS", "No LAB_L/LAB_A/LAB_B or XYZ_X/XYZ_Y/XYZ_Z fields found")),
+    };
+    if lab.len() != chart.patch_count() {
+      return Err(RawlerError::malformed(
+        "CGATS",
+        format!("Found {} samples, but {:?} has {} patches", lab.len(), chart, chart.patch_count()),
+      ));
+    }
+
+    // Reorder by sample IDs, if possible
+    let (cols, rows_count) = chart.grid();
+    let positions: Option<Vec<usize>> = field("SAMPLE_ID")
+      .or(field("SAMPLE_NAME"))
+      .and_then(|id| rows.iter().map(|row| row.get(id).and_then(|id| patch_position(id, cols, rows_count))).collect());
+    match positions {
+      Some(positions) if !has_duplicates(&positions) => {
+        let mut ordered = vec![[0.0; 3]; lab.len()];
+        for (pos, value) in positions.into_iter().zip(lab) {
+          ordered[pos] = value;
+        }
+        Ok(Self { chart, lab: ordered })
+      }
+      _ => Ok(Self { chart, lab }),
+    }
+  }
+}
+
+/// Row-major position for sample IDs like `A1` (column A, row 1)
+fn patch_position(id: &str, cols: usize, rows: usize) -> Option<usize> {
+  let letter = id.chars().next().filter(char::is_ascii_alphabetic)?;
+  let col = (letter.to_ascii_uppercase() as u8 - b'A') as usize;
+  let row = id[1..].parse::<usize>().ok()?.checked_sub(1)?;
+  (col < cols && row < rows).then_some(row * cols + col)
+}
+
+fn has_duplicates(positions: &[usize]) -> bool {
+  let mut sorted = positions.to_vec();
+  sorted.sort_unstable();
+  sorted.windows(2).any(|w| w[0] == w[1])
+}
+
+/// Centers of the four corner patches as (x, y) pixel coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChartCorners {
+  pub top_left: (f32, f32),
+  pub top_right: (f32, f32),
+  pub bottom_right: (f32, f32),
+  pub bottom_left: (f32, f32),
+}
+
+impl ChartCorners {
+  /// Build from `[x1, y1, ..., x4, y4]` in the order top-left, top-right,
+  /// bottom-right, bottom-left
+  pub fn new_from_slice(values: &[f32]) -> Result<Self> {
+    match values {
+      [x1, y1, x2, y2, x3, y3, x4, y4] => Ok(Self {
+        top_left: (*x1, *y1),
+        top_right: (*x2, *y2),
+        bottom_right: (*x3, *y3),
+        bottom_left: (*x4, *y4),
+      }),
+      _ => Err(RawlerError::unsupported_feature(
+        "profiling",
+        format!("{} corner coordinates, 8 values are required", values.len()),
+      )),
+    }
+  }
+
+  /// Same chart, turned upside down
+  pub fn rotate_180(&self) -> Self {
+    Self {
+      top_left: self.bottom_right,
+      top_right: self.bottom_left,
+      bottom_right: self.top_left,
+      bottom_left: self.top_right,
+    }
+  }
+
+  fn patch_center(&self, col: usize, row: usize, cols: usize, rows: usize) -> (f32, f32) {
+    let u = col as f32 / (cols - 1) as f32;
+    let v = row as f32 / (rows - 1) as f32;
+    let mix = |tl: f32, tr: f32, br: f32, bl: f32| (1.0 - u) * (1.0 - v) * tl + u * (1.0 - v) * tr + u * v * br + (1.0 - u) * v * bl;
+    (
+      mix(self.top_left.0, self.top_right.0, self.bottom_right.0, self.bottom_left.0),
+      mix(self.top_left.1, self.top_right.1, self.bottom_right.1, self.bottom_left.1),
+    )
+  }
+
+  /// Smallest distance between two patch centers
+  fn patch_pitch(&self, cols: usize, rows: usize) -> f32 {
+    let dist = |a: (f32, f32), b: (f32, f32)| ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt();
+    let horizontal = dist(self.top_left, self.top_right).min(dist(self.bottom_left, self.bottom_right)) / (cols - 1) as f32;
+    let vertical = dist(self.top_left, self.bottom_left).min(dist(self.top_right, self.bottom_right)) / (rows - 1) as f32;
+    horizontal.min(vertical)
+  }
+}
+
+/// Linear camera RGB image, without white balance and color calibration.
+///
+/// Pixel coordinates match the output of `dnglab process-raw`.
+pub fn camera_image(rawimage: &RawImage) -> Result<Color2D<f32, 3>> {
+  let develop = RawDevelop::new_with(&[
+    ProcessingStep::Rescale,
+    ProcessingStep::Demosaic,
+    ProcessingStep::FujiRotate,
+    ProcessingStep::CropActiveArea,
+    ProcessingStep::CropDefault,
+  ]);
+  match develop.develop_intermediate(rawimage)? {
+    Intermediate::ThreeColor(pixels) => Ok(pixels),
+    _ => Err(RawlerError::unsupported_feature("profiling", "images without three color channels")),
+  }
+}
+
+/// Sample the mean camera response of all patches, in row-major order.
+///
+/// The center half of each patch is used.
+pub fn sample_patches(image: &Color2D<f32, 3>, chart: TargetChart, corners: &ChartCorners) -> Result<Vec<[f32; 3]>> {
+  let (cols, rows) = chart.grid();
+  let radius = (corners.patch_pitch(cols, rows) * 0.25).max(1.0);
+  let mut patches = Vec::with_capacity(chart.patch_count());
+  for row in 0..rows {
+    for col in 0..cols {
+      let (cx, cy) = corners.patch_center(col, row, cols, rows);
+      let (x0, x1) = ((cx - radius).round(), (cx + radius).round());
+      let (y0, y1) = ((cy - radius).round(), (cy + radius).round());
+      if x0 < 0.0 || y0 < 0.0 || x1 >= image.width as f32 || y1 >= image.height as f32 {
+        return Err(RawlerError::DecoderFailed(format!(
+          "Patch at column {}, row {} ({:.0}, {:.0}) is outside of image",
+          col + 1,
+          row + 1,
+          cx,
+          cy
+        )));
+      }
+      let mut sum = [0.0_f64; 3];
+      let mut count = 0;
+      for y in y0 as usize..=y1 as usize {
+        for x in x0 as usize..=x1 as usize {
+          let pix = image.at(y, x);
+          for c in 0..3 {
+            sum[c] += pix[c] as f64;
+          }
+          count += 1;
+        }
+      }
+      patches.push(sum.map(|v| (v / count as f64) as f32));
+    }
+  }
+  Ok(patches)
+}
+
+/// Search an axis aligned color target in the image.
+///
+/// Returns candidate positions, best first. A grid which is shifted by
+/// a patch can look as uniform as the chart itself, so the candidates
+/// should be verified against the reference values, like [`profile_from_raw`] does.
+///
+/// The chart must be roughly horizontal (or upside down) and should
+/// cover a good part of the image.
+pub fn detect_chart(image: &Color2D<f32, 3>, chart: TargetChart) -> Vec<ChartCorners> {
+  let scale = image.width.max(image.height).div_ceil(DETECT_SIZE).max(1);
+  let (sw, sh) = (image.width / scale, image.height / scale);
+  if sw < 16 || sh < 16 {
+    return Vec::new();
+  }
+  // Downscaled, gamma compressed brightness
+  let mut small = vec![0.0_f64; sw * sh];
+  for (i, v) in small.iter_mut().enumerate() {
+    let (sx, sy) = (i % sw, i / sw);
+    let mut sum = 0.0;
+    for y in sy * scale..(sy + 1) * scale {
+      for x in sx * scale..(sx + 1) * scale {
+        let pix = image.at(y, x);
+        sum += (pix[0] + pix[1] + pix[2]).max(0.0) as f64;
+      }
+    }
+    *v = (sum / (scale * scale) as f64).sqrt();
+  }
+  let integral = IntegralImage::new(&small, sw, sh);
+  let (_, total_var) = integral.stats(0, 0, sw, sh);
+  let eps = total_var * 1.0e-3 + 1.0e-12;
+
+  let (cols, rows) = chart.grid();
+  let score = |x: usize, y: usize, w: usize, h: usize| -> f64 {
+    let (px, py) = (w as f64 / cols as f64, h as f64 / rows as f64);
+    let mut within = 0.0;
+    let mut means = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+      for col in 0..cols {
+        let x0 = (x as f64 + (col as f64 + 0.25) * px).round() as usize;
+        let x1 = (x as f64 + (col as f64 + 0.75) * px).round() as usize;
+        let y0 = (y as f64 + (row as f64 + 0.25) * py).round() as usize;
+        let y1 = (y as f64 + (row as f64 + 0.75) * py).round() as usize;
+        let (mean, var) = integral.stats(x0, y0, x1.max(x0 + 1), y1.max(y0 + 1));
+        within += var;
+        means.push(mean);
+      }
+    }
+    // Neighbouring patches of a chart differ, this rejects grids
+    // with a fraction of the patch size.
+    let mut diffs = Vec::with_capacity(cols * rows * 2);
+    for row in 0..rows {
+      for col in 0..cols {
+        let m = means[row * cols + col];
+        if col + 1 < cols {
+          diffs.push((m - means[row * cols + col + 1]).powi(2));
+        }
+        if row + 1 < rows {
+          diffs.push((m - means[(row + 1) * cols + col]).powi(2));
+        }
+      }
+    }
+    let quarter = diffs.len() / 4;
+    let (_, lower_quartile, _) = diffs.select_nth_unstable_by(quarter, f64::total_cmp);
+    (within / means.len() as f64 + eps) / (*lower_quartile + eps)
+  };
+  let height_for = |w: usize, ratio: f64| (w as f64 / cols as f64 * rows as f64 * ratio).round() as usize;
+
+  // Coarse search
+  let step = (sw / 64).max(2);
+  let min_w = (sw / 5).max(cols * 2);
+  let ratios = [0.85, 0.92, 1.0, 1.08, 1.15];
+  let widths: Vec<usize> = (min_w..=sw).step_by(step).collect();
+  let mut coarse: Vec<(f64, (usize, usize, usize, f64))> = widths
+    .into_par_iter()
+    .flat_map_iter(|w| {
+      let mut results = Vec::new();
+      for ratio in ratios {
+        let h = height_for(w, ratio);
+        if h < rows * 2 || h > sh {
+          continue;
+        }
+        for y in (0..=sh - h).step_by(step) {
+          for x in (0..=sw - w).step_by(step) {
+            // Patches must differ much more than the noise within a patch
+            let s = score(x, y, w, h);
+            if s <= MAX_DETECT_SCORE {
+              results.push((s, (x, y, w, ratio)));
+            }
+          }
+        }
+      }
+      results
+    })
+    .collect();
+  coarse.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+  // Best distinct candidates
+  let mut candidates: Vec<(f64, (usize, usize, usize, f64))> = Vec::new();
+  for (s, cand) in coarse {
+    if candidates.len() == MAX_DETECT_CANDIDATES {
+      break;
+    }
+    if !candidates
+      .iter()
+      .any(|(_, c)| c.0.abs_diff(cand.0) <= step && c.1.abs_diff(cand.1) <= step && c.2.abs_diff(cand.2) <= step)
+    {
+      candidates.push((s, cand));
+    }
+  }
+
+  // Refine around each candidate
+  let refine = |(mut best_score, mut best): (f64, (usize, usize, usize, f64))| {
+    let (bx, by, bw, bratio) = best;
+    for dw in -(step as isize)..=step as isize {
+      let w = bw as isize + dw;
+      if w < min_w as isize || w > sw as isize {
+        continue;
+      }
+      let w = w as usize;
+      for ratio in [bratio - 0.04, bratio - 0.02, bratio, bratio + 0.02, bratio + 0.04] {
+        let h = height_for(w, ratio);
+        if h > sh {
+          continue;
+        }
+        for dy in -(step as isize)..=step as isize {
+          for dx in -(step as isize)..=step as isize {
+            let (x, y) = (bx as isize + dx, by as isize + dy);
+            if x < 0 || y < 0 || x as usize + w > sw || y as usize + h > sh {
+              continue;
+            }
+            let s = score(x as usize, y as usize, w, h);
+            if s < best_score {
+              best_score = s;
+              best = (x as usize, y as usize, w, ratio);
+            }
+          }
+        }
+      }
+    }
+    log::debug!("Chart detection score: {}, candidate: {:?}", best_score, best);
+    let (x, y, w, ratio) = best;
+    let h = height_for(w, ratio);
+    let (px, py) = (w as f32 / cols as f32, h as f32 / rows as f32);
+    let to_image = |sx: f32, sy: f32| (sx * scale as f32, sy * scale as f32);
+    let (left, top) = (x as f32 + px / 2.0, y as f32 + py / 2.0);
+    let (right, bottom) = (x as f32 + w as f32 - px / 2.0, y as f32 + h as f32 - py / 2.0);
+    ChartCorners {
+      top_left: to_image(left, top),
+      top_right: to_image(right, top),
+      bottom_right: to_image(right, bottom),
+      bottom_left: to_image(left, bottom),
+    }
+  };
+  candidates.into_par_iter().map(refine).collect()
+}
+
+/// Summed area tables for mean and variance of rectangles
+struct IntegralImage {
+  width: usize,
+  sum: Vec<f64>,
+  sum_sq: Vec<f64>,
+}
+
+impl IntegralImage {
+  fn new(data: &[f64], width: usize, height: usize) -> Self {
+    let stride = width + 1;
+    let mut sum = vec![0.0; stride * (height + 1)];
+    let mut sum_sq = vec![0.0; stride * (height + 1)];
+    for y in 0..height {
+      let (mut row_sum, mut row_sum_sq) = (0.0, 0.0);
+      for x in 0..width {
+        let v = data[y * width + x];
+        row_sum += v;
+        row_sum_sq += v * v;
+        sum[(y + 1) * stride + x + 1] = sum[y * stride + x + 1] + row_sum;
+        sum_sq[(y + 1) * stride + x + 1] = sum_sq[y * stride + x + 1] + row_sum_sq;
+      }
+    }
+    Self { width, sum, sum_sq }
+  }
+
+  /// Mean and variance for area x0..x1, y0..y1 (exclusive)
+  fn stats(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> (f64, f64) {
+    let stride = self.width + 1;
+    let area = |t: &[f64]| t[y1 * stride + x1] - t[y0 * stride + x1] - t[y1 * stride + x0] + t[y0 * stride + x0];
+    let n = ((x1 - x0) * (y1 - y0)) as f64;
+    let mean = area(&self.sum) / n;
+    (mean, (area(&self.sum_sq) / n - mean * mean).max(0.0))
+  }
+}
+
+/// Result of a profile fit
+#[derive(Clone, Debug)]
+pub struct ProfileFit {
+  /// Illuminant of the target shot
+  pub illuminant: Illuminant,
+  /// Camera response to neutral, normalized to a maximum of 1.0
+  pub camera_neutral: [f32; 3],
+  /// XYZ to camera matrix
+  pub color_matrix: [[f32; 3]; 3],
+  /// White balanced camera to XYZ (D50) matrix
+  pub forward_matrix: [[f32; 3]; 3],
+  pub hue_sat_map: Option<HsvTable>,
+  /// Remaining color difference (CIE76) for each patch,
+  /// NaN for clipped patches which are excluded from the fit
+  pub delta_e: Vec<f32>,
+}
+
+impl ProfileFit {
+  pub fn mean_delta_e(&self) -> f32 {
+    let used: Vec<f32> = self.delta_e.iter().copied().filter(|v| v.is_finite()).collect();
+    used.iter().sum::<f32>() / used.len().max(1) as f32
+  }
+
+  pub fn max_delta_e(&self) -> f32 {
+    self.delta_e.iter().copied().filter(|v| v.is_finite()).fold(0.0, f32::max)
+  }
+
+  /// Build a camera profile for a single illuminant
+  pub fn to_dcp_profile(&self, unique_camera_model: &str, name: &str) -> DcpProfile {
+    DcpProfile {
+      name: Some(name.to_string()),
+      unique_camera_model: Some(unique_camera_model.to_string()),
+      calibrations: vec![ProfileCalibration {
+        illuminant: self.illuminant,
+        color_matrix: Some(transform_2d(&self.color_matrix)),
+        forward_matrix: Some(transform_2d(&self.forward_matrix)),
+        hue_sat_map: self.hue_sat_map.clone(),
+        illuminant_data: None,
+      }],
+      ..Default::default()
+    }
+  }
+
+  /// Color matrix snippet for camera definitions in `rawler/data/cameras`
+  pub fn to_camera_toml(&self) -> String {
+    let values: Vec<String> = self.color_matrix.as_flattened().iter().map(|v| format!("{:.4}", v)).collect();
+    format!("[cameras.color_matrix]\n{:?} = [{}]\n", self.illuminant, values.join(", "))
+  }
+}
+
+/// Fit color matrix and forward matrix (and optionally a hue/saturation map)
+/// for the camera response of all patches.
+pub fn fit_profile(camera: &[[f32; 3]], reference: &TargetReference, illuminant: Illuminant, hue_sat_map: bool) -> Result<ProfileFit> {
+  if camera.len() != reference.lab.len() {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Got {} patches, but reference has {} values",
+      camera.len(),
+      reference.lab.len()
+    )));
+  }
+  if !PROFILE_ILLUMINANTS.contains(&illuminant) {
+    return Err(RawlerError::unsupported_feature("profiling", format!("illuminant {:?}", illuminant)));
+  }
+  let white = CIE_1931_TRISTIMULUS_D50;
+  let ref_xyz: Vec<[f32; 3]> = reference.lab.iter().map(|lab| lab_to_XYZ(lab, &white)).collect();
+  let usable: Vec<bool> = camera
+    .iter()
+    .map(|cam| cam.iter().all(|v| v.is_finite()) && cam.iter().all(|v| *v < CLIPPING_THRESHOLD))
+    .collect();
+  let used = usable.iter().filter(|u| **u).count();
+  if used < 8 {
+    return Err(RawlerError::DecoderFailed(format!(
+      "Only {} patches are usable (not clipped), at least 8 are required",
+      used
+    )));
+  }
+
+  let samples: Vec<([f32; 3], [f32; 3], [f32; 3])> = camera
+    .iter()
+    .zip(&ref_xyz)
+    .zip(&reference.lab)
+    .zip(&usable)
+    .filter(|(_, u)| **u)
+    .map(|(((cam, xyz), lab), _)| (*cam, *xyz, *lab))
+    .collect();
+
+  // Initial camera to XYZ matrix by linear least squares, row by row
+  let mut params = [0.0_f64; 9];
+  for k in 0..3 {
+    let mut ata = [[0.0_f64; 3]; 3];
+    let mut atb = [0.0_f64; 3];
+    for (cam, xyz, _) in &samples {
+      for i in 0..3 {
+        for j in 0..3 {
+          ata[i][j] += cam[i] as f64 * cam[j] as f64;
+        }
+        atb[i] += cam[i] as f64 * xyz[k] as f64;
+      }
+    }
+    let row = solve_linear(ata, atb).ok_or_else(|| RawlerError::DecoderFailed("Patches are not sufficient to solve color matrix".into()))?;
+    params[k * 3..k * 3 + 3].copy_from_slice(&row);
+  }
+
+  // Refine in CIELab
+  let residuals = |params: &[f64; 9]| -> Vec<f64> {
+    let matrix = matrix_from_params(params);
+    samples
+      .iter()
+      .flat_map(|(cam, _, lab)| {
+        let fitted = XYZ_to_lab(&multiply_row1(&matrix, cam), &white);
+        (0..3).map(move |c| (fitted[c] - lab[c]) as f64)
+      })
+      .collect()
+  };
+  let camera_to_xyz = matrix_from_params(&levenberg_marquardt(params, residuals));
+
+  // Camera response to a perfect white reflector
+  let gain = multiply_row1(&pseudo_inverse(camera_to_xyz), &white);
+  if gain.iter().any(|g| !g.is_finite() || *g <= 0.0) {
+    return Err(RawlerError::DecoderFailed(format!("Invalid camera neutral: {:?}", gain)));
+  }
+  let max_gain = gain.iter().copied().fold(0.0, f32::max);
+  let camera_neutral = gain.map(|g| g / max_gain);
+  log::debug!("Camera neutral: {:?}", camera_neutral);
+
+  // Forward matrix maps white balanced camera values, so (1, 1, 1) maps to D50
+  let forward_matrix = multiply(&camera_to_xyz, &[[gain[0], 0.0, 0.0], [0.0, gain[1], 0.0], [0.0, 0.0, gain[2]]]);
+  let samples: Vec<([f32; 3], [f32; 3], [f32; 3])> = samples
+    .into_iter()
+    .map(|(cam, xyz, lab)| ([0, 1, 2].map(|c| cam[c] / gain[c]), xyz, lab))
+    .collect();
+
+  // Color matrix for the illuminant of the shot
+  let adaption = bradford_adaption_matrix(&illuminant, &Illuminant::D50);
+  let neutral = [[camera_neutral[0], 0.0, 0.0], [0.0, camera_neutral[1], 0.0], [0.0, 0.0, camera_neutral[2]]];
+  let mut color_matrix = multiply(&multiply(&neutral, &pseudo_inverse(forward_matrix)), &adaption);
+  let scene_white = multiply_row1(&pseudo_inverse(adaption), &white);
+  let max = multiply_row1(&color_matrix, &scene_white).iter().copied().fold(0.0, f32::max);
+  if max > 0.0 {
+    color_matrix.iter_mut().flatten().for_each(|v| *v /= max);
+  }
+
+  let hue_sat_map = if hue_sat_map {
+    Some(build_hue_sat_map(&samples, &forward_matrix))
+  } else {
+    None
+  };
+
+  let mut samples_iter = samples.iter();
+  let delta_e = usable
+    .iter()
+    .map(|usable| {
+      if !usable {
+        return f32::NAN;
+      }
+      let Some((wb, _, lab)) = samples_iter.next() else {
+        return f32::NAN;
+      };
+      let mut xyz = multiply_row1(&forward_matrix, wb);
+      if let Some(table) = &hue_sat_map {
+        let prophoto = multiply_row1(&XYZ_TO_PROFOTORGB_D50, &xyz).map(|v| v.max(0.0));
+        xyz = multiply_row1(&pseudo_inverse(XYZ_TO_PROFOTORGB_D50), &table.apply(prophoto));
+      }
+      let fitted = XYZ_to_lab(&xyz, &white);
+      (0..3).map(|c| (fitted[c] - lab[c]).powi(2)).sum::<f32>().sqrt()
+    })
+    .collect();
+
+  Ok(ProfileFit {
+    illuminant,
+    camera_neutral,
+    color_matrix,
+    forward_matrix,
+    hue_sat_map,
+    delta_e,
+  })
+}
+
+/// Sample the target from a raw image and fit a profile.
+///
+/// If no corners are given, the chart is detected. As the detection can't
+/// tell the orientation, the chart is also tried upside down and the
+/// better fit is used.
+pub fn profile_from_raw(
+  rawimage: &RawImage,
+  reference: &TargetReference,
+  corners: Option<ChartCorners>,
+  illuminant: Illuminant,
+  hue_sat_map: bool,
+) -> Result<ProfileFit> {
+  let image = camera_image(rawimage)?;
+  match corners {
+    Some(corners) => fit_profile(&sample_patches(&image, reference.chart, &corners)?, reference, illuminant, hue_sat_map),
+    None => {
+      let (corners, fit) = fit_best_candidate(&image, reference, &detect_chart(&image, reference.chart), illuminant, hue_sat_map)?;
+      log::info!("Detected color target at {:?}", corners);
+      Ok(fit)
+    }
+  }
+}
+
+/// Fit all candidates in both orientations and return the one
+/// which matches the reference values best.
+fn fit_best_candidate(
+  image: &Color2D<f32, 3>,
+  reference: &TargetReference,
+  candidates: &[ChartCorners],
+  illuminant: Illuminant,
+  hue_sat_map: bool,
+) -> Result<(ChartCorners, ProfileFit)> {
+  candidates
+    .iter()
+    .flat_map(|corners| [*corners, corners.rotate_180()])
+    .filter_map(|corners| {
+      let patches = sample_patches(image, reference.chart, &corners).ok()?;
+      let fit = fit_profile(&patches, reference, illuminant, hue_sat_map).ok()?;
+      Some((corners, fit))
+    })
+    .min_by(|a, b| a.1.mean_delta_e().total_cmp(&b.1.mean_delta_e()))
+    .ok_or_else(|| RawlerError::DecoderFailed("Color target not found in image".into()))
+}
+
+fn matrix_from_params(params: &[f64; 9]) -> [[f32; 3]; 3] {
+  let mut matrix = [[0.0; 3]; 3];
+  matrix.iter_mut().flatten().zip(params).for_each(|(m, p)| *m = *p as f32);
+  matrix
+}
+
+/// Minimize the sum of squared residuals with numeric derivatives
+fn levenberg_marquardt<const N: usize>(mut params: [f64; N], residuals: impl Fn(&[f64; N]) -> Vec<f64>) -> [f64; N] {
+  const STEP: f64 = 1.0e-4;
+  let cost = |r: &[f64]| r.iter().map(|v| v * v).sum::<f64>();
+  let mut current = residuals(&params);
+  let mut lambda = 1.0e-3;
+  for _ in 0..100 {
+    let jacobian: Vec<Vec<f64>> = (0..N)
+      .map(|i| {
+        let mut p = params;
+        p[i] += STEP;
+        residuals(&p).iter().zip(&current).map(|(a, b)| (a - b) / STEP).collect()
+      })
+      .collect();
+    let mut jtj = [[0.0; N]; N];
+    let mut jtr = [0.0; N];
+    for i in 0..N {
+      for j in 0..N {
+        jtj[i][j] = jacobian[i].iter().zip(&jacobian[j]).map(|(a, b)| a * b).sum();
+      }
+      jtr[i] = -jacobian[i].iter().zip(&current).map(|(a, b)| a * b).sum::<f64>();
+    }
+    let mut improved = false;
+    while lambda < 1.0e8 {
+      let mut damped = jtj;
+      for i in 0..N {
+        damped[i][i] += lambda * jtj[i][i].max(1.0e-9);
+      }
+      let Some(delta) = solve_linear(damped, jtr) else {
+        lambda *= 10.0;
+        continue;
+      };
+      let mut candidate = params;
+      candidate.iter_mut().zip(delta).for_each(|(p, d)| *p += d);
+      let next = residuals(&candidate);
+      if cost(&next) < cost(&current) {
+        let gain = cost(&current) - cost(&next);
+        params = candidate;
+        current = next;
+        lambda = (lambda / 10.0).max(1.0e-9);
+        improved = gain > 1.0e-10;
+        break;
+      }
+      lambda *= 10.0;
+    }
+    if !improved {
+      break;
+    }
+  }
+  params
+}
+
+/// Solve linear system by Gaussian elimination with partial pivoting
+fn solve_linear<const N: usize>(mut a: [[f64; N]; N], mut b: [f64; N]) -> Option<[f64; N]> {
+  for col in 0..N {
+    let pivot = (col..N).max_by(|x, y| a[*x][col].abs().total_cmp(&a[*y][col].abs()))?;
+    if a[pivot][col].abs() < 1.0e-15 {
+      return None;
+    }
+    a.swap(col, pivot);
+    b.swap(col, pivot);
+    for row in col + 1..N {
+      let factor = a[row][col] / a[col][col];
+      for k in col..N {
+        a[row][k] -= factor * a[col][k];
+      }
+      b[row] -= factor * b[col];
+    }
+  }
+  let mut x = [0.0; N];
+  for row in (0..N).rev() {
+    let sum: f64 = (row + 1..N).map(|k| a[row][k] * x[k]).sum();
+    x[row] = (b[row] - sum) / a[row][row];
+  }
+  Some(x)
+}
+
+/// Build a hue/saturation map from the residual errors of the forward matrix.
+///
+/// Corrections of the patches are spread over the table by gaussian weights
+/// and fade to identity where no patches are near.
+fn build_hue_sat_map(samples: &[([f32; 3], [f32; 3], [f32; 3])], forward_matrix: &[[f32; 3]; 3]) -> HsvTable {
+  const HUE_DIVISIONS: usize = 12;
+  const SAT_DIVISIONS: usize = 5;
+  const IDENTITY_WEIGHT: f32 = 0.3;
+
+  // (hue, saturation, hue shift in degrees, saturation scale)
+  let corrections: Vec<(f32, f32, f32, f32)> = samples
+    .iter()
+    .filter_map(|(wb, xyz, _)| {
+      let fitted = multiply_row1(&XYZ_TO_PROFOTORGB_D50, &multiply_row1(forward_matrix, wb)).map(|v| v.max(0.0));
+      let target = multiply_row1(&XYZ_TO_PROFOTORGB_D50, xyz).map(|v| v.max(0.0));
+      let (fh, fs, _) = rgb_to_hsv(fitted);
+      let (th, ts, _) = rgb_to_hsv(target);
+      if fs < 0.05 {
+        return None;
+      }
+      let hue_shift = ((th - fh + 3.0).rem_euclid(6.0) - 3.0) * 60.0;
+      Some((fh, fs, hue_shift.clamp(-30.0, 30.0), (ts / fs).clamp(0.5, 2.0)))
+    })
+    .collect();
+
+  let mut data = Vec::with_capacity(HUE_DIVISIONS * SAT_DIVISIONS * 3);
+  for h in 0..HUE_DIVISIONS {
+    let hue = h as f32 * 6.0 / HUE_DIVISIONS as f32;
+    for s in 0..SAT_DIVISIONS {
+      let sat = s as f32 / (SAT_DIVISIONS - 1) as f32;
+      if s == 0 {
+        data.extend([0.0, 1.0, 1.0]);
+        continue;
+      }
+      let (mut weights, mut shift, mut scale) = (0.0, 0.0, 0.0);
+      for (ph, ps, pshift, pscale) in &corrections {
+        let dh = (hue - ph).abs().min(6.0 - (hue - ph).abs()) / 0.75;
+        let ds = (sat - ps) / 0.25;
+        let weight = (-(dh * dh + ds * ds) / 2.0).exp();
+        weights += weight;
+        shift += weight * pshift;
+        scale += weight * pscale;
+      }
+      data.extend([
+        shift / (weights + IDENTITY_WEIGHT),
+        (scale + IDENTITY_WEIGHT) / (weights + IDENTITY_WEIGHT),
+        1.0,
+      ]);
+    }
+  }
+  HsvTable {
+    hue_divisions: HUE_DIVISIONS as u32,
+    sat_divisions: SAT_DIVISIONS as u32,
+    val_divisions: 1,
+    data,
+    srgb_encoding: false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // Rows sum up to the D50 white point
+  const FORWARD_MATRIX: [[f32; 3]; 3] = [[0.65, 0.22, 0.09422], [0.28, 0.78, -0.06], [0.02, -0.12, 0.92521]];
+  const NEUTRAL: [f32; 3] = [0.48, 1.0, 0.62];
+
+  /// Camera response for reference values with a known forward matrix
+  fn synthetic_camera(reference: &TargetReference, exposure: f32) -> Vec<[f32; 3]> {
+    let camera_to_xyz = pseudo_inverse(FORWARD_MATRIX);
+    reference
+      .lab
+      .iter()
+      .map(|lab| {
+        let wb = multiply_row1(&camera_to_xyz, &lab_to_XYZ(lab, &CIE_1931_TRISTIMULUS_D50));
+        [0, 1, 2].map(|c| wb[c] * NEUTRAL[c] * exposure)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn fit_recovers_forward_matrix() -> Result<()> {
+    let reference = TargetReference::colorchecker24();
+    let mut camera = synthetic_camera(&reference, 0.9);
+    camera[18] = [1.0, 1.0, 1.0]; // clipped white patch
+    let fit = fit_profile(&camera, &reference, Illuminant::D65, false)?;
+    assert!(fit.delta_e[18].is_nan());
+    assert!(fit.max_delta_e() < 0.1, "max delta E: {}", fit.max_delta_e());
+    for (a, b) in fit.forward_matrix.iter().flatten().zip(FORWARD_MATRIX.iter().flatten()) {
+      assert!((a - b).abs() < 0.005, "{:?}", fit.forward_matrix);
+    }
+    for (a, b) in fit.camera_neutral.iter().zip(NEUTRAL) {
+      assert!((a - b).abs() < 0.005, "{:?}", fit.camera_neutral);
+    }
+    // Color matrix maps D50 white to the camera neutral for a D50 shot
+    let fit = fit_profile(&camera, &reference, Illuminant::D50, true)?;
+    let neutral = multiply_row1(&fit.color_matrix, &CIE_1931_TRISTIMULUS_D50);
+    for (a, b) in neutral.iter().zip(NEUTRAL) {
+      assert!((a - b).abs() < 0.005, "{:?}", neutral);
+    }
+    assert!(fit.max_delta_e() < 0.5, "max delta E with hue/sat map: {}", fit.max_delta_e());
+
+    let profile = fit.to_dcp_profile("Test Camera", "Test");
+    assert_eq!(profile.color_matrices().len(), 1);
+    assert!(fit.to_camera_toml().starts_with("[cameras.color_matrix]\nD50 = ["));
+    Ok(())
+  }
+
+  #[test]
+  fn detect_and_sample_chart() -> Result<()> {
+    let reference = TargetReference::colorchecker24();
+    let camera = synthetic_camera(&reference, 0.8);
+    let (width, height) = (480, 360);
+    // Chart is upside down, 300x200 pixels at 100, 80
+    let (x0, y0, pitch) = (100, 80, 50);
+    let mut image = Color2D::<f32, 3>::new_with(vec![[0.2, 0.2, 0.2]; width * height], width, height);
+    for row in 0..4 {
+      for col in 0..6 {
+        let value = camera[(3 - row) * 6 + (5 - col)];
+        for y in y0 + row * pitch + 5..y0 + (row + 1) * pitch - 5 {
+          for x in x0 + col * pitch + 5..x0 + (col + 1) * pitch - 5 {
+            *image.at_mut(y, x) = value;
+          }
+        }
+      }
+    }
+    let candidates = detect_chart(&image, TargetChart::ColorChecker24);
+    let (corners, fit) = fit_best_candidate(&image, &reference, &candidates, Illuminant::D65, false)?;
+    // Within a quarter of the patch pitch
+    assert!(
+      (corners.bottom_right.0 - 125.0).abs() < 12.5 && (corners.bottom_right.1 - 105.0).abs() < 12.5,
+      "{:?}",
+      corners
+    );
+    assert!(fit.max_delta_e() < 0.5, "max delta E: {}", fit.max_delta_e());
+    let patches = sample_patches(&image, TargetChart::ColorChecker24, &corners)?;
+    for (a, b) in patches.iter().zip(&camera) {
+      assert!(a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1.0e-4), "{:?} vs. {:?}", a, b);
+    }
+    Ok(())
+  }
+
+  #[test]
+  fn parse_cgats_reference() -> Result<()> {
+    let mut text = String::from("CGATS.17\nNUMBER_OF_FIELDS 4\nBEGIN_DATA_FORMAT\nSAMPLE_ID LAB_L LAB_A LAB_B\nEND_DATA_FORMAT\nBEGIN_DATA\n");
+    // Column-wise order, like most reference files
+    for col in 0..6 {
+      for row in 0..4 {
+        let lab = COLORCHECKER24_LAB[row * 6 + col];
+        text.push_str(&format!("{}{} {} {} {}\n", (b'A' + col as u8) as char, row + 1, lab[0], lab[1], lab[2]));
+      }
+    }
+    text.push_str("END_DATA\n");
+    let reference = TargetReference::from_cgats(TargetChart::ColorChecker24, &text)?;
+    assert_eq!(reference.lab, COLORCHECKER24_LAB.to_vec());
+    assert!(TargetReference::from_cgats(TargetChart::ColorCheckerSG, &text).is_err());
+    Ok(())
+  }
+}
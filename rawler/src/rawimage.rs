@@ -14,7 +14,7 @@ use crate::imgop::sensor::SensorType;
 use crate::imgop::{convert_from_f32_scaled_u16, convert_to_f32_unscaled};
 use crate::pixarray::SubPixel;
 use crate::{
-  CFA,
+  CFA, RawlerError,
   decoders::*,
   formats::tiff::{Rational, Value},
   imgop::{
@@ -179,20 +179,20 @@ pub struct CFAConfig {
 }
 
 impl CFAConfig {
-  pub fn new(cfa: &CFA, colors: &PlaneColor) -> Self {
-    Self {
+  pub fn new(cfa: &CFA, colors: &PlaneColor) -> Result<Self> {
+    Ok(Self {
       cfa: cfa.clone(),
       colors: colors.clone(),
-      sensor: SensorType::from_cfa(cfa),
-    }
+      sensor: SensorType::from_cfa(cfa)?,
+    })
   }
 
-  pub fn new_from_camera(cam: &Camera) -> Self {
-    Self {
+  pub fn new_from_camera(cam: &Camera) -> Result<Self> {
+    Ok(Self {
       cfa: cam.cfa.clone(),
       colors: cam.plane_color.clone(),
-      sensor: SensorType::from_cfa(&cam.cfa),
-    }
+      sensor: SensorType::from_cfa(&cam.cfa)?,
+    })
   }
 }
 
@@ -327,8 +327,13 @@ impl RawImage {
     blacklevel: Option<BlackLevel>,
     whitelevel: Option<WhiteLevel>,
     dummy: bool,
-  ) -> RawImage {
-    assert_eq!(image.width % cpp, 0);
+  ) -> Result<RawImage> {
+    if cpp == 0 || !image.width.is_multiple_of(cpp) {
+      return Err(RawlerError::DecoderFailed(format!(
+        "Image width {} is not a multiple of cpp {}",
+        image.width, cpp
+      )));
+    }
     assert_eq!(dummy, !image.is_initialized());
     let sample_width = image.width;
     let pixel_width = image.width / cpp;
@@ -373,11 +378,11 @@ impl RawImage {
     let whitelevel = cam
       .make_whitelevel(cpp)
       .or(whitelevel)
-      .unwrap_or_else(|| panic!("Need whitelevel in config: {}", cam.clean_model));
+      .ok_or_else(|| RawlerError::malformed(&cam.clean_make, format!("no whitelevel for {}", cam.clean_model)))?;
 
     let crop_area = cam.crop_area.map(|area| Rect::new_with_borders(Dim2::new(pixel_width, image.height), &area));
 
-    RawImage {
+    Ok(RawImage {
       camera: cam.clone(),
       make: cam.make.clone(),
       model: cam.model.clone(),
@@ -400,7 +405,7 @@ impl RawImage {
       color_matrix: cam.color_matrix,
       dng_tags: HashMap::new(),
       fuji_rotation_width: None,
-    }
+    })
   }
 
   #[doc(hidden)]
@@ -415,7 +420,7 @@ impl RawImage {
     blacklevel: Option<BlackLevel>,
     whitelevel: Option<WhiteLevel>,
     dummy: bool,
-  ) -> RawImage {
+  ) -> Result<RawImage> {
     //assert_eq!(image.width % cpp, 0);
     //assert_eq!(dummy, !image.is_initialized());
     let pixel_width = sample_width / cpp;
@@ -463,11 +468,11 @@ impl RawImage {
     let whitelevel = cam
       .make_whitelevel(cpp)
       .or(whitelevel)
-      .unwrap_or_else(|| panic!("Need whitelevel in config: {}", cam.clean_model));
+      .ok_or_else(|| RawlerError::malformed(&cam.clean_make, format!("no whitelevel for {}", cam.clean_model)))?;
 
     let crop_area = cam.crop_area.map(|area| Rect::new_with_borders(Dim2::new(pixel_width, height), &area));
 
-    RawImage {
+    Ok(RawImage {
       camera: cam.clone(),
       make: cam.make.clone(),
       model: cam.model.clone(),
@@ -490,7 +495,7 @@ impl RawImage {
       color_matrix: cam.color_matrix,
       dng_tags: HashMap::new(),
       fuji_rotation_width: None,
-    }
+    })
   }
 
   pub fn dim(&self) -> Dim2 {
@@ -519,7 +524,6 @@ impl RawImage {
   pub fn apply_scaling(&mut self) -> crate::Result<()> {
     let mut pixels = self.data.as_f32();
     match &self.photometric {
-      RawPhotometricInterpretation::BlackIsZero => todo!(),
       RawPhotometricInterpretation::Cfa(_) => {
         correct_blacklevel_cfa(
           pixels.to_mut(),
@@ -530,7 +534,7 @@ impl RawImage {
         );
         self.data = RawImageData::Float(pixels.into_owned());
       }
-      RawPhotometricInterpretation::LinearRaw => {
+      RawPhotometricInterpretation::BlackIsZero | RawPhotometricInterpretation::LinearRaw => {
         correct_blacklevel(pixels.to_mut(), &self.blacklevel.as_vec(), &self.whitelevel.as_vec());
         self.data = RawImageData::Float(pixels.into_owned());
       }
@@ -750,4 +754,14 @@ mod tests {
     );
     Ok(())
   }
+
+  #[test]
+  fn cfa_config_unsupported_pattern() {
+    let cfa = CFA::new("RGGBGGBRRGGBGGBR");
+    assert!(matches!(
+      CFAConfig::new(&cfa, &PlaneColor::default()),
+      Err(crate::RawlerError::UnsupportedFeature { .. })
+    ));
+    assert!(CFAConfig::new(&CFA::new("RGGB"), &PlaneColor::default()).is_ok());
+  }
 }
@@ -3,11 +3,15 @@
 use std::{
   fmt::Debug,
   fs::File,
-  io::Cursor,
+  io::{Read, Seek, SeekFrom},
   iter::repeat,
-  ops::Deref,
+  ops::Range,
   path::{Path, PathBuf},
-  sync::Arc,
+  ptr::NonNull,
+  sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+  },
 };
 
 use md5::Digest;
@@ -15,14 +19,211 @@ use memmap2::MmapOptions;
 
 use crate::buffer::PaddedBuf;
 
+/// Error payload for reads behind the end of a source.
+///
+/// This is wrapped into an [`std::io::Error`] of kind `UnexpectedEof`
+/// so the offset survives conversion into [`crate::RawlerError`].
+#[derive(Debug, thiserror::Error)]
+#[error("{}(): Offset {}+{} is behind EOF", context, offset, size)]
+pub struct OutOfBounds {
+  pub context: &'static str,
+  pub offset: u64,
+  pub size: u64,
+}
+
+impl OutOfBounds {
+  fn io_error(context: &'static str, offset: u64, size: u64) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, Self { context, offset, size })
+  }
+}
+
+/// Backend for sources which are not fully available in memory
+///
+/// Implementations serve byte ranges on request, for example from a
+/// [`Read`] + [`Seek`] stream, HTTP range requests or an object store.
+/// Each range is requested only once, [`RawSource`] caches all data.
+pub trait RawSourceBackend: Send + Sync {
+  /// Total size of the source in bytes
+  fn len(&self) -> u64;
+
+  /// Fill `buf` with the data starting at `offset`
+  ///
+  /// The range is always within the bounds given by [`Self::len`].
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()>;
+
+  fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+}
+
+/// Backend for any [`Read`] + [`Seek`] stream
+pub struct ReaderBackend<R> {
+  reader: Mutex<R>,
+  len: u64,
+}
+
+impl<R: Read + Seek> ReaderBackend<R> {
+  pub fn new(mut reader: R) -> std::io::Result<Self> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    Ok(Self {
+      reader: Mutex::new(reader),
+      len,
+    })
+  }
+}
+
+impl<R: Read + Seek + Send> RawSourceBackend for ReaderBackend<R> {
+  fn len(&self) -> u64 {
+    self.len
+  }
+
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+    let mut reader = self.reader.lock().map_err(|_| std::io::Error::other("Reader lock is poisoned"))?;
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(buf)
+  }
+}
+
+/// Backend for a user-supplied range fetcher
+///
+/// The fetcher is called with the requested byte range and must return
+/// exactly the bytes of this range. This is the natural fit for HTTP
+/// range requests or object storage clients. Async clients can block on
+/// their runtime inside the closure.
+pub struct RangeFetcher<F> {
+  fetch: F,
+  len: u64,
+}
+
+impl<F> RangeFetcher<F>
+where
+  F: Fn(Range<u64>) -> std::io::Result<Vec<u8>> + Send + Sync,
+{
+  pub fn new(len: u64, fetch: F) -> Self {
+    Self { fetch, len }
+  }
+}
+
+impl<F> RawSourceBackend for RangeFetcher<F>
+where
+  F: Fn(Range<u64>) -> std::io::Result<Vec<u8>> + Send + Sync,
+{
+  fn len(&self) -> u64 {
+    self.len
+  }
+
+  fn read_at(&self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+    let range = offset..offset + buf.len() as u64;
+    let data = (self.fetch)(range.clone())?;
+    if data.len() != buf.len() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        format!("Range fetcher returned {} bytes for range {:?}", data.len(), range),
+      ));
+    }
+    buf.copy_from_slice(&data);
+    Ok(())
+  }
+}
+
+/// Lazily filled buffer for a [`RawSourceBackend`]
+///
+/// The buffer is allocated zeroed for the full source size, so the OS
+/// only commits pages which are actually fetched. Data is fetched in
+/// chunks and each chunk is written exactly once, before any reference
+/// to it is handed out. This allows returning plain slices like for
+/// memory mapped files.
+struct LazyBuffer {
+  data: NonNull<u8>,
+  len: usize,
+  chunks: Vec<AtomicBool>,
+  backend: Mutex<Box<dyn RawSourceBackend>>,
+}
+
+// SAFETY: The buffer is only written under the backend lock and only
+// for chunks which are not yet marked as filled. Readers only access
+// filled chunks, which are immutable from then on.
+unsafe impl Send for LazyBuffer {}
+unsafe impl Sync for LazyBuffer {}
+
+impl LazyBuffer {
+  /// Size of the blocks fetched from the backend
+  const CHUNK_SIZE: usize = 64 * 1024;
+
+  fn new(backend: Box<dyn RawSourceBackend>) -> Self {
+    let len = backend.len() as usize;
+    let data = Box::into_raw(vec![0_u8; len].into_boxed_slice());
+    Self {
+      // SAFETY: Box::into_raw never returns a null pointer
+      data: unsafe { NonNull::new_unchecked(data as *mut u8) },
+      len,
+      chunks: (0..len.div_ceil(Self::CHUNK_SIZE)).map(|_| AtomicBool::new(false)).collect(),
+      backend: Mutex::new(backend),
+    }
+  }
+
+  /// Make sure the range is available and return it
+  fn get(&self, range: Range<usize>) -> std::io::Result<&[u8]> {
+    debug_assert!(range.start <= range.end && range.end <= self.len);
+    if range.is_empty() {
+      return Ok(&[]);
+    }
+    let chunks = range.start / Self::CHUNK_SIZE..range.end.div_ceil(Self::CHUNK_SIZE);
+    if !self.chunks[chunks.clone()].iter().all(|chunk| chunk.load(Ordering::Acquire)) {
+      let backend = self.backend.lock().map_err(|_| std::io::Error::other("Backend lock is poisoned"))?;
+      let mut idx = chunks.start;
+      while idx < chunks.end {
+        if self.chunks[idx].load(Ordering::Acquire) {
+          idx += 1;
+          continue;
+        }
+        // Fetch consecutive missing chunks with a single request
+        let first = idx;
+        while idx < chunks.end && !self.chunks[idx].load(Ordering::Acquire) {
+          idx += 1;
+        }
+        let start = first * Self::CHUNK_SIZE;
+        let end = (idx * Self::CHUNK_SIZE).min(self.len);
+        // SAFETY: The chunks are not filled yet, so no references exist
+        // and the backend lock prevents concurrent writes.
+        let buf = unsafe { std::slice::from_raw_parts_mut(self.data.as_ptr().add(start), end - start) };
+        backend.read_at(start as u64, buf)?;
+        self.chunks[first..idx].iter().for_each(|chunk| chunk.store(true, Ordering::Release));
+      }
+    }
+    // SAFETY: All chunks of the range are filled and never written again
+    Ok(unsafe { std::slice::from_raw_parts(self.data.as_ptr().add(range.start), range.len()) })
+  }
+
+  /// Number of bytes fetched from the backend so far
+  fn fetched(&self) -> usize {
+    let chunks = self.chunks.iter().filter(|chunk| chunk.load(Ordering::Acquire)).count();
+    (chunks * Self::CHUNK_SIZE).min(self.len)
+  }
+}
+
+impl Drop for LazyBuffer {
+  fn drop(&mut self) {
+    // SAFETY: Pointer and length are from the boxed slice created in new()
+    drop(unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(self.data.as_ptr(), self.len)) });
+  }
+}
+
+/// Source of raw file data
+///
+/// A source is either a memory mapped file, a shared buffer or a
+/// [`RawSourceBackend`] which is read lazily. Cloning a source is
+/// cheap, all clones share the same data.
+#[derive(Clone)]
 pub struct RawSource {
   path: PathBuf,
-  inner: RawSourceImpl,
+  inner: Arc<RawSourceImpl>,
 }
 
 enum RawSourceImpl {
   Memmap(memmap2::Mmap),
   Memory(Arc<Vec<u8>>),
+  Lazy(LazyBuffer),
 }
 
 impl RawSource {
@@ -36,17 +237,42 @@ impl RawSource {
     }
     Ok(Self {
       path: path.canonicalize().unwrap_or_else(|_| path.to_owned()),
-      inner: RawSourceImpl::Memmap(mmap),
+      inner: Arc::new(RawSourceImpl::Memmap(mmap)),
     })
   }
 
   pub fn new_from_shared_vec(buf: Arc<Vec<u8>>) -> Self {
     Self {
       path: PathBuf::default(),
-      inner: RawSourceImpl::Memory(buf),
+      inner: Arc::new(RawSourceImpl::Memory(buf)),
+    }
+  }
+
+  /// Create a source which fetches data from the backend on demand
+  ///
+  /// Only the byte ranges touched by a decoder are requested, in blocks
+  /// of 64 KiB. Note that [`Self::buf`], [`Self::as_vec`] and [`Self::digest`]
+  /// need the whole source and fetch all remaining data.
+  pub fn new_from_backend(backend: impl RawSourceBackend + 'static) -> Self {
+    Self {
+      path: PathBuf::default(),
+      inner: Arc::new(RawSourceImpl::Lazy(LazyBuffer::new(Box::new(backend)))),
     }
   }
 
+  /// Create a lazy source from a [`Read`] + [`Seek`] stream
+  pub fn new_from_reader<R: Read + Seek + Send + 'static>(reader: R) -> std::io::Result<Self> {
+    Ok(Self::new_from_backend(ReaderBackend::new(reader)?))
+  }
+
+  /// Create a lazy source from a range fetcher, see [`RangeFetcher`]
+  pub fn new_from_range_fetcher<F>(len: u64, fetch: F) -> Self
+  where
+    F: Fn(Range<u64>) -> std::io::Result<Vec<u8>> + Send + Sync + 'static,
+  {
+    Self::new_from_backend(RangeFetcher::new(len, fetch))
+  }
+
   pub fn with_path(self, path: impl AsRef<Path>) -> Self {
     Self {
       path: path.as_ref().to_owned(),
@@ -59,23 +285,64 @@ impl RawSource {
   }
 
   /// Calculate digest for file
-  pub fn digest(&self) -> Digest {
-    md5::compute(self.buf())
+  ///
+  /// For lazy sources, this fetches all remaining data from the backend.
+  pub fn digest(&self) -> std::io::Result<Digest> {
+    Ok(md5::compute(self.buf()?))
   }
 
   pub fn path(&self) -> &Path {
     &self.path
   }
 
-  pub fn buf(&self) -> &[u8] {
-    self.deref()
+  /// Size of the source in bytes
+  pub fn len(&self) -> usize {
+    match self.inner.as_ref() {
+      RawSourceImpl::Memmap(mmap) => mmap.len(),
+      RawSourceImpl::Memory(mem) => mem.len(),
+      RawSourceImpl::Lazy(lazy) => lazy.len,
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// True if the data is fetched on demand from a [`RawSourceBackend`]
+  pub fn is_lazy(&self) -> bool {
+    matches!(self.inner.as_ref(), RawSourceImpl::Lazy(_))
+  }
+
+  /// Number of bytes which are already available in memory
+  ///
+  /// For lazy sources, this is the amount of data fetched from the backend.
+  pub fn fetched_len(&self) -> usize {
+    match self.inner.as_ref() {
+      RawSourceImpl::Lazy(lazy) => lazy.fetched(),
+      _ => self.len(),
+    }
+  }
+
+  /// Whole source as a slice
+  ///
+  /// For lazy sources, this fetches all remaining data from the backend.
+  pub fn buf(&self) -> std::io::Result<&[u8]> {
+    self.subview(0, self.len() as u64)
   }
 
   pub fn subview(&self, offset: u64, size: u64) -> std::io::Result<&[u8]> {
-    self.buf().get(offset as usize..(offset + size) as usize).ok_or(std::io::Error::new(
-      std::io::ErrorKind::UnexpectedEof,
-      format!("subview(): Offset {}+{} is behind EOF", offset, size),
-    ))
+    let range = offset as usize..offset.saturating_add(size) as usize;
+    match self.inner.as_ref() {
+      RawSourceImpl::Memmap(mmap) => mmap.get(range),
+      RawSourceImpl::Memory(mem) => mem.get(range),
+      RawSourceImpl::Lazy(lazy) => {
+        if range.end <= lazy.len {
+          return lazy.get(range);
+        }
+        None
+      }
+    }
+    .ok_or_else(|| OutOfBounds::io_error("subview", offset, size))
   }
 
   pub fn subview_padded(&self, offset: u64, size: u64) -> std::io::Result<PaddedBuf<'_>> {
@@ -89,18 +356,15 @@ impl RawSource {
         Ok(PaddedBuf::new_owned(buf, size as usize))
       }
     } else {
-      Err(std::io::Error::new(
-        std::io::ErrorKind::UnexpectedEof,
-        format!("subview_padded(): Offset {}+{} is behind EOF", offset, size),
-      ))
+      Err(OutOfBounds::io_error("subview_padded", offset, size))
     }
   }
 
   pub fn subview_until_eof(&self, offset: u64) -> std::io::Result<&[u8]> {
-    self.buf().get(offset as usize..).ok_or(std::io::Error::new(
-      std::io::ErrorKind::UnexpectedEof,
-      format!("subview_until_eof(): Offset {} is behind EOF", offset),
-    ))
+    if offset as usize > self.len() {
+      return Err(OutOfBounds::io_error("subview_until_eof", offset, 0));
+    }
+    self.subview(offset, (self.len() - offset as usize) as u64)
   }
 
   pub fn subview_until_eof_padded(&self, offset: u64) -> std::io::Result<PaddedBuf<'_>> {
@@ -110,33 +374,72 @@ impl RawSource {
       buf.extend(repeat(0).take(16));
       Ok(PaddedBuf::new_owned(buf, self.len() - offset as usize))
     } else {
-      Err(std::io::Error::new(
-        std::io::ErrorKind::UnexpectedEof,
-        format!("subview_until_eof_padded(): Offset {} is behind EOF", offset),
-      ))
+      Err(OutOfBounds::io_error("subview_until_eof_padded", offset, 0))
     }
   }
 
-  pub fn reader(&self) -> Cursor<&[u8]> {
-    Cursor::new(self.buf())
+  /// Stream reader for the source
+  ///
+  /// For lazy sources, only the data actually read is fetched.
+  pub fn reader(&self) -> RawSourceReader<'_> {
+    RawSourceReader { source: self, pos: 0 }
   }
 
   pub fn as_vec(&self) -> std::io::Result<Vec<u8>> {
-    Ok(self.buf().to_vec())
+    Ok(self.buf()?.to_vec())
   }
 
   pub fn stream_len(&mut self) -> u64 {
-    self.buf().len() as u64
+    self.len() as u64
   }
 }
 
-impl Deref for RawSource {
-  type Target = [u8];
+/// [`Read`] + [`Seek`] access to a [`RawSource`]
+pub struct RawSourceReader<'a> {
+  source: &'a RawSource,
+  pos: u64,
+}
+
+impl<'a> RawSourceReader<'a> {
+  pub fn position(&self) -> u64 {
+    self.pos
+  }
+}
 
-  fn deref(&self) -> &Self::Target {
-    match &self.inner {
-      RawSourceImpl::Memmap(mmap) => mmap.deref(),
-      RawSourceImpl::Memory(mem) => mem.deref(),
+impl<'a> Read for RawSourceReader<'a> {
+  fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+    let remaining = (self.source.len() as u64).saturating_sub(self.pos);
+    let size = remaining.min(buf.len() as u64);
+    if size == 0 {
+      // Position may be past the end after seeking
+      return Ok(0);
+    }
+    let data = self.source.subview(self.pos, size)?;
+    buf[..data.len()].copy_from_slice(data);
+    self.pos += size;
+    Ok(size as usize)
+  }
+}
+
+impl<'a> Seek for RawSourceReader<'a> {
+  fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+    let (base, offset) = match pos {
+      SeekFrom::Start(offset) => {
+        self.pos = offset;
+        return Ok(offset);
+      }
+      SeekFrom::End(offset) => (self.source.len() as u64, offset),
+      SeekFrom::Current(offset) => (self.pos, offset),
+    };
+    match base.checked_add_signed(offset) {
+      Some(pos) => {
+        self.pos = pos;
+        Ok(pos)
+      }
+      None => Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        "invalid seek to a negative or overflowing position",
+      )),
     }
   }
 }
@@ -146,3 +449,122 @@ impl Debug for RawSource {
     f.debug_struct("RawSource").field("path", &self.path).finish()
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::decoders::RawDecodeParams;
+  use crate::devtools::synthetic::{self, dng, test_pattern};
+  use std::io::Cursor;
+
+  /// In-process stand-in for a HTTP range server, records all requests
+  struct RangeServer {
+    data: Arc<Vec<u8>>,
+    requests: Arc<Mutex<Vec<Range<u64>>>>,
+  }
+
+  impl RangeServer {
+    fn new(data: Vec<u8>) -> Self {
+      Self {
+        data: Arc::new(data),
+        requests: Arc::default(),
+      }
+    }
+
+    fn source(&self) -> RawSource {
+      let (data, requests) = (self.data.clone(), self.requests.clone());
+      RawSource::new_from_range_fetcher(self.data.len() as u64, move |range| {
+        requests.lock().expect("lock").push(range.clone());
+        data
+          .get(range.start as usize..range.end as usize)
+          .map(<[u8]>::to_vec)
+          .ok_or_else(|| std::io::Error::other("Range not satisfiable"))
+      })
+    }
+
+    fn requested_bytes(&self) -> u64 {
+      self.requests.lock().expect("lock").iter().map(|r| r.end - r.start).sum()
+    }
+  }
+
+  #[test]
+  fn lazy_source_fetches_only_touched_ranges() -> crate::Result<()> {
+    let pattern = test_pattern(1024, 512, 16);
+    let server = RangeServer::new(dng::encode_packed(&pattern, 16)?);
+    let source = server.source();
+    assert!(source.is_lazy());
+
+    // Probing only needs the header and IFDs, not the strip data
+    let decoder = crate::get_decoder(&source)?;
+    assert!(server.requested_bytes() < server.data.len() as u64 / 4);
+
+    let image = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+    synthetic::assert_pixels(&image, &pattern);
+    assert_eq!(source.fetched_len() as u64, server.requested_bytes());
+
+    // Cached data is never requested twice
+    let requests = server.requests.lock().expect("lock").clone();
+    assert!(
+      requests
+        .iter()
+        .enumerate()
+        .all(|(i, a)| requests[i + 1..].iter().all(|b| a.end <= b.start || b.end <= a.start))
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn reader_backend_matches_memory() -> crate::Result<()> {
+    let data = dng::encode_ljpeg(&test_pattern(128, 96, 14), 14, 64, 48)?;
+    let memory = RawSource::new_from_slice(&data);
+    let lazy = RawSource::new_from_reader(Cursor::new(data))?;
+    assert_eq!(lazy.len(), memory.len());
+    assert_eq!(lazy.subview(100, 50)?, memory.subview(100, 50)?);
+    assert_eq!(lazy.as_vec()?, memory.as_vec()?);
+    assert_eq!(lazy.digest()?, memory.digest()?);
+    Ok(())
+  }
+
+  #[test]
+  fn lazy_source_reports_out_of_bounds() {
+    let source = RawSource::new_from_reader(Cursor::new(vec![0_u8; 100])).expect("source");
+    assert!(source.subview(90, 20).is_err());
+    assert!(source.subview_until_eof(101).is_err());
+    assert_eq!(source.subview_until_eof(100).map(<[u8]>::len).ok(), Some(0));
+  }
+
+  #[test]
+  fn lazy_source_reports_backend_errors() {
+    let source = RawSource::new_from_range_fetcher(200_000, |range| {
+      if range.start == 0 {
+        Ok(vec![0; (range.end - range.start) as usize])
+      } else {
+        Err(std::io::Error::other("Connection lost"))
+      }
+    });
+    assert!(source.subview(0, 10).is_ok());
+    assert!(source.buf().is_err());
+    assert!(source.digest().is_err());
+    assert!(source.as_vec().is_err());
+  }
+
+  #[test]
+  fn source_reader_read_and_seek() -> std::io::Result<()> {
+    let source = RawSource::new_from_slice(&[1, 2, 3, 4, 5]);
+    let mut reader = source.reader();
+    let mut buf = [0; 3];
+    reader.seek(SeekFrom::End(-2))?;
+    assert_eq!(reader.read(&mut buf)?, 2);
+    assert_eq!(buf[..2], [4, 5]);
+    assert_eq!(reader.read(&mut buf)?, 0);
+    assert!(reader.seek(SeekFrom::Current(-10)).is_err());
+    reader.seek(SeekFrom::Start(1))?;
+    reader.read_exact(&mut buf)?;
+    assert_eq!(buf, [2, 3, 4]);
+    reader.seek(SeekFrom::Start(10))?;
+    assert_eq!(reader.read(&mut buf)?, 0);
+    let mut rest = Vec::new();
+    assert_eq!(reader.read_to_end(&mut rest)?, 0);
+    Ok(())
+  }
+}